@@ -17,17 +17,45 @@ impl Debug for NyarError {
     }
 }
 
-impl Display for NyarErrorKind {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl NyarErrorKind {
+    /// 不带错误类别前缀的说明, 如 `division by zero`, 断言失败与堆内存错误与 [`Display`] 相同
+    pub fn message(&self) -> String {
         match self {
-            NyarErrorKind::Decode { format, message } => {
-                write!(f, "Decode error: {}: {}", format, message)
+            NyarErrorKind::Decode { format, message } | NyarErrorKind::Encode { format, message } => {
+                format!("{}: {}", format, message)
             }
-            NyarErrorKind::Encode { format, message } => {
-                write!(f, "Encode error: {}: {}", format, message)
+            NyarErrorKind::Custom { message } | NyarErrorKind::Syntax { message } | NyarErrorKind::Type { message } => {
+                message.clone()
             }
-            NyarErrorKind::Custom { message } => {
-                write!(f, "Custom error: {}", message)
+            NyarErrorKind::Conversion { value, target } => format!("{} cannot be represented as `{}`", value, target),
+            NyarErrorKind::Overflow { operation, target } => format!("`{}` overflows `{}`", operation, target),
+            NyarErrorKind::Assertion { .. } | NyarErrorKind::UseAfterFree { .. } => self.to_string(),
+        }
+    }
+}
+
+impl Display for NyarErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NyarErrorKind::Decode { .. } => write!(f, "Decode error: {}", self.message()),
+            NyarErrorKind::Encode { .. } => write!(f, "Encode error: {}", self.message()),
+            NyarErrorKind::Custom { .. } => write!(f, "Custom error: {}", self.message()),
+            NyarErrorKind::Syntax { .. } => write!(f, "Syntax error: {}", self.message()),
+            NyarErrorKind::Type { .. } => write!(f, "Type error: {}", self.message()),
+            NyarErrorKind::Conversion { .. } => write!(f, "Conversion error: {}", self.message()),
+            NyarErrorKind::Overflow { .. } => write!(f, "Overflow error: {}", self.message()),
+            NyarErrorKind::Assertion { expression, message, operands } => {
+                f.write_str("Assertion failed")?;
+                if !expression.is_empty() {
+                    write!(f, ": `{}`", expression)?;
+                }
+                if let Some(message) = message {
+                    write!(f, ", {}", message)?;
+                }
+                if let Some((left, right)) = operands {
+                    write!(f, "\n  left: {}\n right: {}", left, right)?;
+                }
+                Ok(())
             }
             NyarErrorKind::UseAfterFree { address } => {
                 write!(f, "Use after free error: {}", address)
@@ -26,6 +26,37 @@ pub enum NyarErrorKind {
     Custom {
         message: String,
     },
+    /// 语法错误
+    Syntax {
+        message: String,
+    },
+    /// 类型错误
+    Type {
+        message: String,
+    },
+    /// 数值无法无损地表示为目标类型
+    Conversion {
+        /// 数值
+        value: String,
+        /// 目标类型
+        target: String,
+    },
+    /// 定宽整数运算的结果超出类型的范围
+    Overflow {
+        /// 运算, 如 `127 + 1`
+        operation: String,
+        /// 运算的类型
+        target: String,
+    },
+    /// 断言失败
+    Assertion {
+        /// 断言的表达式的源码, 没有源码时为空
+        expression: String,
+        /// 断言的说明
+        message: Option<String>,
+        /// 比较断言两侧的值
+        operands: Option<(String, String)>,
+    },
     /// 堆内存错误
     UseAfterFree {
         /// 错误类型
@@ -47,7 +78,59 @@ impl NyarError {
         NyarErrorKind::Custom { message: message.to_string() }.into()
     }
 
+    /// 在指定区间创建语法错误
+    pub fn syntax_error(message: impl ToString, span: Range<usize>) -> NyarError {
+        NyarError::from(NyarErrorKind::Syntax { message: message.to_string() }).with_span(span)
+    }
+
+    /// 在指定区间创建类型错误
+    pub fn type_error(message: impl ToString, span: Range<usize>) -> NyarError {
+        NyarError::from(NyarErrorKind::Type { message: message.to_string() }).with_span(span)
+    }
+
+    /// 无法按 `format` 格式解析数据, 如清单文件
+    pub fn decode(format: impl ToString, message: impl ToString) -> NyarError {
+        NyarErrorKind::Decode { format: format.to_string(), message: message.to_string() }.into()
+    }
+
+    /// 数值无法无损地表示为目标类型
+    pub fn conversion(value: impl ToString, target: impl ToString) -> NyarError {
+        NyarErrorKind::Conversion { value: value.to_string(), target: target.to_string() }.into()
+    }
+
+    /// 定宽整数运算 `operation` 的结果超出类型 `target` 的范围
+    pub fn overflow(operation: impl ToString, target: impl ToString) -> NyarError {
+        NyarErrorKind::Overflow { operation: operation.to_string(), target: target.to_string() }.into()
+    }
+
+    /// 断言失败, `operands` 为比较断言两侧的值
+    pub fn assertion(expression: impl ToString, message: Option<String>, operands: Option<(String, String)>) -> NyarError {
+        NyarErrorKind::Assertion { expression: expression.to_string(), message, operands }.into()
+    }
+
     pub fn use_after_free(index: usize) -> NyarError {
         NyarErrorKind::UseAfterFree { address: index }.into()
     }
+    /// 设置错误所在的源码区间
+    pub fn with_span(mut self, span: Range<usize>) -> NyarError {
+        self.span = span;
+        self
+    }
+    /// 设置错误所在的文件
+    pub fn with_file(mut self, file: ArcStr) -> NyarError {
+        self.file = file;
+        self
+    }
+    /// 错误类型
+    pub fn kind(&self) -> &NyarErrorKind {
+        &self.kind
+    }
+    /// 错误所在的源码区间
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+    /// 错误所在的文件
+    pub fn file(&self) -> &ArcStr {
+        &self.file
+    }
 }
@@ -1,6 +1,6 @@
 mod errors;
 
-pub use crate::errors::{NyarError, NyarErrorKind, Result};
+pub use crate::errors::{HeapErrorKind, NyarError, NyarErrorKind, Result};
 pub use arcstr::ArcStr;
 pub use indexmap::IndexMap;
 pub use num::BigInt;
@@ -0,0 +1,14 @@
+//! 属性标注模块
+
+use crate::ast::{Expression, Span};
+
+/// 属性标注, 如 `#main` 或 `#ffi("wasi_snapshot_preview1", "fd_write")`
+#[derive(Debug, Clone)]
+pub struct Attribute {
+    /// 属性名
+    pub name: String,
+    /// 参数列表
+    pub arguments: Vec<Expression>,
+    /// 源码区间
+    pub span: Span,
+}
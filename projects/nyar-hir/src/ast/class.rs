@@ -0,0 +1,58 @@
+//! 类定义模块
+
+use crate::ast::{Attribute, Expression, FunctionDefinition, Span};
+
+/// 类定义
+#[derive(Debug, Clone)]
+pub struct ClassDefinition {
+    /// 属性标注
+    pub attributes: Vec<Attribute>,
+    /// 类名
+    pub name: String,
+    /// 泛型参数
+    pub generics: Vec<String>,
+    /// 父类（可选）
+    pub parent: Option<String>,
+    /// 实现的特征
+    pub traits: Vec<String>,
+    /// 属性
+    pub properties: Vec<PropertyDefinition>,
+    /// 方法
+    pub methods: Vec<FunctionDefinition>,
+    /// 源码区间
+    pub span: Span,
+}
+
+/// 属性定义
+#[derive(Debug, Clone)]
+pub struct PropertyDefinition {
+    /// 属性名
+    pub name: String,
+    /// 类型注解（可选）
+    pub type_annotation: Option<String>,
+    /// 默认值（可选）
+    pub default: Option<Expression>,
+    /// 源码区间
+    pub span: Span,
+}
+
+/// 类的实例化 `new Point { y: 1 }`, 未给出的属性取类及其父类的默认值
+#[derive(Debug, Clone)]
+pub struct NewExpression {
+    /// 类型, 可以带有泛型参数, 如 `Box<int>`
+    pub class: String,
+    /// 属性的初始值
+    pub properties: Vec<(String, Expression)>,
+}
+
+impl NewExpression {
+    /// 不含泛型参数的类名
+    pub fn class_name(&self) -> &str {
+        type_name(&self.class)
+    }
+}
+
+/// 去掉类型注解中的泛型参数, `Box<int>` 的类名为 `Box`
+pub(crate) fn type_name(annotation: &str) -> &str {
+    annotation.split('<').next().unwrap_or(annotation).trim()
+}
@@ -0,0 +1,81 @@
+//! 枚举定义模块
+
+use crate::ast::{Expression, Parameter, Span};
+use nyar_lir::{NyarValue, values::NyarVariant};
+
+/// 枚举定义
+#[derive(Debug, Clone)]
+pub struct EnumDefinition {
+    /// 枚举名
+    pub name: String,
+    /// 泛型参数, 如 `enum Option<T>` 中的 `T`
+    pub generics: Vec<String>,
+    /// 变体
+    pub variants: Vec<EnumVariant>,
+    /// 源码区间
+    pub span: Span,
+}
+
+/// 枚举变体
+#[derive(Debug, Clone)]
+pub struct EnumVariant {
+    /// 变体名
+    pub name: String,
+    /// 变体的字段
+    pub fields: VariantFields,
+    /// 变体的值（可选）, 缺省时为带有标签的变体值
+    pub value: Option<Expression>,
+    /// 源码区间
+    pub span: Span,
+}
+
+/// 变体的字段
+#[derive(Debug, Clone, Default)]
+pub enum VariantFields {
+    /// 没有字段, 如 `None`
+    #[default]
+    Unit,
+    /// 位置字段的类型, 如 `Some(T)`
+    Positional(Vec<String>),
+    /// 命名字段, 如 `Rect { width: int, height: int }`
+    Named(Vec<Parameter>),
+}
+
+impl VariantFields {
+    /// 字段名, 位置字段以 `0`, `1`, ... 命名
+    pub fn names(&self) -> Vec<String> {
+        match self {
+            VariantFields::Unit => vec![],
+            VariantFields::Positional(types) => (0..types.len()).map(|index| index.to_string()).collect(),
+            VariantFields::Named(fields) => fields.iter().map(|field| field.name.clone()).collect(),
+        }
+    }
+    /// 字段名与类型注解
+    pub fn types(&self) -> Vec<(String, Option<String>)> {
+        match self {
+            VariantFields::Unit => vec![],
+            VariantFields::Positional(types) => {
+                types.iter().enumerate().map(|(index, t)| (index.to_string(), Some(t.clone()))).collect()
+            }
+            VariantFields::Named(fields) => {
+                fields.iter().map(|field| (field.name.clone(), field.type_annotation.clone())).collect()
+            }
+        }
+    }
+}
+
+impl EnumVariant {
+    /// 变体是否带有字段
+    pub fn has_fields(&self) -> bool {
+        !matches!(self.fields, VariantFields::Unit)
+    }
+}
+
+impl EnumDefinition {
+    /// 序号为 `tag` 的无字段变体的值
+    pub fn unit_variant(&self, tag: usize) -> NyarValue {
+        let variant =
+            NyarVariant { enumeration: self.name.clone(), name: self.variants[tag].name.clone(), tag, fields: vec![] };
+        NyarValue::Variant(Box::new(variant))
+    }
+}
@@ -1,13 +1,25 @@
 //! 表达式模块，定义了各种表达式类型
 
-use crate::ast::AstNode;
+use crate::{
+    NyarCompiler,
+    ast::{MatchExpression, NewExpression, Span},
+};
+use indexmap::IndexMap;
 use nyar_error::NyarError;
-use nyar_lir::{Gc, NyarFunction, NyarValue};
-use std::collections::HashMap;
+use nyar_lir::{BinaryOperator, NyarValue, UnaryOperator};
 
 /// 表达式
 #[derive(Debug, Clone)]
-pub enum Expression {
+pub struct Expression {
+    /// 表达式类型
+    pub kind: ExpressionKind,
+    /// 源码区间
+    pub span: Span,
+}
+
+/// 表达式类型
+#[derive(Debug, Clone)]
+pub enum ExpressionKind {
     /// 字面量
     Literal(Literal),
     /// 变量引用
@@ -28,24 +40,151 @@ pub enum Expression {
     IndexAccess(Box<IndexAccessExpression>),
     /// 效应操作
     Effect(Box<EffectExpression>),
+    /// 模式匹配
+    Match(Box<MatchExpression>),
+    /// 类的实例化
+    New(Box<NewExpression>),
+    /// 字符串插值
+    Interpolation(Box<InterpolationExpression>),
+}
+
+impl Expression {
+    /// 创建一个表达式
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl From<ExpressionKind> for Expression {
+    fn from(kind: ExpressionKind) -> Self {
+        Self { kind, span: Default::default() }
+    }
 }
 
-impl AstNode for Expression {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        match self {
-            Expression::Literal(literal) => literal.to_lir(),
-            Expression::Variable(name) => {
-                // 变量引用需要在运行时解析
-                Err(NyarError::new(format!("变量 '{}' 需要在运行时解析", name)))
+impl Expression {
+    /// 在编译期求值, 表达式的值依赖运行时状态时返回 `None`
+    ///
+    /// 只折叠标量上的纯运算, 求值出错 (如除以零) 时返回带有出错子表达式区间的错误。
+    pub(crate) fn fold(&self, compiler: &NyarCompiler) -> Result<Option<NyarValue>, NyarError> {
+        let value = match &self.kind {
+            ExpressionKind::Literal(Literal::Null) => NyarValue::Null,
+            ExpressionKind::Literal(Literal::Boolean(value)) => NyarValue::Boolean(*value),
+            ExpressionKind::Literal(Literal::Integer(value)) => NyarValue::from(*value),
+            ExpressionKind::Literal(Literal::String(value)) => NyarValue::from(value.as_str()),
+            ExpressionKind::Variable(name) => match compiler.constant(name) {
+                Some(value) => value.clone(),
+                None => return Ok(None),
+            },
+            ExpressionKind::Binary(expr) => {
+                let operator = match expr.operator.parse::<BinaryOperator>() {
+                    Ok(operator) => operator,
+                    Err(_) => return Ok(None),
+                };
+                let left = match expr.left.fold(compiler)? {
+                    Some(left) => left,
+                    None => return Ok(None),
+                };
+                match operator {
+                    // 短路求值, 结果为决定结果的那个操作数
+                    BinaryOperator::And if !left.is_truthy() => left,
+                    BinaryOperator::Or if left.is_truthy() => left,
+                    BinaryOperator::And | BinaryOperator::Or => return expr.right.fold(compiler),
+                    _ => match expr.right.fold(compiler)? {
+                        Some(right) => operator.evaluate(&left, &right).map_err(|e| self.span.locate(e))?,
+                        None => return Ok(None),
+                    },
+                }
+            }
+            ExpressionKind::Unary(expr) => {
+                let operator = match expr.operator.parse::<UnaryOperator>() {
+                    Ok(operator) => operator,
+                    Err(_) => return Ok(None),
+                };
+                match expr.operand.fold(compiler)? {
+                    Some(operand) => operator.evaluate(&operand).map_err(|e| self.span.locate(e))?,
+                    None => return Ok(None),
+                }
             }
-            Expression::Binary(expr) => expr.to_lir(),
-            Expression::Unary(expr) => expr.to_lir(),
-            Expression::Call(expr) => expr.to_lir(),
-            Expression::Lambda(expr) => expr.to_lir(),
-            Expression::Conditional(expr) => expr.to_lir(),
-            Expression::MemberAccess(expr) => expr.to_lir(),
-            Expression::IndexAccess(expr) => expr.to_lir(),
-            Expression::Effect(expr) => expr.to_lir(),
+            ExpressionKind::Conditional(expr) => match expr.condition.fold(compiler)? {
+                Some(condition) if condition.is_truthy() => return expr.then_branch.fold(compiler),
+                Some(_) => match &expr.else_branch {
+                    Some(e) => return e.fold(compiler),
+                    None => NyarValue::Null,
+                },
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        Ok(Some(value))
+    }
+
+    /// 收集表达式中引用的、未被 `bound` 绑定的变量, 按首次出现的顺序
+    pub(crate) fn free_variables(&self, bound: &mut Vec<String>, output: &mut Vec<String>) {
+        match &self.kind {
+            ExpressionKind::Literal(Literal::List(items)) => items.iter().for_each(|e| e.free_variables(bound, output)),
+            ExpressionKind::Literal(Literal::Object(items)) => items.values().for_each(|e| e.free_variables(bound, output)),
+            ExpressionKind::Literal(_) => {}
+            ExpressionKind::Variable(name) => {
+                if !bound.contains(name) && !output.contains(name) {
+                    output.push(name.clone())
+                }
+            }
+            ExpressionKind::Binary(expr) => {
+                expr.left.free_variables(bound, output);
+                expr.right.free_variables(bound, output);
+            }
+            ExpressionKind::Unary(expr) => expr.operand.free_variables(bound, output),
+            ExpressionKind::Call(expr) => {
+                expr.callee.free_variables(bound, output);
+                expr.arguments.iter().for_each(|e| e.free_variables(bound, output));
+            }
+            ExpressionKind::Lambda(expr) => {
+                let depth = bound.len();
+                bound.extend(expr.parameters.iter().cloned());
+                expr.body.free_variables(bound, output);
+                bound.truncate(depth);
+            }
+            ExpressionKind::Conditional(expr) => {
+                expr.condition.free_variables(bound, output);
+                expr.then_branch.free_variables(bound, output);
+                if let Some(e) = &expr.else_branch {
+                    e.free_variables(bound, output)
+                }
+            }
+            ExpressionKind::MemberAccess(expr) => expr.object.free_variables(bound, output),
+            ExpressionKind::IndexAccess(expr) => {
+                expr.object.free_variables(bound, output);
+                expr.index.free_variables(bound, output);
+            }
+            ExpressionKind::Effect(expr) => expr.arguments.iter().for_each(|e| e.free_variables(bound, output)),
+            ExpressionKind::Match(expr) => {
+                expr.subject.free_variables(bound, output);
+                for arm in &expr.arms {
+                    let depth = bound.len();
+                    arm.pattern.bindings(bound);
+                    // 枚举与类在运行时按名称查找
+                    let mut names = vec![];
+                    arm.pattern.type_names(&mut names);
+                    for name in names {
+                        if !bound.contains(&name) && !output.contains(&name) {
+                            output.push(name)
+                        }
+                    }
+                    if let Some(guard) = &arm.guard {
+                        guard.free_variables(bound, output)
+                    }
+                    arm.body.free_variables(bound, output);
+                    bound.truncate(depth);
+                }
+            }
+            ExpressionKind::New(expr) => {
+                let name = expr.class_name();
+                if !bound.iter().any(|bound| bound == name) && !output.iter().any(|output| output == name) {
+                    output.push(name.to_string())
+                }
+                expr.properties.iter().for_each(|(_, value)| value.free_variables(bound, output))
+            }
+            ExpressionKind::Interpolation(expr) => expr.expressions().for_each(|e| e.free_variables(bound, output)),
         }
     }
 }
@@ -64,33 +203,32 @@ pub enum Literal {
     /// 列表
     List(Vec<Expression>),
     /// 对象
-    Object(HashMap<String, Expression>),
-}
-
-impl AstNode for Literal {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        match self {
-            Literal::Null => Ok(NyarValue::Null),
-            Literal::Boolean(value) => Ok(NyarValue::Boolean(*value)),
-            Literal::Integer(value) => Ok(NyarValue::Integer(Box::new(value.clone().into()))),
-            Literal::String(value) => Ok(NyarValue::String(Box::new(value.clone()))),
-            Literal::List(items) => {
-                let mut result = Vec::new();
-                for item in items {
-                    let value = item.to_lir()?;
-                    result.push(Gc::new(value));
-                }
-                Ok(NyarValue::List(result))
-            }
-            Literal::Object(properties) => {
-                let mut result = indexmap::IndexMap::new();
-                for (key, value) in properties {
-                    let lir_value = value.to_lir()?;
-                    result.insert(key.clone(), Gc::new(lir_value));
-                }
-                Ok(NyarValue::Object(result))
-            }
-        }
+    Object(IndexMap<String, Expression>),
+}
+
+/// 字符串插值 `"x = ${x}"`, 各部分格式化后依次连接
+#[derive(Debug, Clone)]
+pub struct InterpolationExpression {
+    /// 按源码顺序排列的各部分
+    pub parts: Vec<InterpolationPart>,
+}
+
+/// 字符串插值的组成部分
+#[derive(Debug, Clone)]
+pub enum InterpolationPart {
+    /// 原样输出的文本, 已处理转义
+    Text(String),
+    /// 插入的表达式, 以 `to_string` 格式化
+    Expression(Expression),
+}
+
+impl InterpolationExpression {
+    /// 插入的表达式
+    pub fn expressions(&self) -> impl Iterator<Item = &Expression> {
+        self.parts.iter().filter_map(|part| match part {
+            InterpolationPart::Text(_) => None,
+            InterpolationPart::Expression(expression) => Some(expression),
+        })
     }
 }
 
@@ -105,10 +243,10 @@ pub struct BinaryExpression {
     pub right: Expression,
 }
 
-impl AstNode for BinaryExpression {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 二元表达式需要在运行时计算
-        Err(NyarError::new("二元表达式需要在运行时计算".to_string()))
+impl BinaryExpression {
+    /// 是否为类型检查 `value is Type` 或 `value implements Trait`
+    pub fn is_type_test(&self) -> bool {
+        matches!(self.operator.as_str(), "is" | "implements")
     }
 }
 
@@ -121,13 +259,6 @@ pub struct UnaryExpression {
     pub operand: Expression,
 }
 
-impl AstNode for UnaryExpression {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 一元表达式需要在运行时计算
-        Err(NyarError::new("一元表达式需要在运行时计算".to_string()))
-    }
-}
-
 /// 函数调用表达式
 #[derive(Debug, Clone)]
 pub struct CallExpression {
@@ -137,13 +268,6 @@ pub struct CallExpression {
     pub arguments: Vec<Expression>,
 }
 
-impl AstNode for CallExpression {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 函数调用需要在运行时执行
-        Err(NyarError::new("函数调用需要在运行时执行".to_string()))
-    }
-}
-
 /// Lambda表达式
 #[derive(Debug, Clone)]
 pub struct LambdaExpression {
@@ -153,20 +277,6 @@ pub struct LambdaExpression {
     pub body: Expression,
 }
 
-impl AstNode for LambdaExpression {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 创建一个函数对象
-        let function = NyarFunction {
-            name: None, // Lambda是匿名函数
-            parameters: self.parameters.clone(),
-            body: Vec::new(), // 需要编译器将表达式转换为指令
-            environment: Gc::new(HashMap::new()), // 空环境，需要在运行时捕获
-        };
-        
-        Ok(NyarValue::Function(Box::new(function)))
-    }
-}
-
 /// 条件表达式
 #[derive(Debug, Clone)]
 pub struct ConditionalExpression {
@@ -178,13 +288,6 @@ pub struct ConditionalExpression {
     pub else_branch: Option<Expression>,
 }
 
-impl AstNode for ConditionalExpression {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 条件表达式需要在运行时计算
-        Err(NyarError::new("条件表达式需要在运行时计算".to_string()))
-    }
-}
-
 /// 成员访问表达式
 #[derive(Debug, Clone)]
 pub struct MemberAccessExpression {
@@ -194,13 +297,6 @@ pub struct MemberAccessExpression {
     pub member: String,
 }
 
-impl AstNode for MemberAccessExpression {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 成员访问需要在运行时解析
-        Err(NyarError::new("成员访问需要在运行时解析".to_string()))
-    }
-}
-
 /// 索引访问表达式
 #[derive(Debug, Clone)]
 pub struct IndexAccessExpression {
@@ -210,13 +306,6 @@ pub struct IndexAccessExpression {
     pub index: Expression,
 }
 
-impl AstNode for IndexAccessExpression {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 索引访问需要在运行时解析
-        Err(NyarError::new("索引访问需要在运行时解析".to_string()))
-    }
-}
-
 /// 效应表达式
 #[derive(Debug, Clone)]
 pub struct EffectExpression {
@@ -225,10 +314,3 @@ pub struct EffectExpression {
     /// 效应参数
     pub arguments: Vec<Expression>,
 }
-
-impl AstNode for EffectExpression {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 效应操作需要在运行时处理
-        Err(NyarError::new("效应操作需要在运行时处理".to_string()))
-    }
-}
\ No newline at end of file
@@ -0,0 +1,47 @@
+//! 函数定义模块
+
+use crate::ast::{Attribute, Span, Statement};
+
+/// 函数定义
+#[derive(Debug, Clone)]
+pub struct FunctionDefinition {
+    /// 属性标注, 如 `#main`
+    pub attributes: Vec<Attribute>,
+    /// 函数名
+    pub name: String,
+    /// 泛型参数, 如 `function first<T>(xs: List<T>) -> T` 中的 `T`
+    pub generics: Vec<String>,
+    /// 参数列表
+    pub parameters: Vec<Parameter>,
+    /// 返回类型注解（可选）
+    pub return_type: Option<String>,
+    /// 函数体
+    pub body: Vec<Statement>,
+    /// 源码区间
+    pub span: Span,
+}
+
+/// 函数参数
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    /// 参数名
+    pub name: String,
+    /// 类型注解（可选）
+    pub type_annotation: Option<String>,
+    /// 源码区间
+    pub span: Span,
+}
+
+impl Parameter {
+    /// 创建一个没有类型注解的参数
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), type_annotation: None, span: Default::default() }
+    }
+}
+
+impl FunctionDefinition {
+    /// 参数名列表
+    pub fn parameter_names(&self) -> Vec<String> {
+        self.parameters.iter().map(|p| p.name.clone()).collect()
+    }
+}
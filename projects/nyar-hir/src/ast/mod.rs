@@ -1,27 +1,85 @@
 //! AST模块，定义了抽象语法树的结构
 
-use nyar_error::NyarError;
-use nyar_lir::{Gc, NyarValue};
-use std::collections::HashMap;
+use nyar_error::{ArcStr, NyarError};
+use std::ops::Range;
 
+mod attribute;
 mod class;
 mod enum_def;
 mod expression;
 mod function;
+mod pattern;
 mod statement;
+mod structure;
 mod trait_def;
 
-pub use class::ClassDefinition;
-pub use enum_def::EnumDefinition;
-pub use expression::Expression;
-pub use function::FunctionDefinition;
-pub use statement::Statement;
-pub use trait_def::TraitDefinition;
+pub use self::{
+    attribute::Attribute,
+    class::{ClassDefinition, NewExpression, PropertyDefinition},
+    enum_def::{EnumDefinition, EnumVariant, VariantFields},
+    expression::{
+        BinaryExpression, CallExpression, ConditionalExpression, EffectExpression, Expression, ExpressionKind,
+        IndexAccessExpression, InterpolationExpression, InterpolationPart, LambdaExpression, Literal, MemberAccessExpression,
+        UnaryExpression,
+    },
+    function::{FunctionDefinition, Parameter},
+    pattern::{MatchArm, MatchExpression, Pattern},
+    statement::{
+        Assignment, CatchBlock, EffectHandlerDefinition, ExportStatement, IfStatement, ImportStatement, LoopStatement,
+        Statement, StatementKind, TryCatchStatement, VariableDeclaration,
+    },
+    structure::{StructureDefinition, StructureField},
+    trait_def::{TraitDefinition, TraitMethod},
+};
+pub(crate) use self::class::type_name;
 
-/// AST节点特征，所有AST节点都应实现此特征
-pub trait AstNode {
-    /// 将AST节点转换为LIR值
-    fn to_lir(&self) -> Result<NyarValue, NyarError>;
+/// 源码位置, 由源文件与文件内的字节区间组成
+///
+/// 导入的模块与主程序的节点来自不同的文件, 因此区间必须与文件一同记录.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// 源文件, 与 [`Program::file`] 相同
+    pub file: ArcStr,
+    /// 文件内的字节区间
+    pub range: Range<usize>,
+}
+
+impl Span {
+    /// 创建位于 `file` 中 `range` 处的位置
+    pub fn new(file: ArcStr, range: Range<usize>) -> Self {
+        Self { file, range }
+    }
+
+    /// 起始字节
+    pub fn start(&self) -> usize {
+        self.range.start
+    }
+
+    /// 结束字节
+    pub fn end(&self) -> usize {
+        self.range.end
+    }
+
+    /// 区间是否为空, 合成的节点没有位置
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+
+    /// 从本位置开始, 到 `other` 结束的位置
+    pub fn to(&self, other: &Span) -> Span {
+        Span { file: self.file.clone(), range: self.range.start..other.range.end }
+    }
+
+    /// 把错误定位到本位置
+    pub fn locate(&self, error: NyarError) -> NyarError {
+        error.with_span(self.range.clone()).with_file(self.file.clone())
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Self { file: ArcStr::default(), range }
+    }
 }
 
 /// 程序，由多个语句组成
@@ -29,14 +87,18 @@ pub trait AstNode {
 pub struct Program {
     /// 语句列表
     pub statements: Vec<Statement>,
+    /// 源码位置
+    pub span: Span,
+    /// 源文件
+    pub file: ArcStr,
+    /// 源码, 断言失败时据此显示表达式
+    pub source: ArcStr,
 }
 
 impl Program {
     /// 创建一个新的程序
     pub fn new() -> Self {
-        Self {
-            statements: Vec::new(),
-        }
+        Self { statements: Vec::new(), span: Default::default(), file: Default::default(), source: Default::default() }
     }
 
     /// 添加语句
@@ -50,17 +112,3 @@ impl Default for Program {
         Self::new()
     }
 }
-
-impl AstNode for Program {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 将程序转换为对象，包含所有语句的执行结果
-        let mut results = Vec::new();
-        
-        for statement in &self.statements {
-            let result = statement.to_lir()?;
-            results.push(Gc::new(result));
-        }
-        
-        Ok(NyarValue::List(results))
-    }
-}
\ No newline at end of file
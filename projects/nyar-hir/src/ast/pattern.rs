@@ -0,0 +1,103 @@
+//! 模式匹配模块
+
+use crate::ast::{Expression, Literal, Span};
+
+/// 匹配表达式, 依次尝试各分支, 值为第一个匹配的分支的值
+#[derive(Debug, Clone)]
+pub struct MatchExpression {
+    /// 被匹配的值
+    pub subject: Expression,
+    /// 分支
+    pub arms: Vec<MatchArm>,
+}
+
+/// 匹配分支, 如 `[x, ..] if x > 0 => x`
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    /// 模式
+    pub pattern: Pattern,
+    /// 守卫条件（可选）
+    pub guard: Option<Expression>,
+    /// 分支的值
+    pub body: Expression,
+    /// 源码区间
+    pub span: Span,
+}
+
+/// 模式
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// 通配符 `_`
+    Wildcard,
+    /// 空值、布尔值、整数或字符串字面量
+    Literal(Literal),
+    /// 绑定到变量
+    Binding(String),
+    /// 列表 `[a, b]`, `rest` 为真时为 `[a, b, ..]`, 匹配更长的列表
+    List {
+        /// 元素的模式
+        items: Vec<Pattern>,
+        /// 是否允许更多元素
+        rest: bool,
+    },
+    /// 对象 `{ x, y: 0 }`, 只检查列出的属性
+    Object(Vec<(String, Pattern)>),
+    /// 枚举变体 `Color.Red`, `Option.Some(x)` 或 `Shape.Rect { width, height: 0 }`
+    Variant {
+        /// 枚举名
+        enumeration: String,
+        /// 变体名
+        variant: String,
+        /// 字段的模式, 位置字段以 `0`, `1`, ... 命名
+        fields: Vec<(String, Pattern)>,
+    },
+    /// 类或其子类的实例 `Point { x, y: 0 }`
+    Class {
+        /// 类名
+        name: String,
+        /// 属性的模式
+        fields: Vec<(String, Pattern)>,
+    },
+}
+
+impl Pattern {
+    /// 是否匹配任何值
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Pattern::Wildcard | Pattern::Binding(_))
+    }
+    /// 位置字段的变体模式 `Option.Some(x)` 的字段数, 其余模式返回 `None`
+    pub fn arity(&self) -> Option<usize> {
+        match self {
+            Pattern::Variant { fields, .. }
+                if !fields.is_empty() && fields.iter().enumerate().all(|(index, (key, _))| *key == index.to_string()) =>
+            {
+                Some(fields.len())
+            }
+            _ => None,
+        }
+    }
+    /// 按出现顺序收集绑定的变量
+    pub fn bindings(&self, output: &mut Vec<String>) {
+        match self {
+            Pattern::Binding(name) => output.push(name.clone()),
+            Pattern::List { items, .. } => items.iter().for_each(|item| item.bindings(output)),
+            Pattern::Object(fields) | Pattern::Class { fields, .. } | Pattern::Variant { fields, .. } => {
+                fields.iter().for_each(|(_, field)| field.bindings(output))
+            }
+            Pattern::Wildcard | Pattern::Literal(_) => {}
+        }
+    }
+
+    /// 按出现顺序收集引用的枚举名与类名
+    pub fn type_names(&self, output: &mut Vec<String>) {
+        match self {
+            Pattern::Variant { enumeration: name, fields, .. } | Pattern::Class { name, fields } => {
+                output.push(name.clone());
+                fields.iter().for_each(|(_, field)| field.type_names(output))
+            }
+            Pattern::List { items, .. } => items.iter().for_each(|item| item.type_names(output)),
+            Pattern::Object(fields) => fields.iter().for_each(|(_, field)| field.type_names(output)),
+            Pattern::Wildcard | Pattern::Literal(_) | Pattern::Binding(_) => {}
+        }
+    }
+}
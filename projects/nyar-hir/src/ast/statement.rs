@@ -1,12 +1,19 @@
 //! 语句模块，定义了各种语句类型
 
-use crate::ast::{AstNode, Expression};
-use nyar_error::NyarError;
-use nyar_lir::{Gc, NyarValue};
+use crate::ast::{Expression, Span};
 
 /// 语句
 #[derive(Debug, Clone)]
-pub enum Statement {
+pub struct Statement {
+    /// 语句类型
+    pub kind: StatementKind,
+    /// 源码区间
+    pub span: Span,
+}
+
+/// 语句类型
+#[derive(Debug, Clone)]
+pub enum StatementKind {
     /// 表达式语句
     Expression(Expression),
     /// 变量声明
@@ -17,6 +24,10 @@ pub enum Statement {
     If(IfStatement),
     /// 循环语句
     Loop(LoopStatement),
+    /// 跳出循环
+    Break,
+    /// 继续循环
+    Continue,
     /// 返回语句
     Return(Option<Expression>),
     /// 块语句
@@ -29,6 +40,8 @@ pub enum Statement {
     TraitDeclaration(crate::ast::TraitDefinition),
     /// 枚举定义
     EnumDeclaration(crate::ast::EnumDefinition),
+    /// 结构体定义
+    StructureDeclaration(crate::ast::StructureDefinition),
     /// 效应处理器定义
     EffectHandler(EffectHandlerDefinition),
     /// 导入语句
@@ -43,40 +56,16 @@ pub enum Statement {
     Assert(Expression, Option<String>),
 }
 
-impl AstNode for Statement {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        match self {
-            Statement::Expression(expr) => expr.to_lir(),
-            Statement::VariableDeclaration(decl) => decl.to_lir(),
-            Statement::Assignment(assign) => assign.to_lir(),
-            Statement::If(if_stmt) => if_stmt.to_lir(),
-            Statement::Loop(loop_stmt) => loop_stmt.to_lir(),
-            Statement::Return(expr) => {
-                if let Some(e) = expr {
-                    e.to_lir()
-                } else {
-                    Ok(NyarValue::Null)
-                }
-            },
-            Statement::Block(statements) => {
-                let mut results = Vec::new();
-                for stmt in statements {
-                    let result = stmt.to_lir()?;
-                    results.push(Gc::new(result));
-                }
-                Ok(NyarValue::List(results))
-            },
-            Statement::FunctionDeclaration(func) => func.to_lir(),
-            Statement::ClassDeclaration(class) => class.to_lir(),
-            Statement::TraitDeclaration(trait_def) => trait_def.to_lir(),
-            Statement::EnumDeclaration(enum_def) => enum_def.to_lir(),
-            Statement::EffectHandler(handler) => handler.to_lir(),
-            Statement::Import(import) => import.to_lir(),
-            Statement::Export(export) => export.to_lir(),
-            Statement::TryCatch(try_catch) => try_catch.to_lir(),
-            Statement::Throw(expr) => expr.to_lir(),
-            Statement::Assert(expr, _) => expr.to_lir(),
-        }
+impl Statement {
+    /// 创建一个语句
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl From<StatementKind> for Statement {
+    fn from(kind: StatementKind) -> Self {
+        Self { kind, span: Default::default() }
     }
 }
 
@@ -93,16 +82,6 @@ pub struct VariableDeclaration {
     pub is_constant: bool,
 }
 
-impl AstNode for VariableDeclaration {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        if let Some(initializer) = &self.initializer {
-            initializer.to_lir()
-        } else {
-            Ok(NyarValue::Null)
-        }
-    }
-}
-
 /// 赋值语句
 #[derive(Debug, Clone)]
 pub struct Assignment {
@@ -112,13 +91,6 @@ pub struct Assignment {
     pub value: Expression,
 }
 
-impl AstNode for Assignment {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 赋值语句需要在运行时执行
-        self.value.to_lir()
-    }
-}
-
 /// 条件语句
 #[derive(Debug, Clone)]
 pub struct IfStatement {
@@ -130,13 +102,6 @@ pub struct IfStatement {
     pub else_branch: Option<Vec<Statement>>,
 }
 
-impl AstNode for IfStatement {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 条件语句需要在运行时执行
-        Err(NyarError::new("条件语句需要在运行时执行".to_string()))
-    }
-}
-
 /// 循环语句
 #[derive(Debug, Clone)]
 pub enum LoopStatement {
@@ -174,13 +139,6 @@ pub enum LoopStatement {
     },
 }
 
-impl AstNode for LoopStatement {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 循环语句需要在运行时执行
-        Err(NyarError::new("循环语句需要在运行时执行".to_string()))
-    }
-}
-
 /// 效应处理器定义
 #[derive(Debug, Clone)]
 pub struct EffectHandlerDefinition {
@@ -190,13 +148,6 @@ pub struct EffectHandlerDefinition {
     pub handler: crate::ast::FunctionDefinition,
 }
 
-impl AstNode for EffectHandlerDefinition {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 效应处理器需要在运行时注册
-        self.handler.to_lir()
-    }
-}
-
 /// 导入语句
 #[derive(Debug, Clone)]
 pub struct ImportStatement {
@@ -210,10 +161,13 @@ pub struct ImportStatement {
     pub alias: Option<String>,
 }
 
-impl AstNode for ImportStatement {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 导入语句需要在运行时处理
-        Ok(NyarValue::Null)
+impl ImportStatement {
+    /// 未指定别名时模块绑定的名称, `a.b` 为 `b`, `"lib/util.vk"` 为 `util`
+    pub fn binding_name(&self) -> &str {
+        match self.path.rsplit_once(['/', '\\']) {
+            Some((_, file)) => file.split('.').next().unwrap_or(file),
+            None => self.path.rsplit('.').next().unwrap_or(&self.path),
+        }
     }
 }
 
@@ -224,10 +178,18 @@ pub struct ExportStatement {
     pub declaration: Box<Statement>,
 }
 
-impl AstNode for ExportStatement {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 导出语句需要在运行时处理
-        self.declaration.to_lir()
+impl ExportStatement {
+    /// 导出的名称, 不绑定名称的声明返回 `None`
+    pub fn name(&self) -> Option<&str> {
+        match &self.declaration.kind {
+            StatementKind::VariableDeclaration(declaration) => Some(&declaration.name),
+            StatementKind::FunctionDeclaration(function) => Some(&function.name),
+            StatementKind::ClassDeclaration(class) => Some(&class.name),
+            StatementKind::TraitDeclaration(definition) => Some(&definition.name),
+            StatementKind::EnumDeclaration(definition) => Some(&definition.name),
+            StatementKind::StructureDeclaration(definition) => Some(&definition.name),
+            _ => None,
+        }
     }
 }
 
@@ -251,11 +213,6 @@ pub struct CatchBlock {
     pub error_variable: String,
     /// 处理块
     pub handler: Vec<Statement>,
+    /// 源码区间
+    pub span: Span,
 }
-
-impl AstNode for TryCatchStatement {
-    fn to_lir(&self) -> Result<NyarValue, NyarError> {
-        // 尝试-捕获语句需要在运行时执行
-        Err(NyarError::new("尝试-捕获语句需要在运行时执行".to_string()))
-    }
-}
\ No newline at end of file
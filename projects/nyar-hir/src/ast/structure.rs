@@ -0,0 +1,46 @@
+//! 结构体定义模块
+
+use crate::ast::Span;
+use nyar_error::NyarError;
+use nyar_lir::values::{FieldType, NyarStructure};
+
+/// 结构体定义, 字段具有固定的类型与声明顺序, 值按值传递
+#[derive(Debug, Clone)]
+pub struct StructureDefinition {
+    /// 结构体名
+    pub name: String,
+    /// 字段
+    pub fields: Vec<StructureField>,
+    /// 源码区间
+    pub span: Span,
+}
+
+/// 结构体字段
+#[derive(Debug, Clone)]
+pub struct StructureField {
+    /// 字段名
+    pub name: String,
+    /// 字段类型, 如 `u32`
+    pub field_type: String,
+    /// 源码区间
+    pub span: Span,
+}
+
+impl StructureDefinition {
+    /// 结构体的布局, 字段类型不受支持时报错
+    pub fn layout(&self) -> Result<NyarStructure, NyarError> {
+        let mut fields = Vec::with_capacity(self.fields.len());
+        for field in &self.fields {
+            let field_type = match field.field_type.parse::<FieldType>() {
+                Ok(field_type) => field_type,
+                Err(_) => {
+                    let message =
+                        format!("unsupported field type `{}`, expected a fixed-width integer or `f64`", field.field_type);
+                    return Err(field.span.locate(NyarError::type_error(message, field.span.range.clone())));
+                }
+            };
+            fields.push((field.name.clone(), field_type));
+        }
+        Ok(NyarStructure { name: self.name.clone(), fields })
+    }
+}
@@ -0,0 +1,29 @@
+//! 特征定义模块
+
+use crate::ast::{Parameter, Span, Statement};
+
+/// 特征定义
+#[derive(Debug, Clone)]
+pub struct TraitDefinition {
+    /// 特征名
+    pub name: String,
+    /// 方法签名
+    pub methods: Vec<TraitMethod>,
+    /// 源码区间
+    pub span: Span,
+}
+
+/// 特征方法
+#[derive(Debug, Clone)]
+pub struct TraitMethod {
+    /// 方法名
+    pub name: String,
+    /// 参数列表
+    pub parameters: Vec<Parameter>,
+    /// 返回类型注解（可选）
+    pub return_type: Option<String>,
+    /// 默认实现（可选）
+    pub body: Option<Vec<Statement>>,
+    /// 源码区间
+    pub span: Span,
+}
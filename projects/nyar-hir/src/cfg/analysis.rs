@@ -0,0 +1,386 @@
+//! 控制流图上的数据流分析
+//!
+//! 只有函数内部的变量参与分析, 外层作用域的变量可能被调用的函数修改。
+
+use super::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 指令在函数中的位置, `index` 等于块内指令数时表示终结指令
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Location {
+    /// 基本块
+    pub block: BlockId,
+    /// 块内下标
+    pub index: usize,
+}
+
+/// 变量的一次定义
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Definition {
+    /// 被定义的变量
+    pub variable: Variable,
+    /// 定义所在的位置
+    pub location: Location,
+}
+
+/// 支配树
+#[derive(Debug, Clone)]
+pub struct Dominators {
+    /// 直接支配者, 入口指向自身, 不可达的块为 `None`
+    immediate: Vec<Option<BlockId>>,
+    /// 可达块的逆后序
+    order: Vec<BlockId>,
+}
+
+impl Dominators {
+    /// 计算函数的支配树
+    pub fn new(function: &HirFunction) -> Self {
+        let order = reverse_postorder(function);
+        let mut position = vec![usize::MAX; function.blocks.len()];
+        for (index, block) in order.iter().enumerate() {
+            position[*block] = index;
+        }
+        let predecessors = function.predecessors();
+        let mut immediate = vec![None; function.blocks.len()];
+        if let Some(entry) = order.first() {
+            immediate[*entry] = Some(*entry);
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in order.iter().skip(1) {
+                let mut candidate = None;
+                for predecessor in &predecessors[*block] {
+                    if immediate[*predecessor].is_none() {
+                        continue;
+                    }
+                    candidate = Some(match candidate {
+                        None => *predecessor,
+                        Some(other) => intersect(&immediate, &position, *predecessor, other),
+                    });
+                }
+                if candidate.is_some() && immediate[*block] != candidate {
+                    immediate[*block] = candidate;
+                    changed = true;
+                }
+            }
+        }
+        Self { immediate, order }
+    }
+
+    /// 直接支配者, 入口与不可达的块没有直接支配者
+    pub fn immediate_dominator(&self, block: BlockId) -> Option<BlockId> {
+        self.immediate[block].filter(|dominator| *dominator != block)
+    }
+
+    /// 块是否从入口可达
+    pub fn is_reachable(&self, block: BlockId) -> bool {
+        self.immediate[block].is_some()
+    }
+
+    /// `dominator` 是否支配 `block`, 每个可达的块都支配自身
+    pub fn dominates(&self, dominator: BlockId, block: BlockId) -> bool {
+        if !self.is_reachable(block) {
+            return false;
+        }
+        let mut current = block;
+        loop {
+            if current == dominator {
+                return true;
+            }
+            match self.immediate_dominator(current) {
+                Some(next) => current = next,
+                None => return false,
+            }
+        }
+    }
+
+    /// 可达块的逆后序, 第一个为入口
+    pub fn reverse_postorder(&self) -> &[BlockId] {
+        &self.order
+    }
+
+    /// 支配树中的子节点
+    pub fn children(&self, block: BlockId) -> Vec<BlockId> {
+        self.order.iter().copied().filter(|child| self.immediate_dominator(*child) == Some(block)).collect()
+    }
+
+    /// 每个块的支配边界
+    pub fn frontiers(&self, function: &HirFunction) -> Vec<BTreeSet<BlockId>> {
+        let mut frontiers = vec![BTreeSet::new(); function.blocks.len()];
+        for (block, predecessors) in function.predecessors().into_iter().enumerate() {
+            let dominator = match self.immediate[block] {
+                Some(dominator) if predecessors.len() >= 2 => dominator,
+                _ => continue,
+            };
+            for predecessor in predecessors {
+                let mut runner = predecessor;
+                while self.is_reachable(runner) && runner != dominator {
+                    frontiers[runner].insert(block);
+                    match self.immediate_dominator(runner) {
+                        Some(next) => runner = next,
+                        None => break,
+                    }
+                }
+            }
+        }
+        frontiers
+    }
+}
+
+fn reverse_postorder(function: &HirFunction) -> Vec<BlockId> {
+    if function.blocks.is_empty() {
+        return vec![];
+    }
+    let mut visited = vec![false; function.blocks.len()];
+    let mut postorder = vec![];
+    // 显式栈, 避免深层控制流导致递归过深
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    while let Some((block, next)) = stack.pop() {
+        let successors = function.blocks[block].terminator.successors();
+        match successors.get(next) {
+            Some(successor) => {
+                stack.push((block, next + 1));
+                if !visited[*successor] {
+                    visited[*successor] = true;
+                    stack.push((*successor, 0));
+                }
+            }
+            None => postorder.push(block),
+        }
+    }
+    postorder.reverse();
+    postorder
+}
+
+fn intersect(immediate: &[Option<BlockId>], position: &[usize], mut a: BlockId, mut b: BlockId) -> BlockId {
+    while a != b {
+        while position[a] > position[b] {
+            a = immediate[a].unwrap_or(a);
+        }
+        while position[b] > position[a] {
+            b = immediate[b].unwrap_or(b);
+        }
+    }
+    a
+}
+
+/// φ 指令在前驱 `predecessor` 末尾读取的变量
+fn phi_uses(block: &BasicBlock, predecessor: BlockId) -> impl Iterator<Item = &Variable> {
+    block.instructions.iter().flat_map(move |instruction| match instruction {
+        HirInstruction::Phi { sources, .. } => sources
+            .iter()
+            .filter(|(source, _)| *source == predecessor)
+            .filter_map(|(_, value)| value.variable())
+            .collect::<Vec<_>>(),
+        _ => vec![],
+    })
+}
+
+/// 活跃变量分析
+#[derive(Debug, Clone)]
+pub struct Liveness {
+    live_in: Vec<BTreeSet<Variable>>,
+    live_out: Vec<BTreeSet<Variable>>,
+}
+
+impl Liveness {
+    /// 计算每个块入口与出口处的活跃变量
+    pub fn new(function: &HirFunction) -> Self {
+        let count = function.blocks.len();
+        let mut uses = vec![BTreeSet::new(); count];
+        let mut definitions = vec![BTreeSet::new(); count];
+        for (id, block) in function.blocks.iter().enumerate() {
+            let reads = block.instructions.iter().map(|instruction| (instruction.uses(), instruction.definition())).chain(
+                std::iter::once((
+                    block.terminator.operands().into_iter().filter_map(|operand| operand.variable().cloned()).collect(),
+                    None,
+                )),
+            );
+            for (read, written) in reads {
+                for variable in read.into_iter().filter(Variable::is_local) {
+                    if !definitions[id].contains(&variable) {
+                        uses[id].insert(variable);
+                    }
+                }
+                if let Some(variable) = written.filter(Variable::is_local) {
+                    definitions[id].insert(variable);
+                }
+            }
+        }
+        let mut live_in = vec![BTreeSet::new(); count];
+        let mut live_out = vec![BTreeSet::new(); count];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for id in (0..count).rev() {
+                let mut out = BTreeSet::new();
+                for successor in function.blocks[id].terminator.successors() {
+                    out.extend(live_in[successor].iter().cloned());
+                    out.extend(phi_uses(&function.blocks[successor], id).filter(|v| v.is_local()).cloned());
+                }
+                let mut input = uses[id].clone();
+                input.extend(out.iter().filter(|variable| !definitions[id].contains(*variable)).cloned());
+                if input != live_in[id] || out != live_out[id] {
+                    live_in[id] = input;
+                    live_out[id] = out;
+                    changed = true;
+                }
+            }
+        }
+        Self { live_in, live_out }
+    }
+
+    /// 块入口处活跃的变量
+    pub fn live_in(&self, block: BlockId) -> &BTreeSet<Variable> {
+        &self.live_in[block]
+    }
+
+    /// 块出口处活跃的变量
+    pub fn live_out(&self, block: BlockId) -> &BTreeSet<Variable> {
+        &self.live_out[block]
+    }
+}
+
+/// 到达定义分析
+#[derive(Debug, Clone)]
+pub struct ReachingDefinitions {
+    definitions: Vec<Definition>,
+    reach_in: Vec<BTreeSet<usize>>,
+    reach_out: Vec<BTreeSet<usize>>,
+}
+
+impl ReachingDefinitions {
+    /// 计算到达每个块入口与出口的定义
+    pub fn new(function: &HirFunction) -> Self {
+        let mut definitions = vec![];
+        for (block, basic) in function.blocks.iter().enumerate() {
+            for (index, instruction) in basic.instructions.iter().enumerate() {
+                if let Some(variable) = instruction.definition().filter(Variable::is_local) {
+                    definitions.push(Definition { variable, location: Location { block, index } })
+                }
+            }
+        }
+        let count = function.blocks.len();
+        let predecessors = function.predecessors();
+        let mut reach_in = vec![BTreeSet::new(); count];
+        let mut reach_out: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); count];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in 0..count {
+                let input: BTreeSet<usize> = predecessors[block].iter().flat_map(|p| reach_out[*p].iter().copied()).collect();
+                let output = transfer(&definitions, block, usize::MAX, &input);
+                if input != reach_in[block] || output != reach_out[block] {
+                    reach_in[block] = input;
+                    reach_out[block] = output;
+                    changed = true;
+                }
+            }
+        }
+        Self { definitions, reach_in, reach_out }
+    }
+
+    /// 函数中的全部定义
+    pub fn definitions(&self) -> &[Definition] {
+        &self.definitions
+    }
+
+    /// 到达块入口的定义
+    pub fn reach_in(&self, block: BlockId) -> Vec<&Definition> {
+        self.reach_in[block].iter().map(|index| &self.definitions[*index]).collect()
+    }
+
+    /// 到达块出口的定义
+    pub fn reach_out(&self, block: BlockId) -> Vec<&Definition> {
+        self.reach_out[block].iter().map(|index| &self.definitions[*index]).collect()
+    }
+
+    /// 在 `location` 处的指令执行之前到达的定义
+    pub fn reaching(&self, location: Location) -> Vec<&Definition> {
+        transfer(&self.definitions, location.block, location.index, &self.reach_in[location.block])
+            .into_iter()
+            .map(|index| &self.definitions[index])
+            .collect()
+    }
+}
+
+/// 依次应用块内下标小于 `until` 的定义
+fn transfer(definitions: &[Definition], block: BlockId, until: usize, input: &BTreeSet<usize>) -> BTreeSet<usize> {
+    let mut output = input.clone();
+    for (index, definition) in definitions.iter().enumerate() {
+        if definition.location.block == block && definition.location.index < until {
+            output.retain(|other| definitions[*other].variable != definition.variable);
+            output.insert(index);
+        }
+    }
+    output
+}
+
+/// 使用-定义链与定义-使用链
+#[derive(Debug, Clone, Default)]
+pub struct UseDef {
+    chains: BTreeMap<(Location, Variable), Vec<Location>>,
+}
+
+impl UseDef {
+    /// 为函数中每一次变量读取找到可能到达的定义
+    pub fn new(function: &HirFunction) -> Self {
+        let reaching = ReachingDefinitions::new(function);
+        let mut chains = BTreeMap::new();
+        let mut link = |location: Location, variable: &Variable, definitions: &[&Definition]| {
+            if !variable.is_local() {
+                return;
+            }
+            let sources = definitions.iter().filter(|d| &d.variable == variable).map(|d| d.location).collect();
+            chains.insert((location, variable.clone()), sources);
+        };
+        for (block, basic) in function.blocks.iter().enumerate() {
+            for (index, instruction) in basic.instructions.iter().enumerate() {
+                let location = Location { block, index };
+                match instruction {
+                    // φ 的来源在对应前驱的出口处读取
+                    HirInstruction::Phi { sources, .. } => {
+                        for (predecessor, value) in sources {
+                            if let Some(variable) = value.variable() {
+                                link(location, variable, &reaching.reach_out(*predecessor))
+                            }
+                        }
+                    }
+                    _ => {
+                        let definitions = reaching.reaching(location);
+                        for variable in instruction.uses() {
+                            link(location, &variable, &definitions)
+                        }
+                    }
+                }
+            }
+            let location = Location { block, index: basic.instructions.len() };
+            let definitions = reaching.reaching(location);
+            for operand in basic.terminator.operands() {
+                if let Some(variable) = operand.variable() {
+                    link(location, variable, &definitions)
+                }
+            }
+        }
+        Self { chains }
+    }
+
+    /// `location` 处读取的 `variable` 可能来自的定义, 没有定义时来自参数或外层作用域
+    pub fn definitions(&self, location: Location, variable: &Variable) -> &[Location] {
+        match self.chains.get(&(location, variable.clone())) {
+            Some(definitions) => definitions,
+            None => &[],
+        }
+    }
+
+    /// 读取 `definition` 处所定义的变量的位置
+    pub fn uses(&self, definition: Location) -> Vec<(Location, &Variable)> {
+        self.chains
+            .iter()
+            .filter(|(_, definitions)| definitions.contains(&definition))
+            .map(|((location, variable), _)| (*location, variable))
+            .collect()
+    }
+}
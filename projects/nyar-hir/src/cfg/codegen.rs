@@ -0,0 +1,511 @@
+use super::*;
+use nyar_error::NyarError;
+use nyar_lir::{Instruction, LineTable};
+use std::collections::{BTreeMap, BTreeSet};
+
+impl HirModule {
+    /// 将控制流图编译为 LIR 指令序列, 入口函数的返回值即程序的结果
+    pub fn to_lir(&self) -> Result<Vec<Instruction>, NyarError> {
+        Ok(self.to_lir_with_line_table()?.0)
+    }
+
+    /// 将控制流图编译为 LIR 指令序列, 并按 [`HirInstruction::Locate`] 记录每条指令来自的源码位置
+    pub fn to_lir_with_line_table(&self) -> Result<(Vec<Instruction>, LineTable), NyarError> {
+        let entry = match self.entry() {
+            Some(entry) => entry,
+            None => return Err(NyarError::custom("module has no entry function")),
+        };
+        let mut codegen = Codegen {
+            module: self,
+            instructions: vec![],
+            line_table: LineTable::new(),
+            span: Span::default(),
+            stack: vec![],
+            stackable: BTreeSet::new(),
+            skip: 0,
+        };
+        codegen.function_body(entry)?;
+        Ok((codegen.instructions, codegen.line_table))
+    }
+}
+
+struct Codegen<'m> {
+    module: &'m HirModule,
+    instructions: Vec<Instruction>,
+    line_table: LineTable,
+    /// 正在生成的指令来自的源码位置
+    span: Span,
+    /// 留在栈上尚未写入变量的临时变量, 栈顶在最后
+    stack: Vec<Variable>,
+    /// 当前函数中值可以留在栈上的临时变量
+    stackable: BTreeSet<Variable>,
+    /// 接下来压入的几个操作数已经在栈上
+    skip: usize,
+}
+
+impl Codegen<'_> {
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        if !self.span.is_empty() {
+            self.line_table.record(self.instructions.len(), self.span.file.clone(), self.span.range.clone());
+        }
+        self.instructions.push(instruction);
+        self.instructions.len() - 1
+    }
+
+    /// 按顺序排列基本块, 跳转到下一个块时省略跳转指令
+    ///
+    /// φ 指令在每条边上展开为并行复制, 条件跳转的两条边各自复制后再跳转。
+    fn function_body(&mut self, function: &HirFunction) -> Result<(), NyarError> {
+        let stack = std::mem::take(&mut self.stack);
+        let stackable = std::mem::replace(&mut self.stackable, stackable(function));
+        let span = self.span.clone();
+        let mut starts = Vec::with_capacity(function.blocks.len());
+        let mut jumps = vec![];
+        for (id, block) in function.blocks.iter().enumerate() {
+            starts.push(self.instructions.len());
+            for instruction in &block.instructions {
+                self.instruction(instruction)?;
+            }
+            let terminator = &block.terminator;
+            self.prepare(&terminator.operands(), &terminator.operands(), true);
+            match terminator {
+                Terminator::Jump(target) => {
+                    self.copies(function, id, *target);
+                    if *target != id + 1 {
+                        jumps.push((self.emit(Instruction::Jump { offset: 0 }), *target))
+                    }
+                }
+                Terminator::Branch { condition, then_block, else_block } => {
+                    self.operand(condition);
+                    let else_edge = self.emit(Instruction::JumpIfFalse { offset: 0 });
+                    self.copies(function, id, *then_block);
+                    match edge_copies(function, id, *else_block).is_empty() {
+                        true => {
+                            jumps.push((else_edge, *else_block));
+                            if *then_block != id + 1 {
+                                jumps.push((self.emit(Instruction::Jump { offset: 0 }), *then_block));
+                            }
+                        }
+                        false => {
+                            // 为假的边在条件为真的跳转之后复制
+                            jumps.push((self.emit(Instruction::Jump { offset: 0 }), *then_block));
+                            let offset = (self.instructions.len() - else_edge) as isize;
+                            self.instructions[else_edge].set_jump_offset(offset);
+                            self.copies(function, id, *else_block);
+                            if *else_block != id + 1 {
+                                jumps.push((self.emit(Instruction::Jump { offset: 0 }), *else_block));
+                            }
+                        }
+                    }
+                }
+                Terminator::Return(value) => {
+                    self.operand(value);
+                    self.emit(Instruction::Return);
+                }
+                Terminator::TailCall { callee, arguments } => {
+                    self.operand(callee);
+                    self.operands(arguments);
+                    self.emit(Instruction::TailCall { argument_count: arguments.len() });
+                }
+                Terminator::Try { body, handler } => {
+                    jumps.push((self.emit(Instruction::EnterTry { offset: 0 }), *handler));
+                    if *body != id + 1 {
+                        jumps.push((self.emit(Instruction::Jump { offset: 0 }), *body));
+                    }
+                }
+                Terminator::Throw(value) => {
+                    self.operand(value);
+                    self.emit(Instruction::Throw);
+                }
+                Terminator::Unmatched(value) => {
+                    self.operand(value);
+                    self.emit(Instruction::MatchEnd);
+                }
+            }
+        }
+        for (at, target) in jumps {
+            let offset = starts[target] as isize - at as isize;
+            self.instructions[at].set_jump_offset(offset);
+        }
+        self.stack = stack;
+        self.stackable = stackable;
+        self.span = span;
+        Ok(())
+    }
+
+    /// 从 `from` 跳转到 `to` 时为 φ 指令赋值, 先压入全部来源再依次写入, 相当于并行复制
+    fn copies(&mut self, function: &HirFunction, from: BlockId, to: BlockId) {
+        let copies = edge_copies(function, from, to);
+        for (_, source) in &copies {
+            self.operand(source);
+        }
+        for (target, _) in copies.iter().rev() {
+            self.store(target);
+        }
+    }
+
+    /// 准备生成读取 `operands` 的指令, `leading` 是指令最先压入的那几个操作数
+    ///
+    /// 栈顶的临时变量恰好是最先压入的操作数时直接使用, 否则先写入变量再按名称读取。
+    /// 终结指令之后栈须为空, `whole` 为真时栈上的临时变量必须全部被使用。
+    fn prepare(&mut self, leading: &[&Operand], operands: &[&Operand], whole: bool) {
+        if self.stack.is_empty() {
+            return;
+        }
+        let used = operands.iter().filter(|operand| operand.variable().is_some_and(|v| self.stack.contains(v))).count();
+        let matched = (1..=self.stack.len().min(leading.len())).rev().find(|&count| {
+            let top = &self.stack[self.stack.len() - count..];
+            top.iter().zip(leading).all(|(variable, operand)| operand.variable() == Some(variable))
+        });
+        match matched {
+            Some(count) if count == used && (!whole || count == self.stack.len()) => {
+                self.stack.truncate(self.stack.len() - count);
+                self.skip = count;
+            }
+            _ if used > 0 || whole => self.flush(),
+            _ => {}
+        }
+    }
+
+    /// 将栈上的临时变量全部写入变量
+    fn flush(&mut self) {
+        while let Some(variable) = self.stack.pop() {
+            self.store(&variable)
+        }
+    }
+
+    fn instruction(&mut self, instruction: &HirInstruction) -> Result<(), NyarError> {
+        if let HirInstruction::Locate(span) = instruction {
+            self.span = span.clone();
+            return Ok(());
+        }
+        self.prepare(&instruction.leading_operands(), &instruction.operands(), false);
+        match instruction {
+            HirInstruction::Declare { name, value } => {
+                self.rvalue(value)?;
+                self.emit(Instruction::DeclareVariable { name: name.clone() });
+            }
+            HirInstruction::Assign { target, value } => {
+                self.rvalue(value)?;
+                match self.stackable.contains(target) {
+                    true => self.stack.push(target.clone()),
+                    false => self.store(target),
+                }
+            }
+            HirInstruction::Evaluate(value) => {
+                self.rvalue(value)?;
+                self.emit(Instruction::Pop);
+            }
+            HirInstruction::SetProperty { object, name, value } => {
+                self.operand(object);
+                self.operand(value);
+                self.emit(Instruction::SetProperty { name: name.clone() });
+            }
+            HirInstruction::SetIndex { object, index, value } => {
+                self.operand(object);
+                match constant_index(index) {
+                    Some(index) => {
+                        self.operand(value);
+                        self.emit(Instruction::SetIndex { index });
+                    }
+                    None => {
+                        self.operands([index, value]);
+                        self.emit(Instruction::SetItem);
+                    }
+                }
+            }
+            HirInstruction::Handle { effect, handler } => {
+                self.operand(handler);
+                self.emit(Instruction::HandleEffect { name: effect.clone() });
+            }
+            HirInstruction::Assert { condition, comparison, message, expression } => {
+                self.operand(condition);
+                if let Some((_, right)) = comparison {
+                    self.operand(right);
+                }
+                self.emit(Instruction::Assert {
+                    message: message.clone(),
+                    expression: expression.clone(),
+                    comparison: comparison.as_ref().map(|(operator, _)| *operator),
+                });
+            }
+            HirInstruction::LeaveTry => {
+                self.emit(Instruction::LeaveTry);
+            }
+            // 抛出的值在栈顶
+            HirInstruction::Catch(target) => self.store(target),
+            // 在前驱的跳转处展开
+            HirInstruction::Phi { .. } | HirInstruction::Locate(_) => {}
+        }
+        Ok(())
+    }
+
+    fn store(&mut self, variable: &Variable) {
+        let instruction = match variable {
+            // 临时变量总是声明在当前调用帧, 避免写入外层函数的同名临时变量
+            Variable::Temporary(_) | Variable::Versioned(..) => Instruction::DeclareVariable { name: variable.to_string() },
+            Variable::Local(name) | Variable::Global(name) => Instruction::StoreVariable { name: name.clone() },
+        };
+        self.emit(instruction);
+    }
+
+    fn operand(&mut self, operand: &Operand) {
+        if self.skip > 0 {
+            self.skip -= 1;
+            return;
+        }
+        let instruction = match operand {
+            Operand::Variable(variable @ (Variable::Temporary(_) | Variable::Versioned(..))) => {
+                Instruction::PushVariable { name: variable.to_string() }
+            }
+            Operand::Variable(Variable::Local(name) | Variable::Global(name)) => {
+                Instruction::PushVariable { name: name.clone() }
+            }
+            Operand::Constant(value) => Instruction::PushConstant { value: value.clone() },
+        };
+        self.emit(instruction);
+    }
+
+    fn operands<'a>(&mut self, operands: impl IntoIterator<Item = &'a Operand>) {
+        for operand in operands {
+            self.operand(operand)
+        }
+    }
+
+    /// 生成在栈上留下右值的指令
+    fn rvalue(&mut self, value: &Rvalue) -> Result<(), NyarError> {
+        match value {
+            Rvalue::Use(operand) => self.operand(operand),
+            Rvalue::Binary { operator, left, right } => {
+                self.operands([left, right]);
+                self.emit(Instruction::Binary { operator: *operator });
+            }
+            Rvalue::Unary { operator, operand } => {
+                self.operand(operand);
+                self.emit(Instruction::Unary { operator: *operator });
+            }
+            Rvalue::Call { callee, arguments } => {
+                self.operand(callee);
+                self.operands(arguments);
+                self.emit(Instruction::Call { argument_count: arguments.len() });
+            }
+            Rvalue::CallMethod { object, name, arguments } => {
+                self.operand(object);
+                self.operands(arguments);
+                self.emit(Instruction::CallMethod { name: name.clone(), argument_count: arguments.len() });
+            }
+            Rvalue::CallSuper { receiver, parent, name, arguments } => {
+                self.operands([receiver, parent]);
+                self.operands(arguments);
+                self.emit(Instruction::CallSuper { name: name.clone(), argument_count: arguments.len() });
+            }
+            Rvalue::Raise { effect, arguments } => {
+                self.operands(arguments);
+                self.emit(Instruction::RaiseEffect { name: effect.clone(), argument_count: arguments.len() });
+            }
+            Rvalue::List(items) => {
+                self.operands(items);
+                self.emit(Instruction::CreateArray { size: items.len() });
+            }
+            Rvalue::Object(properties) => {
+                self.properties(properties);
+                self.emit(Instruction::CreateObject { property_count: properties.len() });
+            }
+            Rvalue::GetProperty { object, name } => {
+                self.operand(object);
+                self.emit(Instruction::GetProperty { name: name.clone() });
+            }
+            Rvalue::GetIndex { object, index } => {
+                self.operand(object);
+                match constant_index(index) {
+                    Some(index) => {
+                        self.emit(Instruction::GetIndex { index });
+                    }
+                    None => {
+                        self.operand(index);
+                        self.emit(Instruction::GetItem);
+                    }
+                }
+            }
+            Rvalue::Length(operand) => {
+                self.operand(operand);
+                self.emit(Instruction::Length);
+            }
+            Rvalue::MatchShape { value, shape, against } => {
+                self.operand(value);
+                self.operands(against);
+                self.emit(Instruction::MatchShape { shape: shape.clone() });
+            }
+            Rvalue::New { class, properties } => {
+                self.operand(class);
+                self.properties(properties);
+                self.emit(Instruction::CreateInstance { property_count: properties.len() });
+            }
+            Rvalue::Variant { enumeration, variant, tag, fields } => {
+                self.operands(fields.iter().map(|(_, value)| value));
+                self.emit(Instruction::CreateVariant {
+                    enumeration: enumeration.clone(),
+                    variant: variant.clone(),
+                    tag: *tag,
+                    fields: fields.iter().map(|(name, _)| name.clone()).collect(),
+                });
+            }
+            Rvalue::Function { function, captures } => {
+                self.function(function)?;
+                if !captures.is_empty() {
+                    self.emit(Instruction::CreateClosure { captured_variables: captures.clone() });
+                }
+            }
+            Rvalue::Class { name, parent, traits, properties, methods } => {
+                match parent {
+                    Some(parent) => self.operand(parent),
+                    None => self.operand(&Operand::Constant(NyarValue::Null)),
+                }
+                for t in traits {
+                    self.operand(t);
+                }
+                self.properties(properties);
+                for (key, function) in methods {
+                    self.emit(Instruction::PushConstant { value: NyarValue::from(key.as_str()) });
+                    self.function(function)?;
+                }
+                self.emit(Instruction::CreateClass {
+                    name: name.clone(),
+                    trait_count: traits.len(),
+                    method_count: methods.len(),
+                    property_count: properties.len(),
+                });
+            }
+            Rvalue::Trait { name, methods, defaults } => {
+                for (method, parameters) in methods {
+                    self.emit(Instruction::PushConstant { value: NyarValue::from(method.as_str()) });
+                    for parameter in parameters {
+                        self.emit(Instruction::PushConstant { value: NyarValue::from(parameter.as_str()) });
+                    }
+                    self.emit(Instruction::CreateArray { size: parameters.len() });
+                    match defaults.iter().find(|(key, _)| key == method) {
+                        Some((_, function)) => self.function(function)?,
+                        None => self.operand(&Operand::Constant(NyarValue::Null)),
+                    }
+                }
+                self.emit(Instruction::CreateTrait { name: name.clone(), method_count: methods.len() });
+            }
+            Rvalue::Enum { name, variants } => {
+                self.properties(variants);
+                self.emit(Instruction::CreateEnum { name: name.clone(), variant_count: variants.len() });
+            }
+        }
+        Ok(())
+    }
+
+    /// 依次压入键与值
+    fn properties(&mut self, properties: &[(String, Operand)]) {
+        for (key, value) in properties {
+            self.emit(Instruction::PushConstant { value: NyarValue::from(key.as_str()) });
+            self.operand(value);
+        }
+    }
+
+    /// 生成创建函数值的指令, 函数体紧随其后
+    fn function(&mut self, name: &str) -> Result<(), NyarError> {
+        let function = match self.module.function(name) {
+            Some(function) => function,
+            None => return Err(NyarError::custom(format!("unknown function `{}`", name))),
+        };
+        let parameters = function.parameters.iter().map(|parameter| parameter.name.clone()).collect();
+        let start =
+            self.emit(Instruction::CreateFunction { name: function.source_name.clone(), parameters, body_size: 0 });
+        self.function_body(function)?;
+        let size = self.instructions.len() - start - 1;
+        if let Instruction::CreateFunction { body_size, .. } = &mut self.instructions[start] {
+            *body_size = size
+        }
+        Ok(())
+    }
+}
+
+impl Rvalue {
+    /// 生成指令时最先压入栈的操作数, 常量键与函数体之后的操作数不在其中
+    fn leading_operands(&self) -> Vec<&Operand> {
+        match self {
+            Rvalue::GetIndex { object, index } if constant_index(index).is_some() => vec![object],
+            Rvalue::Class { parent: Some(parent), traits, .. } => std::iter::once(parent).chain(traits).collect(),
+            Rvalue::Object(_) | Rvalue::Class { .. } | Rvalue::Enum { .. } => vec![],
+            Rvalue::New { class, .. } => vec![class],
+            value => value.operands(),
+        }
+    }
+}
+
+impl HirInstruction {
+    /// 生成指令时最先压入栈的操作数
+    fn leading_operands(&self) -> Vec<&Operand> {
+        match self {
+            HirInstruction::Declare { value, .. } | HirInstruction::Assign { value, .. } | HirInstruction::Evaluate(value) => {
+                value.leading_operands()
+            }
+            HirInstruction::SetIndex { object, index, value } if constant_index(index).is_some() => vec![object, value],
+            instruction => instruction.operands(),
+        }
+    }
+}
+
+/// 可以用 `GetIndex` 与 `SetIndex` 访问的常量索引
+fn constant_index(index: &Operand) -> Option<usize> {
+    match index {
+        Operand::Constant(NyarValue::Integer(index)) => usize::try_from(index.as_ref()).ok(),
+        _ => None,
+    }
+}
+
+/// 只由一条赋值定义、只在定义所在的基本块中被读取一次的临时变量
+///
+/// 这样的值不必写入变量, 留在栈上直到被读取, 表达式因此编译为连续的压栈与运算。
+fn stackable(function: &HirFunction) -> BTreeSet<Variable> {
+    let mut definitions: BTreeMap<Variable, (usize, Option<BlockId>)> = BTreeMap::new();
+    let mut uses: BTreeMap<Variable, Vec<Option<BlockId>>> = BTreeMap::new();
+    for (id, block) in function.blocks.iter().enumerate() {
+        for instruction in &block.instructions {
+            if let HirInstruction::Phi { sources, .. } = instruction {
+                // φ 指令的来源在前驱的跳转处读取
+                for variable in sources.iter().filter_map(|(_, source)| source.variable()) {
+                    uses.entry(variable.clone()).or_default().push(None)
+                }
+            }
+            if let Some(variable) = instruction.definition() {
+                let definition = definitions.entry(variable).or_default();
+                definition.0 += 1;
+                definition.1 = matches!(instruction, HirInstruction::Assign { .. }).then_some(id);
+            }
+            for variable in instruction.uses() {
+                uses.entry(variable).or_default().push(Some(id))
+            }
+        }
+        for variable in block.terminator.operands().into_iter().filter_map(Operand::variable) {
+            uses.entry(variable.clone()).or_default().push(Some(id))
+        }
+    }
+    definitions
+        .into_iter()
+        .filter(|(variable, (count, block))| {
+            matches!(variable, Variable::Temporary(_) | Variable::Versioned(..))
+                && *count == 1
+                && block.is_some()
+                && uses.get(variable).is_some_and(|uses| uses.as_slice() == [*block])
+        })
+        .map(|(variable, _)| variable)
+        .collect()
+}
+
+/// 边 `from -> to` 上 φ 指令的目标与来源
+fn edge_copies(function: &HirFunction, from: BlockId, to: BlockId) -> Vec<(&Variable, &Operand)> {
+    let mut copies = vec![];
+    for instruction in &function.blocks[to].instructions {
+        if let HirInstruction::Phi { target, sources } = instruction {
+            if let Some((_, source)) = sources.iter().find(|(predecessor, _)| *predecessor == from) {
+                copies.push((target, source))
+            }
+        }
+    }
+    copies
+}
@@ -0,0 +1,189 @@
+use super::*;
+use std::fmt::{Display, Formatter, Write};
+
+impl Display for HirModule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (index, function) in self.functions.iter().enumerate() {
+            if index > 0 {
+                f.write_char('\n')?;
+            }
+            write!(f, "{}", function)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for HirFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "function {}", self.name)?;
+        writeln!(f, "(")?;
+        for parameter in &self.parameters {
+            writeln!(f, "    %{}: {},", parameter.name, parameter.type_annotation.as_deref().unwrap_or("auto"))?;
+        }
+        writeln!(f, "    ^return: {},", self.return_type.as_deref().unwrap_or("auto"))?;
+        writeln!(f, ")")?;
+        writeln!(f, "{{")?;
+        for block in &self.blocks {
+            write!(f, "{}", block)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+impl Display for BasicBlock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}:", self.label)?;
+        // 源码位置不属于程序的语义
+        for instruction in self.instructions.iter().filter(|instruction| !matches!(instruction, HirInstruction::Locate(_))) {
+            writeln!(f, "    {};", instruction)?;
+        }
+        match &self.terminator {
+            Terminator::Branch { condition, then_block, else_block } => {
+                writeln!(f, "    jump {} if {};", then_block, condition)?;
+                writeln!(f, "    jump {};", else_block)
+            }
+            terminator => writeln!(f, "    {};", terminator),
+        }
+    }
+}
+
+impl Display for Variable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Variable::Local(name) => write!(f, "%{}", name),
+            Variable::Global(name) => f.write_str(name),
+            Variable::Temporary(index) => write!(f, "%tmp{}", index),
+            Variable::Versioned(variable, version) => match variable.as_ref() {
+                Variable::Global(name) => write!(f, "%{}.{}", name, version),
+                variable => write!(f, "{}.{}", variable, version),
+            },
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Variable(variable) => write!(f, "{}", variable),
+            Operand::Constant(NyarValue::Null) => f.write_str("null"),
+            Operand::Constant(NyarValue::Boolean(value)) => write!(f, "{}", value),
+            Operand::Constant(NyarValue::Integer(value)) => write!(f, "{}", value),
+            Operand::Constant(NyarValue::String(value)) => write!(f, "{:?}", value.as_str()),
+            Operand::Constant(value) => write!(f, "<{}>", value.type_name()),
+        }
+    }
+}
+
+/// 逗号分隔的列表
+fn join<T: Display>(items: impl IntoIterator<Item = T>) -> String {
+    items.into_iter().map(|item| item.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+impl Display for Rvalue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rvalue::Use(operand) => write!(f, "{}", operand),
+            Rvalue::Binary { operator, left, right } => write!(f, "binary {} ({}, {})", operator, left, right),
+            Rvalue::Unary { operator, operand } => write!(f, "unary {} ({})", operator, operand),
+            Rvalue::Call { callee, arguments } => write!(f, "call {} ({})", callee, join(arguments)),
+            Rvalue::CallMethod { object, name, arguments } => write!(f, "call {}.{} ({})", object, name, join(arguments)),
+            Rvalue::CallSuper { receiver, parent, name, arguments } => {
+                write!(f, "call super {}.{} ({})", parent, name, join(std::iter::once(receiver).chain(arguments)))
+            }
+            Rvalue::Raise { effect, arguments } => write!(f, "raise {} ({})", effect, join(arguments)),
+            Rvalue::List(items) => write!(f, "list [{}]", join(items)),
+            Rvalue::Object(properties) => {
+                write!(f, "object {{ {} }}", join(properties.iter().map(|(key, value)| format!("{}: {}", key, value))))
+            }
+            Rvalue::GetProperty { object, name } => write!(f, "{}.{}", object, name),
+            Rvalue::GetIndex { object, index } => write!(f, "{}[{}]", object, index),
+            Rvalue::Length(operand) => write!(f, "length ({})", operand),
+            Rvalue::MatchShape { value, shape, against } => {
+                write!(f, "shape {} {:?}", value, shape)?;
+                match against {
+                    Some(against) => write!(f, " of {}", against),
+                    None => Ok(()),
+                }
+            }
+            Rvalue::New { class, properties } => {
+                write!(f, "new {} {{ {} }}", class, join(properties.iter().map(|(key, value)| format!("{}: {}", key, value))))
+            }
+            Rvalue::Variant { enumeration, variant, fields, .. } => {
+                let fields = join(fields.iter().map(|(key, value)| format!("{}: {}", key, value)));
+                write!(f, "variant {}.{} {{ {} }}", enumeration, variant, fields)
+            }
+            Rvalue::Function { function, captures } if captures.is_empty() => write!(f, "function {}", function),
+            Rvalue::Function { function, captures } => write!(f, "closure {} [{}]", function, join(captures)),
+            Rvalue::Class { name, parent, traits, properties, methods } => {
+                write!(f, "class {}", name)?;
+                if let Some(parent) = parent {
+                    write!(f, " extends {}", parent)?;
+                }
+                if !traits.is_empty() {
+                    write!(f, " implements {}", join(traits))?;
+                }
+                let members = properties
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .chain(methods.iter().map(|(key, function)| format!("{}: function {}", key, function)));
+                write!(f, " {{ {} }}", join(members))
+            }
+            Rvalue::Trait { name, methods, defaults } => {
+                let methods = methods.iter().map(|(method, parameters)| match defaults.iter().find(|(key, _)| key == method) {
+                    Some((_, function)) => format!("{}({}): function {}", method, parameters.join(", "), function),
+                    None => format!("{}({})", method, parameters.join(", ")),
+                });
+                write!(f, "trait {} {{ {} }}", name, join(methods))
+            }
+            Rvalue::Enum { name, variants } => {
+                write!(f, "enum {} {{ {} }}", name, join(variants.iter().map(|(key, value)| format!("{} = {}", key, value))))
+            }
+        }
+    }
+}
+
+impl Display for HirInstruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HirInstruction::Declare { name, value } => write!(f, "let %{} = {}", name, value),
+            HirInstruction::Assign { target, value } => write!(f, "{} = {}", target, value),
+            HirInstruction::Evaluate(value) => write!(f, "{}", value),
+            HirInstruction::SetProperty { object, name, value } => write!(f, "{}.{} = {}", object, name, value),
+            HirInstruction::SetIndex { object, index, value } => write!(f, "{}[{}] = {}", object, index, value),
+            HirInstruction::Handle { effect, handler } => write!(f, "handle {} with {}", effect, handler),
+            HirInstruction::Assert { condition, comparison, message, .. } => {
+                match comparison {
+                    Some((operator, right)) => write!(f, "assert binary {} ({}, {})", operator, condition, right)?,
+                    None => write!(f, "assert {}", condition)?,
+                }
+                match message {
+                    Some(message) => write!(f, ", {:?}", message),
+                    None => Ok(()),
+                }
+            }
+            HirInstruction::LeaveTry => f.write_str("leave try"),
+            HirInstruction::Catch(target) => write!(f, "{} = catch", target),
+            HirInstruction::Locate(span) => write!(f, "locate {}..{}", span.start(), span.end()),
+            HirInstruction::Phi { target, sources } => {
+                let sources = sources.iter().map(|(block, value)| format!("{}: {}", block, value));
+                write!(f, "{} = phi [{}]", target, join(sources))
+            }
+        }
+    }
+}
+
+impl Display for Terminator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Terminator::Jump(target) => write!(f, "jump {}", target),
+            Terminator::Branch { condition, then_block, else_block } => {
+                write!(f, "jump {} if {}; jump {}", then_block, condition, else_block)
+            }
+            Terminator::Return(value) => write!(f, "^return({})", value),
+            Terminator::TailCall { callee, arguments } => write!(f, "tail call {} ({})", callee, join(arguments)),
+            Terminator::Try { body, handler } => write!(f, "try {} catch {}", body, handler),
+            Terminator::Throw(value) => write!(f, "throw {}", value),
+            Terminator::Unmatched(value) => write!(f, "unmatched {}", value),
+        }
+    }
+}
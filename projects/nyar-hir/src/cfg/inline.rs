@@ -0,0 +1,375 @@
+//! 函数内联
+//!
+//! 被调用的函数在编译期唯一确定、足够小且不递归时, 将其基本块复制到调用处。被内联函数的参数与局部变量
+//! 改写为调用者的临时变量, 外层作用域的变量仍按名称读取, 因此只在调用者看到的是同一个变量时才内联。
+
+use super::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+impl HirModule {
+    /// 内联指令数不超过 `max_size` 的函数, 应在 [`HirModule::to_ssa`] 与尾调用消除之前调用
+    ///
+    /// 闭包捕获的是创建时的值, 注册效应处理器的函数的处理器属于自己的调用帧, 这两类函数与包含嵌套函数的函数都不内联。
+    pub fn inline_functions(&self, max_size: usize) -> HirModule {
+        let has_phi = self
+            .functions
+            .iter()
+            .flat_map(|function| &function.blocks)
+            .any(|block| block.instructions.iter().any(|instruction| matches!(instruction, HirInstruction::Phi { .. })));
+        if has_phi {
+            return self.clone();
+        }
+        let bindings = self.static_bindings();
+        let parents = self.parents();
+        let closures: BTreeSet<&str> = self
+            .functions
+            .iter()
+            .flat_map(|function| &function.blocks)
+            .flat_map(|block| &block.instructions)
+            .filter_map(|instruction| match instruction {
+                HirInstruction::Declare { value: Rvalue::Function { function, captures }, .. } if !captures.is_empty() => {
+                    Some(function.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        // 可以内联的函数: 绑定变量名到函数下标
+        let mut candidates = BTreeMap::new();
+        for (name, (_, binding)) in &bindings {
+            let index = match self.functions.iter().position(|function| function.name == *name) {
+                Some(index) => index,
+                None => continue,
+            };
+            if !closures.contains(name) && self.functions[index].is_inlinable(max_size) {
+                candidates.insert(*binding, index);
+            }
+        }
+        // 去掉直接或间接调用自身的函数
+        let calls: BTreeMap<usize, BTreeSet<usize>> =
+            candidates.values().map(|index| (*index, self.functions[*index].static_calls(&candidates))).collect();
+        candidates.retain(|_, index| {
+            let mut visited = BTreeSet::new();
+            let mut work: Vec<usize> = calls[index].iter().copied().collect();
+            while let Some(next) = work.pop() {
+                if next == *index {
+                    return false;
+                }
+                if visited.insert(next) {
+                    work.extend(calls.get(&next).into_iter().flatten().copied())
+                }
+            }
+            true
+        });
+        let mut module = self.clone();
+        for caller in 0..module.functions.len() {
+            let mut inlined = 0;
+            while let Some((block, index, callee)) =
+                self.find_inline_site(&module.functions[caller], caller, &candidates, &parents)
+            {
+                inlined += 1;
+                let function = &mut module.functions[caller];
+                *function = function.inline_call(block, index, &self.functions[callee], inlined);
+            }
+        }
+        module
+    }
+
+    /// 每个函数所在的外层函数, 入口函数没有外层函数
+    fn parents(&self) -> BTreeMap<usize, usize> {
+        let mut parents = BTreeMap::new();
+        for (index, function) in self.functions.iter().enumerate() {
+            for instruction in function.blocks.iter().flat_map(|block| &block.instructions) {
+                let children: Vec<&String> = match instruction {
+                    HirInstruction::Declare { value, .. }
+                    | HirInstruction::Assign { value, .. }
+                    | HirInstruction::Evaluate(value) => match value {
+                        Rvalue::Function { function, .. } => vec![function],
+                        Rvalue::Class { methods, .. } | Rvalue::Trait { defaults: methods, .. } => {
+                            methods.iter().map(|(_, function)| function).collect()
+                        }
+                        _ => vec![],
+                    },
+                    _ => vec![],
+                };
+                for child in children {
+                    if let Some(child) = self.functions.iter().position(|function| &function.name == child) {
+                        parents.insert(child, index);
+                    }
+                }
+            }
+        }
+        parents
+    }
+
+    /// 调用者中第一处可以内联的调用: 基本块, 指令下标与被调用的函数
+    fn find_inline_site(
+        &self,
+        caller: &HirFunction,
+        caller_index: usize,
+        candidates: &BTreeMap<&str, usize>,
+        parents: &BTreeMap<usize, usize>,
+    ) -> Option<(BlockId, usize, usize)> {
+        for (block_id, block) in caller.blocks.iter().enumerate() {
+            for (index, instruction) in block.instructions.iter().enumerate() {
+                let (callee, arguments) = match instruction {
+                    HirInstruction::Declare { value: Rvalue::Call { callee, arguments }, .. }
+                    | HirInstruction::Assign { value: Rvalue::Call { callee, arguments }, .. }
+                    | HirInstruction::Evaluate(Rvalue::Call { callee, arguments }) => (callee, arguments),
+                    _ => continue,
+                };
+                let (name, local) = match callee {
+                    Operand::Variable(Variable::Global(name)) => (name, false),
+                    Operand::Variable(Variable::Local(name)) => (name, true),
+                    _ => continue,
+                };
+                let callee = match candidates.get(name.as_str()) {
+                    Some(callee) => *callee,
+                    None => continue,
+                };
+                let visible = match local {
+                    // 在声明函数的地方调用, 声明须在调用之前执行
+                    true => {
+                        parents.get(&callee) == Some(&caller_index)
+                            && caller.declaration_dominates(name, Location { block: block_id, index })
+                    }
+                    false => callee != caller_index && self.sees_same_globals(caller_index, callee, parents),
+                };
+                if visible && self.functions[callee].parameters.len() == arguments.len() {
+                    return Some((block_id, index, callee));
+                }
+            }
+        }
+        None
+    }
+
+    /// 调用者是否与被调用的函数看到同样的外层变量
+    ///
+    /// 调用者须是声明被调用函数的函数或其内部的函数, 且从调用者到声明处之间没有函数绑定被调用函数读写的外层变量。
+    fn sees_same_globals(&self, caller: usize, callee: usize, parents: &BTreeMap<usize, usize>) -> bool {
+        let globals = self.functions[callee].globals();
+        let scope = match parents.get(&callee) {
+            Some(scope) => *scope,
+            None => return false,
+        };
+        let mut current = caller;
+        while current != scope {
+            if self.functions[current].locals().iter().any(|name| globals.contains(name)) {
+                return false;
+            }
+            current = match parents.get(&current) {
+                Some(parent) => *parent,
+                None => return false,
+            };
+        }
+        true
+    }
+}
+
+impl HirFunction {
+    /// 指令与终结指令的总数, 不计记录源码位置的指令
+    pub fn size(&self) -> usize {
+        let size = |block: &BasicBlock| {
+            block.instructions.iter().filter(|instruction| !matches!(instruction, HirInstruction::Locate(_))).count() + 1
+        };
+        self.blocks.iter().map(size).sum()
+    }
+
+    fn is_inlinable(&self, max_size: usize) -> bool {
+        let simple_instructions =
+            self.blocks.iter().flat_map(|block| &block.instructions).all(|instruction| match instruction {
+                HirInstruction::Handle { .. }
+                | HirInstruction::Phi { .. }
+                | HirInstruction::LeaveTry
+                | HirInstruction::Catch(_) => false,
+                HirInstruction::Declare { value, .. }
+                | HirInstruction::Assign { value, .. }
+                | HirInstruction::Evaluate(value) => !matches!(value, Rvalue::Function { .. } | Rvalue::Class { .. }),
+                _ => true,
+            });
+        let simple_terminators = self
+            .blocks
+            .iter()
+            .all(|block| !matches!(block.terminator, Terminator::TailCall { .. } | Terminator::Try { .. }));
+        self.size() <= max_size && simple_instructions && simple_terminators
+    }
+
+    /// 按绑定变量名调用的候选函数
+    fn static_calls(&self, candidates: &BTreeMap<&str, usize>) -> BTreeSet<usize> {
+        let mut calls = BTreeSet::new();
+        for instruction in self.blocks.iter().flat_map(|block| &block.instructions) {
+            if let HirInstruction::Declare { value: Rvalue::Call { callee, .. }, .. }
+            | HirInstruction::Assign { value: Rvalue::Call { callee, .. }, .. }
+            | HirInstruction::Evaluate(Rvalue::Call { callee, .. }) = instruction
+            {
+                if let Some(Variable::Global(name)) = callee.variable() {
+                    calls.extend(candidates.get(name.as_str()))
+                }
+            }
+        }
+        calls
+    }
+
+    /// 变量 `name` 的声明是否在到达 `location` 的每条路径上都已执行
+    fn declaration_dominates(&self, name: &str, location: Location) -> bool {
+        let dominators = Dominators::new(self);
+        self.blocks.iter().enumerate().any(|(block, basic)| {
+            basic.instructions.iter().enumerate().any(|(index, instruction)| match instruction {
+                HirInstruction::Declare { name: declared, .. } if declared == name => match block == location.block {
+                    true => index < location.index,
+                    false => dominators.dominates(block, location.block),
+                },
+                _ => false,
+            })
+        })
+    }
+
+    /// 函数内部绑定的变量名, 包括参数
+    fn locals(&self) -> BTreeSet<String> {
+        let mut locals: BTreeSet<String> = self.parameters.iter().map(|parameter| parameter.name.clone()).collect();
+        for instruction in self.blocks.iter().flat_map(|block| &block.instructions) {
+            for variable in instruction.uses().into_iter().chain(instruction.definition()) {
+                if let Variable::Local(name) = variable {
+                    locals.insert(name);
+                }
+            }
+        }
+        locals
+    }
+
+    /// 函数读写的外层变量名
+    fn globals(&self) -> BTreeSet<String> {
+        let mut globals = BTreeSet::new();
+        for block in &self.blocks {
+            let instructions = block
+                .instructions
+                .iter()
+                .flat_map(|instruction| instruction.uses().into_iter().chain(instruction.definition()));
+            let terminator = block.terminator.operands().into_iter().filter_map(|operand| operand.variable().cloned());
+            for variable in instructions.chain(terminator) {
+                if let Variable::Global(name) = variable {
+                    globals.insert(name);
+                }
+            }
+        }
+        globals
+    }
+
+    /// 将 `block` 中第 `index` 条指令的调用替换为 `callee` 的函数体
+    ///
+    /// 调用之前的指令留在原来的块中, 之后的指令移入函数体之后的新块, 原块的跳转目标随之后移。
+    fn inline_call(&self, block: BlockId, index: usize, callee: &HirFunction, inlined: usize) -> HirFunction {
+        let mut temporaries = self
+            .blocks
+            .iter()
+            .flat_map(|block| &block.instructions)
+            .filter_map(|instruction| match instruction.definition() {
+                Some(Variable::Temporary(index)) => Some(index),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let mut fresh = || {
+            temporaries += 1;
+            Variable::Temporary(temporaries)
+        };
+        let locals = self.locals();
+        let mut renamed: BTreeMap<Variable, Variable> = BTreeMap::new();
+        let mut rename = |variable: &Variable, fresh: &mut dyn FnMut() -> Variable| match variable {
+            Variable::Global(name) if locals.contains(name) => Variable::Local(name.clone()),
+            Variable::Global(_) | Variable::Versioned(..) => variable.clone(),
+            Variable::Local(_) | Variable::Temporary(_) => renamed.entry(variable.clone()).or_insert_with(fresh).clone(),
+        };
+
+        let offset = block + 1;
+        let continuation = offset + callee.blocks.len();
+        let mapping: Vec<BlockId> =
+            (0..self.blocks.len()).map(|id| if id <= block { id } else { id + callee.blocks.len() + 1 }).collect();
+        let mut head = self.blocks[block].clone();
+        let mut rest = head.instructions.split_off(index);
+        let call = rest.remove(0);
+        // 调用之后的指令仍来自调用处的源码
+        let location = head.instructions.iter().rev().find(|instruction| matches!(instruction, HirInstruction::Locate(_)));
+        rest.splice(0..0, location.cloned());
+        let (call, arguments) = match call {
+            HirInstruction::Declare { name, value: Rvalue::Call { arguments, .. } } => {
+                (Some(HirInstruction::Declare { name, value: Rvalue::Use(Operand::Constant(NyarValue::Null)) }), arguments)
+            }
+            HirInstruction::Assign { target, value: Rvalue::Call { arguments, .. } } => {
+                (Some(HirInstruction::Assign { target, value: Rvalue::Use(Operand::Constant(NyarValue::Null)) }), arguments)
+            }
+            HirInstruction::Evaluate(Rvalue::Call { arguments, .. }) => (None, arguments),
+            _ => unreachable!(),
+        };
+        // 按值传递参数
+        for (parameter, argument) in callee.parameters.iter().zip(arguments) {
+            let target = rename(&Variable::Local(parameter.name.clone()), &mut fresh);
+            head.instructions.push(HirInstruction::Assign { target, value: Rvalue::Use(argument) });
+        }
+        head.terminator = Terminator::Jump(offset);
+        let result = call.as_ref().map(|_| fresh());
+
+        let mut body = vec![];
+        for original in &callee.blocks {
+            let mut instructions = vec![];
+            for instruction in &original.instructions {
+                let mut instruction = instruction.clone();
+                for operand in instruction.operands_mut() {
+                    if let Operand::Variable(variable) = operand {
+                        *variable = rename(variable, &mut fresh)
+                    }
+                }
+                instruction = match instruction {
+                    HirInstruction::Declare { name, value } => {
+                        HirInstruction::Assign { target: rename(&Variable::Local(name), &mut fresh), value }
+                    }
+                    HirInstruction::Assign { target, value } => {
+                        HirInstruction::Assign { target: rename(&target, &mut fresh), value }
+                    }
+                    instruction => instruction,
+                };
+                instructions.push(instruction);
+            }
+            let terminator = match original.terminator.clone() {
+                Terminator::Return(mut value) => {
+                    if let Operand::Variable(variable) = &mut value {
+                        *variable = rename(variable, &mut fresh)
+                    }
+                    if let Some(result) = &result {
+                        instructions.push(HirInstruction::Assign { target: result.clone(), value: Rvalue::Use(value) })
+                    }
+                    Terminator::Jump(continuation)
+                }
+                mut terminator => {
+                    for operand in terminator.operands_mut() {
+                        if let Operand::Variable(variable) = operand {
+                            *variable = rename(variable, &mut fresh)
+                        }
+                    }
+                    let mapping: Vec<BlockId> = (0..callee.blocks.len()).map(|id| id + offset).collect();
+                    terminator.renumber(&mapping)
+                }
+            };
+            body.push(BasicBlock { label: format!("inline{}.{}", inlined, original.label), instructions, terminator });
+        }
+
+        // 调用的结果写回原来的目标
+        if let (Some(mut call), Some(result)) = (call, result) {
+            if let HirInstruction::Declare { value, .. } | HirInstruction::Assign { value, .. } = &mut call {
+                *value = Rvalue::Use(result.into())
+            }
+            rest.insert(0, call);
+        }
+        let tail = BasicBlock {
+            label: format!("inline{}.end", inlined),
+            instructions: rest,
+            terminator: self.blocks[block].terminator.clone().renumber(&mapping),
+        };
+        let mut function = self.clone();
+        for original in function.blocks.iter_mut() {
+            original.terminator = original.terminator.clone().renumber(&mapping)
+        }
+        function.blocks[block] = head;
+        function.blocks.splice(offset..offset, body.into_iter().chain(std::iter::once(tail)));
+        function
+    }
+}
@@ -0,0 +1,1122 @@
+use super::*;
+use crate::{
+    NyarCompiler,
+    ast::{
+        Assignment, CatchBlock, ClassDefinition, EnumDefinition, Expression, ExpressionKind, FunctionDefinition, IfStatement,
+        ImportStatement, InterpolationPart, Literal, LoopStatement, MatchExpression, MemberAccessExpression, Parameter,
+        Pattern, Program, Statement, StatementKind, TraitDefinition, TryCatchStatement, type_name,
+    },
+    compiler::module_variable,
+};
+use nyar_error::NyarError;
+use std::{collections::HashSet, sync::Arc};
+
+impl HirModule {
+    /// 将程序转换为控制流图, 程序本身成为名为 `main` 的入口函数
+    ///
+    /// 不做类型检查, 也不加载导入的模块, 转换中记录的第一个错误作为结果返回。
+    pub fn lower(program: &Program) -> Result<HirModule, NyarError> {
+        NyarCompiler::new().lower_unchecked(program)
+    }
+
+    /// 用编译器的状态转换程序, 入口函数先按初始化顺序调用已加载模块的函数
+    pub(crate) fn lower_program(compiler: &mut NyarCompiler, program: &Program) -> Result<HirModule, NyarError> {
+        let mut builder = FunctionBuilder::new(compiler, "main");
+        for module in builder.compiler.loaded_modules().to_vec() {
+            let function = builder.materialize(Rvalue::Function { function: module.function, captures: vec![] });
+            builder.declare(&module_variable(&module.id), Rvalue::Call { callee: function, arguments: vec![] });
+        }
+        let value = builder.block_value(&program.statements)?;
+        builder.terminate(Terminator::Return(value));
+        let main = builder.finish(vec![], None, None);
+        let mut functions = compiler.take_functions();
+        functions.insert(0, main);
+        Ok(HirModule { functions })
+    }
+
+    /// 将模块的顶层语句转换为函数, 调用后得到只含导出绑定的模块对象, 返回函数名称
+    pub(crate) fn lower_module(
+        compiler: &mut NyarCompiler,
+        id: &str,
+        program: &Program,
+        exports: &[String],
+    ) -> Result<String, NyarError> {
+        let name = compiler.function_name(id);
+        let mut builder = FunctionBuilder::new(compiler, &name);
+        builder.compiler.enter_constants(&program.statements);
+        let result = builder.statements(&program.statements);
+        builder.compiler.leave_constants();
+        result?;
+        let exports = exports.iter().map(|name| (name.clone(), Operand::Variable(builder.resolve(name)))).collect();
+        let value = builder.materialize(Rvalue::Object(exports));
+        builder.terminate(Terminator::Return(value));
+        let function = builder.finish(vec![], None, Some(id.to_string()));
+        compiler.add_function(function);
+        Ok(name)
+    }
+}
+
+/// 正在构造的基本块
+struct PendingBlock {
+    label: String,
+    instructions: Vec<HirInstruction>,
+    terminator: Option<Terminator>,
+}
+
+/// 正在转换的循环
+struct LoopContext {
+    /// 继续的目标
+    next: BlockId,
+    /// 跳出的目标
+    exit: BlockId,
+    /// 进入循环时所在的尝试块个数, 跳出时需要离开更内层的尝试块
+    tries: usize,
+}
+
+/// 正在转换的尝试块
+struct TryContext {
+    /// 已注册且尚未移除的异常处理器个数
+    handlers: usize,
+    /// 离开尝试块时须执行的最终块
+    finally: Option<Vec<Statement>>,
+}
+
+/// 匹配分支的模式检查
+struct ArmTest {
+    /// 检查通过后进入的基本块的标签前缀
+    label: String,
+    /// 已生成的检查个数
+    checks: usize,
+    /// 检查失败时跳转的基本块
+    fail: BlockId,
+}
+
+/// 单个函数的构造器
+struct FunctionBuilder<'c> {
+    compiler: &'c mut NyarCompiler,
+    name: String,
+    blocks: Vec<PendingBlock>,
+    /// 正在追加指令的基本块, 终结之后的代码不可达
+    current: Option<BlockId>,
+    /// 基本块首次进入的顺序
+    order: Vec<BlockId>,
+    locals: HashSet<String>,
+    temporaries: usize,
+    labels: usize,
+    loops: Vec<LoopContext>,
+    tries: Vec<TryContext>,
+    /// 方法所在类的父类, `super` 从这里开始查找方法
+    parent: Option<String>,
+    /// 当前基本块中最近记录的源码位置
+    located: Option<Span>,
+}
+
+impl<'c> FunctionBuilder<'c> {
+    fn new(compiler: &'c mut NyarCompiler, name: &str) -> Self {
+        let mut builder = Self {
+            compiler,
+            name: name.to_string(),
+            blocks: vec![],
+            current: None,
+            order: vec![],
+            locals: HashSet::new(),
+            temporaries: 0,
+            labels: 0,
+            loops: vec![],
+            tries: vec![],
+            parent: None,
+            located: None,
+        };
+        let entry = builder.new_block("function.begin".to_string());
+        builder.switch_to(entry);
+        builder
+    }
+
+    fn finish(
+        mut self,
+        parameters: Vec<HirParameter>,
+        return_type: Option<String>,
+        source_name: Option<String>,
+    ) -> HirFunction {
+        // 按首次进入的顺序排列基本块, 使其与源码顺序一致
+        for id in 0..self.blocks.len() {
+            if !self.order.contains(&id) {
+                self.order.push(id)
+            }
+        }
+        let order = std::mem::take(&mut self.order);
+        let mut renumber = vec![0; order.len()];
+        for (index, id) in order.iter().enumerate() {
+            renumber[*id] = index;
+        }
+        let mut pending: Vec<_> = self.blocks.into_iter().map(Some).collect();
+        let blocks = order
+            .iter()
+            .filter_map(|id| pending[*id].take())
+            .map(|block| BasicBlock {
+                label: block.label,
+                instructions: block.instructions,
+                // 不可达的空块也需要终结指令
+                terminator: match block.terminator {
+                    Some(terminator) => terminator.renumber(&renumber),
+                    None => Terminator::Return(Operand::Constant(NyarValue::Null)),
+                },
+            })
+            .collect();
+        HirFunction { name: self.name, source_name, parameters, return_type, blocks }
+    }
+
+    fn error(&self, message: impl ToString) -> NyarError {
+        self.compiler.span().locate(NyarError::custom(message))
+    }
+
+    /// 记录可恢复的错误, 转换继续进行
+    fn report(&mut self, message: impl ToString) {
+        self.compiler.report(NyarError::custom(message))
+    }
+
+    fn new_block(&mut self, label: String) -> BlockId {
+        self.blocks.push(PendingBlock { label, instructions: vec![], terminator: None });
+        self.blocks.len() - 1
+    }
+
+    /// 分配形如 `if1` 的标签前缀
+    fn label(&mut self, kind: &str) -> String {
+        self.labels += 1;
+        format!("{}{}", kind, self.labels)
+    }
+
+    fn switch_to(&mut self, block: BlockId) {
+        if !self.order.contains(&block) {
+            self.order.push(block)
+        }
+        self.current = Some(block);
+        self.located = None;
+    }
+
+    /// 正在转换的节点的位置与之前的指令不同时, 记录新的位置
+    fn locate(&mut self, current: BlockId) {
+        let span = self.compiler.span();
+        if !span.is_empty() && self.located.as_ref() != Some(span) {
+            self.located = Some(span.clone());
+            self.blocks[current].instructions.push(HirInstruction::Locate(span.clone()));
+        }
+    }
+
+    fn emit(&mut self, instruction: HirInstruction) {
+        if let Some(current) = self.current {
+            self.locate(current);
+            self.blocks[current].instructions.push(instruction)
+        }
+    }
+
+    fn terminate(&mut self, terminator: Terminator) {
+        if let Some(current) = self.current.take() {
+            self.locate(current);
+            self.blocks[current].terminator = Some(terminator)
+        }
+    }
+
+    /// `condition` 为真时在新的基本块中继续, 为假时跳转到 `test.fail`
+    fn check(&mut self, condition: Operand, test: &mut ArmTest) {
+        test.checks += 1;
+        let next = self.new_block(format!("{}.{}", test.label, test.checks));
+        self.terminate(Terminator::Branch { condition, then_block: next, else_block: test.fail });
+        self.switch_to(next);
+    }
+
+    fn temporary(&mut self) -> Variable {
+        self.temporaries += 1;
+        Variable::Temporary(self.temporaries)
+    }
+
+    fn resolve(&self, name: &str) -> Variable {
+        match self.locals.contains(name) {
+            true => Variable::Local(name.to_string()),
+            false => Variable::Global(name.to_string()),
+        }
+    }
+
+    /// 将右值保存到临时变量, 返回可以直接使用的操作数
+    fn materialize(&mut self, value: Rvalue) -> Operand {
+        match value {
+            Rvalue::Use(operand) => operand,
+            value => {
+                let target = self.temporary();
+                self.emit(HirInstruction::Assign { target: target.clone(), value });
+                Operand::Variable(target)
+            }
+        }
+    }
+
+    /// 转换函数体或程序的语句序列, 结果为最后一个表达式语句的值
+    fn block_value(&mut self, statements: &[Statement]) -> Result<Operand, NyarError> {
+        self.compiler.enter_constants(statements);
+        let result = self.statements_value(statements);
+        self.compiler.leave_constants();
+        result
+    }
+
+    fn statements_value(&mut self, statements: &[Statement]) -> Result<Operand, NyarError> {
+        match statements.split_last() {
+            Some((Statement { kind: StatementKind::Expression(last), span }, rest)) => {
+                self.statements(rest)?;
+                let outer = self.compiler.swap_span(span.clone());
+                let value = self.expression(last);
+                self.compiler.swap_span(outer);
+                value
+            }
+            _ => {
+                self.statements(statements)?;
+                Ok(Operand::Constant(NyarValue::Null))
+            }
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) -> Result<(), NyarError> {
+        for statement in statements {
+            // 终结之后的语句不可达
+            if self.current.is_none() {
+                break;
+            }
+            self.statement(statement)?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), NyarError> {
+        let outer = self.compiler.swap_span(statement.span.clone());
+        let result = self.statement_kind(&statement.kind);
+        self.compiler.swap_span(outer);
+        result
+    }
+
+    fn statement_kind(&mut self, statement: &StatementKind) -> Result<(), NyarError> {
+        match statement {
+            StatementKind::Expression(expression) => match self.rvalue(expression)? {
+                Rvalue::Use(_) => {}
+                value => self.emit(HirInstruction::Evaluate(value)),
+            },
+            StatementKind::VariableDeclaration(declaration) => {
+                if let (true, Some(initializer)) = (declaration.is_constant, &declaration.initializer) {
+                    if let Ok(Some(value)) = initializer.fold(self.compiler) {
+                        self.compiler.define_constant(&declaration.name, value)
+                    }
+                }
+                let value = match &declaration.initializer {
+                    Some(initializer) => self.rvalue(initializer)?,
+                    None => Rvalue::Use(Operand::Constant(NyarValue::Null)),
+                };
+                self.declare(&declaration.name, value)
+            }
+            StatementKind::Assignment(assignment) => self.assignment(assignment)?,
+            StatementKind::If(statement) => self.if_statement(statement)?,
+            StatementKind::Loop(statement) => self.loop_statement(statement)?,
+            StatementKind::Break | StatementKind::Continue => {
+                let is_break = matches!(statement, StatementKind::Break);
+                match self.loops.last() {
+                    Some(context) => {
+                        let (target, tries) = (if is_break { context.exit } else { context.next }, context.tries);
+                        self.unwind(tries)?;
+                        self.terminate(Terminator::Jump(target))
+                    }
+                    None if is_break => self.report("`break` outside of a loop"),
+                    None => self.report("`continue` outside of a loop"),
+                }
+            }
+            // 尝试块内返回前须执行最终块, 返回值先存入临时变量
+            StatementKind::Return(value) if !self.tries.is_empty() => {
+                let value = match value {
+                    Some(value) => self.rvalue(value)?,
+                    None => Rvalue::Use(Operand::Constant(NyarValue::Null)),
+                };
+                let saved = self.temporary();
+                self.emit(HirInstruction::Assign { target: saved.clone(), value });
+                self.unwind(0)?;
+                self.terminate(Terminator::Return(saved.into()))
+            }
+            StatementKind::Return(value) => {
+                let value = match value {
+                    Some(value) => self.expression(value)?,
+                    None => Operand::Constant(NyarValue::Null),
+                };
+                self.terminate(Terminator::Return(value))
+            }
+            StatementKind::Block(statements) => self.statements(statements)?,
+            StatementKind::FunctionDeclaration(function) => {
+                // 先声明名称, 函数体内通过外层作用域引用自身
+                let value = self.function(function)?;
+                self.declare(&function.name, value)
+            }
+            StatementKind::ClassDeclaration(class) => self.class(class)?,
+            StatementKind::TraitDeclaration(definition) => self.trait_definition(definition)?,
+            StatementKind::EnumDeclaration(definition) => self.enum_definition(definition)?,
+            StatementKind::StructureDeclaration(definition) => {
+                let layout = match definition.layout() {
+                    Ok(layout) => NyarValue::Structure(Arc::new(layout)),
+                    Err(error) => {
+                        self.compiler.report(error);
+                        NyarValue::Null
+                    }
+                };
+                self.declare(&definition.name, Rvalue::Use(Operand::Constant(layout)))
+            }
+            StatementKind::EffectHandler(handler) => {
+                // 处理器在当前调用帧内有效
+                let value = self.function(&handler.handler)?;
+                let handler_value = self.materialize(value);
+                self.emit(HirInstruction::Handle { effect: handler.name.clone(), handler: handler_value })
+            }
+            StatementKind::Import(import) => self.import(import),
+            // 导出的声明在模块内与普通声明相同, 模块对象只包含导出的名称
+            StatementKind::Export(export) => self.statement(&export.declaration)?,
+            StatementKind::TryCatch(statement) => self.try_catch(statement)?,
+            StatementKind::Throw(value) => {
+                let value = self.expression(value)?;
+                self.terminate(Terminator::Throw(value))
+            }
+            StatementKind::Assert(condition, message) => {
+                let expression = self.compiler.source_text(&condition.span);
+                // 比较断言保留两侧的值, 失败时一并报告
+                let comparison = match &condition.kind {
+                    ExpressionKind::Binary(binary) => {
+                        let operator = binary.operator.parse::<BinaryOperator>().ok();
+                        operator.filter(BinaryOperator::is_comparison).map(|operator| (binary, operator))
+                    }
+                    _ => None,
+                };
+                let (condition, comparison) = match comparison {
+                    Some((binary, operator)) => {
+                        let left = self.expression(&binary.left)?;
+                        (left, Some((operator, self.expression(&binary.right)?)))
+                    }
+                    None => (self.expression(condition)?, None),
+                };
+                self.emit(HirInstruction::Assert { condition, comparison, message: message.clone(), expression })
+            }
+        }
+        Ok(())
+    }
+
+    fn declare(&mut self, name: &str, value: Rvalue) {
+        self.emit(HirInstruction::Declare { name: name.to_string(), value });
+        self.locals.insert(name.to_string());
+    }
+
+    fn assignment(&mut self, assignment: &Assignment) -> Result<(), NyarError> {
+        match &assignment.target.kind {
+            ExpressionKind::Variable(name) => {
+                let value = self.rvalue(&assignment.value)?;
+                let target = self.resolve(name);
+                self.emit(HirInstruction::Assign { target, value })
+            }
+            ExpressionKind::MemberAccess(access) => {
+                let object = self.expression(&access.object)?;
+                let value = self.expression(&assignment.value)?;
+                self.emit(HirInstruction::SetProperty { object, name: access.member.clone(), value })
+            }
+            ExpressionKind::IndexAccess(access) => {
+                let object = self.expression(&access.object)?;
+                let index = self.expression(&access.index)?;
+                let value = self.expression(&assignment.value)?;
+                self.emit(HirInstruction::SetIndex { object, index, value })
+            }
+            _ => self.report("invalid assignment target"),
+        }
+        Ok(())
+    }
+
+    fn if_statement(&mut self, statement: &IfStatement) -> Result<(), NyarError> {
+        // 条件为常量时只转换被选中的分支
+        if let Ok(Some(condition)) = statement.condition.fold(self.compiler) {
+            return match condition.is_truthy() {
+                true => self.statements(&statement.then_branch),
+                false => self.statements(statement.else_branch.as_deref().unwrap_or_default()),
+            };
+        }
+        let label = self.label("if");
+        let condition = self.expression(&statement.condition)?;
+        let then_block = self.new_block(format!("{}.begin", label));
+        let else_block = statement.else_branch.as_ref().map(|_| self.new_block(format!("{}.else", label)));
+        let end = self.new_block(format!("{}.end", label));
+        self.terminate(Terminator::Branch { condition, then_block, else_block: else_block.unwrap_or(end) });
+        self.switch_to(then_block);
+        self.statements(&statement.then_branch)?;
+        self.terminate(Terminator::Jump(end));
+        if let (Some(block), Some(branch)) = (else_block, &statement.else_branch) {
+            self.switch_to(block);
+            self.statements(branch)?;
+            self.terminate(Terminator::Jump(end));
+        }
+        self.switch_to(end);
+        Ok(())
+    }
+
+    /// 转换循环体, `next` 为继续目标, 循环体结束后跳转到 `next`
+    fn loop_body(&mut self, body: &[Statement], next: BlockId, exit: BlockId) -> Result<(), NyarError> {
+        self.loops.push(LoopContext { next, exit, tries: self.tries.len() });
+        let result = self.statements(body);
+        self.loops.pop();
+        result?;
+        self.terminate(Terminator::Jump(next));
+        Ok(())
+    }
+
+    fn loop_statement(&mut self, statement: &LoopStatement) -> Result<(), NyarError> {
+        match statement {
+            LoopStatement::While { condition, body } => {
+                let label = self.label("while");
+                let begin = self.new_block(format!("{}.begin", label));
+                let body_block = self.new_block(format!("{}.body", label));
+                let end = self.new_block(format!("{}.end", label));
+                self.terminate(Terminator::Jump(begin));
+                self.switch_to(begin);
+                let condition = self.expression(condition)?;
+                self.terminate(Terminator::Branch { condition, then_block: body_block, else_block: end });
+                self.switch_to(body_block);
+                self.loop_body(body, begin, end)?;
+                self.switch_to(end);
+            }
+            LoopStatement::For { initializer, condition, update, body } => {
+                self.statement(initializer)?;
+                let label = self.label("for");
+                let begin = self.new_block(format!("{}.begin", label));
+                let body_block = self.new_block(format!("{}.body", label));
+                let next = self.new_block(format!("{}.next", label));
+                let end = self.new_block(format!("{}.end", label));
+                self.terminate(Terminator::Jump(begin));
+                self.switch_to(begin);
+                let condition = self.expression(condition)?;
+                self.terminate(Terminator::Branch { condition, then_block: body_block, else_block: end });
+                self.switch_to(body_block);
+                self.loop_body(body, next, end)?;
+                self.switch_to(next);
+                self.statement(update)?;
+                self.terminate(Terminator::Jump(begin));
+                self.switch_to(end);
+            }
+            LoopStatement::ForEach { variable, iterable, body } => {
+                // 迭代对象只求值一次, 用游标逐个读取元素
+                let iterable = self.rvalue(iterable)?;
+                let items = self.temporary();
+                self.emit(HirInstruction::Assign { target: items.clone(), value: iterable });
+                let cursor = self.temporary();
+                self.emit(HirInstruction::Assign { target: cursor.clone(), value: Rvalue::Use(NyarValue::from(0).into()) });
+                let label = self.label("for");
+                let begin = self.new_block(format!("{}.begin", label));
+                let body_block = self.new_block(format!("{}.body", label));
+                let end = self.new_block(format!("{}.end", label));
+                self.terminate(Terminator::Jump(begin));
+                self.switch_to(begin);
+                let length = self.materialize(Rvalue::Length(items.clone().into()));
+                let condition = self.materialize(Rvalue::Binary {
+                    operator: BinaryOperator::Less,
+                    left: cursor.clone().into(),
+                    right: length,
+                });
+                self.terminate(Terminator::Branch { condition, then_block: body_block, else_block: end });
+                self.switch_to(body_block);
+                self.declare(variable, Rvalue::GetIndex { object: items.into(), index: cursor.clone().into() });
+                self.emit(HirInstruction::Assign {
+                    target: cursor.clone(),
+                    value: Rvalue::Binary {
+                        operator: BinaryOperator::Add,
+                        left: cursor.into(),
+                        right: NyarValue::from(1).into(),
+                    },
+                });
+                self.loop_body(body, begin, end)?;
+                self.switch_to(end);
+            }
+            LoopStatement::Infinite { body } => {
+                let label = self.label("loop");
+                let begin = self.new_block(format!("{}.begin", label));
+                let end = self.new_block(format!("{}.end", label));
+                self.terminate(Terminator::Jump(begin));
+                self.switch_to(begin);
+                self.loop_body(body, begin, end)?;
+                self.switch_to(end);
+            }
+        }
+        Ok(())
+    }
+
+    /// 从内到外离开第 `depth` 层之内的尝试块, 移除其异常处理器并执行最终块
+    fn unwind(&mut self, depth: usize) -> Result<(), NyarError> {
+        let mut unwound = vec![];
+        let mut result = Ok(());
+        while self.tries.len() > depth {
+            let Some(context) = self.tries.pop()
+            else {
+                break;
+            };
+            for _ in 0..context.handlers {
+                self.emit(HirInstruction::LeaveTry);
+            }
+            // 最终块在外层尝试块中执行
+            if let Some(finally) = &context.finally {
+                result = result.and_then(|_| self.statements(finally));
+            }
+            unwound.push(context);
+        }
+        self.tries.extend(unwound.into_iter().rev());
+        result
+    }
+
+    /// 最终块的处理器在外, 捕获块的处理器在内, 捕获块中抛出的值仍会经过最终块
+    fn try_catch(&mut self, statement: &TryCatchStatement) -> Result<(), NyarError> {
+        let label = self.label("try");
+        let rethrow = statement.finally_block.as_ref().map(|_| self.new_block(format!("{}.rethrow", label)));
+        let catch = match statement.catch_blocks.is_empty() {
+            true => None,
+            false => Some(self.new_block(format!("{}.catch", label))),
+        };
+        let end = self.new_block(format!("{}.end", label));
+        // 尝试块与捕获块正常结束后执行最终块
+        let after = match rethrow {
+            Some(_) => self.new_block(format!("{}.finally", label)),
+            None => end,
+        };
+        for (handler, kind) in [(rethrow, "begin"), (catch, "body")] {
+            if let Some(handler) = handler {
+                let body = self.new_block(format!("{}.{}", label, kind));
+                self.terminate(Terminator::Try { body, handler });
+                self.switch_to(body);
+            }
+        }
+        let handlers = usize::from(rethrow.is_some()) + usize::from(catch.is_some());
+        self.tries.push(TryContext { handlers, finally: statement.finally_block.clone() });
+        let result = self.try_body(statement, catch, after);
+        self.tries.pop();
+        result?;
+        if let (Some(rethrow), Some(finally)) = (rethrow, &statement.finally_block) {
+            self.switch_to(after);
+            self.emit(HirInstruction::LeaveTry);
+            self.statements(finally)?;
+            self.terminate(Terminator::Jump(end));
+            // 抛出的值经过最终块后重新抛出
+            self.switch_to(rethrow);
+            let error = self.temporary();
+            self.emit(HirInstruction::Catch(error.clone()));
+            self.statements(finally)?;
+            self.terminate(Terminator::Throw(error.into()));
+        }
+        self.switch_to(end);
+        Ok(())
+    }
+
+    /// 转换尝试块与捕获块, 两者正常结束后跳转到 `after`
+    fn try_body(&mut self, statement: &TryCatchStatement, catch: Option<BlockId>, after: BlockId) -> Result<(), NyarError> {
+        self.statements(&statement.try_block)?;
+        let Some(catch) = catch
+        else {
+            self.terminate(Terminator::Jump(after));
+            return Ok(());
+        };
+        self.emit(HirInstruction::LeaveTry);
+        if let Some(context) = self.tries.last_mut() {
+            context.handlers -= 1
+        }
+        self.terminate(Terminator::Jump(after));
+        self.switch_to(catch);
+        self.catch_blocks(&statement.catch_blocks, after)
+    }
+
+    /// 选择第一个类型匹配的捕获块, 没有匹配时重新抛出
+    fn catch_blocks(&mut self, blocks: &[CatchBlock], after: BlockId) -> Result<(), NyarError> {
+        let error = self.temporary();
+        self.emit(HirInstruction::Catch(error.clone()));
+        for block in blocks {
+            let outer = self.compiler.swap_span(block.span.clone());
+            let mismatch = match &block.error_type {
+                Some(error_type) => {
+                    let label = self.label("catch");
+                    let against = Some(Operand::Variable(self.resolve(error_type)));
+                    let value = Rvalue::MatchShape { value: error.clone().into(), shape: Shape::Instance, against };
+                    let condition = self.materialize(value);
+                    let matched = self.new_block(format!("{}.begin", label));
+                    let mismatch = self.new_block(format!("{}.end", label));
+                    self.terminate(Terminator::Branch { condition, then_block: matched, else_block: mismatch });
+                    self.switch_to(matched);
+                    Some(mismatch)
+                }
+                None => None,
+            };
+            self.declare(&block.error_variable, Rvalue::Use(error.clone().into()));
+            self.compiler.swap_span(outer);
+            self.statements(&block.handler)?;
+            self.terminate(Terminator::Jump(after));
+            match mismatch {
+                Some(mismatch) => self.switch_to(mismatch),
+                None => break,
+            }
+        }
+        self.terminate(Terminator::Throw(error.into()));
+        Ok(())
+    }
+
+    fn import(&mut self, import: &ImportStatement) {
+        // 模块加载失败的错误已在编译开始时报告
+        let Some(module) = self.compiler.imported_module(&import.path).cloned()
+        else {
+            return;
+        };
+        let variable = Operand::Variable(self.resolve(&module_variable(&module.id)));
+        if !import.is_all && import.symbols.is_empty() {
+            let name = import.alias.as_deref().unwrap_or_else(|| import.binding_name());
+            return self.declare(name, Rvalue::Use(variable));
+        }
+        let symbols = if import.is_all { &module.exports } else { &import.symbols };
+        if import.alias.is_some() && symbols.len() != 1 {
+            return self.report("only a single imported symbol can be renamed");
+        }
+        for symbol in symbols {
+            if !module.exports.contains(symbol) {
+                self.report(format!("module `{}` does not export `{}`", module.id, symbol));
+                continue;
+            }
+            let name = import.alias.as_ref().unwrap_or(symbol);
+            self.declare(name, Rvalue::GetProperty { object: variable.clone(), name: symbol.clone() });
+        }
+    }
+
+    /// 将嵌套函数转换为模块中的独立函数, 返回创建函数值的右值
+    fn function(&mut self, function: &FunctionDefinition) -> Result<Rvalue, NyarError> {
+        let name = match self.name.as_str() {
+            "main" => function.name.clone(),
+            outer => format!("{}.{}", outer, function.name),
+        };
+        let parent = self.parent.clone();
+        let name = self.nested(&name, Some(&function.name), &function.parameters, function.return_type.clone(), parent, |b| {
+            b.block_value(&function.body)
+        })?;
+        Ok(Rvalue::Function { function: name, captures: vec![] })
+    }
+
+    /// 构造嵌套函数并加入模块, 返回其名称
+    ///
+    /// `source_name` 为运行时函数值的名称, `parent` 为方法所在类的父类。
+    fn nested<F>(
+        &mut self,
+        name: &str,
+        source_name: Option<&str>,
+        parameters: &[Parameter],
+        return_type: Option<String>,
+        parent: Option<String>,
+        body: F,
+    ) -> Result<String, NyarError>
+    where
+        F: FnOnce(&mut FunctionBuilder) -> Result<Operand, NyarError>,
+    {
+        let name = self.compiler.function_name(name);
+        let mut builder = FunctionBuilder::new(&mut *self.compiler, &name);
+        builder.parent = parent;
+        builder.locals.extend(parameters.iter().map(|parameter| parameter.name.clone()));
+        let value = body(&mut builder)?;
+        builder.terminate(Terminator::Return(value));
+        let parameters = parameters
+            .iter()
+            .map(|parameter| HirParameter { name: parameter.name.clone(), type_annotation: parameter.type_annotation.clone() })
+            .collect();
+        let function = builder.finish(parameters, return_type, source_name.map(str::to_string));
+        self.compiler.add_function(function);
+        Ok(name)
+    }
+
+    fn class(&mut self, class: &ClassDefinition) -> Result<(), NyarError> {
+        let parent = class.parent.as_ref().map(|parent| Operand::Variable(self.resolve(type_name(parent))));
+        let traits = class.traits.iter().map(|t| Operand::Variable(self.resolve(type_name(t)))).collect();
+        let mut properties = vec![];
+        for property in &class.properties {
+            let value = match &property.default {
+                Some(default) => self.expression(default)?,
+                None => Operand::Constant(NyarValue::Null),
+            };
+            properties.push((property.name.clone(), value));
+        }
+        let mut methods = vec![];
+        for method in &class.methods {
+            let name = format!("{}.{}", class.name, method.name);
+            let parent = class.parent.clone();
+            let name = self.nested(&name, Some(&method.name), &method.parameters, method.return_type.clone(), parent, |b| {
+                b.block_value(&method.body)
+            })?;
+            methods.push((method.name.clone(), name));
+        }
+        self.declare(&class.name, Rvalue::Class { name: class.name.clone(), parent, traits, properties, methods });
+        Ok(())
+    }
+
+    fn trait_definition(&mut self, definition: &TraitDefinition) -> Result<(), NyarError> {
+        let methods = definition
+            .methods
+            .iter()
+            .map(|method| (method.name.clone(), method.parameters.iter().map(|p| p.name.clone()).collect()))
+            .collect();
+        let mut defaults = vec![];
+        for method in &definition.methods {
+            // 默认实现与类的方法一样以 `self` 绑定到实例
+            if let Some(body) = &method.body {
+                let name = format!("{}.{}", definition.name, method.name);
+                let name = self.nested(&name, Some(&method.name), &method.parameters, method.return_type.clone(), None, |b| {
+                    b.block_value(body)
+                })?;
+                defaults.push((method.name.clone(), name));
+            }
+        }
+        self.declare(&definition.name, Rvalue::Trait { name: definition.name.clone(), methods, defaults });
+        Ok(())
+    }
+
+    fn enum_definition(&mut self, definition: &EnumDefinition) -> Result<(), NyarError> {
+        let mut variants = vec![];
+        for (tag, variant) in definition.variants.iter().enumerate() {
+            let value = match &variant.value {
+                Some(value) => self.expression(value)?,
+                // 带字段的变体转换为以字段名为参数的构造函数
+                None if variant.has_fields() => {
+                    let fields = variant.fields.names();
+                    let parameters: Vec<_> = fields.iter().map(|field| Parameter::new(field.as_str())).collect();
+                    let name = format!("{}.{}", definition.name, variant.name);
+                    let name = self.nested(&name, Some(&variant.name), &parameters, None, None, |builder| {
+                        let fields =
+                            fields.iter().map(|field| (field.clone(), Variable::Local(field.clone()).into())).collect();
+                        let enumeration = definition.name.clone();
+                        let value = Rvalue::Variant { enumeration, variant: variant.name.clone(), tag, fields };
+                        Ok(builder.materialize(value))
+                    })?;
+                    self.materialize(Rvalue::Function { function: name, captures: vec![] })
+                }
+                None => Operand::Constant(definition.unit_variant(tag)),
+            };
+            variants.push((variant.name.clone(), value));
+        }
+        self.declare(&definition.name, Rvalue::Enum { name: definition.name.clone(), variants });
+        Ok(())
+    }
+
+    /// 转换表达式, 返回可以直接使用的操作数
+    fn expression(&mut self, expression: &Expression) -> Result<Operand, NyarError> {
+        let outer = self.compiler.span().clone();
+        let value = self.rvalue(expression).map(|value| self.materialize(value));
+        self.compiler.swap_span(outer);
+        value
+    }
+
+    fn expressions(&mut self, expressions: &[Expression]) -> Result<Vec<Operand>, NyarError> {
+        expressions.iter().map(|expression| self.expression(expression)).collect()
+    }
+
+    /// 转换表达式, 返回尚未保存到临时变量的右值
+    ///
+    /// 之后的指令仍属于这个表达式, 由调用者恢复之前的源码位置。
+    fn rvalue(&mut self, expression: &Expression) -> Result<Rvalue, NyarError> {
+        self.compiler.swap_span(expression.span.clone());
+        if matches!(
+            expression.kind,
+            ExpressionKind::Variable(_) | ExpressionKind::Binary(_) | ExpressionKind::Unary(_) | ExpressionKind::Conditional(_)
+        ) {
+            // 求值出错时仍生成原本的运算, 运行时照常抛出错误
+            // 错误只在出错的子表达式处报告, 类型检查已在此处报告过的错误不再重复
+            match expression.fold(self.compiler) {
+                Ok(Some(value)) => return Ok(Rvalue::Use(Operand::Constant(value))),
+                Ok(None) => {}
+                Err(error) if error.span() == expression.span.range && !self.compiler.is_reported(&error) => {
+                    self.compiler.report(error)
+                }
+                Err(_) => {}
+            }
+        }
+        let value = match &expression.kind {
+            ExpressionKind::Literal(literal) => self.literal(literal)?,
+            ExpressionKind::Variable(name) => Rvalue::Use(Operand::Variable(self.resolve(name))),
+            ExpressionKind::Binary(binary) if binary.is_type_test() => {
+                let value = self.expression(&binary.left)?;
+                let against = Some(self.expression(&binary.right)?);
+                let shape = if binary.operator == "is" { Shape::Instance } else { Shape::Implements };
+                Rvalue::MatchShape { value, shape, against }
+            }
+            ExpressionKind::Binary(binary) => {
+                let operator = match binary.operator.parse::<BinaryOperator>() {
+                    Ok(operator) => operator,
+                    Err(error) => {
+                        self.compiler.report(error);
+                        return Ok(Rvalue::Use(Operand::Constant(NyarValue::Null)));
+                    }
+                };
+                match operator {
+                    BinaryOperator::And | BinaryOperator::Or => {
+                        self.short_circuit(operator == BinaryOperator::And, &binary.left, &binary.right)?
+                    }
+                    _ => {
+                        let left = self.expression(&binary.left)?;
+                        Rvalue::Binary { operator, left, right: self.expression(&binary.right)? }
+                    }
+                }
+            }
+            ExpressionKind::Unary(unary) => {
+                let operand = self.expression(&unary.operand)?;
+                match unary.operator.parse::<UnaryOperator>() {
+                    Ok(operator) => Rvalue::Unary { operator, operand },
+                    Err(error) => {
+                        self.compiler.report(error);
+                        Rvalue::Use(operand)
+                    }
+                }
+            }
+            ExpressionKind::Call(call) => match &call.callee.kind {
+                ExpressionKind::MemberAccess(access) => self.method_call(access, &call.arguments)?,
+                _ => {
+                    let callee = self.expression(&call.callee)?;
+                    Rvalue::Call { callee, arguments: self.expressions(&call.arguments)? }
+                }
+            },
+            ExpressionKind::Lambda(lambda) => {
+                let mut bound = lambda.parameters.clone();
+                let mut captures = vec![];
+                lambda.body.free_variables(&mut bound, &mut captures);
+                let parameters: Vec<_> = lambda.parameters.iter().map(|name| Parameter::new(name.as_str())).collect();
+                let parent = self.parent.clone();
+                let name = self.nested("lambda", None, &parameters, None, parent, |builder| builder.expression(&lambda.body))?;
+                Rvalue::Function { function: name, captures }
+            }
+            ExpressionKind::Conditional(conditional) => {
+                if let Ok(Some(condition)) = conditional.condition.fold(self.compiler) {
+                    return match (condition.is_truthy(), &conditional.else_branch) {
+                        (true, _) => self.rvalue(&conditional.then_branch),
+                        (false, Some(branch)) => self.rvalue(branch),
+                        (false, None) => Ok(Rvalue::Use(Operand::Constant(NyarValue::Null))),
+                    };
+                }
+                let label = self.label("if");
+                let condition = self.expression(&conditional.condition)?;
+                let result = self.temporary();
+                let then_block = self.new_block(format!("{}.begin", label));
+                let else_block = self.new_block(format!("{}.else", label));
+                let end = self.new_block(format!("{}.end", label));
+                self.terminate(Terminator::Branch { condition, then_block, else_block });
+                self.switch_to(then_block);
+                let value = self.rvalue(&conditional.then_branch)?;
+                self.emit(HirInstruction::Assign { target: result.clone(), value });
+                self.terminate(Terminator::Jump(end));
+                self.switch_to(else_block);
+                let value = match &conditional.else_branch {
+                    Some(branch) => self.rvalue(branch)?,
+                    None => Rvalue::Use(Operand::Constant(NyarValue::Null)),
+                };
+                self.emit(HirInstruction::Assign { target: result.clone(), value });
+                self.terminate(Terminator::Jump(end));
+                self.switch_to(end);
+                Rvalue::Use(result.into())
+            }
+            ExpressionKind::MemberAccess(access) => {
+                Rvalue::GetProperty { object: self.expression(&access.object)?, name: access.member.clone() }
+            }
+            ExpressionKind::IndexAccess(access) => {
+                let object = self.expression(&access.object)?;
+                Rvalue::GetIndex { object, index: self.expression(&access.index)? }
+            }
+            ExpressionKind::Effect(effect) => {
+                Rvalue::Raise { effect: effect.name.clone(), arguments: self.expressions(&effect.arguments)? }
+            }
+            ExpressionKind::Match(expression) => self.match_expression(expression)?,
+            ExpressionKind::New(expression) => {
+                let class = Operand::Variable(self.resolve(expression.class_name()));
+                let mut properties = vec![];
+                for (name, value) in &expression.properties {
+                    properties.push((name.clone(), self.expression(value)?));
+                }
+                Rvalue::New { class, properties }
+            }
+            ExpressionKind::Interpolation(interpolation) => {
+                // 各部分以 `to_string` 格式化后依次连接
+                let mut result = None;
+                for part in &interpolation.parts {
+                    let value = match part {
+                        InterpolationPart::Text(text) => Operand::Constant(NyarValue::from(text.as_str())),
+                        InterpolationPart::Expression(expression) => {
+                            let object = self.expression(expression)?;
+                            self.materialize(Rvalue::CallMethod { object, name: "to_string".to_string(), arguments: vec![] })
+                        }
+                    };
+                    result = Some(match result {
+                        Some(left) => self.materialize(Rvalue::Binary { operator: BinaryOperator::Add, left, right: value }),
+                        None => value,
+                    });
+                }
+                Rvalue::Use(result.unwrap_or_else(|| Operand::Constant(NyarValue::from(""))))
+            }
+        };
+        // 子表达式转换之后, 剩下的指令属于这个表达式
+        self.compiler.swap_span(expression.span.clone());
+        Ok(value)
+    }
+
+    /// 短路求值, 结果为决定结果的那个操作数
+    fn short_circuit(&mut self, is_and: bool, left: &Expression, right: &Expression) -> Result<Rvalue, NyarError> {
+        // 左操作数为常量时只需转换决定结果的那个操作数
+        if let Ok(Some(left)) = left.fold(self.compiler) {
+            return match left.is_truthy() == is_and {
+                true => self.rvalue(right),
+                false => Ok(Rvalue::Use(Operand::Constant(left))),
+            };
+        }
+        let label = self.label(if is_and { "and" } else { "or" });
+        let result = self.temporary();
+        let left = self.rvalue(left)?;
+        self.emit(HirInstruction::Assign { target: result.clone(), value: left });
+        let right_block = self.new_block(format!("{}.rhs", label));
+        let end = self.new_block(format!("{}.end", label));
+        let (then_block, else_block) = if is_and { (right_block, end) } else { (end, right_block) };
+        self.terminate(Terminator::Branch { condition: result.clone().into(), then_block, else_block });
+        self.switch_to(right_block);
+        let right = self.rvalue(right)?;
+        self.emit(HirInstruction::Assign { target: result.clone(), value: right });
+        self.terminate(Terminator::Jump(end));
+        self.switch_to(end);
+        Ok(Rvalue::Use(result.into()))
+    }
+
+    /// `object.method(args)`, `super.method(args)` 从父类开始查找方法, 两者都以 `self` 绑定到实例
+    fn method_call(&mut self, access: &MemberAccessExpression, arguments: &[Expression]) -> Result<Rvalue, NyarError> {
+        let name = access.member.clone();
+        let object = match &access.object.kind {
+            ExpressionKind::Variable(object) if object == "super" => {
+                let Some(parent) = self.parent.as_deref()
+                else {
+                    let message = "`super` can only be used in methods of a class with a parent class";
+                    let error = NyarError::syntax_error(message, access.object.span.range.clone());
+                    self.compiler.report(access.object.span.locate(error));
+                    return Ok(Rvalue::Use(Operand::Constant(NyarValue::Null)));
+                };
+                let parent = Operand::Variable(self.resolve(type_name(parent)));
+                let receiver = Operand::Variable(self.resolve("self"));
+                return Ok(Rvalue::CallSuper { receiver, parent, name, arguments: self.expressions(arguments)? });
+            }
+            _ => self.expression(&access.object)?,
+        };
+        Ok(Rvalue::CallMethod { object, name, arguments: self.expressions(arguments)? })
+    }
+
+    /// 依次尝试各分支, 值为第一个匹配的分支的值, 都不匹配时报错
+    fn match_expression(&mut self, expression: &MatchExpression) -> Result<Rvalue, NyarError> {
+        // 被匹配的值存入临时变量, 分支中的绑定不会覆盖它
+        let value = self.rvalue(&expression.subject)?;
+        let subject = self.temporary();
+        self.emit(HirInstruction::Assign { target: subject.clone(), value });
+        let label = self.label("match");
+        let result = self.temporary();
+        let end = self.new_block(format!("{}.end", label));
+        for (index, arm) in expression.arms.iter().enumerate() {
+            let outer = self.compiler.swap_span(arm.span.clone());
+            let label = format!("{}.arm{}", label, index + 1);
+            let fail = self.new_block(format!("{}.miss", label));
+            let mut test = ArmTest { label, checks: 0, fail };
+            let tested = match (self.pattern(&arm.pattern, subject.clone().into(), &mut test), &arm.guard) {
+                (Ok(()), Some(guard)) => self.expression(guard).map(|condition| self.check(condition, &mut test)),
+                (tested, _) => tested,
+            };
+            self.compiler.swap_span(outer);
+            tested?;
+            let value = self.rvalue(&arm.body)?;
+            self.emit(HirInstruction::Assign { target: result.clone(), value });
+            self.terminate(Terminator::Jump(end));
+            self.switch_to(test.fail);
+        }
+        self.terminate(Terminator::Unmatched(subject.into()));
+        self.switch_to(end);
+        Ok(Rvalue::Use(result.into()))
+    }
+
+    /// 检查 `subject` 是否匹配模式, 不匹配时跳转到 `test.fail`, 匹配时绑定变量
+    fn pattern(&mut self, pattern: &Pattern, subject: Operand, test: &mut ArmTest) -> Result<(), NyarError> {
+        match pattern {
+            Pattern::Wildcard => {}
+            Pattern::Binding(name) => self.declare(name, Rvalue::Use(subject)),
+            Pattern::Literal(literal) => {
+                let value = match literal {
+                    Literal::Null => NyarValue::Null,
+                    Literal::Boolean(value) => NyarValue::from(*value),
+                    Literal::Integer(value) => NyarValue::from(*value),
+                    Literal::String(value) => NyarValue::from(value.as_str()),
+                    Literal::List(_) | Literal::Object(_) => {
+                        return Err(self.error("only scalar literals can be used as patterns"));
+                    }
+                };
+                let value = Rvalue::Binary { operator: BinaryOperator::Equal, left: subject, right: value.into() };
+                let condition = self.materialize(value);
+                self.check(condition, test);
+            }
+            Pattern::Variant { enumeration, variant, fields } => {
+                let against = Some(Operand::Variable(self.resolve(enumeration)));
+                let shape = Shape::Variant { name: variant.clone(), fields: pattern.arity() };
+                let condition = self.materialize(Rvalue::MatchShape { value: subject.clone(), shape, against });
+                self.check(condition, test);
+                self.fields(&subject, fields, test)?;
+            }
+            Pattern::List { items, rest } => {
+                let shape = Shape::List { length: items.len(), rest: *rest };
+                let condition = self.materialize(Rvalue::MatchShape { value: subject.clone(), shape, against: None });
+                self.check(condition, test);
+                for (index, item) in items.iter().enumerate() {
+                    if !matches!(item, Pattern::Wildcard) {
+                        let index = NyarValue::from(index as i64).into();
+                        self.subpattern(item, Rvalue::GetIndex { object: subject.clone(), index }, test)?;
+                    }
+                }
+            }
+            Pattern::Object(fields) => {
+                let shape = Shape::Object { keys: fields.iter().map(|(key, _)| key.clone()).collect() };
+                let condition = self.materialize(Rvalue::MatchShape { value: subject.clone(), shape, against: None });
+                self.check(condition, test);
+                self.fields(&subject, fields, test)?;
+            }
+            Pattern::Class { name, fields } => {
+                let against = Some(Operand::Variable(self.resolve(name)));
+                let condition =
+                    self.materialize(Rvalue::MatchShape { value: subject.clone(), shape: Shape::Instance, against });
+                self.check(condition, test);
+                self.fields(&subject, fields, test)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查属性的模式
+    fn fields(&mut self, subject: &Operand, fields: &[(String, Pattern)], test: &mut ArmTest) -> Result<(), NyarError> {
+        for (key, field) in fields {
+            if !matches!(field, Pattern::Wildcard) {
+                self.subpattern(field, Rvalue::GetProperty { object: subject.clone(), name: key.clone() }, test)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查嵌套的模式, 绑定直接声明, 其他模式先将值存入临时变量
+    fn subpattern(&mut self, pattern: &Pattern, value: Rvalue, test: &mut ArmTest) -> Result<(), NyarError> {
+        if let Pattern::Binding(name) = pattern {
+            self.declare(name, value);
+            return Ok(());
+        }
+        let value = self.materialize(value);
+        self.pattern(pattern, value, test)
+    }
+
+    fn literal(&mut self, literal: &Literal) -> Result<Rvalue, NyarError> {
+        let value = match literal {
+            Literal::Null => NyarValue::Null,
+            Literal::Boolean(value) => NyarValue::Boolean(*value),
+            Literal::Integer(value) => NyarValue::from(*value),
+            Literal::String(value) => NyarValue::from(value.as_str()),
+            Literal::List(items) => return Ok(Rvalue::List(self.expressions(items)?)),
+            Literal::Object(properties) => {
+                let mut items = vec![];
+                for (key, value) in properties {
+                    items.push((key.clone(), self.expression(value)?));
+                }
+                return Ok(Rvalue::Object(items));
+            }
+        };
+        Ok(Rvalue::Use(Operand::Constant(value)))
+    }
+}
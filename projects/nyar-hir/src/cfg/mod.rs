@@ -0,0 +1,582 @@
+//! 控制流图模块，位于 AST 与 [`nyar_lir`] 之间的中间表示
+//!
+//! 每个函数由若干基本块组成, 基本块内是不含分支的三地址指令, 以一条终结指令结尾。
+//! 表达式的中间结果保存在临时变量 `%tmpN` 中, 短路运算与条件表达式都展开为分支。
+
+use crate::ast::Span;
+use nyar_lir::{BinaryOperator, NyarValue, Shape, UnaryOperator};
+use std::collections::BTreeMap;
+
+mod analysis;
+mod codegen;
+mod display;
+mod inline;
+mod lower;
+mod ssa;
+mod tail_call;
+
+pub use self::analysis::{Definition, Dominators, Liveness, Location, ReachingDefinitions, UseDef};
+
+/// 基本块编号, 即基本块在函数中的下标
+pub type BlockId = usize;
+
+/// 控制流图模块, 第一个函数是程序的入口
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HirModule {
+    /// 模块中的全部函数
+    pub functions: Vec<HirFunction>,
+}
+
+/// 控制流图函数
+#[derive(Debug, Clone, PartialEq)]
+pub struct HirFunction {
+    /// 函数名称, 在模块内唯一, lambda 与嵌套函数使用自动生成的名称
+    pub name: String,
+    /// 源码中的函数名, 即运行时函数值的名称, lambda 没有名称
+    pub source_name: Option<String>,
+    /// 参数列表
+    pub parameters: Vec<HirParameter>,
+    /// 返回值类型
+    pub return_type: Option<String>,
+    /// 基本块, 第一个为入口
+    pub blocks: Vec<BasicBlock>,
+}
+
+/// 函数参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct HirParameter {
+    /// 参数名称
+    pub name: String,
+    /// 类型注解
+    pub type_annotation: Option<String>,
+}
+
+/// 基本块
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    /// 标签, 如 `function.begin`, `if1.end`
+    pub label: String,
+    /// 块内指令
+    pub instructions: Vec<HirInstruction>,
+    /// 终结指令
+    pub terminator: Terminator,
+}
+
+/// 变量
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Variable {
+    /// 当前函数的参数或局部变量, 写作 `%name`
+    Local(String),
+    /// 外层作用域中的变量, 写作 `name`
+    Global(String),
+    /// 编译器生成的临时变量, 写作 `%tmpN`
+    Temporary(usize),
+    /// SSA 形式中变量的某个版本, 写作 `%x.N`
+    Versioned(Box<Variable>, usize),
+}
+
+/// 操作数
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    /// 变量
+    Variable(Variable),
+    /// 常量
+    Constant(NyarValue),
+}
+
+/// 赋值语句的右值
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rvalue {
+    /// 直接使用操作数
+    Use(Operand),
+    /// 二元运算, 不含短路运算
+    Binary {
+        /// 运算符
+        operator: BinaryOperator,
+        /// 左操作数
+        left: Operand,
+        /// 右操作数
+        right: Operand,
+    },
+    /// 一元运算
+    Unary {
+        /// 运算符
+        operator: UnaryOperator,
+        /// 操作数
+        operand: Operand,
+    },
+    /// 函数调用
+    Call {
+        /// 被调用者
+        callee: Operand,
+        /// 参数
+        arguments: Vec<Operand>,
+    },
+    /// 方法调用, 方法以 `self` 绑定到对象
+    CallMethod {
+        /// 对象
+        object: Operand,
+        /// 方法名
+        name: String,
+        /// 参数
+        arguments: Vec<Operand>,
+    },
+    /// `super.name(...)`, 从父类开始查找方法
+    CallSuper {
+        /// 接收者, 即当前方法的 `self`
+        receiver: Operand,
+        /// 父类
+        parent: Operand,
+        /// 方法名
+        name: String,
+        /// 参数
+        arguments: Vec<Operand>,
+    },
+    /// 触发效应
+    Raise {
+        /// 效应名称
+        effect: String,
+        /// 参数
+        arguments: Vec<Operand>,
+    },
+    /// 创建列表
+    List(Vec<Operand>),
+    /// 创建对象
+    Object(Vec<(String, Operand)>),
+    /// 读取属性
+    GetProperty {
+        /// 对象
+        object: Operand,
+        /// 属性名
+        name: String,
+    },
+    /// 读取元素
+    GetIndex {
+        /// 对象
+        object: Operand,
+        /// 索引
+        index: Operand,
+    },
+    /// 可迭代值的长度
+    Length(Operand),
+    /// 检查值的结构, 结果为布尔值
+    MatchShape {
+        /// 被检查的值
+        value: Operand,
+        /// 结构要求
+        shape: Shape,
+        /// 比较的类、特征或枚举, 只有 [`Shape::Instance`], [`Shape::Implements`] 与 [`Shape::Variant`] 需要
+        against: Option<Operand>,
+    },
+    /// 类的实例化, 未给出的属性取默认值
+    New {
+        /// 类
+        class: Operand,
+        /// 属性的初始值
+        properties: Vec<(String, Operand)>,
+    },
+    /// 创建带字段的枚举变体
+    Variant {
+        /// 枚举名
+        enumeration: String,
+        /// 变体名
+        variant: String,
+        /// 变体的序号
+        tag: usize,
+        /// 字段名及其值
+        fields: Vec<(String, Operand)>,
+    },
+    /// 创建函数值, lambda 按值捕获 `captures` 中的变量
+    Function {
+        /// 函数名称
+        function: String,
+        /// 按值捕获的变量
+        captures: Vec<String>,
+    },
+    /// 创建类
+    Class {
+        /// 类名
+        name: String,
+        /// 父类
+        parent: Option<Operand>,
+        /// 实现的特征
+        traits: Vec<Operand>,
+        /// 属性及其默认值
+        properties: Vec<(String, Operand)>,
+        /// 方法名及其函数名称
+        methods: Vec<(String, String)>,
+    },
+    /// 创建特征
+    Trait {
+        /// 特征名
+        name: String,
+        /// 方法名及其参数
+        methods: Vec<(String, Vec<String>)>,
+        /// 带有默认实现的方法名及其函数名称
+        defaults: Vec<(String, String)>,
+    },
+    /// 创建枚举
+    Enum {
+        /// 枚举名
+        name: String,
+        /// 变体及其值
+        variants: Vec<(String, Operand)>,
+    },
+}
+
+/// 基本块内的指令
+#[derive(Debug, Clone, PartialEq)]
+pub enum HirInstruction {
+    /// 在当前作用域声明局部变量, 写作 `let %x = ...`
+    Declare {
+        /// 变量名
+        name: String,
+        /// 初始值
+        value: Rvalue,
+    },
+    /// 为变量赋值
+    Assign {
+        /// 目标变量
+        target: Variable,
+        /// 值
+        value: Rvalue,
+    },
+    /// 求值并丢弃结果
+    Evaluate(Rvalue),
+    /// 设置属性
+    SetProperty {
+        /// 对象
+        object: Operand,
+        /// 属性名
+        name: String,
+        /// 值
+        value: Operand,
+    },
+    /// 设置元素
+    SetIndex {
+        /// 对象
+        object: Operand,
+        /// 索引
+        index: Operand,
+        /// 值
+        value: Operand,
+    },
+    /// 为当前函数注册效应处理器
+    Handle {
+        /// 效应名称
+        effect: String,
+        /// 处理器函数
+        handler: Operand,
+    },
+    /// 断言, 比较断言保留两侧的值, 失败时一并报告
+    Assert {
+        /// 条件, 比较断言为左侧的值
+        condition: Operand,
+        /// 比较断言的运算符与右侧的值
+        comparison: Option<(BinaryOperator, Operand)>,
+        /// 失败时的消息
+        message: Option<String>,
+        /// 断言的源码
+        expression: Option<String>,
+    },
+    /// 移除最内层的异常处理器
+    LeaveTry,
+    /// 异常处理块的第一条指令, 将抛出的值写入变量
+    Catch(Variable),
+    /// 之后的指令来自的源码位置, 不影响语义
+    Locate(Span),
+    /// SSA 形式中合并各前驱的值, 只出现在基本块开头
+    Phi {
+        /// 目标变量
+        target: Variable,
+        /// 前驱及其提供的值
+        sources: Vec<(BlockId, Operand)>,
+    },
+}
+
+/// 基本块的终结指令
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    /// 无条件跳转, 写作 `jump N`
+    Jump(BlockId),
+    /// 条件跳转, 写作 `jump N if %c; jump M`
+    Branch {
+        /// 条件
+        condition: Operand,
+        /// 条件为真时的目标
+        then_block: BlockId,
+        /// 条件为假时的目标
+        else_block: BlockId,
+    },
+    /// 返回, 写作 `^return(%x)`
+    Return(Operand),
+    /// 尾调用, 复用当前调用帧并直接返回被调用函数的结果, 写作 `tail call f (%a, %b)`
+    TailCall {
+        /// 被调用的函数
+        callee: Operand,
+        /// 参数
+        arguments: Vec<Operand>,
+    },
+    /// 注册异常处理器后进入 `body`, 之后抛出的值交给 `handler`, 写作 `try N catch M`
+    Try {
+        /// 尝试块
+        body: BlockId,
+        /// 异常处理块, 以 [`HirInstruction::Catch`] 开头
+        handler: BlockId,
+    },
+    /// 抛出值, 写作 `throw %x`
+    Throw(Operand),
+    /// 没有匹配的分支, 以被匹配的值报错, 写作 `unmatched %x`
+    Unmatched(Operand),
+}
+
+impl HirModule {
+    /// 程序入口函数
+    pub fn entry(&self) -> Option<&HirFunction> {
+        self.functions.first()
+    }
+
+    /// 按名称查找函数
+    pub fn function(&self, name: &str) -> Option<&HirFunction> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    /// 以唯一的变量名声明且该变量从未被重新赋值的函数, 值为声明所在函数的下标与变量名
+    pub(crate) fn static_bindings(&self) -> BTreeMap<&str, (usize, &str)> {
+        let mut definitions: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut bindings = BTreeMap::new();
+        for (index, function) in self.functions.iter().enumerate() {
+            for instruction in function.blocks.iter().flat_map(|block| &block.instructions) {
+                match instruction {
+                    HirInstruction::Declare { name, value } => {
+                        *definitions.entry(name).or_default() += 1;
+                        if let Rvalue::Function { function, .. } = value {
+                            bindings.insert(function.as_str(), (index, name.as_str()));
+                        }
+                    }
+                    HirInstruction::Assign { target: Variable::Local(name) | Variable::Global(name), .. } => {
+                        *definitions.entry(name).or_default() += 1
+                    }
+                    _ => {}
+                }
+            }
+        }
+        bindings.retain(|_, (_, name)| definitions[*name] == 1);
+        bindings
+    }
+}
+
+impl HirFunction {
+    /// 每个基本块的前驱
+    pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+        let mut predecessors = vec![vec![]; self.blocks.len()];
+        for (id, block) in self.blocks.iter().enumerate() {
+            for successor in block.terminator.successors() {
+                predecessors[successor].push(id)
+            }
+        }
+        predecessors
+    }
+}
+
+impl Terminator {
+    /// 可能跳转到的基本块
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Jump(target) => vec![*target],
+            Terminator::Branch { then_block, else_block, .. } => vec![*then_block, *else_block],
+            Terminator::Try { body, handler } => vec![*body, *handler],
+            Terminator::Return(_) | Terminator::TailCall { .. } | Terminator::Throw(_) | Terminator::Unmatched(_) => vec![],
+        }
+    }
+
+    /// 按 `mapping` 重新编号跳转目标
+    pub(crate) fn renumber(self, mapping: &[BlockId]) -> Self {
+        match self {
+            Terminator::Jump(target) => Terminator::Jump(mapping[target]),
+            Terminator::Branch { condition, then_block, else_block } => {
+                Terminator::Branch { condition, then_block: mapping[then_block], else_block: mapping[else_block] }
+            }
+            Terminator::Try { body, handler } => Terminator::Try { body: mapping[body], handler: mapping[handler] },
+            terminator @ (Terminator::Return(_)
+            | Terminator::TailCall { .. }
+            | Terminator::Throw(_)
+            | Terminator::Unmatched(_)) => terminator,
+        }
+    }
+}
+
+impl Rvalue {
+    /// 右值读取的全部操作数
+    pub fn operands(&self) -> Vec<&Operand> {
+        match self {
+            Rvalue::Use(operand) | Rvalue::Unary { operand, .. } | Rvalue::Length(operand) => vec![operand],
+            Rvalue::Binary { left, right, .. } => vec![left, right],
+            Rvalue::Call { callee: object, arguments } | Rvalue::CallMethod { object, arguments, .. } => {
+                std::iter::once(object).chain(arguments).collect()
+            }
+            Rvalue::CallSuper { receiver, parent, arguments, .. } => [receiver, parent].into_iter().chain(arguments).collect(),
+            Rvalue::Raise { arguments, .. } | Rvalue::List(arguments) => arguments.iter().collect(),
+            Rvalue::Object(properties) | Rvalue::Variant { fields: properties, .. } => {
+                properties.iter().map(|(_, value)| value).collect()
+            }
+            Rvalue::MatchShape { value, against, .. } => std::iter::once(value).chain(against).collect(),
+            Rvalue::New { class, properties } => {
+                std::iter::once(class).chain(properties.iter().map(|(_, value)| value)).collect()
+            }
+            Rvalue::GetProperty { object, .. } => vec![object],
+            Rvalue::GetIndex { object, index } => vec![object, index],
+            Rvalue::Function { .. } | Rvalue::Trait { .. } => vec![],
+            Rvalue::Class { parent, traits, properties, .. } => {
+                parent.iter().chain(traits).chain(properties.iter().map(|(_, value)| value)).collect()
+            }
+            Rvalue::Enum { variants, .. } => variants.iter().map(|(_, value)| value).collect(),
+        }
+    }
+
+    /// 右值读取的全部操作数的可变引用
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Rvalue::Use(operand) | Rvalue::Unary { operand, .. } | Rvalue::Length(operand) => vec![operand],
+            Rvalue::Binary { left, right, .. } => vec![left, right],
+            Rvalue::Call { callee: object, arguments } | Rvalue::CallMethod { object, arguments, .. } => {
+                std::iter::once(object).chain(arguments).collect()
+            }
+            Rvalue::CallSuper { receiver, parent, arguments, .. } => [receiver, parent].into_iter().chain(arguments).collect(),
+            Rvalue::Raise { arguments, .. } | Rvalue::List(arguments) => arguments.iter_mut().collect(),
+            Rvalue::Object(properties) | Rvalue::Variant { fields: properties, .. } => {
+                properties.iter_mut().map(|(_, value)| value).collect()
+            }
+            Rvalue::MatchShape { value, against, .. } => std::iter::once(value).chain(against).collect(),
+            Rvalue::New { class, properties } => {
+                std::iter::once(class).chain(properties.iter_mut().map(|(_, value)| value)).collect()
+            }
+            Rvalue::GetProperty { object, .. } => vec![object],
+            Rvalue::GetIndex { object, index } => vec![object, index],
+            Rvalue::Function { .. } | Rvalue::Trait { .. } => vec![],
+            Rvalue::Class { parent, traits, properties, .. } => {
+                parent.iter_mut().chain(traits.iter_mut()).chain(properties.iter_mut().map(|(_, value)| value)).collect()
+            }
+            Rvalue::Enum { variants, .. } => variants.iter_mut().map(|(_, value)| value).collect(),
+        }
+    }
+
+    /// 闭包按值捕获的变量名
+    pub fn captures(&self) -> Vec<String> {
+        match self {
+            Rvalue::Function { captures, .. } => captures.clone(),
+            _ => vec![],
+        }
+    }
+}
+
+impl Operand {
+    /// 操作数引用的变量
+    pub fn variable(&self) -> Option<&Variable> {
+        match self {
+            Operand::Variable(variable) => Some(variable),
+            Operand::Constant(_) => None,
+        }
+    }
+}
+
+impl HirInstruction {
+    /// 指令定义的变量
+    pub fn definition(&self) -> Option<Variable> {
+        match self {
+            HirInstruction::Declare { name, .. } => Some(Variable::Local(name.clone())),
+            HirInstruction::Assign { target, .. } | HirInstruction::Phi { target, .. } | HirInstruction::Catch(target) => {
+                Some(target.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// 指令读取的全部操作数, φ 指令的来源视为在对应前驱的末尾读取, 不包含在内
+    pub fn operands(&self) -> Vec<&Operand> {
+        match self {
+            HirInstruction::Declare { value, .. } | HirInstruction::Assign { value, .. } | HirInstruction::Evaluate(value) => {
+                value.operands()
+            }
+            HirInstruction::SetProperty { object, value, .. } => vec![object, value],
+            HirInstruction::SetIndex { object, index, value } => vec![object, index, value],
+            HirInstruction::Handle { handler, .. } => vec![handler],
+            HirInstruction::Assert { condition, comparison, .. } => {
+                std::iter::once(condition).chain(comparison.as_ref().map(|(_, right)| right)).collect()
+            }
+            HirInstruction::Phi { .. } | HirInstruction::LeaveTry | HirInstruction::Catch(_) | HirInstruction::Locate(_) => {
+                vec![]
+            }
+        }
+    }
+
+    /// 指令读取的全部操作数的可变引用, 不包含 φ 指令的来源
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            HirInstruction::Declare { value, .. } | HirInstruction::Assign { value, .. } | HirInstruction::Evaluate(value) => {
+                value.operands_mut()
+            }
+            HirInstruction::SetProperty { object, value, .. } => vec![object, value],
+            HirInstruction::SetIndex { object, index, value } => vec![object, index, value],
+            HirInstruction::Handle { handler, .. } => vec![handler],
+            HirInstruction::Assert { condition, comparison, .. } => {
+                std::iter::once(condition).chain(comparison.as_mut().map(|(_, right)| right)).collect()
+            }
+            HirInstruction::Phi { .. } | HirInstruction::LeaveTry | HirInstruction::Catch(_) | HirInstruction::Locate(_) => {
+                vec![]
+            }
+        }
+    }
+
+    /// 指令读取的变量, 闭包按值捕获的变量也视为读取
+    pub fn uses(&self) -> Vec<Variable> {
+        let mut uses: Vec<_> = self.operands().into_iter().filter_map(|operand| operand.variable().cloned()).collect();
+        if let HirInstruction::Declare { value, .. } | HirInstruction::Assign { value, .. } | HirInstruction::Evaluate(value) =
+            self
+        {
+            uses.extend(value.captures().into_iter().map(Variable::Local))
+        }
+        uses
+    }
+}
+
+impl Terminator {
+    /// 终结指令读取的操作数
+    pub fn operands(&self) -> Vec<&Operand> {
+        match self {
+            Terminator::Jump(_) | Terminator::Try { .. } => vec![],
+            Terminator::Branch { condition, .. } => vec![condition],
+            Terminator::Return(value) | Terminator::Throw(value) | Terminator::Unmatched(value) => vec![value],
+            Terminator::TailCall { callee, arguments } => std::iter::once(callee).chain(arguments).collect(),
+        }
+    }
+
+    /// 终结指令读取的操作数的可变引用
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Terminator::Jump(_) | Terminator::Try { .. } => vec![],
+            Terminator::Branch { condition, .. } => vec![condition],
+            Terminator::Return(value) | Terminator::Throw(value) | Terminator::Unmatched(value) => vec![value],
+            Terminator::TailCall { callee, arguments } => std::iter::once(callee).chain(arguments).collect(),
+        }
+    }
+}
+
+impl Variable {
+    /// 是否为函数内部的变量, 外层作用域的变量可能被调用的函数修改, 不参与数据流分析
+    pub fn is_local(&self) -> bool {
+        !matches!(self, Variable::Global(_))
+    }
+}
+
+impl From<Variable> for Operand {
+    fn from(variable: Variable) -> Self {
+        Operand::Variable(variable)
+    }
+}
+
+impl From<NyarValue> for Operand {
+    fn from(value: NyarValue) -> Self {
+        Operand::Constant(value)
+    }
+}
@@ -0,0 +1,200 @@
+//! 静态单赋值形式
+//!
+//! 在支配边界插入 φ 指令, 并沿支配树为每次定义分配新的版本。只在入口活跃的变量才需要 φ,
+//! 版本 0 即变量本身, 表示参数或尚未赋值的变量。
+
+use super::*;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+impl HirModule {
+    /// 将全部函数转换为 SSA 形式
+    ///
+    /// 被其他函数按名称读写或被闭包捕获的局部变量保持原样, 它们的值可能在调用时改变。
+    pub fn to_ssa(&self) -> HirModule {
+        let mut pinned = BTreeSet::new();
+        for function in &self.functions {
+            for block in &function.blocks {
+                for instruction in &block.instructions {
+                    let definition = instruction.definition();
+                    for variable in instruction.uses().into_iter().chain(definition) {
+                        if let Variable::Global(name) = variable {
+                            pinned.insert(name);
+                        }
+                    }
+                }
+                for operand in block.terminator.operands() {
+                    if let Some(Variable::Global(name)) = operand.variable() {
+                        pinned.insert(name.clone());
+                    }
+                }
+            }
+        }
+        HirModule { functions: self.functions.iter().map(|function| function.to_ssa(&pinned)).collect() }
+    }
+}
+
+impl HirFunction {
+    /// 将函数转换为 SSA 形式, `pinned` 中的局部变量与被闭包捕获的变量保持原样
+    ///
+    /// 含有尝试块的函数保持原样, 抛出的值可以从块的中间跳转到异常处理块, 不能用 φ 指令合并。
+    pub fn to_ssa(&self, pinned: &BTreeSet<String>) -> HirFunction {
+        if self.blocks.iter().any(|block| matches!(block.terminator, Terminator::Try { .. })) {
+            return self.clone();
+        }
+        // 入口块有前驱时 (如尾调用消除后的 `jump 0`), 先插入新的入口, 参数的 φ 才有来源
+        if self.blocks.iter().any(|block| block.terminator.successors().contains(&0)) {
+            let mapping: Vec<BlockId> = (1..=self.blocks.len()).collect();
+            let mut function = self.clone();
+            for block in function.blocks.iter_mut() {
+                block.terminator = block.terminator.clone().renumber(&mapping)
+            }
+            let entry =
+                BasicBlock { label: "function.entry".to_string(), instructions: vec![], terminator: Terminator::Jump(1) };
+            function.blocks.insert(0, entry);
+            return function.to_ssa(pinned);
+        }
+        let mut pinned = pinned.clone();
+        for block in &self.blocks {
+            for instruction in &block.instructions {
+                if let HirInstruction::Declare { value, .. }
+                | HirInstruction::Assign { value, .. }
+                | HirInstruction::Evaluate(value) = instruction
+                {
+                    pinned.extend(value.captures())
+                }
+            }
+        }
+        let renamable = |variable: &Variable| match variable {
+            Variable::Local(name) => !pinned.contains(name),
+            Variable::Temporary(_) => true,
+            Variable::Global(_) | Variable::Versioned(..) => false,
+        };
+        let dominators = Dominators::new(self);
+        let frontiers = dominators.frontiers(self);
+        let liveness = Liveness::new(self);
+
+        // 插入 φ 指令
+        let mut sites: BTreeMap<Variable, BTreeSet<BlockId>> = BTreeMap::new();
+        for (id, block) in self.blocks.iter().enumerate() {
+            if !dominators.is_reachable(id) {
+                continue;
+            }
+            for instruction in &block.instructions {
+                if let Some(variable) = instruction.definition().filter(renamable) {
+                    sites.entry(variable).or_default().insert(id);
+                }
+            }
+        }
+        let mut phis: Vec<Vec<Variable>> = vec![vec![]; self.blocks.len()];
+        for (variable, blocks) in sites {
+            let mut inserted = HashSet::new();
+            let mut work: Vec<_> = blocks.iter().copied().collect();
+            while let Some(block) = work.pop() {
+                for frontier in &frontiers[block] {
+                    if !liveness.live_in(*frontier).contains(&variable) || !inserted.insert(*frontier) {
+                        continue;
+                    }
+                    phis[*frontier].push(variable.clone());
+                    if !blocks.contains(frontier) {
+                        work.push(*frontier);
+                    }
+                }
+            }
+        }
+        let mut function = self.clone();
+        for (block, variables) in function.blocks.iter_mut().zip(&phis) {
+            let inserted = variables.iter().map(|variable| HirInstruction::Phi { target: variable.clone(), sources: vec![] });
+            block.instructions.splice(0..0, inserted);
+        }
+
+        // 沿支配树重命名
+        let mut renamer = Renamer { versions: BTreeMap::new(), stacks: BTreeMap::new(), renamable: &renamable };
+        let mut work = vec![Visit::Enter(0)];
+        while let Some(visit) = work.pop() {
+            let block = match visit {
+                Visit::Enter(block) if dominators.is_reachable(block) => block,
+                Visit::Enter(_) => continue,
+                Visit::Exit(pushed) => {
+                    for variable in pushed {
+                        renamer.stacks.entry(variable).or_default().pop();
+                    }
+                    continue;
+                }
+            };
+            let mut pushed = vec![];
+            for instruction in function.blocks[block].instructions.iter_mut() {
+                if !matches!(instruction, HirInstruction::Phi { .. }) {
+                    instruction.operands_mut().into_iter().for_each(|operand| renamer.read(operand));
+                }
+                renamer.write(instruction, &mut pushed);
+            }
+            function.blocks[block].terminator.operands_mut().into_iter().for_each(|operand| renamer.read(operand));
+            for successor in function.blocks[block].terminator.successors() {
+                for (index, original) in phis[successor].iter().enumerate() {
+                    let mut source = Operand::Variable(original.clone());
+                    renamer.read(&mut source);
+                    if let HirInstruction::Phi { sources, .. } = &mut function.blocks[successor].instructions[index] {
+                        if !sources.iter().any(|(predecessor, _)| *predecessor == block) {
+                            sources.push((block, source))
+                        }
+                    }
+                }
+            }
+            work.push(Visit::Exit(pushed));
+            work.extend(dominators.children(block).into_iter().rev().map(Visit::Enter));
+        }
+        for block in function.blocks.iter_mut() {
+            for instruction in block.instructions.iter_mut() {
+                if let HirInstruction::Phi { sources, .. } = instruction {
+                    sources.sort_by_key(|(predecessor, _)| *predecessor)
+                }
+            }
+        }
+        function
+    }
+}
+
+/// 支配树的非递归遍历
+enum Visit {
+    Enter(BlockId),
+    /// 离开子树时弹出的版本
+    Exit(Vec<Variable>),
+}
+
+struct Renamer<'r> {
+    /// 每个变量已分配的版本数
+    versions: BTreeMap<Variable, usize>,
+    /// 每个变量当前可见的版本
+    stacks: BTreeMap<Variable, Vec<usize>>,
+    renamable: &'r dyn Fn(&Variable) -> bool,
+}
+
+impl Renamer<'_> {
+    fn read(&self, operand: &mut Operand) {
+        if let Operand::Variable(variable) = operand {
+            if let Some(version) = self.stacks.get(variable).and_then(|stack| stack.last()) {
+                *variable = Variable::Versioned(Box::new(variable.clone()), *version)
+            }
+        }
+    }
+
+    fn write(&mut self, instruction: &mut HirInstruction, pushed: &mut Vec<Variable>) {
+        let variable = match instruction.definition() {
+            Some(variable) if (self.renamable)(&variable) => variable,
+            _ => return,
+        };
+        let version = self.versions.entry(variable.clone()).or_default();
+        *version += 1;
+        self.stacks.entry(variable.clone()).or_default().push(*version);
+        let target = Variable::Versioned(Box::new(variable.clone()), *version);
+        pushed.push(variable);
+        match instruction {
+            HirInstruction::Declare { value, .. } => {
+                let value = std::mem::replace(value, Rvalue::Use(Operand::Constant(NyarValue::Null)));
+                *instruction = HirInstruction::Assign { target, value }
+            }
+            HirInstruction::Assign { target: old, .. } | HirInstruction::Phi { target: old, .. } => *old = target,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! 尾调用消除
+//!
+//! 形如 `%t = call f (...); ^return(%t)` 的尾调用中, 对自身的调用改写为参数赋值后跳转到入口,
+//! 其他调用改写为复用调用帧的 `tail call`。调用后跳转到只返回 `%t` 的基本块, 如条件表达式的分支, 也是尾调用。
+
+use super::*;
+use std::collections::BTreeMap;
+
+impl HirModule {
+    /// 消除全部函数中的尾调用, 应在 [`HirModule::to_ssa`] 之前调用
+    ///
+    /// 只有函数在模块中以唯一的名称声明且从未被重新赋值时, 对该名称的调用才视为对自身的调用。
+    pub fn eliminate_tail_calls(&self) -> HirModule {
+        let bindings = self.static_bindings();
+        let functions = self.functions.iter().enumerate().map(|(index, function)| match index {
+            // 入口函数没有可以复用的调用帧
+            0 => function.clone(),
+            _ => {
+                let binding = bindings.get(function.name.as_str()).map(|(_, name)| *name);
+                function.eliminate_tail_calls(binding)
+            }
+        });
+        HirModule { functions: functions.collect() }
+    }
+}
+
+impl HirFunction {
+    /// 消除尾调用, `binding` 是外层作用域中指向该函数自身的变量名
+    ///
+    /// 注册了效应处理器的函数保持原样, 复用调用帧会提前移除处理器。
+    pub fn eliminate_tail_calls(&self, binding: Option<&str>) -> HirFunction {
+        let mut function = self.clone();
+        let instructions = || self.blocks.iter().flat_map(|block| &block.instructions);
+        if instructions().any(|instruction| matches!(instruction, HirInstruction::Handle { .. })) {
+            return function;
+        }
+        let mut uses: BTreeMap<Variable, usize> = BTreeMap::new();
+        for variable in instructions().flat_map(|instruction| instruction.uses()) {
+            *uses.entry(variable).or_default() += 1
+        }
+        for operand in self.blocks.iter().flat_map(|block| block.terminator.operands()) {
+            if let Some(variable) = operand.variable() {
+                *uses.entry(variable.clone()).or_default() += 1
+            }
+        }
+        let mut temporaries = instructions()
+            .filter_map(|instruction| match instruction.definition() {
+                Some(Variable::Temporary(index)) => Some(index),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let myself = binding.map(|name| Operand::Variable(Variable::Global(name.to_string())));
+        // 只返回某个变量的基本块
+        let returns: Vec<Option<&Variable>> = self
+            .blocks
+            .iter()
+            .map(|block| match &block.terminator {
+                Terminator::Return(Operand::Variable(result))
+                    if block.instructions.iter().all(|instruction| matches!(instruction, HirInstruction::Locate(_))) =>
+                {
+                    Some(result)
+                }
+                _ => None,
+            })
+            .collect();
+        for block in function.blocks.iter_mut() {
+            // 调用之后只可能有记录源码位置的指令
+            let Some(last) =
+                block.instructions.iter().rposition(|instruction| !matches!(instruction, HirInstruction::Locate(_)))
+            else {
+                continue;
+            };
+            let result = match &block.terminator {
+                Terminator::Return(Operand::Variable(result)) => Some(result),
+                Terminator::Jump(target) => returns[*target],
+                _ => None,
+            };
+            match (&block.instructions[last], result) {
+                (
+                    HirInstruction::Assign { target: target @ Variable::Temporary(_), value: Rvalue::Call { .. } },
+                    Some(result),
+                ) if target == result && uses[result] == 1 => {}
+                _ => continue,
+            }
+            block.instructions.truncate(last + 1);
+            let (callee, arguments) = match block.instructions.pop() {
+                Some(HirInstruction::Assign { value: Rvalue::Call { callee, arguments }, .. }) => (callee, arguments),
+                _ => unreachable!(),
+            };
+            if myself.as_ref() != Some(&callee) || arguments.len() != self.parameters.len() {
+                block.terminator = Terminator::TailCall { callee, arguments };
+                continue;
+            }
+            // 参数依次赋值, 读取已被覆盖的参数时先复制到临时变量
+            let mut assigned = vec![];
+            let mut assignments = vec![];
+            for (parameter, mut argument) in self.parameters.iter().zip(arguments) {
+                let target = Variable::Local(parameter.name.clone());
+                if argument.variable() == Some(&target) {
+                    continue;
+                }
+                if let Some(variable) = argument.variable().filter(|variable| assigned.contains(*variable)) {
+                    temporaries += 1;
+                    let copy = Variable::Temporary(temporaries);
+                    block
+                        .instructions
+                        .push(HirInstruction::Assign { target: copy.clone(), value: Rvalue::Use(variable.clone().into()) });
+                    argument = copy.into();
+                }
+                assigned.push(target.clone());
+                assignments.push(HirInstruction::Assign { target, value: Rvalue::Use(argument) });
+            }
+            block.instructions.extend(assignments);
+            block.terminator = Terminator::Jump(0);
+        }
+        function
+    }
+}
@@ -0,0 +1,48 @@
+//! 编译期常量
+//!
+//! 直接位于函数体或程序顶层的 `const` 声明, 若其名称在整个作用域 (包括嵌套函数) 中只绑定这一次,
+//! 声明之后的读取不会得到其他值, 初始值可以在编译期求出时即代入使用处。
+
+use super::NyarCompiler;
+use crate::{
+    ast::{Statement, StatementKind},
+    visit::Bindings,
+};
+use nyar_lir::NyarValue;
+use std::collections::BTreeMap;
+
+impl NyarCompiler {
+    /// 进入函数体或程序的作用域, 选出其中可以在编译期代入的常量
+    pub(crate) fn enter_constants(&mut self, statements: &[Statement]) {
+        let bindings = Bindings::count(statements);
+        let scope = statements
+            .iter()
+            .filter_map(|statement| match &statement.kind {
+                StatementKind::VariableDeclaration(declaration)
+                    if declaration.is_constant && bindings.get(&declaration.name) == 1 =>
+                {
+                    Some((declaration.name.clone(), None))
+                }
+                _ => None,
+            })
+            .collect();
+        self.constants.push(scope)
+    }
+    /// 离开函数体或程序的作用域
+    pub(crate) fn leave_constants(&mut self) {
+        self.constants.pop();
+    }
+    /// 记录常量的值, 进入作用域时未被选出的常量会被忽略
+    pub(crate) fn define_constant(&mut self, name: &str, value: NyarValue) {
+        if let Some(slot) = self.constants.last_mut().and_then(|scope| scope.get_mut(name)) {
+            *slot = Some(value)
+        }
+    }
+    /// 变量在编译期已知的值
+    pub(crate) fn constant(&self, name: &str) -> Option<&NyarValue> {
+        self.constants.iter().rev().find_map(|scope| scope.get(name)).and_then(|value| value.as_ref())
+    }
+}
+
+/// 编译期常量的作用域, 值为 `None` 表示常量尚未声明或初始值不是常量
+pub(crate) type ConstantScope = BTreeMap<String, Option<NyarValue>>;
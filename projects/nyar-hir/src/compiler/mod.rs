@@ -1,19 +1,209 @@
-use std::ops::Range;
+use crate::{
+    ast::{Program, Span},
+    cfg::{HirFunction, HirModule},
+    types::{EffectChecker, TypeChecker},
+};
 use nyar_error::{ArcStr, NyarError};
-use nyar_lir::Instruction;
-use crate::NyarProgram;
+use nyar_lir::{Instruction, LineTable, OptimizationLevel, PeepholeOptimizer};
+use std::{collections::HashSet, ops::Range, sync::Arc};
 
+mod constants;
+mod modules;
+
+pub use self::modules::ModuleResolver;
+pub(crate) use self::modules::module_variable;
+
+/// 编译器, 将 AST 转换为控制流图后编译为 LIR 指令序列
+#[derive(Debug, Default)]
 pub struct NyarCompiler {
     errors: Vec<NyarError>,
+    /// 已转换的函数, 入口函数在转换结束后加入
+    functions: Vec<HirFunction>,
+    /// 已使用的函数名称
+    function_names: HashSet<String>,
+    constants: Vec<constants::ConstantScope>,
+    /// 正在编译的节点的源码位置, 其中的文件即正在编译的文件
+    span: Span,
+    /// 正在编译的程序的源码
+    source: ArcStr,
+    optimization: OptimizationLevel,
+    /// 模块解析器
+    resolver: Option<Arc<dyn ModuleResolver>>,
+    /// 已编译的模块, 按初始化顺序排列
+    modules: Vec<modules::LoadedModule>,
+    /// 正在加载的模块, 用于检测循环导入
+    loading: Vec<String>,
 }
+
+/// 编译结果
+#[derive(Debug)]
 pub struct NyarCompiled {
     bytecode: Vec<Instruction>,
+    line_table: LineTable,
     errors: Vec<NyarError>,
     span: Range<usize>,
     file: ArcStr,
 }
+
 impl NyarCompiler {
-    pub fn compile(&mut self, ast: NyarProgram) -> nyar_error::Result<NyarCompiled> {
-        todo!()
+    /// 创建一个新的编译器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置优化级别, 默认不优化
+    ///
+    /// 优化时生成指令后做窥孔优化, [`OptimizationLevel::Full`] 还会先在控制流图上内联小函数。
+    pub fn with_optimization_level(mut self, level: OptimizationLevel) -> Self {
+        self.optimization = level;
+        self
+    }
+
+    /// 编译程序
+    ///
+    /// 可恢复的错误与类型错误会被收集到 [`NyarCompiled::errors`] 中, 编译会继续进行。
+    pub fn compile(&mut self, ast: &Program) -> nyar_error::Result<NyarCompiled> {
+        self.reset(ast);
+        for error in TypeChecker::new().check(ast) {
+            self.report(error)
+        }
+        for error in EffectChecker::new().check(ast) {
+            self.report(error)
+        }
+        self.load_imports(&ast.statements, &ast.file);
+        let mut module = HirModule::lower_program(self, ast)?;
+        if self.optimization == OptimizationLevel::Full {
+            module = module.inline_functions(INLINE_SIZE);
+        }
+        let (instructions, line_table) = module.eliminate_tail_calls().to_lir_with_line_table()?;
+        let (bytecode, line_table) = match self.optimization {
+            OptimizationLevel::None => (instructions, line_table),
+            level => PeepholeOptimizer::new(level).optimize_with_line_table(instructions, &line_table),
+        };
+        Ok(NyarCompiled {
+            bytecode,
+            line_table,
+            errors: std::mem::take(&mut self.errors),
+            span: ast.span.range.clone(),
+            file: ast.file.clone(),
+        })
+    }
+
+    /// 不做检查地将程序转换为控制流图, 转换中记录的第一个错误作为结果返回
+    pub(crate) fn lower_unchecked(&mut self, ast: &Program) -> nyar_error::Result<HirModule> {
+        self.reset(ast);
+        let module = HirModule::lower_program(self, ast)?;
+        match self.errors.drain(..).next() {
+            Some(error) => Err(error),
+            None => Ok(module),
+        }
+    }
+
+    fn reset(&mut self, ast: &Program) {
+        self.errors.clear();
+        self.functions.clear();
+        self.function_names = HashSet::from(["main".to_string()]);
+        self.constants.clear();
+        self.modules.clear();
+        self.loading.clear();
+        self.span = Span::new(ast.file.clone(), ast.span.range.clone());
+        self.source = ast.source.clone();
+    }
+
+    /// 以 `base` 为基础分配模块内唯一的函数名称
+    pub(crate) fn function_name(&mut self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut index = 1;
+        while self.function_names.contains(&name) {
+            index += 1;
+            name = format!("{}#{}", base, index);
+        }
+        self.function_names.insert(name.clone());
+        name
+    }
+    /// 加入转换完成的函数
+    pub(crate) fn add_function(&mut self, function: HirFunction) {
+        self.functions.push(function)
+    }
+    /// 取出已转换的函数
+    pub(crate) fn take_functions(&mut self) -> Vec<HirFunction> {
+        std::mem::take(&mut self.functions)
+    }
+    /// 正在编译的节点的源码位置
+    pub(crate) fn span(&self) -> &Span {
+        &self.span
+    }
+    /// 记录一个可恢复的错误, 错误没有位置信息时使用正在编译的节点的位置
+    pub(crate) fn report(&mut self, mut error: NyarError) {
+        if error.span().is_empty() {
+            error = error.with_span(self.span.range.clone());
+        }
+        if error.file().is_empty() {
+            error = error.with_file(self.span.file.clone());
+        }
+        self.errors.push(error);
+    }
+
+    /// 同一位置是否已经报告过错误, 如类型检查已报告的运算数类型错误
+    pub(crate) fn is_reported(&self, error: &NyarError) -> bool {
+        self.errors.iter().any(|reported| reported.span() == error.span() && reported.file() == error.file())
+    }
+    /// 区间内的源码, 没有源码时返回 `None`
+    pub(crate) fn source_text(&self, span: &Span) -> Option<String> {
+        match span.is_empty() {
+            true => None,
+            false => self.source.get(span.range.clone()).map(str::to_string),
+        }
+    }
+    /// 设置正在编译的节点的源码位置, 返回之前的位置
+    ///
+    /// 没有区间的合成节点沿用外层节点的区间, 没有文件的节点属于正在编译的文件。
+    pub(crate) fn swap_span(&mut self, span: Span) -> Span {
+        match (span.is_empty(), span.file.is_empty()) {
+            (true, _) => self.span.clone(),
+            (false, true) => {
+                let span = Span::new(self.span.file.clone(), span.range);
+                std::mem::replace(&mut self.span, span)
+            }
+            (false, false) => std::mem::replace(&mut self.span, span),
+        }
+    }
+}
+
+/// 优化时内联的函数的最大指令数
+const INLINE_SIZE: usize = 16;
+
+impl NyarCompiled {
+    /// 生成的指令序列
+    pub fn bytecode(&self) -> &[Instruction] {
+        &self.bytecode
+    }
+    /// 取出生成的指令序列
+    pub fn into_bytecode(self) -> Vec<Instruction> {
+        self.bytecode
+    }
+    /// 指令对应的源码位置
+    pub fn line_table(&self) -> &LineTable {
+        &self.line_table
+    }
+    /// 取出生成的指令序列与行号表
+    pub fn into_parts(self) -> (Vec<Instruction>, LineTable) {
+        (self.bytecode, self.line_table)
+    }
+    /// 编译期间收集到的错误
+    pub fn errors(&self) -> &[NyarError] {
+        &self.errors
+    }
+    /// 是否没有任何错误
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+    }
+    /// 源码区间
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+    /// 源文件
+    pub fn file(&self) -> &ArcStr {
+        &self.file
     }
 }
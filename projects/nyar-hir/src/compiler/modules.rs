@@ -0,0 +1,138 @@
+//! 模块加载
+//!
+//! 编译程序前先加载其导入的模块, 每个模块只编译一次, 被依赖的模块先于导入者初始化。
+//! 模块的顶层语句转换为一个函数, 入口函数开头依次调用, 得到只含导出绑定的模块对象,
+//! 存入以 `$module:` 开头的全局变量, 导入语句从中读取。
+
+use super::NyarCompiler;
+use crate::{
+    ast::{Program, Span, Statement, StatementKind},
+    cfg::HirModule,
+    types::{EffectChecker, TypeChecker},
+    visit::{Visitor, walk_statement},
+};
+use nyar_error::{ArcStr, NyarError, Result};
+use std::{fmt::Debug, sync::Arc};
+
+/// 模块解析器, 将导入路径映射到模块的源文件
+pub trait ModuleResolver: Debug {
+    /// 将 `importer` 中的导入路径解析为模块的唯一标识, 如规范化后的文件路径
+    fn resolve(&self, path: &str, importer: &str) -> Result<String>;
+    /// 读取并解析模块, 程序的源文件须为模块标识
+    fn load(&self, module: &str) -> Result<Program>;
+}
+
+/// 已编译的模块
+#[derive(Debug, Clone)]
+pub(crate) struct LoadedModule {
+    /// 模块的唯一标识
+    pub id: String,
+    /// 导出的名称, 按声明顺序排列
+    pub exports: Vec<String>,
+    /// 初始化模块的函数名称
+    pub function: String,
+}
+
+/// 收集语句中的导入路径及其源码区间, 包括函数体内的导入
+#[derive(Default)]
+struct Imports(Vec<(String, Span)>);
+
+impl Visitor for Imports {
+    fn visit_statement(&mut self, statement: &Statement) {
+        if let StatementKind::Import(import) = &statement.kind {
+            self.0.push((import.path.clone(), statement.span.clone()))
+        }
+        walk_statement(self, statement)
+    }
+}
+
+impl NyarCompiler {
+    /// 设置模块解析器, 未设置时导入语句报错
+    pub fn with_resolver(mut self, resolver: impl ModuleResolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// 加载 `importer` 导入的所有模块, 转换模块的初始化函数
+    pub(crate) fn load_imports(&mut self, statements: &[Statement], importer: &ArcStr) {
+        let mut imports = Imports::default();
+        for statement in statements {
+            imports.visit_statement(statement)
+        }
+        for (path, span) in imports.0 {
+            if let Err(error) = self.load_module(&path, importer) {
+                self.report_at(error, span)
+            }
+        }
+    }
+    fn load_module(&mut self, path: &str, importer: &ArcStr) -> Result<()> {
+        let Some(resolver) = self.resolver.clone()
+        else {
+            return Err(NyarError::custom(format!("cannot import `{}`, no module resolver is configured", path)));
+        };
+        let id = resolver.resolve(path, importer)?;
+        if self.module(&id).is_some() {
+            return Ok(());
+        }
+        if let Some(start) = self.loading.iter().position(|module| module == &id) {
+            let mut cycle = self.loading[start..].to_vec();
+            cycle.push(id);
+            return Err(NyarError::custom(format!("import cycle: {}", cycle.join(" -> "))));
+        }
+        let program = resolver.load(&id)?;
+        self.loading.push(id.clone());
+        self.load_imports(&program.statements, &ArcStr::from(id.as_str()));
+        let result = self.compile_module(id, &program);
+        self.loading.pop();
+        result
+    }
+    /// 转换模块的初始化函数, 函数返回模块对象
+    fn compile_module(&mut self, id: String, program: &Program) -> Result<()> {
+        let exports: Vec<String> = program
+            .statements
+            .iter()
+            .filter_map(|statement| match &statement.kind {
+                StatementKind::Export(export) => export.name(),
+                _ => None,
+            })
+            .map(str::to_string)
+            .collect();
+        let source = std::mem::replace(&mut self.source, program.source.clone());
+        let span = std::mem::replace(&mut self.span, Span::new(program.file.clone(), program.span.range.clone()));
+        for error in TypeChecker::new().check(program) {
+            self.report(error)
+        }
+        for error in EffectChecker::new().check(program) {
+            self.report(error)
+        }
+        let result = HirModule::lower_module(self, &id, program, &exports);
+        self.source = source;
+        self.span = span;
+        self.modules.push(LoadedModule { id, exports, function: result? });
+        Ok(())
+    }
+    /// 已编译的模块, 按初始化顺序排列
+    pub(crate) fn loaded_modules(&self) -> &[LoadedModule] {
+        &self.modules
+    }
+    /// 正在编译的文件中导入路径对应的已编译模块, 加载失败时返回 `None`
+    pub(crate) fn imported_module(&self, path: &str) -> Option<&LoadedModule> {
+        let id = self.resolver.as_ref()?.resolve(path, &self.span.file).ok()?;
+        self.module(&id)
+    }
+    fn module(&self, id: &str) -> Option<&LoadedModule> {
+        self.modules.iter().find(|module| module.id == id)
+    }
+    /// 记录导入语句处的错误, 错误已有位置信息时保留原位置
+    fn report_at(&mut self, error: NyarError, span: Span) {
+        match error.span().is_empty() {
+            true => self.errors.push(span.locate(error)),
+            false => self.errors.push(error),
+        }
+    }
+}
+
+/// 保存模块对象的全局变量名
+pub(crate) fn module_variable(id: &str) -> String {
+    format!("$module:{}", id)
+}
@@ -1,7 +1,11 @@
+//! Nyar 高级中间表示与编译器
+//!
+//! 这个模块定义了 Nyar 语言的抽象语法树, 将其转换为控制流图后编译为 [`nyar_lir`] 指令序列。
 
-
+pub mod ast;
+pub mod cfg;
 mod compiler;
-mod statements;
+pub mod types;
+pub mod visit;
 
-pub use crate::compiler::{NyarCompiled, NyarCompiler};
-pub use crate::statements::{NyarProgram, NyarStatement};
\ No newline at end of file
+pub use crate::compiler::{ModuleResolver, NyarCompiled, NyarCompiler};
@@ -0,0 +1,1341 @@
+//! 渐进的类型检查与推断
+//!
+//! 作用域按函数划分, 与虚拟机的调用帧一致。没有注解的变量与参数只在整个函数中仅绑定一次时才推断类型,
+//! 否则为 [`NyarType::Any`], 重新赋值不会与推断的类型冲突。没有注解的返回类型在各处返回值类型相同时才确定。
+//! 绑定到 lambda 的变量被泛化, 没有注解的列表可以存入任何类型的元素。
+
+use super::NyarType;
+use crate::{
+    ast::{
+        Assignment, ClassDefinition, EnumDefinition, Expression, ExpressionKind, FunctionDefinition, IfStatement, Literal,
+        LoopStatement, MatchExpression, NewExpression, Parameter, Pattern, Program, Span, Statement, StatementKind,
+        StructureDefinition, TraitDefinition,
+    },
+    visit::{Bindings, Visitor, walk_class, walk_enum, walk_trait},
+};
+use nyar_error::NyarError;
+use nyar_lir::{
+    BinaryOperator, UnaryOperator,
+    values::{NumericMethod, NumericType, OverflowMode, StringMethod},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+};
+
+/// 类型检查器, 收集类型错误而不中断检查
+///
+/// 检查之后可以用 [`TypeChecker::type_of`] 与 [`TypeChecker::type_at`] 查询节点的类型。
+#[derive(Debug, Default)]
+pub struct TypeChecker {
+    definitions: BTreeMap<String, TypeDefinition>,
+    scopes: Vec<FunctionScope>,
+    /// 作用域中的泛型参数
+    generics: Vec<String>,
+    /// 类型变量的绑定
+    substitution: Vec<Option<NyarType>>,
+    /// 表达式、声明与参数的类型
+    types: Vec<(Range<usize>, NyarType)>,
+    diagnostics: Vec<NyarError>,
+    /// 正在检查的语句的源码位置
+    span: Span,
+}
+
+/// 类、特征或枚举的声明
+#[derive(Debug)]
+struct TypeDefinition {
+    kind: DefinitionKind,
+    /// 泛型参数
+    generics: Vec<String>,
+    /// 父类
+    parent: Option<String>,
+    /// 实现的特征
+    traits: Vec<String>,
+    /// 属性与方法的类型
+    members: BTreeMap<String, NyarType>,
+    /// 特征中没有默认实现的方法
+    abstracts: BTreeSet<String>,
+    /// 枚举的变体与其字段的类型
+    variants: Vec<(String, Vec<(String, NyarType)>)>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum DefinitionKind {
+    Class,
+    Trait,
+    Enum,
+    Structure,
+}
+
+/// 类型方案, `parameters` 中的泛型参数在每次使用时替换为新的类型变量
+#[derive(Debug, Clone)]
+struct Scheme {
+    parameters: Vec<String>,
+    body: NyarType,
+}
+
+/// 正在检查的函数
+#[derive(Debug)]
+struct FunctionScope {
+    variables: BTreeMap<String, Scheme>,
+    /// 函数体中每个名称被绑定的次数
+    bindings: Bindings,
+    /// 被多次绑定而视为 `Any` 的变量初始值的类型, 用于诊断信息
+    initial: BTreeMap<String, NyarType>,
+    /// 没有注解的变量与参数
+    inferred: BTreeSet<String>,
+    /// 注解的返回类型
+    output: Option<NyarType>,
+    /// 没有注解时收集的返回值类型
+    returns: Vec<NyarType>,
+}
+
+impl TypeChecker {
+    /// 创建一个新的类型检查器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 检查程序, 返回全部类型错误
+    pub fn check(&mut self, program: &Program) -> Vec<NyarError> {
+        self.definitions.clear();
+        self.scopes.clear();
+        self.generics.clear();
+        self.substitution.clear();
+        self.types.clear();
+        self.span = program.span.clone();
+        self.collect_definitions(program);
+        self.scopes.push(FunctionScope::new(BTreeMap::new(), &program.statements, None));
+        self.check_block(&program.statements);
+        self.scopes.pop();
+        // 仍未确定的类型变量可以是任何类型
+        let types = std::mem::take(&mut self.types);
+        self.types = types.into_iter().map(|(span, t)| (span, self.finish(&t))).collect();
+        std::mem::take(&mut self.diagnostics)
+    }
+    /// 区间恰好为 `span` 的节点的类型
+    pub fn type_of(&self, span: Range<usize>) -> Option<&NyarType> {
+        self.types.iter().rev().find(|(node, _)| *node == span).map(|(_, t)| t)
+    }
+    /// 包含位置 `offset` 的最内层节点的区间与类型
+    pub fn type_at(&self, offset: usize) -> Option<(Range<usize>, &NyarType)> {
+        self.types
+            .iter()
+            .rev()
+            .filter(|(node, _)| node.contains(&offset))
+            .min_by_key(|(node, _)| node.len())
+            .map(|(node, t)| (node.clone(), t))
+    }
+
+    fn collect_definitions(&mut self, program: &Program) {
+        let mut collector = Definitions::default();
+        collector.visit_program(program);
+        for (name, (kind, generics)) in collector.kinds {
+            let definition = TypeDefinition {
+                kind,
+                generics,
+                parent: None,
+                traits: vec![],
+                members: BTreeMap::new(),
+                abstracts: BTreeSet::new(),
+                variants: vec![],
+            };
+            self.definitions.insert(name, definition);
+        }
+        for class in &collector.classes {
+            self.generics.extend(class.generics.iter().cloned());
+            let mut members = BTreeMap::new();
+            for property in &class.properties {
+                members.insert(property.name.clone(), self.resolve_silently(property.type_annotation.as_deref()));
+            }
+            for method in &class.methods {
+                members.insert(
+                    method.name.clone(),
+                    self.signature(&method.generics, receiverless(&method.parameters), &method.return_type).body,
+                );
+            }
+            self.generics.clear();
+            if let Some(definition) = self.definitions.get_mut(&class.name) {
+                definition.parent = class.parent.clone();
+                definition.traits = class.traits.clone();
+                definition.members = members;
+            }
+        }
+        for definition in &collector.traits {
+            let members = definition
+                .methods
+                .iter()
+                .map(|method| {
+                    (method.name.clone(), self.signature(&[], receiverless(&method.parameters), &method.return_type).body)
+                })
+                .collect();
+            let abstracts =
+                definition.methods.iter().filter(|method| method.body.is_none()).map(|method| method.name.clone()).collect();
+            if let Some(definition) = self.definitions.get_mut(&definition.name) {
+                definition.members = members;
+                definition.abstracts = abstracts;
+            }
+        }
+        for definition in &collector.enums {
+            self.generics.extend(definition.generics.iter().cloned());
+            let variants = definition
+                .variants
+                .iter()
+                .map(|variant| {
+                    let fields = variant.fields.types().into_iter();
+                    (variant.name.clone(), fields.map(|(name, t)| (name, self.resolve_silently(t.as_deref()))).collect())
+                })
+                .collect();
+            self.generics.clear();
+            if let Some(found) = self.definitions.get_mut(&definition.name) {
+                found.variants = variants;
+            }
+        }
+        for definition in &collector.structures {
+            let members = definition
+                .fields
+                .iter()
+                .map(|field| (field.name.clone(), self.resolve_silently(Some(&field.field_type))))
+                .collect();
+            if let Some(found) = self.definitions.get_mut(&definition.name) {
+                found.members = members;
+            }
+        }
+    }
+
+    /// 类 `from` 是否为 `to` 或继承了 `to`, 或实现了特征 `to`
+    fn is_subtype(&self, from: &str, to: &str) -> bool {
+        let mut visited = BTreeSet::new();
+        let mut work = vec![from];
+        while let Some(name) = work.pop() {
+            if name == to {
+                return true;
+            }
+            if !visited.insert(name) {
+                continue;
+            }
+            if let Some(definition) = self.definitions.get(name) {
+                work.extend(definition.parent.as_deref());
+                work.extend(definition.traits.iter().map(|name| name.as_str()));
+            }
+        }
+        false
+    }
+    /// `E.A` 形式的变体构造器的名称与字段数, 枚举名被变量遮蔽时不是构造器
+    fn constructor_arity(&self, callee: &Expression) -> Option<(String, usize)> {
+        let ExpressionKind::MemberAccess(access) = &callee.kind
+        else {
+            return None;
+        };
+        let ExpressionKind::Variable(name) = &access.object.kind
+        else {
+            return None;
+        };
+        if self.scopes.iter().any(|scope| scope.variables.contains_key(name)) {
+            return None;
+        }
+        let definition = self.definitions.get(name).filter(|definition| definition.kind == DefinitionKind::Enum)?;
+        let (variant, fields) = definition.variants.iter().find(|(variant, _)| *variant == access.member)?;
+        Some((format!("{}.{}", name, variant), fields.len()))
+    }
+    /// 在类及其父类与特征中查找成员, 返回声明成员的类型名与成员的类型
+    fn member(&self, name: &str, member: &str) -> Option<(String, NyarType)> {
+        let mut visited = BTreeSet::new();
+        let mut work = vec![name];
+        while let Some(name) = work.pop() {
+            if !visited.insert(name) {
+                continue;
+            }
+            let definition = self.definitions.get(name)?;
+            if let Some(found) = definition.members.get(member) {
+                return Some((name.to_string(), found.clone()));
+            }
+            work.extend(definition.traits.iter().rev().map(|name| name.as_str()));
+            work.extend(definition.parent.as_deref());
+        }
+        None
+    }
+
+    /// 新的类型变量
+    fn fresh(&mut self) -> NyarType {
+        self.substitution.push(None);
+        NyarType::Variable(self.substitution.len() - 1)
+    }
+    /// 展开已绑定的类型变量
+    fn apply(&self, t: &NyarType) -> NyarType {
+        t.replace(&mut |t| match t {
+            NyarType::Variable(v) => Some(self.substitution[*v].as_ref().map_or_else(|| t.clone(), |bound| self.apply(bound))),
+            _ => None,
+        })
+    }
+    /// 展开已绑定的类型变量, 未绑定的类型变量视为未知类型
+    fn finish(&self, t: &NyarType) -> NyarType {
+        self.apply(t).replace(&mut |t| matches!(t, NyarType::Variable(_)).then_some(NyarType::Any))
+    }
+    /// 合一两个类型, `found` 的值能否用在需要 `expected` 的位置
+    ///
+    /// 类可以用在需要其父类或特征的位置, 未知类型与任何类型兼容。
+    fn unify(&mut self, found: &NyarType, expected: &NyarType) -> bool {
+        let found = self.apply(found);
+        let expected = self.apply(expected);
+        match (&found, &expected) {
+            (NyarType::Any, _) | (_, NyarType::Any) => true,
+            (NyarType::Variable(a), NyarType::Variable(b)) if a == b => true,
+            (NyarType::Variable(v), other) | (other, NyarType::Variable(v)) => {
+                let mut variables = vec![];
+                other.variables(&mut variables);
+                if variables.contains(v) {
+                    return false;
+                }
+                self.substitution[*v] = Some(other.clone());
+                true
+            }
+            (NyarType::List(found), NyarType::List(expected)) => self.unify(found, expected),
+            // 整数可以用在需要浮点数的位置
+            (NyarType::Integer, NyarType::Float) => true,
+            (
+                NyarType::Function { parameters: found_parameters, output: found_output },
+                NyarType::Function { parameters: expected_parameters, output: expected_output },
+            ) => {
+                found_parameters.len() == expected_parameters.len()
+                    && expected_parameters.iter().zip(found_parameters).all(|(expected, found)| self.unify(expected, found))
+                    && self.unify(found_output, expected_output)
+            }
+            (
+                NyarType::Named { name: found_name, arguments: found_arguments },
+                NyarType::Named { name: expected_name, arguments: expected_arguments },
+            ) if found_name == expected_name => {
+                found_arguments.is_empty()
+                    || expected_arguments.is_empty()
+                    || found_arguments.len() == expected_arguments.len()
+                        && found_arguments.iter().zip(expected_arguments).all(|(found, expected)| self.unify(found, expected))
+            }
+            (NyarType::Named { name: found, .. }, NyarType::Named { name: expected, .. }) => self.is_subtype(found, expected),
+            _ => found == expected,
+        }
+    }
+    /// 泛化不在环境中出现的类型变量
+    fn generalize(&self, t: &NyarType, generics: &[String]) -> Scheme {
+        let t = self.apply(t);
+        let mut environment = vec![];
+        for scope in &self.scopes {
+            for scheme in scope.variables.values() {
+                self.apply(&scheme.body).variables(&mut environment)
+            }
+        }
+        let mut variables = vec![];
+        t.variables(&mut variables);
+        variables.retain(|v| !environment.contains(v));
+        let names: Vec<String> = (0..variables.len())
+            .map(|index| match index < 26 {
+                true => format!("'{}", (b'a' + index as u8) as char),
+                false => format!("'t{}", index),
+            })
+            .collect();
+        let body = t.replace(&mut |t| match t {
+            NyarType::Variable(v) => variables.iter().position(|u| u == v).map(|index| NyarType::Generic(names[index].clone())),
+            _ => None,
+        });
+        Scheme { parameters: generics.iter().cloned().chain(names).collect(), body }
+    }
+    /// 将类型方案的泛型参数替换为新的类型变量
+    fn instantiate(&mut self, scheme: &Scheme) -> NyarType {
+        let fresh: Vec<NyarType> = scheme.parameters.iter().map(|_| self.fresh()).collect();
+        scheme.body.replace(&mut |t| match t {
+            NyarType::Generic(name) => scheme.parameters.iter().position(|p| p == name).map(|index| fresh[index].clone()),
+            _ => None,
+        })
+    }
+
+    fn report(&mut self, message: impl ToString, span: Span) {
+        let span = if span.is_empty() { self.span.clone() } else { span };
+        self.diagnostics.push(span.locate(NyarError::type_error(message, span.range.clone())))
+    }
+    /// 检查 `found` 能否用在需要 `expected` 的位置
+    fn expect(&mut self, found: &NyarType, expected: &NyarType, span: Span) -> bool {
+        if self.unify(found, expected) {
+            return true;
+        }
+        let message = format!("expected `{}`, found `{}`", self.apply(expected), self.apply(found));
+        self.report(message, span);
+        false
+    }
+    fn record(&mut self, span: &Span, t: &NyarType) {
+        if !span.is_empty() {
+            self.types.push((span.range.clone(), t.clone()))
+        }
+    }
+
+    /// 解析类型注解, 未声明的类型名替换为 [`NyarType::Any`]
+    fn lower(&self, annotation: NyarType, problems: &mut Vec<String>) -> NyarType {
+        annotation.replace(&mut |t| {
+            let (name, arguments) = match t {
+                NyarType::Named { name, arguments } => (name, arguments),
+                _ => return None,
+            };
+            if arguments.is_empty() && self.generics.contains(name) {
+                return Some(NyarType::Generic(name.clone()));
+            }
+            let generics = match self.definitions.get(name) {
+                Some(definition) => definition.generics.len(),
+                None => {
+                    problems.push(format!("unknown type `{}`", name));
+                    return Some(NyarType::Any);
+                }
+            };
+            if !arguments.is_empty() && arguments.len() != generics {
+                problems.push(format!("`{}` expects {} type arguments, found {}", name, generics, arguments.len()));
+                return Some(NyarType::named(name.clone()));
+            }
+            let arguments = arguments.iter().map(|argument| self.lower(argument.clone(), problems)).collect();
+            Some(NyarType::Named { name: name.clone(), arguments })
+        })
+    }
+    /// 解析类型注解并报告其中的错误
+    fn resolve(&mut self, annotation: &str, span: Span) -> NyarType {
+        let parsed = match NyarType::parse(annotation) {
+            Ok(parsed) => parsed,
+            Err(message) => {
+                self.report(message, span);
+                return NyarType::Any;
+            }
+        };
+        let mut problems = vec![];
+        let resolved = self.lower(parsed, &mut problems);
+        for message in problems {
+            self.report(message, span.clone())
+        }
+        resolved
+    }
+    /// 解析类型注解, 不报告错误, 没有注解时为 [`NyarType::Any`]
+    fn resolve_silently(&self, annotation: Option<&str>) -> NyarType {
+        match annotation.map(NyarType::parse) {
+            Some(Ok(parsed)) => self.lower(parsed, &mut vec![]),
+            _ => NyarType::Any,
+        }
+    }
+    /// 由注解得到的函数类型方案
+    fn signature(&mut self, generics: &[String], parameters: &[Parameter], output: &Option<String>) -> Scheme {
+        let depth = self.generics.len();
+        self.generics.extend(generics.iter().cloned());
+        let parameters = parameters.iter().map(|parameter| self.resolve_silently(parameter.type_annotation.as_deref()));
+        let body = NyarType::function(parameters.collect(), self.resolve_silently(output.as_deref()));
+        self.generics.truncate(depth);
+        Scheme { parameters: generics.to_vec(), body }
+    }
+
+    fn scope(&mut self) -> &mut FunctionScope {
+        self.scopes.last_mut().expect("type checking outside of a function")
+    }
+    fn lookup(&mut self, name: &str) -> Option<NyarType> {
+        let scheme = self.scopes.iter().rev().find_map(|scope| scope.variables.get(name)).cloned()?;
+        Some(self.instantiate(&scheme))
+    }
+    /// 绑定没有注解的名称, 只绑定一次时才使用推断的类型
+    fn bind_inferred(&mut self, name: &str, scheme: Scheme) {
+        let scope = self.scope();
+        scope.inferred.insert(name.to_string());
+        let scheme = match scope.bindings.get(name) {
+            1 => scheme,
+            _ => {
+                scope.initial.insert(name.to_string(), scheme.body);
+                Scheme::from(NyarType::Any)
+            }
+        };
+        scope.variables.insert(name.to_string(), scheme);
+    }
+    /// 表达式是否为没有注解的变量
+    fn is_inferred(&self, expression: &Expression) -> bool {
+        let ExpressionKind::Variable(name) = &expression.kind
+        else {
+            return false;
+        };
+        let scope = self.scopes.iter().rev().find(|scope| scope.variables.contains_key(name));
+        scope.is_some_and(|scope| scope.inferred.contains(name))
+    }
+    /// 诊断信息中展示的类型, 被重新赋值的变量展示其初始值的类型而不是 `Any`
+    fn shown(&self, expression: &Expression, t: &NyarType) -> NyarType {
+        let t = self.apply(t);
+        let ExpressionKind::Variable(name) = &expression.kind
+        else {
+            return t;
+        };
+        let scope = self.scopes.iter().rev().find(|scope| scope.variables.contains_key(name));
+        match (t, scope.and_then(|scope| scope.initial.get(name))) {
+            (NyarType::Any, Some(initial)) => self.apply(initial),
+            (t, _) => t,
+        }
+    }
+
+    /// 检查函数体, 返回函数的返回类型
+    fn check_body(
+        &mut self,
+        parameters: &[Parameter],
+        annotations: Vec<Option<NyarType>>,
+        output: Option<NyarType>,
+        body: &[Statement],
+    ) -> (Vec<NyarType>, NyarType) {
+        let bindings = Bindings::count(body);
+        let mut types = vec![];
+        let mut variables = BTreeMap::new();
+        let mut inferred = BTreeSet::new();
+        for (parameter, annotation) in parameters.iter().zip(annotations) {
+            if annotation.is_none() {
+                inferred.insert(parameter.name.clone());
+            }
+            // 在函数体中被重新绑定的参数可能具有不同的类型
+            let t = match annotation {
+                Some(t) => t,
+                None if bindings.get(&parameter.name) == 0 => self.fresh(),
+                None => NyarType::Any,
+            };
+            self.record(&parameter.span, &t);
+            variables.insert(parameter.name.clone(), Scheme::from(t.clone()));
+            types.push(t);
+        }
+        self.scopes.push(FunctionScope {
+            variables,
+            bindings,
+            initial: BTreeMap::new(),
+            inferred,
+            output: output.clone(),
+            returns: vec![],
+        });
+        if let Some((value, span)) = self.check_block(body) {
+            self.returned(value, span)
+        }
+        let scope = self.scopes.pop().expect("type checking outside of a function");
+        let output = match output {
+            Some(output) => output,
+            None => {
+                let returns: Vec<NyarType> = scope.returns.iter().map(|t| self.apply(t)).collect();
+                match returns.split_first() {
+                    None => NyarType::Null,
+                    Some((first, rest)) if rest.iter().all(|t| t == first) => first.clone(),
+                    Some(_) => NyarType::Any,
+                }
+            }
+        };
+        (types, output)
+    }
+    /// 函数返回了 `value`
+    fn returned(&mut self, value: NyarType, span: Span) {
+        match self.scope().output.clone() {
+            Some(output) => {
+                self.expect(&value, &output, span);
+            }
+            None => self.scope().returns.push(value),
+        }
+    }
+    /// 检查函数定义, 返回泛化后的函数类型
+    fn check_function(&mut self, function: &FunctionDefinition) -> Scheme {
+        let depth = self.generics.len();
+        self.generics.extend(function.generics.iter().cloned());
+        let annotations = function
+            .parameters
+            .iter()
+            .map(|parameter| {
+                parameter.type_annotation.as_ref().map(|annotation| self.resolve(annotation, parameter.span.clone()))
+            })
+            .collect();
+        let output = function.return_type.as_ref().map(|annotation| self.resolve(annotation, function.span.clone()));
+        let (parameters, output) = self.check_body(&function.parameters, annotations, output, &function.body);
+        self.generics.truncate(depth);
+        let scheme = self.generalize(&NyarType::function(parameters, output), &function.generics);
+        self.record(&function.span, &scheme.body);
+        scheme
+    }
+    /// 检查语句序列, 返回最后一个表达式语句的类型与区间
+    ///
+    /// 序列中声明的函数先以注解得到的类型绑定, 以便在声明之前的函数中调用。
+    fn check_block(&mut self, statements: &[Statement]) -> Option<(NyarType, Span)> {
+        for statement in statements {
+            if let StatementKind::FunctionDeclaration(function) = &statement.kind {
+                let signature = self.signature(&function.generics, &function.parameters, &function.return_type);
+                self.bind_inferred(&function.name, signature)
+            }
+        }
+        let mut value = None;
+        for statement in statements {
+            value = self.check_statement(statement);
+        }
+        value
+    }
+    /// 检查语句, 表达式语句返回其类型与区间
+    fn check_statement(&mut self, statement: &Statement) -> Option<(NyarType, Span)> {
+        let outer = match statement.span.is_empty() {
+            true => self.span.clone(),
+            false => std::mem::replace(&mut self.span, statement.span.clone()),
+        };
+        let mut value = None;
+        match &statement.kind {
+            StatementKind::Expression(expression) => {
+                let span = self.span_of(expression);
+                value = Some((self.infer(expression), span))
+            }
+            StatementKind::Throw(expression) | StatementKind::Assert(expression, _) => {
+                self.infer(expression);
+            }
+            StatementKind::VariableDeclaration(declaration) => {
+                let inferred =
+                    declaration.initializer.as_ref().map(|initializer| (self.infer(initializer), self.span_of(initializer)));
+                let declared = match &declaration.type_annotation {
+                    Some(annotation) => {
+                        let declared = self.resolve(annotation, statement.span.clone());
+                        if let Some((inferred, span)) = inferred {
+                            self.expect(&inferred, &declared, span);
+                        }
+                        self.scope().variables.insert(declaration.name.clone(), Scheme::from(declared.clone()));
+                        declared
+                    }
+                    None => {
+                        let inferred = inferred.map_or(NyarType::Null, |(inferred, _)| inferred);
+                        // 只泛化 lambda, 其他值的类型变量可能在之后被确定
+                        let scheme = match declaration.initializer.as_ref().map(|initializer| &initializer.kind) {
+                            Some(ExpressionKind::Lambda(_)) => self.generalize(&inferred, &[]),
+                            _ => Scheme::from(inferred),
+                        };
+                        self.bind_inferred(&declaration.name, scheme);
+                        let declared = self.scope().variables[&declaration.name].body.clone();
+                        declared
+                    }
+                };
+                self.record(&statement.span, &declared)
+            }
+            StatementKind::Assignment(assignment) => self.check_assignment(assignment),
+            StatementKind::If(IfStatement { condition, then_branch, else_branch }) => {
+                self.infer(condition);
+                self.check_block(then_branch);
+                if let Some(branch) = else_branch {
+                    self.check_block(branch);
+                }
+            }
+            StatementKind::Loop(statement) => self.check_loop(statement),
+            StatementKind::Break | StatementKind::Continue => {}
+            StatementKind::Return(returned) => {
+                let (found, span) = match returned {
+                    Some(returned) => (self.infer(returned), self.span_of(returned)),
+                    None => (NyarType::Null, statement.span.clone()),
+                };
+                self.returned(found, span)
+            }
+            StatementKind::Block(statements) => {
+                self.check_block(statements);
+            }
+            StatementKind::FunctionDeclaration(function) => {
+                let scheme = self.check_function(function);
+                self.bind_inferred(&function.name, scheme)
+            }
+            StatementKind::ClassDeclaration(class) => self.check_class(class),
+            StatementKind::TraitDeclaration(definition) => self.check_trait(definition),
+            StatementKind::StructureDeclaration(definition) => {
+                if let Err(e) = definition.layout() {
+                    self.diagnostics.push(e);
+                }
+            }
+            StatementKind::EnumDeclaration(definition) => {
+                for variant in &definition.variants {
+                    if let Some(value) = &variant.value {
+                        self.infer(value);
+                    }
+                }
+            }
+            StatementKind::EffectHandler(handler) => {
+                self.check_function(&handler.handler);
+            }
+            StatementKind::Import(_) => {}
+            StatementKind::Export(export) => value = self.check_statement(&export.declaration),
+            StatementKind::TryCatch(statement) => {
+                self.check_block(&statement.try_block);
+                for block in &statement.catch_blocks {
+                    self.scope().variables.insert(block.error_variable.clone(), Scheme::from(NyarType::Any));
+                    self.check_block(&block.handler);
+                }
+                if let Some(block) = &statement.finally_block {
+                    self.check_block(block);
+                }
+            }
+        }
+        self.span = outer;
+        value
+    }
+    fn check_assignment(&mut self, assignment: &Assignment) {
+        let value = self.infer(&assignment.value);
+        let span = self.span_of(&assignment.value);
+        let expected = match &assignment.target.kind {
+            ExpressionKind::Variable(name) => self.lookup(name),
+            // 没有注解的列表的元素类型只是初始值的类型, 可以存入其他类型的元素
+            ExpressionKind::IndexAccess(access) if self.is_inferred(&access.object) => {
+                self.infer(&assignment.target);
+                None
+            }
+            ExpressionKind::MemberAccess(_) | ExpressionKind::IndexAccess(_) => Some(self.infer(&assignment.target)),
+            _ => None,
+        };
+        if let Some(expected) = expected {
+            self.expect(&value, &expected, span);
+        }
+    }
+    fn check_loop(&mut self, statement: &LoopStatement) {
+        match statement {
+            LoopStatement::While { condition, body } => {
+                self.infer(condition);
+                self.check_block(body);
+            }
+            LoopStatement::For { initializer, condition, update, body } => {
+                self.check_statement(initializer);
+                self.infer(condition);
+                self.check_statement(update);
+                self.check_block(body);
+            }
+            LoopStatement::ForEach { variable, iterable, body } => {
+                let iterable = self.infer(iterable);
+                let item = match self.apply(&iterable) {
+                    NyarType::List(item) => *item,
+                    _ => NyarType::Any,
+                };
+                self.bind_inferred(variable, Scheme::from(item));
+                self.check_block(body);
+            }
+            LoopStatement::Infinite { body } => {
+                self.check_block(body);
+            }
+        }
+    }
+    fn check_class(&mut self, class: &ClassDefinition) {
+        if let Some(parent) = &class.parent {
+            self.resolve(parent, class.span.clone());
+        }
+        for name in &class.traits {
+            match self.definitions.get(name).map(|definition| definition.kind) {
+                Some(DefinitionKind::Trait) | None => {}
+                Some(_) => self.report(format!("`{}` is not a trait", name), class.span.clone()),
+            }
+        }
+        self.check_implementations(class);
+        self.generics.extend(class.generics.iter().cloned());
+        for property in &class.properties {
+            let declared = match &property.type_annotation {
+                Some(annotation) => self.resolve(annotation, property.span.clone()),
+                None => NyarType::Any,
+            };
+            if let Some(default) = &property.default {
+                let found = self.infer(default);
+                let span = self.span_of(default);
+                self.expect(&found, &declared, span);
+            }
+        }
+        // 方法中的 `self` 为类的实例, `super` 为父类的实例
+        let arguments = class.generics.iter().map(|name| NyarType::Generic(name.clone())).collect();
+        let mut variables = BTreeMap::new();
+        variables.insert("self".to_string(), Scheme::from(NyarType::Named { name: class.name.clone(), arguments }));
+        if let Some(parent) = &class.parent {
+            variables.insert("super".to_string(), Scheme::from(self.resolve_silently(Some(parent))));
+        }
+        self.scopes.push(FunctionScope::new(variables, &[], None));
+        for method in &class.methods {
+            self.check_function(method);
+        }
+        self.scopes.pop();
+        self.generics.clear();
+    }
+    /// 检查类提供了特征中没有默认实现的方法, 且方法的参数个数与特征一致
+    fn check_implementations(&mut self, class: &ClassDefinition) {
+        for name in &class.traits {
+            let definition = match self.definitions.get(name) {
+                Some(definition) if definition.kind == DefinitionKind::Trait => definition,
+                _ => continue,
+            };
+            let abstracts = definition.abstracts.clone();
+            for (method, expected) in definition.members.clone() {
+                let expected = match expected {
+                    NyarType::Function { parameters, .. } => parameters.len(),
+                    _ => continue,
+                };
+                match class.methods.iter().find(|found| found.name == method) {
+                    Some(found) => {
+                        let arity = receiverless(&found.parameters).len();
+                        if arity != expected {
+                            let message = format!(
+                                "`{}.{}` expects {} arguments, but `{}.{}` expects {}",
+                                class.name, method, arity, name, method, expected
+                            );
+                            self.report(message, found.span.clone());
+                        }
+                    }
+                    // 父类提供的方法同样满足特征
+                    None if class.parent.as_deref().and_then(|parent| self.member(parent, &method)).is_some() => {}
+                    None if abstracts.contains(&method) => {
+                        let message =
+                            format!("class `{}` is missing method `{}` required by trait `{}`", class.name, method, name);
+                        self.report(message, class.span.clone());
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+    /// 检查属性的初始值, 值的类型为类的实例
+    fn check_new(&mut self, expression: &NewExpression, span: Span) -> NyarType {
+        let (name, arguments) = match self.resolve(&expression.class, span.clone()) {
+            NyarType::Named { name, arguments } => (name, arguments),
+            _ => {
+                expression.properties.iter().for_each(|(_, value)| {
+                    self.infer(value);
+                });
+                return NyarType::Any;
+            }
+        };
+        let generics = match self.definitions.get(&name) {
+            Some(definition) if matches!(definition.kind, DefinitionKind::Class | DefinitionKind::Structure) => {
+                definition.generics.len()
+            }
+            _ => {
+                self.report(format!("`{}` is not a class", name), span);
+                expression.properties.iter().for_each(|(_, value)| {
+                    self.infer(value);
+                });
+                return NyarType::Any;
+            }
+        };
+        // 没有给出泛型参数时由属性的值推断
+        let arguments = match arguments.is_empty() {
+            true => (0..generics).map(|_| self.fresh()).collect(),
+            false => arguments,
+        };
+        for (property, value) in &expression.properties {
+            let found = self.infer(value);
+            match self.member(&name, property) {
+                Some((owner, member)) => {
+                    let expected = self.member_type(&name, &arguments, &owner, &member);
+                    let span = self.span_of(value);
+                    self.expect(&found, &expected, span);
+                }
+                None => self.report(format!("`{}` has no member `{}`", name, property), span.clone()),
+            }
+        }
+        let arguments = arguments.iter().map(|argument| self.apply(argument)).collect();
+        NyarType::Named { name, arguments }
+    }
+    fn check_trait(&mut self, definition: &TraitDefinition) {
+        for method in &definition.methods {
+            let annotations = method
+                .parameters
+                .iter()
+                .map(|parameter| {
+                    parameter.type_annotation.as_ref().map(|annotation| self.resolve(annotation, parameter.span.clone()))
+                })
+                .collect();
+            let output = method.return_type.as_ref().map(|annotation| self.resolve(annotation, method.span.clone()));
+            if let Some(body) = &method.body {
+                self.check_body(&method.parameters, annotations, output, body);
+            }
+        }
+    }
+
+    fn span_of(&self, expression: &Expression) -> Span {
+        match expression.span.is_empty() {
+            true => self.span.clone(),
+            false => expression.span.clone(),
+        }
+    }
+    /// 推断表达式的类型
+    fn infer(&mut self, expression: &Expression) -> NyarType {
+        let inferred = self.infer_kind(expression);
+        self.record(&expression.span, &inferred);
+        inferred
+    }
+    fn infer_kind(&mut self, expression: &Expression) -> NyarType {
+        let span = self.span_of(expression);
+        match &expression.kind {
+            ExpressionKind::Literal(literal) => match literal {
+                Literal::Null => NyarType::Null,
+                Literal::Boolean(_) => NyarType::Boolean,
+                Literal::Integer(_) => NyarType::Integer,
+                Literal::String(_) => NyarType::String,
+                Literal::List(items) => {
+                    let items: Vec<_> = items.iter().map(|item| self.infer(item)).collect();
+                    let items: Vec<_> = items.iter().map(|item| self.apply(item)).collect();
+                    let item = items.split_first().map_or_else(
+                        || self.fresh(),
+                        |(first, rest)| rest.iter().fold(first.clone(), |joined, item| joined.join(item)),
+                    );
+                    NyarType::List(Box::new(item))
+                }
+                Literal::Object(properties) => {
+                    properties.values().for_each(|value| {
+                        self.infer(value);
+                    });
+                    NyarType::Object
+                }
+            },
+            ExpressionKind::Variable(name) => self.lookup(name).unwrap_or(NyarType::Any),
+            ExpressionKind::Binary(binary) if binary.is_type_test() => {
+                self.infer(&binary.left);
+                self.infer(&binary.right);
+                if let ExpressionKind::Variable(name) = &binary.right.kind {
+                    let kind = self.definitions.get(name).map(|definition| definition.kind);
+                    if binary.operator == "implements" && kind.is_some_and(|kind| kind != DefinitionKind::Trait) {
+                        self.report(format!("`{}` is not a trait", name), self.span_of(&binary.right));
+                    }
+                }
+                NyarType::Boolean
+            }
+            ExpressionKind::Binary(binary) => {
+                let left = self.infer(&binary.left);
+                let right = self.infer(&binary.right);
+                let operator = match binary.operator.parse::<BinaryOperator>() {
+                    Ok(operator) => operator,
+                    Err(_) => return NyarType::Any,
+                };
+                match self.binary(operator, &left, &right) {
+                    Some(result) => result,
+                    None => {
+                        let (left, right) = (self.shown(&binary.left, &left), self.shown(&binary.right, &right));
+                        self.report(format!("operator `{}` cannot be applied to `{}` and `{}`", operator, left, right), span);
+                        NyarType::Any
+                    }
+                }
+            }
+            ExpressionKind::Unary(unary) => {
+                let operand = self.infer(&unary.operand);
+                let operator = match unary.operator.parse::<UnaryOperator>() {
+                    Ok(operator) => operator,
+                    Err(_) => return NyarType::Any,
+                };
+                let result = match operator {
+                    UnaryOperator::Positive | UnaryOperator::Negative if self.apply(&operand) == NyarType::Float => {
+                        NyarType::Float
+                    }
+                    UnaryOperator::Positive | UnaryOperator::Negative => NyarType::Integer,
+                    UnaryOperator::Not => NyarType::Boolean,
+                };
+                if !self.unify(&operand, &result) {
+                    let message = format!("operator `{}` cannot be applied to `{}`", operator, self.apply(&operand));
+                    self.report(message, span);
+                    return NyarType::Any;
+                }
+                result
+            }
+            ExpressionKind::Call(call) => {
+                let callee = self.infer(&call.callee);
+                let arguments: Vec<_> =
+                    call.arguments.iter().map(|argument| (self.infer(argument), self.span_of(argument))).collect();
+                if let Some((constructor, fields)) = self.constructor_arity(&call.callee) {
+                    if fields != arguments.len() {
+                        let message = format!("`{}` has {} fields, found {}", constructor, fields, arguments.len());
+                        self.report(message, span.clone());
+                    }
+                }
+                match self.apply(&callee) {
+                    NyarType::Function { parameters, output } => {
+                        if parameters.len() != arguments.len() {
+                            let message = format!("expected {} arguments, found {}", parameters.len(), arguments.len());
+                            self.report(message, span);
+                        }
+                        for ((found, span), expected) in arguments.iter().zip(&parameters) {
+                            self.expect(found, expected, span.clone());
+                        }
+                        *output
+                    }
+                    // 调用参数时推断参数的函数类型
+                    variable @ NyarType::Variable(_) => {
+                        let output = self.fresh();
+                        let parameters = arguments.into_iter().map(|(argument, _)| argument).collect();
+                        self.unify(&variable, &NyarType::function(parameters, output.clone()));
+                        output
+                    }
+                    callee @ (NyarType::Null
+                    | NyarType::Boolean
+                    | NyarType::Integer
+                    | NyarType::Float
+                    | NyarType::String
+                    | NyarType::List(_)) => {
+                        self.report(format!("`{}` is not callable", callee), span);
+                        NyarType::Any
+                    }
+                    _ => NyarType::Any,
+                }
+            }
+            ExpressionKind::Lambda(lambda) => {
+                let bindings = Bindings::count(&[Statement::from(StatementKind::Expression(lambda.body.clone()))]);
+                let parameters: Vec<_> = lambda
+                    .parameters
+                    .iter()
+                    .map(|name| match bindings.get(name) {
+                        0 => self.fresh(),
+                        _ => NyarType::Any,
+                    })
+                    .collect();
+                let variables = lambda.parameters.iter().cloned().zip(parameters.iter().cloned().map(Scheme::from)).collect();
+                let inferred = lambda.parameters.iter().cloned().collect();
+                let (initial, output) = (BTreeMap::new(), None);
+                self.scopes.push(FunctionScope { variables, bindings, initial, inferred, output, returns: vec![] });
+                let output = self.infer(&lambda.body);
+                self.scopes.pop();
+                NyarType::function(parameters, output)
+            }
+            ExpressionKind::Conditional(conditional) => {
+                self.infer(&conditional.condition);
+                let then_branch = self.infer(&conditional.then_branch);
+                let else_branch = match &conditional.else_branch {
+                    Some(branch) => self.infer(branch),
+                    None => NyarType::Null,
+                };
+                self.apply(&then_branch).join(&self.apply(&else_branch))
+            }
+            ExpressionKind::MemberAccess(access) => {
+                let object = self.infer(&access.object);
+                match self.apply(&object) {
+                    NyarType::Named { name, arguments } => match self.member(&name, &access.member) {
+                        Some((owner, member)) => self.member_type(&name, &arguments, &owner, &member),
+                        None if self
+                            .definitions
+                            .get(&name)
+                            .is_some_and(|definition| definition.kind != DefinitionKind::Enum) =>
+                        {
+                            self.report(format!("`{}` has no member `{}`", name, access.member), span);
+                            NyarType::Any
+                        }
+                        None => NyarType::Any,
+                    },
+                    object @ (NyarType::Null | NyarType::Boolean | NyarType::Integer | NyarType::Float | NyarType::String) => {
+                        let numeric = NumericMethod::parse(&access.member);
+                        let string = StringMethod::parse(&access.member);
+                        match (numeric, string) {
+                            _ if access.member == "to_string" => NyarType::function(vec![], NyarType::String),
+                            (Some(method), _) if matches!(object, NyarType::Integer | NyarType::Float) => {
+                                numeric_method_type(method)
+                            }
+                            (_, Some(method)) if object == NyarType::String => string_method_type(method),
+                            _ => {
+                                self.report(format!("`{}` has no member `{}`", object, access.member), span);
+                                NyarType::Any
+                            }
+                        }
+                    }
+                    _ => NyarType::Any,
+                }
+            }
+            ExpressionKind::IndexAccess(access) => {
+                let object = self.infer(&access.object);
+                let index = self.infer(&access.index);
+                match self.apply(&object) {
+                    NyarType::List(item) => {
+                        let span = self.span_of(&access.index);
+                        self.expect(&index, &NyarType::Integer, span);
+                        *item
+                    }
+                    // 以整数下标访问的未知值推断为列表, 泛型函数的返回类型因此可以随参数实例化
+                    object @ NyarType::Variable(_) if self.apply(&index) == NyarType::Integer => {
+                        let item = self.fresh();
+                        self.unify(&object, &NyarType::List(Box::new(item.clone())));
+                        item
+                    }
+                    object @ (NyarType::Null
+                    | NyarType::Boolean
+                    | NyarType::Integer
+                    | NyarType::Float
+                    | NyarType::Function { .. }) => {
+                        self.report(format!("`{}` cannot be indexed", object), span);
+                        NyarType::Any
+                    }
+                    _ => NyarType::Any,
+                }
+            }
+            ExpressionKind::Effect(effect) => {
+                effect.arguments.iter().for_each(|argument| {
+                    self.infer(argument);
+                });
+                NyarType::Any
+            }
+            ExpressionKind::Match(expression) => self.check_match(expression, span),
+            ExpressionKind::New(expression) => self.check_new(expression, span),
+            ExpressionKind::Interpolation(expression) => {
+                expression.expressions().for_each(|part| {
+                    self.infer(part);
+                });
+                NyarType::String
+            }
+        }
+    }
+    /// 检查各分支, 值的类型为各分支的公共类型
+    fn check_match(&mut self, expression: &MatchExpression, span: Span) -> NyarType {
+        let subject = self.infer(&expression.subject);
+        let mut output: Option<NyarType> = None;
+        for arm in &expression.arms {
+            let subject = self.apply(&subject);
+            self.check_pattern(&arm.pattern, &subject, arm.span.clone());
+            if let Some(guard) = &arm.guard {
+                self.infer(guard);
+            }
+            let body = self.infer(&arm.body);
+            let body = self.apply(&body);
+            output = Some(match output {
+                Some(output) => output.join(&body),
+                None => body,
+            });
+        }
+        self.check_exhaustive(expression, span);
+        output.unwrap_or(NyarType::Any)
+    }
+    /// 绑定模式中的变量, `expected` 为被匹配的值的类型
+    fn check_pattern(&mut self, pattern: &Pattern, expected: &NyarType, span: Span) {
+        match pattern {
+            Pattern::Wildcard | Pattern::Literal(_) => {}
+            Pattern::Binding(name) => self.bind_inferred(name, Scheme::from(expected.clone())),
+            Pattern::List { items, .. } => {
+                let item = match expected {
+                    NyarType::List(item) => item.as_ref().clone(),
+                    _ => NyarType::Any,
+                };
+                items.iter().for_each(|pattern| self.check_pattern(pattern, &item, span.clone()))
+            }
+            Pattern::Object(fields) => {
+                fields.iter().for_each(|(_, pattern)| self.check_pattern(pattern, &NyarType::Any, span.clone()))
+            }
+            Pattern::Variant { enumeration, variant, fields } => {
+                let found = match self.definitions.get(enumeration) {
+                    Some(definition) if definition.kind == DefinitionKind::Enum => {
+                        match definition.variants.iter().find(|(name, _)| name == variant) {
+                            Some((_, fields)) => Some(fields.clone()),
+                            None => {
+                                self.report(format!("`{}` has no member `{}`", enumeration, variant), span.clone());
+                                None
+                            }
+                        }
+                    }
+                    Some(_) => {
+                        self.report(format!("`{}` is not an enum", enumeration), span.clone());
+                        None
+                    }
+                    None => {
+                        self.report(format!("unknown type `{}`", enumeration), span.clone());
+                        None
+                    }
+                };
+                // 位置字段的个数不同时不再逐个报告字段
+                let found = match (found, pattern.arity()) {
+                    (Some(found), Some(count)) if found.len() != count => {
+                        let message = format!("`{}.{}` has {} fields, found {}", enumeration, variant, found.len(), count);
+                        self.report(message, span.clone());
+                        None
+                    }
+                    (found, _) => found,
+                };
+                // 字段类型中的泛型参数由被匹配的值的类型参数代入
+                let arguments = match expected {
+                    NyarType::Named { name, arguments } if name == enumeration => arguments.clone(),
+                    _ => vec![],
+                };
+                for (field, pattern) in fields {
+                    let field = match found.as_ref().map(|found| found.iter().find(|(name, _)| name == field)) {
+                        Some(Some((_, member))) => self.member_type(enumeration, &arguments, enumeration, member),
+                        Some(None) => {
+                            self.report(format!("`{}.{}` has no field `{}`", enumeration, variant, field), span.clone());
+                            NyarType::Any
+                        }
+                        None => NyarType::Any,
+                    };
+                    self.check_pattern(pattern, &field, span.clone())
+                }
+            }
+            Pattern::Class { name, fields } => {
+                match self.definitions.get(name).map(|definition| definition.kind) {
+                    Some(DefinitionKind::Class) => {}
+                    Some(_) => self.report(format!("`{}` is not a class", name), span.clone()),
+                    None => self.report(format!("unknown type `{}`", name), span.clone()),
+                }
+                for (field, pattern) in fields {
+                    let field = match self.member(name, field) {
+                        Some((owner, member)) => self.member_type(name, &[], &owner, &member),
+                        None => {
+                            if self.definitions.get(name).is_some_and(|definition| definition.kind == DefinitionKind::Class) {
+                                self.report(format!("`{}` has no member `{}`", name, field), span.clone())
+                            }
+                            NyarType::Any
+                        }
+                    };
+                    self.check_pattern(pattern, &field, span.clone())
+                }
+            }
+        }
+    }
+    /// 分支都是同一枚举的变体时, 没有守卫的分支必须覆盖全部变体
+    fn check_exhaustive(&mut self, expression: &MatchExpression, span: Span) {
+        let mut enumeration = None;
+        let mut covered = BTreeSet::new();
+        for arm in &expression.arms {
+            match &arm.pattern {
+                pattern if pattern.is_irrefutable() && arm.guard.is_none() => return,
+                Pattern::Variant { enumeration: name, variant, fields } => {
+                    if enumeration.get_or_insert(name) != &name {
+                        return;
+                    }
+                    // 字段的模式可能不匹配时不算覆盖了该变体
+                    if arm.guard.is_none() && fields.iter().all(|(_, field)| field.is_irrefutable()) {
+                        covered.insert(variant);
+                    }
+                }
+                _ => return,
+            }
+        }
+        let Some(enumeration) = enumeration
+        else {
+            return;
+        };
+        let missing: Vec<_> = match self.definitions.get(enumeration) {
+            Some(definition) if definition.kind == DefinitionKind::Enum => definition
+                .variants
+                .iter()
+                .map(|(variant, _)| variant)
+                .filter(|variant| !covered.contains(variant))
+                .map(|variant| format!("`{}.{}`", enumeration, variant))
+                .collect(),
+            _ => return,
+        };
+        if !missing.is_empty() {
+            self.report(format!("non-exhaustive match, missing {}", missing.join(", ")), span)
+        }
+    }
+    /// `name<arguments>` 的成员的类型, 成员由 `owner` 声明
+    ///
+    /// 泛型参数由实例的类型参数代入, 无法代入时替换为新的类型变量。
+    fn member_type(&mut self, name: &str, arguments: &[NyarType], owner: &str, member: &NyarType) -> NyarType {
+        let generics = self.definitions.get(owner).map(|definition| definition.generics.clone()).unwrap_or_default();
+        let arguments = match owner == name && arguments.len() == generics.len() {
+            true => arguments.to_vec(),
+            false => generics.iter().map(|_| self.fresh()).collect(),
+        };
+        member.replace(&mut |t| match t {
+            NyarType::Generic(generic) => generics.iter().position(|g| g == generic).map(|index| arguments[index].clone()),
+            _ => None,
+        })
+    }
+    /// 二元运算的结果类型, 操作数类型不匹配时报告错误
+    /// 二元运算的结果类型, 操作数类型不匹配时返回 `None`
+    fn binary(&mut self, operator: BinaryOperator, left: &NyarType, right: &NyarType) -> Option<NyarType> {
+        let left = self.apply(left);
+        let right = self.apply(right);
+        let unknown = |t: &NyarType| matches!(t, NyarType::Any | NyarType::Variable(_));
+        match operator {
+            BinaryOperator::Equal | BinaryOperator::NotEqual => Some(NyarType::Boolean),
+            // 短路求值的结果为决定结果的那个操作数
+            BinaryOperator::And | BinaryOperator::Or => Some(left.join(&right)),
+            // 加法与比较可以作用于整数或字符串, 两个操作数都未知时无法确定
+            BinaryOperator::Add if unknown(&left) && unknown(&right) => Some(NyarType::Any),
+            // 浮点数与整数运算时整数转换为浮点数
+            _ if left == NyarType::Float || right == NyarType::Float => {
+                let numeric = |t: &NyarType| unknown(t) || matches!(t, NyarType::Integer | NyarType::Float);
+                let result = match operator {
+                    BinaryOperator::Less
+                    | BinaryOperator::LessEqual
+                    | BinaryOperator::Greater
+                    | BinaryOperator::GreaterEqual => NyarType::Boolean,
+                    _ => NyarType::Float,
+                };
+                (numeric(&left) && numeric(&right)).then_some(result)
+            }
+            BinaryOperator::Less | BinaryOperator::LessEqual | BinaryOperator::Greater | BinaryOperator::GreaterEqual
+                if unknown(&left) && unknown(&right) =>
+            {
+                Some(NyarType::Boolean)
+            }
+            BinaryOperator::Add => {
+                let operand =
+                    if left == NyarType::String || right == NyarType::String { NyarType::String } else { NyarType::Integer };
+                (self.unify(&left, &operand) && self.unify(&right, &operand)).then_some(operand)
+            }
+            BinaryOperator::Subtract | BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Remainder => {
+                (self.unify(&left, &NyarType::Integer) && self.unify(&right, &NyarType::Integer)).then_some(NyarType::Integer)
+            }
+            BinaryOperator::Less | BinaryOperator::LessEqual | BinaryOperator::Greater | BinaryOperator::GreaterEqual => {
+                let operand = if unknown(&left) { right.clone() } else { left.clone() };
+                let comparable = matches!(operand, NyarType::Integer | NyarType::String);
+                (comparable && self.unify(&left, &operand) && self.unify(&right, &operand)).then_some(NyarType::Boolean)
+            }
+        }
+    }
+}
+
+impl FunctionScope {
+    fn new(variables: BTreeMap<String, Scheme>, body: &[Statement], output: Option<NyarType>) -> Self {
+        let bindings = Bindings::count(body);
+        Self { variables, bindings, initial: BTreeMap::new(), inferred: BTreeSet::new(), output, returns: vec![] }
+    }
+}
+
+impl From<NyarType> for Scheme {
+    fn from(body: NyarType) -> Self {
+        Self { parameters: vec![], body }
+    }
+}
+
+/// 去掉方法显式声明的 `self` 参数, 调用时实例不作为参数传入
+fn receiverless(parameters: &[Parameter]) -> &[Parameter] {
+    match parameters.split_first() {
+        Some((first, rest)) if first.name == "self" => rest,
+        _ => parameters,
+    }
+}
+
+/// 收集程序中声明的类、特征与枚举, 包括嵌套在函数中的声明
+#[derive(Default)]
+struct Definitions {
+    kinds: BTreeMap<String, (DefinitionKind, Vec<String>)>,
+    classes: Vec<ClassDefinition>,
+    traits: Vec<TraitDefinition>,
+    enums: Vec<EnumDefinition>,
+    structures: Vec<StructureDefinition>,
+}
+
+impl Visitor for Definitions {
+    fn visit_class(&mut self, class: &ClassDefinition) {
+        self.kinds.insert(class.name.clone(), (DefinitionKind::Class, class.generics.clone()));
+        self.classes.push(class.clone());
+        walk_class(self, class)
+    }
+    fn visit_trait(&mut self, definition: &TraitDefinition) {
+        self.kinds.insert(definition.name.clone(), (DefinitionKind::Trait, vec![]));
+        self.traits.push(definition.clone());
+        walk_trait(self, definition)
+    }
+    fn visit_enum(&mut self, definition: &EnumDefinition) {
+        self.kinds.insert(definition.name.clone(), (DefinitionKind::Enum, definition.generics.clone()));
+        self.enums.push(definition.clone());
+        walk_enum(self, definition)
+    }
+    fn visit_structure(&mut self, definition: &StructureDefinition) {
+        self.kinds.insert(definition.name.clone(), (DefinitionKind::Structure, vec![]));
+        self.structures.push(definition.clone());
+    }
+}
+
+/// 数值内建方法的类型, 定宽整数与大整数同为 [`NyarType::Integer`], `checked_*` 溢出时返回空值
+fn numeric_method_type(method: NumericMethod) -> NyarType {
+    match method {
+        NumericMethod::Convert(NumericType::Float) => NyarType::function(vec![], NyarType::Float),
+        NumericMethod::Convert(_) | NumericMethod::Truncate(_) => NyarType::function(vec![], NyarType::Integer),
+        NumericMethod::Arithmetic(OverflowMode::Checked, _) => NyarType::function(vec![NyarType::Integer], NyarType::Any),
+        NumericMethod::Arithmetic(..) => NyarType::function(vec![NyarType::Integer], NyarType::Integer),
+    }
+}
+
+/// 字符串内建方法的类型, 参数个数可变的方法为 [`NyarType::Any`], `find` 找不到时返回空值
+fn string_method_type(method: StringMethod) -> NyarType {
+    match method {
+        StringMethod::Slice | StringMethod::Split => NyarType::Any,
+        StringMethod::Length | StringMethod::ByteLength => NyarType::function(vec![], NyarType::Integer),
+        StringMethod::Trim | StringMethod::TrimStart | StringMethod::TrimEnd | StringMethod::Upper | StringMethod::Lower => {
+            NyarType::function(vec![], NyarType::String)
+        }
+        StringMethod::Chars => NyarType::function(vec![], NyarType::List(Box::new(NyarType::String))),
+        StringMethod::Join => NyarType::function(vec![NyarType::List(Box::new(NyarType::Any))], NyarType::String),
+        StringMethod::Find => NyarType::function(vec![NyarType::String], NyarType::Any),
+        StringMethod::Replace => NyarType::function(vec![NyarType::String, NyarType::String], NyarType::String),
+        StringMethod::StartsWith | StringMethod::EndsWith | StringMethod::Contains => {
+            NyarType::function(vec![NyarType::String], NyarType::Boolean)
+        }
+        StringMethod::Repeat => NyarType::function(vec![NyarType::Integer], NyarType::String),
+    }
+}
@@ -0,0 +1,285 @@
+//! 效应检查
+//!
+//! 效应处理器注册在调用帧上, 对之后在该帧及其调用的函数中引发的效应有效。检查时处理器只对同一块中其后的语句有效,
+//! 条件分支或循环中注册的处理器不会延续到块外。每个函数可能引发的效应为函数体中直接引发的效应与所调用函数的效应之并,
+//! 再去掉引发处已注册处理器的效应; 作为参数传入的函数可能被被调用者调用, 其效应计入调用处,
+//! 但被调用者在调用该参数的每一处都已处理的效应除外。只追踪以唯一名称声明的函数与 lambda, 无法确定被调用者时不追踪,
+//! 与渐进的类型检查一致。
+
+use crate::{
+    ast::{
+        Attribute, EffectHandlerDefinition, Expression, ExpressionKind, FunctionDefinition, LambdaExpression, Literal, Program,
+        Span, Statement, StatementKind, TraitMethod, VariableDeclaration,
+    },
+    visit::{
+        Bindings, Visitor, walk_block, walk_expression, walk_function, walk_lambda, walk_statement, walk_variable_declaration,
+    },
+};
+use nyar_error::NyarError;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 效应检查器, 推断每个函数可能引发的效应
+///
+/// 函数可以用 `#raises(ask, log)` 声明可能引发的效应, 声明之外的效应会被报告;
+/// 顶层程序没有调用者, 其中引发的效应都必须被处理。
+#[derive(Debug, Default)]
+pub struct EffectChecker {
+    units: Vec<Unit>,
+    /// 以唯一名称声明的函数
+    functions: BTreeMap<String, usize>,
+    /// 正在收集的函数
+    stack: Vec<usize>,
+    /// 下一个函数或 lambda 绑定的名称
+    pending: Option<String>,
+    bindings: Bindings,
+}
+
+/// 顶层程序、函数、方法或 lambda
+#[derive(Debug, Default)]
+struct Unit {
+    name: Option<String>,
+    /// 参数名
+    parameters: Vec<String>,
+    /// `#raises` 声明的效应
+    declared: Option<BTreeSet<String>>,
+    /// 直接引发的效应, 及引发处已注册处理器的效应
+    raises: Vec<(String, Span, BTreeSet<String>)>,
+    /// 调用的函数
+    calls: Vec<Call>,
+    /// 当前位置已注册处理器的效应, 按注册顺序排列
+    handlers: Vec<String>,
+    /// 推断的效应
+    effects: BTreeSet<String>,
+}
+
+/// 函数调用, 或把函数作为参数传入的调用
+#[derive(Debug)]
+struct Call {
+    callee: Callee,
+    span: Span,
+    /// 调用处已注册处理器的效应
+    handled: BTreeSet<String>,
+    /// 作为参数传入时, 接收参数的函数与参数的位置
+    receiver: Option<(String, usize)>,
+}
+
+/// 被调用的函数
+#[derive(Debug)]
+enum Callee {
+    /// 按名称调用或作为参数传入的函数
+    Named(String),
+    /// 作为参数传入的 lambda
+    Lambda(usize),
+}
+
+impl EffectChecker {
+    /// 创建一个新的效应检查器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 检查程序, 返回未处理与未声明的效应
+    pub fn check(&mut self, program: &Program) -> Vec<NyarError> {
+        *self = Self { bindings: Bindings::count(&program.statements), ..Self::default() };
+        self.enter(None, vec![], None);
+        walk_block(self, &program.statements);
+        self.stack.pop();
+        self.infer();
+        let mut diagnostics = vec![];
+        for (index, unit) in self.units.iter().enumerate() {
+            // 顶层程序没有声明, 没有声明的函数由调用者处理
+            let allowed = match &unit.declared {
+                _ if index == 0 => BTreeSet::new(),
+                Some(declared) => declared.clone(),
+                None => continue,
+            };
+            let mut reported = BTreeSet::new();
+            for (effect, span) in self.sources(unit) {
+                if allowed.contains(effect) || !reported.insert(effect) {
+                    continue;
+                }
+                let message = match &unit.name {
+                    Some(name) if index != 0 => format!("function `{}` raises undeclared effect `{}`", name, effect),
+                    _ => format!("unhandled effect `{}`", effect),
+                };
+                diagnostics.push(span.locate(NyarError::type_error(message, span.range.clone())))
+            }
+        }
+        diagnostics.sort_by_key(|error| error.span().start);
+        diagnostics
+    }
+
+    /// 以唯一名称声明的函数可能引发的效应, 须在 [`EffectChecker::check`] 之后调用
+    pub fn effects_of(&self, function: &str) -> Option<&BTreeSet<String>> {
+        self.functions.get(function).map(|index| &self.units[*index].effects)
+    }
+
+    fn enter(&mut self, name: Option<String>, parameters: Vec<String>, declared: Option<BTreeSet<String>>) {
+        let index = self.units.len();
+        if let Some(name) = self.pending.take().filter(|name| self.bindings.get(name) == 1) {
+            self.functions.insert(name, index);
+        }
+        self.units.push(Unit { name, parameters, declared, ..Unit::default() });
+        self.stack.push(index);
+    }
+
+    fn current(&mut self) -> &mut Unit {
+        let index = self.stack.last().copied().unwrap_or(0);
+        &mut self.units[index]
+    }
+
+    /// 当前位置已注册处理器的效应
+    fn handled(&mut self) -> BTreeSet<String> {
+        self.current().handlers.iter().cloned().collect()
+    }
+
+    /// 直接引发与调用引入的未被处理的效应, 及其源码区间
+    fn sources<'a>(&'a self, unit: &'a Unit) -> impl Iterator<Item = (&'a String, &'a Span)> {
+        let raises = unit.raises.iter().filter(|(effect, _, handled)| !handled.contains(effect));
+        let raises = raises.map(|(effect, span, _)| (effect, span));
+        let calls = unit.calls.iter().filter_map(|call| {
+            let index = match &call.callee {
+                Callee::Named(name) => *self.functions.get(name)?,
+                Callee::Lambda(index) => *index,
+            };
+            let mut handled = call.handled.clone();
+            if let Some((function, position)) = &call.receiver {
+                handled.extend(self.handled_parameter(function, *position))
+            }
+            let effects = self.units[index].effects.iter().filter(move |effect| !handled.contains(*effect));
+            Some(effects.map(move |effect| (effect, &call.span)))
+        });
+        raises.chain(calls.flatten())
+    }
+
+    /// 函数在调用第 `position` 个参数的每一处都已注册处理器的效应, 没有调用该参数时为空
+    fn handled_parameter(&self, function: &str, position: usize) -> BTreeSet<String> {
+        let Some(unit) = self.functions.get(function).map(|index| &self.units[*index])
+        else {
+            return BTreeSet::new();
+        };
+        let Some(parameter) = unit.parameters.get(position)
+        else {
+            return BTreeSet::new();
+        };
+        let mut calls = unit.calls.iter().filter(|call| matches!(&call.callee, Callee::Named(name) if name == parameter));
+        let first = match calls.next() {
+            Some(call) => call.handled.clone(),
+            None => return BTreeSet::new(),
+        };
+        calls.fold(first, |handled, call| handled.intersection(&call.handled).cloned().collect())
+    }
+
+    /// 迭代到不动点, 声明了效应的函数以声明为准
+    fn infer(&mut self) {
+        loop {
+            let mut changed = false;
+            for index in 0..self.units.len() {
+                let unit = &self.units[index];
+                let effects: BTreeSet<String> = match &unit.declared {
+                    Some(declared) => declared.clone(),
+                    None => self.sources(unit).map(|(effect, _)| effect).cloned().collect(),
+                };
+                if effects != self.units[index].effects {
+                    self.units[index].effects = effects;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+}
+
+/// `#raises(...)` 声明的效应名, 参数为名称或字符串
+fn declared_effects(attributes: &[Attribute]) -> Option<BTreeSet<String>> {
+    let attribute = attributes.iter().find(|attribute| attribute.name == "raises")?;
+    let names = attribute.arguments.iter().filter_map(|argument| match &argument.kind {
+        ExpressionKind::Variable(name) | ExpressionKind::Literal(Literal::String(name)) => Some(name.clone()),
+        _ => None,
+    });
+    Some(names.collect())
+}
+
+impl Visitor for EffectChecker {
+    /// 语句中嵌套的块注册的处理器在语句结束时失效, 处理器语句注册的处理器对其后的语句有效
+    fn visit_statement(&mut self, statement: &Statement) {
+        if let StatementKind::FunctionDeclaration(function) = &statement.kind {
+            self.pending = Some(function.name.clone())
+        }
+        let depth = self.current().handlers.len();
+        walk_statement(self, statement);
+        if !matches!(statement.kind, StatementKind::EffectHandler(_)) {
+            self.current().handlers.truncate(depth)
+        }
+    }
+    fn visit_expression(&mut self, expression: &Expression) {
+        let span = expression.span.clone();
+        match &expression.kind {
+            ExpressionKind::Effect(effect) => {
+                let handled = self.handled();
+                self.current().raises.push((effect.name.clone(), span, handled))
+            }
+            ExpressionKind::Call(call) => {
+                let handled = self.handled();
+                let function = match &call.callee.kind {
+                    ExpressionKind::Variable(name) => Some(name.clone()),
+                    _ => None,
+                };
+                if let Some(name) = &function {
+                    let callee = Callee::Named(name.clone());
+                    self.current().calls.push(Call { callee, span: span.clone(), handled: handled.clone(), receiver: None })
+                }
+                self.visit_expression(&call.callee);
+                for (position, argument) in call.arguments.iter().enumerate() {
+                    // 参数中的 lambda 是下一个收集的函数
+                    let callee = match &argument.kind {
+                        ExpressionKind::Lambda(_) => Some(Callee::Lambda(self.units.len())),
+                        ExpressionKind::Variable(name) => Some(Callee::Named(name.clone())),
+                        _ => None,
+                    };
+                    if let Some(callee) = callee {
+                        let receiver = function.clone().map(|function| (function, position));
+                        self.current().calls.push(Call { callee, span: span.clone(), handled: handled.clone(), receiver })
+                    }
+                    self.visit_expression(argument)
+                }
+                return;
+            }
+            _ => {}
+        }
+        walk_expression(self, expression)
+    }
+    fn visit_variable_declaration(&mut self, declaration: &VariableDeclaration) {
+        if let Some(Expression { kind: ExpressionKind::Lambda(_), .. }) = &declaration.initializer {
+            self.pending = Some(declaration.name.clone())
+        }
+        walk_variable_declaration(self, declaration)
+    }
+    fn visit_lambda(&mut self, lambda: &LambdaExpression) {
+        let name = self.pending.clone();
+        self.enter(name, lambda.parameters.clone(), None);
+        walk_lambda(self, lambda);
+        self.stack.pop();
+    }
+    fn visit_function(&mut self, function: &FunctionDefinition) {
+        let parameters = function.parameters.iter().map(|parameter| parameter.name.clone()).collect();
+        self.enter(Some(function.name.clone()), parameters, declared_effects(&function.attributes));
+        walk_function(self, function);
+        self.stack.pop();
+    }
+    fn visit_trait_method(&mut self, method: &TraitMethod) {
+        let parameters = method.parameters.iter().map(|parameter| parameter.name.clone()).collect();
+        self.enter(Some(method.name.clone()), parameters, None);
+        if let Some(body) = &method.body {
+            walk_block(self, body)
+        }
+        self.stack.pop();
+    }
+    /// 处理器在注册它的调用帧中运行, 其中引发的效应归属于该帧, 且不由处理器自身处理
+    fn visit_effect_handler(&mut self, handler: &EffectHandlerDefinition) {
+        walk_block(self, &handler.handler.body);
+        self.current().handlers.push(handler.name.clone())
+    }
+}
@@ -0,0 +1,208 @@
+//! 静态类型模块，检查类型注解并推断变量、参数与返回值的类型
+//!
+//! 推断基于 Hindley-Milner 的合一算法, 函数声明的类型会被泛化, 每次使用时实例化。
+//! 类型检查是渐进的: 无法推断的表达式具有 [`NyarType::Any`], 它与任何类型兼容。
+//! [`EffectChecker`] 推断每个函数可能引发的效应, 报告顶层程序中未处理的效应。
+
+use std::fmt::{Display, Formatter};
+
+mod checker;
+mod effects;
+
+pub use self::{checker::TypeChecker, effects::EffectChecker};
+
+/// 静态类型
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NyarType {
+    /// 未知类型, 与任何类型兼容
+    Any,
+    /// 空值
+    Null,
+    /// 布尔值
+    Boolean,
+    /// 整数
+    Integer,
+    /// 浮点数
+    Float,
+    /// 字符串
+    String,
+    /// 列表, 元素类型相同
+    List(Box<NyarType>),
+    /// 对象字面量
+    Object,
+    /// 函数
+    Function {
+        /// 参数类型
+        parameters: Vec<NyarType>,
+        /// 返回类型
+        output: Box<NyarType>,
+    },
+    /// 类、特征或枚举, 可以带有泛型参数, 如 `Map<String, Integer>`
+    Named {
+        /// 类型名
+        name: String,
+        /// 泛型参数
+        arguments: Vec<NyarType>,
+    },
+    /// 泛型参数, 在声明它的函数或类中是确定但未知的类型
+    Generic(String),
+    /// 待推断的类型变量
+    Variable(usize),
+}
+
+impl NyarType {
+    /// 没有泛型参数的命名类型
+    pub fn named(name: impl Into<String>) -> Self {
+        NyarType::Named { name: name.into(), arguments: vec![] }
+    }
+    /// 函数类型
+    pub fn function(parameters: Vec<NyarType>, output: NyarType) -> Self {
+        NyarType::Function { parameters, output: Box::new(output) }
+    }
+    /// 解析类型注解, 注解为语法分析器输出的规范形式, 如 `(Integer, String) -> List<Integer>`
+    ///
+    /// 内置类型的别名会被归一化, 如 `i32` 与 `int` 均为 [`NyarType::Integer`]; 其余名称解析为命名类型。
+    pub fn parse(annotation: &str) -> Result<NyarType, String> {
+        let mut parser = AnnotationParser { rest: annotation };
+        let parsed = parser.parse()?;
+        match parser.rest.trim() {
+            "" => Ok(parsed),
+            rest => Err(format!("unexpected `{}` in type `{}`", rest, annotation)),
+        }
+    }
+    /// 是否为未知类型
+    pub fn is_any(&self) -> bool {
+        matches!(self, NyarType::Any)
+    }
+    /// 自顶向下替换子类型, `replace` 返回 `None` 的类型继续替换其子类型
+    pub fn replace(&self, replace: &mut impl FnMut(&NyarType) -> Option<NyarType>) -> NyarType {
+        if let Some(replaced) = replace(self) {
+            return replaced;
+        }
+        match self {
+            NyarType::List(item) => NyarType::List(Box::new(item.replace(replace))),
+            NyarType::Function { parameters, output } => NyarType::function(
+                parameters.iter().map(|parameter| parameter.replace(replace)).collect(),
+                output.replace(replace),
+            ),
+            NyarType::Named { name, arguments } => NyarType::Named {
+                name: name.clone(),
+                arguments: arguments.iter().map(|argument| argument.replace(replace)).collect(),
+            },
+            other => other.clone(),
+        }
+    }
+    /// 按出现顺序收集其中的类型变量
+    pub fn variables(&self, variables: &mut Vec<usize>) {
+        self.replace(&mut |t| match t {
+            NyarType::Variable(v) => {
+                if !variables.contains(v) {
+                    variables.push(*v)
+                }
+                Some(t.clone())
+            }
+            _ => None,
+        });
+    }
+    /// 两个类型的公共类型, 不同时为未知类型
+    pub fn join(&self, other: &NyarType) -> NyarType {
+        match (self, other) {
+            (NyarType::List(a), NyarType::List(b)) => NyarType::List(Box::new(a.join(b))),
+            _ if self == other => self.clone(),
+            _ => NyarType::Any,
+        }
+    }
+}
+
+impl Display for NyarType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let list = |types: &[NyarType]| types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ");
+        match self {
+            NyarType::Any => f.write_str("Any"),
+            NyarType::Null => f.write_str("null"),
+            NyarType::Boolean => f.write_str("Boolean"),
+            NyarType::Integer => f.write_str("Integer"),
+            NyarType::Float => f.write_str("Float"),
+            NyarType::String => f.write_str("String"),
+            NyarType::List(item) => write!(f, "List<{}>", item),
+            NyarType::Object => f.write_str("Object"),
+            NyarType::Function { parameters, output } => write!(f, "({}) -> {}", list(parameters), output),
+            NyarType::Named { name, arguments } if arguments.is_empty() => f.write_str(name),
+            NyarType::Named { name, arguments } => write!(f, "{}<{}>", name, list(arguments)),
+            NyarType::Generic(name) => f.write_str(name),
+            NyarType::Variable(index) => write!(f, "?{}", index),
+        }
+    }
+}
+
+/// 类型注解的递归下降解析
+struct AnnotationParser<'s> {
+    rest: &'s str,
+}
+
+impl AnnotationParser<'_> {
+    fn eat(&mut self, token: &str) -> bool {
+        match self.rest.trim_start().strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(format!("expected `{}` in type", token)),
+        }
+    }
+    /// 以 `,` 分隔的类型列表, 直到 `close`
+    fn parse_list(&mut self, close: &str) -> Result<Vec<NyarType>, String> {
+        let mut types = vec![];
+        while !self.eat(close) {
+            types.push(self.parse()?);
+            if !self.eat(",") {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(types)
+    }
+    fn parse(&mut self) -> Result<NyarType, String> {
+        if self.eat("(") {
+            let parameters = self.parse_list(")")?;
+            self.expect("->")?;
+            return Ok(NyarType::function(parameters, self.parse()?));
+        }
+        let rest = self.rest.trim_start();
+        let length = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.')).unwrap_or(rest.len());
+        let (name, rest) = rest.split_at(length);
+        if name.is_empty() {
+            return Err("expected a type name".to_string());
+        }
+        self.rest = rest;
+        let arguments = if self.eat("<") { self.parse_list(">")? } else { vec![] };
+        let primitive = match name {
+            "Any" | "any" => NyarType::Any,
+            "null" | "Null" | "Unit" => NyarType::Null,
+            "Boolean" | "bool" => NyarType::Boolean,
+            "Integer" | "int" | "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128"
+            | "usize" => NyarType::Integer,
+            "Float" | "f32" | "f64" => NyarType::Float,
+            "String" | "string" | "str" => NyarType::String,
+            "Object" => NyarType::Object,
+            "List" | "Array" => {
+                return match <[NyarType; 1]>::try_from(arguments) {
+                    Ok([item]) => Ok(NyarType::List(Box::new(item))),
+                    Err(arguments) if arguments.is_empty() => Ok(NyarType::List(Box::new(NyarType::Any))),
+                    Err(_) => Err(format!("`{}` takes one type argument", name)),
+                };
+            }
+            _ => return Ok(NyarType::Named { name: name.to_string(), arguments }),
+        };
+        match arguments.is_empty() {
+            true => Ok(primitive),
+            false => Err(format!("`{}` does not take type arguments", name)),
+        }
+    }
+}
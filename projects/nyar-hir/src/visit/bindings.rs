@@ -0,0 +1,77 @@
+//! 名称绑定的统计
+
+use super::*;
+use std::collections::BTreeMap;
+
+/// 统计每个名称被绑定的次数, 包括嵌套函数中的参数与局部变量
+#[derive(Debug, Default)]
+pub(crate) struct Bindings {
+    counts: BTreeMap<String, usize>,
+}
+
+impl Bindings {
+    /// 统计语句序列中的绑定
+    pub(crate) fn count(statements: &[Statement]) -> Self {
+        let mut bindings = Bindings::default();
+        walk_block(&mut bindings, statements);
+        bindings
+    }
+    /// 名称被绑定的次数
+    pub(crate) fn get(&self, name: &str) -> usize {
+        self.counts.get(name).copied().unwrap_or(0)
+    }
+    fn bind(&mut self, name: &str) {
+        *self.counts.entry(name.to_string()).or_default() += 1
+    }
+}
+
+impl Visitor for Bindings {
+    fn visit_lambda(&mut self, lambda: &LambdaExpression) {
+        lambda.parameters.iter().for_each(|parameter| self.bind(parameter));
+        walk_lambda(self, lambda)
+    }
+    fn visit_variable_declaration(&mut self, declaration: &VariableDeclaration) {
+        self.bind(&declaration.name);
+        walk_variable_declaration(self, declaration)
+    }
+    fn visit_assignment(&mut self, assignment: &Assignment) {
+        if let ExpressionKind::Variable(name) = &assignment.target.kind {
+            self.bind(name)
+        }
+        walk_assignment(self, assignment)
+    }
+    fn visit_loop(&mut self, statement: &LoopStatement) {
+        if let LoopStatement::ForEach { variable, .. } = statement {
+            self.bind(variable)
+        }
+        walk_loop(self, statement)
+    }
+    fn visit_function(&mut self, function: &FunctionDefinition) {
+        self.bind(&function.name);
+        walk_function(self, function)
+    }
+    fn visit_parameter(&mut self, parameter: &Parameter) {
+        self.bind(&parameter.name)
+    }
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        let mut names = vec![];
+        pattern.bindings(&mut names);
+        names.iter().for_each(|name| self.bind(name))
+    }
+    fn visit_class(&mut self, class: &ClassDefinition) {
+        self.bind(&class.name);
+        walk_class(self, class)
+    }
+    fn visit_trait(&mut self, definition: &TraitDefinition) {
+        self.bind(&definition.name);
+        walk_trait(self, definition)
+    }
+    fn visit_enum(&mut self, definition: &EnumDefinition) {
+        self.bind(&definition.name);
+        walk_enum(self, definition)
+    }
+    fn visit_catch_block(&mut self, block: &CatchBlock) {
+        self.bind(&block.error_variable);
+        walk_catch_block(self, block)
+    }
+}
@@ -0,0 +1,414 @@
+//! AST 访问者模块，提供只读遍历 [`Visitor`] 与可变改写 [`VisitorMut`]
+//!
+//! 自定义分析只需实现关心节点的方法, 其余节点由默认的 `walk` 函数穷尽遍历,
+//! AST 增加节点时只需修改这里。
+
+use crate::ast::{
+    Assignment, Attribute, BinaryExpression, CallExpression, CatchBlock, ClassDefinition, ConditionalExpression,
+    EffectExpression, EffectHandlerDefinition, EnumDefinition, EnumVariant, ExportStatement, Expression, ExpressionKind,
+    FunctionDefinition, IfStatement, ImportStatement, IndexAccessExpression, InterpolationExpression, LambdaExpression,
+    Literal, LoopStatement, MatchExpression, MemberAccessExpression, NewExpression, Parameter, Pattern, Program,
+    PropertyDefinition, Statement, StatementKind, StructureDefinition, TraitDefinition, TraitMethod, TryCatchStatement,
+    UnaryExpression, VariableDeclaration,
+};
+
+mod bindings;
+mod visit_mut;
+
+pub(crate) use self::bindings::Bindings;
+pub use self::visit_mut::*;
+
+/// 遍历 AST 的只读访问者, 每个方法默认调用对应的 `walk` 函数访问子节点
+///
+/// 只需覆盖关心的节点, 在覆盖的方法中调用 `walk` 函数以继续访问子节点。
+pub trait Visitor {
+    /// 访问程序
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program)
+    }
+    /// 访问语句
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement)
+    }
+    /// 访问表达式
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression)
+    }
+    /// 访问字面量
+    fn visit_literal(&mut self, literal: &Literal) {
+        walk_literal(self, literal)
+    }
+    /// 访问变量引用
+    fn visit_variable(&mut self, _name: &String) {}
+    /// 访问二元表达式
+    fn visit_binary(&mut self, binary: &BinaryExpression) {
+        walk_binary(self, binary)
+    }
+    /// 访问一元表达式
+    fn visit_unary(&mut self, unary: &UnaryExpression) {
+        walk_unary(self, unary)
+    }
+    /// 访问函数调用
+    fn visit_call(&mut self, call: &CallExpression) {
+        walk_call(self, call)
+    }
+    /// 访问 lambda 表达式
+    fn visit_lambda(&mut self, lambda: &LambdaExpression) {
+        walk_lambda(self, lambda)
+    }
+    /// 访问条件表达式
+    fn visit_conditional(&mut self, conditional: &ConditionalExpression) {
+        walk_conditional(self, conditional)
+    }
+    /// 访问成员访问
+    fn visit_member_access(&mut self, access: &MemberAccessExpression) {
+        walk_member_access(self, access)
+    }
+    /// 访问索引访问
+    fn visit_index_access(&mut self, access: &IndexAccessExpression) {
+        walk_index_access(self, access)
+    }
+    /// 访问效应操作
+    fn visit_effect(&mut self, effect: &EffectExpression) {
+        walk_effect(self, effect)
+    }
+    /// 访问模式匹配
+    fn visit_match(&mut self, expression: &MatchExpression) {
+        walk_match(self, expression)
+    }
+    /// 访问类的实例化
+    fn visit_new(&mut self, expression: &NewExpression) {
+        walk_new(self, expression)
+    }
+    /// 访问字符串插值
+    fn visit_interpolation(&mut self, expression: &InterpolationExpression) {
+        walk_interpolation(self, expression)
+    }
+    /// 访问模式
+    fn visit_pattern(&mut self, _pattern: &Pattern) {}
+    /// 访问变量声明
+    fn visit_variable_declaration(&mut self, declaration: &VariableDeclaration) {
+        walk_variable_declaration(self, declaration)
+    }
+    /// 访问赋值语句
+    fn visit_assignment(&mut self, assignment: &Assignment) {
+        walk_assignment(self, assignment)
+    }
+    /// 访问条件语句
+    fn visit_if(&mut self, statement: &IfStatement) {
+        walk_if(self, statement)
+    }
+    /// 访问循环语句
+    fn visit_loop(&mut self, statement: &LoopStatement) {
+        walk_loop(self, statement)
+    }
+    /// 访问函数定义
+    fn visit_function(&mut self, function: &FunctionDefinition) {
+        walk_function(self, function)
+    }
+    /// 访问函数参数
+    fn visit_parameter(&mut self, _parameter: &Parameter) {}
+    /// 访问属性标注
+    fn visit_attribute(&mut self, attribute: &Attribute) {
+        walk_attribute(self, attribute)
+    }
+    /// 访问类定义
+    fn visit_class(&mut self, class: &ClassDefinition) {
+        walk_class(self, class)
+    }
+    /// 访问类的属性定义
+    fn visit_property(&mut self, property: &PropertyDefinition) {
+        walk_property(self, property)
+    }
+    /// 访问特征定义
+    fn visit_trait(&mut self, definition: &TraitDefinition) {
+        walk_trait(self, definition)
+    }
+    /// 访问特征方法
+    fn visit_trait_method(&mut self, method: &TraitMethod) {
+        walk_trait_method(self, method)
+    }
+    /// 访问枚举定义
+    fn visit_enum(&mut self, definition: &EnumDefinition) {
+        walk_enum(self, definition)
+    }
+    /// 访问枚举变体
+    fn visit_enum_variant(&mut self, variant: &EnumVariant) {
+        walk_enum_variant(self, variant)
+    }
+    /// 访问结构体定义
+    fn visit_structure(&mut self, _definition: &StructureDefinition) {}
+    /// 访问效应处理器定义
+    fn visit_effect_handler(&mut self, handler: &EffectHandlerDefinition) {
+        walk_effect_handler(self, handler)
+    }
+    /// 访问导入语句
+    fn visit_import(&mut self, _import: &ImportStatement) {}
+    /// 访问导出语句
+    fn visit_export(&mut self, export: &ExportStatement) {
+        walk_export(self, export)
+    }
+    /// 访问尝试-捕获语句
+    fn visit_try_catch(&mut self, statement: &TryCatchStatement) {
+        walk_try_catch(self, statement)
+    }
+    /// 访问捕获块
+    fn visit_catch_block(&mut self, block: &CatchBlock) {
+        walk_catch_block(self, block)
+    }
+}
+
+/// 访问程序的全部语句
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    walk_block(visitor, &program.statements)
+}
+
+/// 依次访问语句序列
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, statements: &[Statement]) {
+    for statement in statements {
+        visitor.visit_statement(statement)
+    }
+}
+
+/// 访问语句的子节点
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match &statement.kind {
+        StatementKind::Expression(expression) | StatementKind::Throw(expression) => visitor.visit_expression(expression),
+        StatementKind::VariableDeclaration(declaration) => visitor.visit_variable_declaration(declaration),
+        StatementKind::Assignment(assignment) => visitor.visit_assignment(assignment),
+        StatementKind::If(statement) => visitor.visit_if(statement),
+        StatementKind::Loop(statement) => visitor.visit_loop(statement),
+        StatementKind::Break | StatementKind::Continue => {}
+        StatementKind::Return(value) => {
+            if let Some(value) = value {
+                visitor.visit_expression(value)
+            }
+        }
+        StatementKind::Block(statements) => walk_block(visitor, statements),
+        StatementKind::FunctionDeclaration(function) => visitor.visit_function(function),
+        StatementKind::ClassDeclaration(class) => visitor.visit_class(class),
+        StatementKind::TraitDeclaration(definition) => visitor.visit_trait(definition),
+        StatementKind::EnumDeclaration(definition) => visitor.visit_enum(definition),
+        StatementKind::StructureDeclaration(definition) => visitor.visit_structure(definition),
+        StatementKind::EffectHandler(handler) => visitor.visit_effect_handler(handler),
+        StatementKind::Import(import) => visitor.visit_import(import),
+        StatementKind::Export(export) => visitor.visit_export(export),
+        StatementKind::TryCatch(statement) => visitor.visit_try_catch(statement),
+        StatementKind::Assert(condition, _) => visitor.visit_expression(condition),
+    }
+}
+
+/// 访问表达式的子节点
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match &expression.kind {
+        ExpressionKind::Literal(literal) => visitor.visit_literal(literal),
+        ExpressionKind::Variable(name) => visitor.visit_variable(name),
+        ExpressionKind::Binary(binary) => visitor.visit_binary(binary),
+        ExpressionKind::Unary(unary) => visitor.visit_unary(unary),
+        ExpressionKind::Call(call) => visitor.visit_call(call),
+        ExpressionKind::Lambda(lambda) => visitor.visit_lambda(lambda),
+        ExpressionKind::Conditional(conditional) => visitor.visit_conditional(conditional),
+        ExpressionKind::MemberAccess(access) => visitor.visit_member_access(access),
+        ExpressionKind::IndexAccess(access) => visitor.visit_index_access(access),
+        ExpressionKind::Effect(effect) => visitor.visit_effect(effect),
+        ExpressionKind::Match(expression) => visitor.visit_match(expression),
+        ExpressionKind::New(expression) => visitor.visit_new(expression),
+        ExpressionKind::Interpolation(expression) => visitor.visit_interpolation(expression),
+    }
+}
+
+/// 访问列表与对象字面量的元素
+pub fn walk_literal<V: Visitor + ?Sized>(visitor: &mut V, literal: &Literal) {
+    match literal {
+        Literal::Null | Literal::Boolean(_) | Literal::Integer(_) | Literal::String(_) => {}
+        Literal::List(items) => items.iter().for_each(|item| visitor.visit_expression(item)),
+        Literal::Object(properties) => properties.values().for_each(|value| visitor.visit_expression(value)),
+    }
+}
+
+/// 访问二元表达式的操作数
+pub fn walk_binary<V: Visitor + ?Sized>(visitor: &mut V, binary: &BinaryExpression) {
+    visitor.visit_expression(&binary.left);
+    visitor.visit_expression(&binary.right)
+}
+
+/// 访问一元表达式的操作数
+pub fn walk_unary<V: Visitor + ?Sized>(visitor: &mut V, unary: &UnaryExpression) {
+    visitor.visit_expression(&unary.operand)
+}
+
+/// 访问被调用者与参数
+pub fn walk_call<V: Visitor + ?Sized>(visitor: &mut V, call: &CallExpression) {
+    visitor.visit_expression(&call.callee);
+    call.arguments.iter().for_each(|argument| visitor.visit_expression(argument))
+}
+
+/// 访问 lambda 的函数体
+pub fn walk_lambda<V: Visitor + ?Sized>(visitor: &mut V, lambda: &LambdaExpression) {
+    visitor.visit_expression(&lambda.body)
+}
+
+/// 访问条件与两个分支
+pub fn walk_conditional<V: Visitor + ?Sized>(visitor: &mut V, conditional: &ConditionalExpression) {
+    visitor.visit_expression(&conditional.condition);
+    visitor.visit_expression(&conditional.then_branch);
+    if let Some(branch) = &conditional.else_branch {
+        visitor.visit_expression(branch)
+    }
+}
+
+/// 访问被访问成员的对象
+pub fn walk_member_access<V: Visitor + ?Sized>(visitor: &mut V, access: &MemberAccessExpression) {
+    visitor.visit_expression(&access.object)
+}
+
+/// 访问对象与索引
+pub fn walk_index_access<V: Visitor + ?Sized>(visitor: &mut V, access: &IndexAccessExpression) {
+    visitor.visit_expression(&access.object);
+    visitor.visit_expression(&access.index)
+}
+
+/// 访问效应参数
+pub fn walk_effect<V: Visitor + ?Sized>(visitor: &mut V, effect: &EffectExpression) {
+    effect.arguments.iter().for_each(|argument| visitor.visit_expression(argument))
+}
+
+/// 访问被匹配的值与各分支的模式、守卫和值
+pub fn walk_match<V: Visitor + ?Sized>(visitor: &mut V, expression: &MatchExpression) {
+    visitor.visit_expression(&expression.subject);
+    for arm in &expression.arms {
+        visitor.visit_pattern(&arm.pattern);
+        if let Some(guard) = &arm.guard {
+            visitor.visit_expression(guard)
+        }
+        visitor.visit_expression(&arm.body)
+    }
+}
+
+/// 访问属性的初始值
+pub fn walk_new<V: Visitor + ?Sized>(visitor: &mut V, expression: &NewExpression) {
+    expression.properties.iter().for_each(|(_, value)| visitor.visit_expression(value))
+}
+
+/// 访问插入的表达式
+pub fn walk_interpolation<V: Visitor + ?Sized>(visitor: &mut V, expression: &InterpolationExpression) {
+    expression.expressions().for_each(|expression| visitor.visit_expression(expression))
+}
+
+/// 访问变量的初始值
+pub fn walk_variable_declaration<V: Visitor + ?Sized>(visitor: &mut V, declaration: &VariableDeclaration) {
+    if let Some(initializer) = &declaration.initializer {
+        visitor.visit_expression(initializer)
+    }
+}
+
+/// 访问赋值目标与值
+pub fn walk_assignment<V: Visitor + ?Sized>(visitor: &mut V, assignment: &Assignment) {
+    visitor.visit_expression(&assignment.target);
+    visitor.visit_expression(&assignment.value)
+}
+
+/// 访问条件与两个分支
+pub fn walk_if<V: Visitor + ?Sized>(visitor: &mut V, statement: &IfStatement) {
+    visitor.visit_expression(&statement.condition);
+    walk_block(visitor, &statement.then_branch);
+    if let Some(branch) = &statement.else_branch {
+        walk_block(visitor, branch)
+    }
+}
+
+/// 访问循环的各个部分
+pub fn walk_loop<V: Visitor + ?Sized>(visitor: &mut V, statement: &LoopStatement) {
+    match statement {
+        LoopStatement::While { condition, body } => {
+            visitor.visit_expression(condition);
+            walk_block(visitor, body)
+        }
+        LoopStatement::For { initializer, condition, update, body } => {
+            visitor.visit_statement(initializer);
+            visitor.visit_expression(condition);
+            visitor.visit_statement(update);
+            walk_block(visitor, body)
+        }
+        LoopStatement::ForEach { iterable, body, .. } => {
+            visitor.visit_expression(iterable);
+            walk_block(visitor, body)
+        }
+        LoopStatement::Infinite { body } => walk_block(visitor, body),
+    }
+}
+
+/// 访问属性标注、参数与函数体
+pub fn walk_function<V: Visitor + ?Sized>(visitor: &mut V, function: &FunctionDefinition) {
+    function.attributes.iter().for_each(|attribute| visitor.visit_attribute(attribute));
+    function.parameters.iter().for_each(|parameter| visitor.visit_parameter(parameter));
+    walk_block(visitor, &function.body)
+}
+
+/// 访问属性标注的参数
+pub fn walk_attribute<V: Visitor + ?Sized>(visitor: &mut V, attribute: &Attribute) {
+    attribute.arguments.iter().for_each(|argument| visitor.visit_expression(argument))
+}
+
+/// 访问类的属性标注、属性与方法
+pub fn walk_class<V: Visitor + ?Sized>(visitor: &mut V, class: &ClassDefinition) {
+    class.attributes.iter().for_each(|attribute| visitor.visit_attribute(attribute));
+    class.properties.iter().for_each(|property| visitor.visit_property(property));
+    class.methods.iter().for_each(|method| visitor.visit_function(method))
+}
+
+/// 访问属性的默认值
+pub fn walk_property<V: Visitor + ?Sized>(visitor: &mut V, property: &PropertyDefinition) {
+    if let Some(default) = &property.default {
+        visitor.visit_expression(default)
+    }
+}
+
+/// 访问特征方法
+pub fn walk_trait<V: Visitor + ?Sized>(visitor: &mut V, definition: &TraitDefinition) {
+    definition.methods.iter().for_each(|method| visitor.visit_trait_method(method))
+}
+
+/// 访问特征方法的参数与默认实现
+pub fn walk_trait_method<V: Visitor + ?Sized>(visitor: &mut V, method: &TraitMethod) {
+    method.parameters.iter().for_each(|parameter| visitor.visit_parameter(parameter));
+    if let Some(body) = &method.body {
+        walk_block(visitor, body)
+    }
+}
+
+/// 访问枚举变体
+pub fn walk_enum<V: Visitor + ?Sized>(visitor: &mut V, definition: &EnumDefinition) {
+    definition.variants.iter().for_each(|variant| visitor.visit_enum_variant(variant))
+}
+
+/// 访问枚举变体的值
+pub fn walk_enum_variant<V: Visitor + ?Sized>(visitor: &mut V, variant: &EnumVariant) {
+    if let Some(value) = &variant.value {
+        visitor.visit_expression(value)
+    }
+}
+
+/// 访问处理器函数
+pub fn walk_effect_handler<V: Visitor + ?Sized>(visitor: &mut V, handler: &EffectHandlerDefinition) {
+    visitor.visit_function(&handler.handler)
+}
+
+/// 访问被导出的声明
+pub fn walk_export<V: Visitor + ?Sized>(visitor: &mut V, export: &ExportStatement) {
+    visitor.visit_statement(&export.declaration)
+}
+
+/// 访问尝试块、捕获块与最终块
+pub fn walk_try_catch<V: Visitor + ?Sized>(visitor: &mut V, statement: &TryCatchStatement) {
+    walk_block(visitor, &statement.try_block);
+    statement.catch_blocks.iter().for_each(|block| visitor.visit_catch_block(block));
+    if let Some(block) = &statement.finally_block {
+        walk_block(visitor, block)
+    }
+}
+
+/// 访问捕获块的处理语句
+pub fn walk_catch_block<V: Visitor + ?Sized>(visitor: &mut V, block: &CatchBlock) {
+    walk_block(visitor, &block.handler)
+}
@@ -0,0 +1,407 @@
+use crate::ast::{
+    Assignment, Attribute, BinaryExpression, CallExpression, CatchBlock, ClassDefinition, ConditionalExpression,
+    EffectExpression, EffectHandlerDefinition, EnumDefinition, EnumVariant, ExportStatement, Expression, ExpressionKind,
+    FunctionDefinition, IfStatement, ImportStatement, IndexAccessExpression, InterpolationExpression, InterpolationPart,
+    LambdaExpression, Literal, LoopStatement, MatchExpression, MemberAccessExpression, NewExpression, Parameter, Pattern,
+    Program, PropertyDefinition, Statement, StatementKind, StructureDefinition, TraitDefinition, TraitMethod,
+    TryCatchStatement, UnaryExpression, VariableDeclaration,
+};
+
+/// 遍历 AST 的可变访问者, 每个方法默认调用对应的 `walk` 函数访问子节点
+///
+/// 只需覆盖关心的节点, 在覆盖的方法中调用 `walk` 函数以继续访问子节点。
+pub trait VisitorMut {
+    /// 访问程序
+    fn visit_program(&mut self, program: &mut Program) {
+        walk_program_mut(self, program)
+    }
+    /// 访问语句
+    fn visit_statement(&mut self, statement: &mut Statement) {
+        walk_statement_mut(self, statement)
+    }
+    /// 访问表达式
+    fn visit_expression(&mut self, expression: &mut Expression) {
+        walk_expression_mut(self, expression)
+    }
+    /// 访问字面量
+    fn visit_literal(&mut self, literal: &mut Literal) {
+        walk_literal_mut(self, literal)
+    }
+    /// 访问变量引用
+    fn visit_variable(&mut self, _name: &mut String) {}
+    /// 访问二元表达式
+    fn visit_binary(&mut self, binary: &mut BinaryExpression) {
+        walk_binary_mut(self, binary)
+    }
+    /// 访问一元表达式
+    fn visit_unary(&mut self, unary: &mut UnaryExpression) {
+        walk_unary_mut(self, unary)
+    }
+    /// 访问函数调用
+    fn visit_call(&mut self, call: &mut CallExpression) {
+        walk_call_mut(self, call)
+    }
+    /// 访问 lambda 表达式
+    fn visit_lambda(&mut self, lambda: &mut LambdaExpression) {
+        walk_lambda_mut(self, lambda)
+    }
+    /// 访问条件表达式
+    fn visit_conditional(&mut self, conditional: &mut ConditionalExpression) {
+        walk_conditional_mut(self, conditional)
+    }
+    /// 访问成员访问
+    fn visit_member_access(&mut self, access: &mut MemberAccessExpression) {
+        walk_member_access_mut(self, access)
+    }
+    /// 访问索引访问
+    fn visit_index_access(&mut self, access: &mut IndexAccessExpression) {
+        walk_index_access_mut(self, access)
+    }
+    /// 访问效应操作
+    fn visit_effect(&mut self, effect: &mut EffectExpression) {
+        walk_effect_mut(self, effect)
+    }
+    /// 访问模式匹配
+    fn visit_match(&mut self, expression: &mut MatchExpression) {
+        walk_match_mut(self, expression)
+    }
+    /// 访问类的实例化
+    fn visit_new(&mut self, expression: &mut NewExpression) {
+        walk_new_mut(self, expression)
+    }
+    /// 访问字符串插值
+    fn visit_interpolation(&mut self, expression: &mut InterpolationExpression) {
+        walk_interpolation_mut(self, expression)
+    }
+    /// 访问模式
+    fn visit_pattern(&mut self, _pattern: &mut Pattern) {}
+    /// 访问变量声明
+    fn visit_variable_declaration(&mut self, declaration: &mut VariableDeclaration) {
+        walk_variable_declaration_mut(self, declaration)
+    }
+    /// 访问赋值语句
+    fn visit_assignment(&mut self, assignment: &mut Assignment) {
+        walk_assignment_mut(self, assignment)
+    }
+    /// 访问条件语句
+    fn visit_if(&mut self, statement: &mut IfStatement) {
+        walk_if_mut(self, statement)
+    }
+    /// 访问循环语句
+    fn visit_loop(&mut self, statement: &mut LoopStatement) {
+        walk_loop_mut(self, statement)
+    }
+    /// 访问函数定义
+    fn visit_function(&mut self, function: &mut FunctionDefinition) {
+        walk_function_mut(self, function)
+    }
+    /// 访问函数参数
+    fn visit_parameter(&mut self, _parameter: &mut Parameter) {}
+    /// 访问属性标注
+    fn visit_attribute(&mut self, attribute: &mut Attribute) {
+        walk_attribute_mut(self, attribute)
+    }
+    /// 访问类定义
+    fn visit_class(&mut self, class: &mut ClassDefinition) {
+        walk_class_mut(self, class)
+    }
+    /// 访问类的属性定义
+    fn visit_property(&mut self, property: &mut PropertyDefinition) {
+        walk_property_mut(self, property)
+    }
+    /// 访问特征定义
+    fn visit_trait(&mut self, definition: &mut TraitDefinition) {
+        walk_trait_mut(self, definition)
+    }
+    /// 访问特征方法
+    fn visit_trait_method(&mut self, method: &mut TraitMethod) {
+        walk_trait_method_mut(self, method)
+    }
+    /// 访问枚举定义
+    fn visit_enum(&mut self, definition: &mut EnumDefinition) {
+        walk_enum_mut(self, definition)
+    }
+    /// 访问枚举变体
+    fn visit_enum_variant(&mut self, variant: &mut EnumVariant) {
+        walk_enum_variant_mut(self, variant)
+    }
+    /// 访问结构体定义
+    fn visit_structure(&mut self, _definition: &mut StructureDefinition) {}
+    /// 访问效应处理器定义
+    fn visit_effect_handler(&mut self, handler: &mut EffectHandlerDefinition) {
+        walk_effect_handler_mut(self, handler)
+    }
+    /// 访问导入语句
+    fn visit_import(&mut self, _import: &mut ImportStatement) {}
+    /// 访问导出语句
+    fn visit_export(&mut self, export: &mut ExportStatement) {
+        walk_export_mut(self, export)
+    }
+    /// 访问尝试-捕获语句
+    fn visit_try_catch(&mut self, statement: &mut TryCatchStatement) {
+        walk_try_catch_mut(self, statement)
+    }
+    /// 访问捕获块
+    fn visit_catch_block(&mut self, block: &mut CatchBlock) {
+        walk_catch_block_mut(self, block)
+    }
+}
+
+/// 访问程序的全部语句
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+    walk_block_mut(visitor, &mut program.statements)
+}
+
+/// 依次访问语句序列
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statements: &mut [Statement]) {
+    for statement in statements {
+        visitor.visit_statement(statement)
+    }
+}
+
+/// 访问语句的子节点
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statement: &mut Statement) {
+    match &mut statement.kind {
+        StatementKind::Expression(expression) | StatementKind::Throw(expression) => visitor.visit_expression(expression),
+        StatementKind::VariableDeclaration(declaration) => visitor.visit_variable_declaration(declaration),
+        StatementKind::Assignment(assignment) => visitor.visit_assignment(assignment),
+        StatementKind::If(statement) => visitor.visit_if(statement),
+        StatementKind::Loop(statement) => visitor.visit_loop(statement),
+        StatementKind::Break | StatementKind::Continue => {}
+        StatementKind::Return(value) => {
+            if let Some(value) = value {
+                visitor.visit_expression(value)
+            }
+        }
+        StatementKind::Block(statements) => walk_block_mut(visitor, statements),
+        StatementKind::FunctionDeclaration(function) => visitor.visit_function(function),
+        StatementKind::ClassDeclaration(class) => visitor.visit_class(class),
+        StatementKind::TraitDeclaration(definition) => visitor.visit_trait(definition),
+        StatementKind::EnumDeclaration(definition) => visitor.visit_enum(definition),
+        StatementKind::StructureDeclaration(definition) => visitor.visit_structure(definition),
+        StatementKind::EffectHandler(handler) => visitor.visit_effect_handler(handler),
+        StatementKind::Import(import) => visitor.visit_import(import),
+        StatementKind::Export(export) => visitor.visit_export(export),
+        StatementKind::TryCatch(statement) => visitor.visit_try_catch(statement),
+        StatementKind::Assert(condition, _) => visitor.visit_expression(condition),
+    }
+}
+
+/// 访问表达式的子节点
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut Expression) {
+    match &mut expression.kind {
+        ExpressionKind::Literal(literal) => visitor.visit_literal(literal),
+        ExpressionKind::Variable(name) => visitor.visit_variable(name),
+        ExpressionKind::Binary(binary) => visitor.visit_binary(binary),
+        ExpressionKind::Unary(unary) => visitor.visit_unary(unary),
+        ExpressionKind::Call(call) => visitor.visit_call(call),
+        ExpressionKind::Lambda(lambda) => visitor.visit_lambda(lambda),
+        ExpressionKind::Conditional(conditional) => visitor.visit_conditional(conditional),
+        ExpressionKind::MemberAccess(access) => visitor.visit_member_access(access),
+        ExpressionKind::IndexAccess(access) => visitor.visit_index_access(access),
+        ExpressionKind::Effect(effect) => visitor.visit_effect(effect),
+        ExpressionKind::Match(expression) => visitor.visit_match(expression),
+        ExpressionKind::New(expression) => visitor.visit_new(expression),
+        ExpressionKind::Interpolation(expression) => visitor.visit_interpolation(expression),
+    }
+}
+
+/// 访问列表与对象字面量的元素
+pub fn walk_literal_mut<V: VisitorMut + ?Sized>(visitor: &mut V, literal: &mut Literal) {
+    match literal {
+        Literal::Null | Literal::Boolean(_) | Literal::Integer(_) | Literal::String(_) => {}
+        Literal::List(items) => items.iter_mut().for_each(|item| visitor.visit_expression(item)),
+        Literal::Object(properties) => properties.values_mut().for_each(|value| visitor.visit_expression(value)),
+    }
+}
+
+/// 访问二元表达式的操作数
+pub fn walk_binary_mut<V: VisitorMut + ?Sized>(visitor: &mut V, binary: &mut BinaryExpression) {
+    visitor.visit_expression(&mut binary.left);
+    visitor.visit_expression(&mut binary.right)
+}
+
+/// 访问一元表达式的操作数
+pub fn walk_unary_mut<V: VisitorMut + ?Sized>(visitor: &mut V, unary: &mut UnaryExpression) {
+    visitor.visit_expression(&mut unary.operand)
+}
+
+/// 访问被调用者与参数
+pub fn walk_call_mut<V: VisitorMut + ?Sized>(visitor: &mut V, call: &mut CallExpression) {
+    visitor.visit_expression(&mut call.callee);
+    call.arguments.iter_mut().for_each(|argument| visitor.visit_expression(argument))
+}
+
+/// 访问 lambda 的函数体
+pub fn walk_lambda_mut<V: VisitorMut + ?Sized>(visitor: &mut V, lambda: &mut LambdaExpression) {
+    visitor.visit_expression(&mut lambda.body)
+}
+
+/// 访问条件与两个分支
+pub fn walk_conditional_mut<V: VisitorMut + ?Sized>(visitor: &mut V, conditional: &mut ConditionalExpression) {
+    visitor.visit_expression(&mut conditional.condition);
+    visitor.visit_expression(&mut conditional.then_branch);
+    if let Some(branch) = &mut conditional.else_branch {
+        visitor.visit_expression(branch)
+    }
+}
+
+/// 访问被访问成员的对象
+pub fn walk_member_access_mut<V: VisitorMut + ?Sized>(visitor: &mut V, access: &mut MemberAccessExpression) {
+    visitor.visit_expression(&mut access.object)
+}
+
+/// 访问对象与索引
+pub fn walk_index_access_mut<V: VisitorMut + ?Sized>(visitor: &mut V, access: &mut IndexAccessExpression) {
+    visitor.visit_expression(&mut access.object);
+    visitor.visit_expression(&mut access.index)
+}
+
+/// 访问效应参数
+pub fn walk_effect_mut<V: VisitorMut + ?Sized>(visitor: &mut V, effect: &mut EffectExpression) {
+    effect.arguments.iter_mut().for_each(|argument| visitor.visit_expression(argument))
+}
+
+/// 访问被匹配的值与各分支的模式、守卫和值
+pub fn walk_match_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut MatchExpression) {
+    visitor.visit_expression(&mut expression.subject);
+    for arm in &mut expression.arms {
+        visitor.visit_pattern(&mut arm.pattern);
+        if let Some(guard) = &mut arm.guard {
+            visitor.visit_expression(guard)
+        }
+        visitor.visit_expression(&mut arm.body)
+    }
+}
+
+/// 访问属性的初始值
+pub fn walk_new_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut NewExpression) {
+    expression.properties.iter_mut().for_each(|(_, value)| visitor.visit_expression(value))
+}
+
+/// 访问插入的表达式
+pub fn walk_interpolation_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut InterpolationExpression) {
+    for part in &mut expression.parts {
+        if let InterpolationPart::Expression(expression) = part {
+            visitor.visit_expression(expression)
+        }
+    }
+}
+
+/// 访问变量的初始值
+pub fn walk_variable_declaration_mut<V: VisitorMut + ?Sized>(visitor: &mut V, declaration: &mut VariableDeclaration) {
+    if let Some(initializer) = &mut declaration.initializer {
+        visitor.visit_expression(initializer)
+    }
+}
+
+/// 访问赋值目标与值
+pub fn walk_assignment_mut<V: VisitorMut + ?Sized>(visitor: &mut V, assignment: &mut Assignment) {
+    visitor.visit_expression(&mut assignment.target);
+    visitor.visit_expression(&mut assignment.value)
+}
+
+/// 访问条件与两个分支
+pub fn walk_if_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statement: &mut IfStatement) {
+    visitor.visit_expression(&mut statement.condition);
+    walk_block_mut(visitor, &mut statement.then_branch);
+    if let Some(branch) = &mut statement.else_branch {
+        walk_block_mut(visitor, branch)
+    }
+}
+
+/// 访问循环的各个部分
+pub fn walk_loop_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statement: &mut LoopStatement) {
+    match statement {
+        LoopStatement::While { condition, body } => {
+            visitor.visit_expression(condition);
+            walk_block_mut(visitor, body)
+        }
+        LoopStatement::For { initializer, condition, update, body } => {
+            visitor.visit_statement(initializer);
+            visitor.visit_expression(condition);
+            visitor.visit_statement(update);
+            walk_block_mut(visitor, body)
+        }
+        LoopStatement::ForEach { iterable, body, .. } => {
+            visitor.visit_expression(iterable);
+            walk_block_mut(visitor, body)
+        }
+        LoopStatement::Infinite { body } => walk_block_mut(visitor, body),
+    }
+}
+
+/// 访问属性标注、参数与函数体
+pub fn walk_function_mut<V: VisitorMut + ?Sized>(visitor: &mut V, function: &mut FunctionDefinition) {
+    function.attributes.iter_mut().for_each(|attribute| visitor.visit_attribute(attribute));
+    function.parameters.iter_mut().for_each(|parameter| visitor.visit_parameter(parameter));
+    walk_block_mut(visitor, &mut function.body)
+}
+
+/// 访问属性标注的参数
+pub fn walk_attribute_mut<V: VisitorMut + ?Sized>(visitor: &mut V, attribute: &mut Attribute) {
+    attribute.arguments.iter_mut().for_each(|argument| visitor.visit_expression(argument))
+}
+
+/// 访问类的属性标注、属性与方法
+pub fn walk_class_mut<V: VisitorMut + ?Sized>(visitor: &mut V, class: &mut ClassDefinition) {
+    class.attributes.iter_mut().for_each(|attribute| visitor.visit_attribute(attribute));
+    class.properties.iter_mut().for_each(|property| visitor.visit_property(property));
+    class.methods.iter_mut().for_each(|method| visitor.visit_function(method))
+}
+
+/// 访问属性的默认值
+pub fn walk_property_mut<V: VisitorMut + ?Sized>(visitor: &mut V, property: &mut PropertyDefinition) {
+    if let Some(default) = &mut property.default {
+        visitor.visit_expression(default)
+    }
+}
+
+/// 访问特征方法
+pub fn walk_trait_mut<V: VisitorMut + ?Sized>(visitor: &mut V, definition: &mut TraitDefinition) {
+    definition.methods.iter_mut().for_each(|method| visitor.visit_trait_method(method))
+}
+
+/// 访问特征方法的参数与默认实现
+pub fn walk_trait_method_mut<V: VisitorMut + ?Sized>(visitor: &mut V, method: &mut TraitMethod) {
+    method.parameters.iter_mut().for_each(|parameter| visitor.visit_parameter(parameter));
+    if let Some(body) = &mut method.body {
+        walk_block_mut(visitor, body)
+    }
+}
+
+/// 访问枚举变体
+pub fn walk_enum_mut<V: VisitorMut + ?Sized>(visitor: &mut V, definition: &mut EnumDefinition) {
+    definition.variants.iter_mut().for_each(|variant| visitor.visit_enum_variant(variant))
+}
+
+/// 访问枚举变体的值
+pub fn walk_enum_variant_mut<V: VisitorMut + ?Sized>(visitor: &mut V, variant: &mut EnumVariant) {
+    if let Some(value) = &mut variant.value {
+        visitor.visit_expression(value)
+    }
+}
+
+/// 访问处理器函数
+pub fn walk_effect_handler_mut<V: VisitorMut + ?Sized>(visitor: &mut V, handler: &mut EffectHandlerDefinition) {
+    visitor.visit_function(&mut handler.handler)
+}
+
+/// 访问被导出的声明
+pub fn walk_export_mut<V: VisitorMut + ?Sized>(visitor: &mut V, export: &mut ExportStatement) {
+    visitor.visit_statement(&mut export.declaration)
+}
+
+/// 访问尝试块、捕获块与最终块
+pub fn walk_try_catch_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statement: &mut TryCatchStatement) {
+    walk_block_mut(visitor, &mut statement.try_block);
+    statement.catch_blocks.iter_mut().for_each(|block| visitor.visit_catch_block(block));
+    if let Some(block) = &mut statement.finally_block {
+        walk_block_mut(visitor, block)
+    }
+}
+
+/// 访问捕获块的处理语句
+pub fn walk_catch_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, block: &mut CatchBlock) {
+    walk_block_mut(visitor, &mut block.handler)
+}
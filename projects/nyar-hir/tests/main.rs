@@ -0,0 +1,604 @@
+use nyar_error::{NyarError, NyarErrorKind};
+use nyar_hir::{
+    NyarCompiler,
+    ast::{
+        BinaryExpression, CallExpression, Expression, ExpressionKind, FunctionDefinition, IfStatement, ImportStatement,
+        Literal, LoopStatement, Parameter, Program, Span, Statement, StatementKind, VariableDeclaration,
+    },
+    cfg::{
+        BasicBlock, Dominators, HirFunction, HirInstruction, HirModule, Liveness, Location, Operand, ReachingDefinitions,
+        Rvalue, Terminator, UseDef, Variable,
+    },
+    types::{EffectChecker, NyarType, TypeChecker},
+    visit::{Visitor, VisitorMut, walk_expression_mut},
+};
+use nyar_lir::{BinaryOperator, Instruction, NyarValue};
+
+fn integer(value: i64) -> Expression {
+    ExpressionKind::Literal(Literal::Integer(value)).into()
+}
+
+fn binary(left: Expression, operator: &str, right: Expression) -> Expression {
+    ExpressionKind::Binary(Box::new(BinaryExpression { left, operator: operator.to_string(), right })).into()
+}
+
+fn compile(statements: Vec<Statement>) -> Vec<Instruction> {
+    let mut program = Program::new();
+    statements.into_iter().for_each(|s| program.add_statement(s));
+    let compiled = NyarCompiler::new().compile(&program).unwrap();
+    assert!(compiled.is_success(), "{:?}", compiled.errors());
+    compiled.into_bytecode()
+}
+
+fn parse(source: &str) -> Program {
+    valkyrie_parser::ValkyrieParser::new().parse(source).into_result().unwrap()
+}
+
+/// 比较类型错误的信息与所在的源码
+fn assert_diagnostics(errors: &[NyarError], source: &str, expected: &[(&str, &str)]) {
+    let diagnostics: Vec<_> = errors
+        .iter()
+        .map(|error| match error.kind() {
+            NyarErrorKind::Type { message } => (message.as_str(), &source[error.span()]),
+            _ => panic!("{:?}", error),
+        })
+        .collect();
+    assert_eq!(diagnostics, expected);
+}
+
+#[test]
+fn compile_expression() {
+    let x = ExpressionKind::Variable("x".to_string()).into();
+    let bytecode = compile(vec![StatementKind::Expression(binary(x, "+", integer(2))).into()]);
+    assert_eq!(
+        bytecode,
+        vec![
+            Instruction::PushVariable { name: "x".to_string() },
+            Instruction::PushConstant { value: NyarValue::from(2) },
+            Instruction::Binary { operator: BinaryOperator::Add },
+            Instruction::Return,
+        ]
+    );
+}
+
+#[test]
+fn compile_if_else() {
+    let declare = |value: i64| -> Statement {
+        let initializer = Some(integer(value));
+        let declaration = VariableDeclaration { name: "y".to_string(), type_annotation: None, initializer, is_constant: false };
+        StatementKind::VariableDeclaration(declaration).into()
+    };
+    let bytecode = compile(vec![
+        StatementKind::If(IfStatement {
+            condition: ExpressionKind::Variable("c".to_string()).into(),
+            then_branch: vec![declare(1)],
+            else_branch: Some(vec![declare(2)]),
+        })
+        .into(),
+    ]);
+    assert_eq!(bytecode[1], Instruction::JumpIfFalse { offset: 4 });
+    assert_eq!(bytecode[4], Instruction::Jump { offset: 3 });
+}
+
+#[test]
+fn constant_folding() {
+    let variable = |name: &str| -> Expression { ExpressionKind::Variable(name.to_string()).into() };
+    let constant = |name: &str, initializer: Expression| -> Statement {
+        let declaration = VariableDeclaration {
+            name: name.to_string(),
+            type_annotation: None,
+            initializer: Some(initializer),
+            is_constant: true,
+        };
+        StatementKind::VariableDeclaration(declaration).into()
+    };
+    let bytecode = compile(vec![
+        constant("x", binary(integer(2), "*", integer(3))),
+        StatementKind::If(IfStatement {
+            condition: binary(variable("x"), ">", integer(5)),
+            then_branch: vec![StatementKind::Expression(binary(variable("x"), "+", integer(1))).into()],
+            else_branch: Some(vec![StatementKind::Expression(variable("missing")).into()]),
+        })
+        .into(),
+    ]);
+    assert_eq!(
+        bytecode,
+        vec![
+            Instruction::PushConstant { value: NyarValue::from(6) },
+            Instruction::DeclareVariable { name: "x".to_string() },
+            Instruction::PushConstant { value: NyarValue::Null },
+            Instruction::Return,
+        ]
+    );
+
+    // 在嵌套函数中被重新绑定的常量不会被代入
+    let function = FunctionDefinition {
+        attributes: vec![],
+        name: "f".to_string(),
+        generics: vec![],
+        parameters: vec![Parameter::new("y")],
+        return_type: None,
+        body: vec![constant("y", variable("y"))],
+        span: Default::default(),
+    };
+    let bytecode = compile(vec![
+        constant("y", integer(1)),
+        StatementKind::FunctionDeclaration(function).into(),
+        StatementKind::Expression(variable("y")).into(),
+    ]);
+    assert_eq!(bytecode[bytecode.len() - 2], Instruction::PushVariable { name: "y".to_string() });
+
+    let mut program = Program::new();
+    let mut division: Expression = binary(integer(1), "/", binary(integer(2), "-", integer(2)));
+    division.span = Span::from(4..13);
+    program.add_statement(StatementKind::Expression(binary(integer(1), "+", division)).into());
+    let compiled = NyarCompiler::new().compile(&program).unwrap();
+    assert_eq!(compiled.errors().len(), 1);
+    assert!(matches!(compiled.errors()[0].kind(), NyarErrorKind::Custom { message } if message == "division by zero"));
+    assert_eq!(compiled.errors()[0].span(), 4..13);
+
+    // 类型检查已报告的运算数类型错误不会被再次报告
+    let mut program = Program::new();
+    let mut addition = binary(ExpressionKind::Literal(Literal::String("a".to_string())).into(), "+", integer(1));
+    addition.span = Span::from(0..7);
+    program.add_statement(StatementKind::Expression(addition).into());
+    let compiled = NyarCompiler::new().compile(&program).unwrap();
+    assert_eq!(compiled.errors().len(), 1, "{:?}", compiled.errors());
+    assert!(matches!(compiled.errors()[0].kind(), NyarErrorKind::Type { .. }));
+}
+
+#[test]
+fn compile_while_with_break() {
+    let bytecode = compile(vec![
+        StatementKind::VariableDeclaration(VariableDeclaration {
+            name: "i".to_string(),
+            type_annotation: None,
+            initializer: Some(integer(0)),
+            is_constant: false,
+        })
+        .into(),
+        StatementKind::Loop(LoopStatement::While {
+            condition: binary(ExpressionKind::Variable("i".to_string()).into(), "<", integer(3)),
+            body: vec![StatementKind::Break.into()],
+        })
+        .into(),
+    ]);
+    let jumps: Vec<_> = bytecode.iter().enumerate().filter_map(|(i, c)| c.jump_offset().map(|o| i as isize + o)).collect();
+    assert!(jumps.iter().all(|target| (*target as usize) < bytecode.len()));
+}
+
+#[test]
+fn report_break_outside_loop() {
+    let mut program = Program::new();
+    program.add_statement(StatementKind::Break.into());
+    let compiled = NyarCompiler::new().compile(&program).unwrap();
+    assert_eq!(compiled.errors().len(), 1);
+}
+
+#[test]
+fn line_table_maps_instructions() {
+    let mut program = Program::new();
+    program.file = "main.vk".into();
+    program.add_statement(Statement::new(StatementKind::Expression(binary(integer(1), "+", integer(2))), (0..5).into()));
+    let compiled = NyarCompiler::new().compile(&program).unwrap();
+    let entry = compiled.line_table().lookup(2).unwrap();
+    assert_eq!((entry.file.as_str(), entry.span.clone()), ("main.vk", 0..5));
+}
+
+#[test]
+fn visitor_collects_imports() {
+    struct Imports(Vec<String>);
+    impl Visitor for Imports {
+        fn visit_import(&mut self, import: &ImportStatement) {
+            self.0.push(import.path.clone())
+        }
+    }
+    let import = |path: &str| -> Statement {
+        StatementKind::Import(ImportStatement { path: path.to_string(), symbols: vec![], is_all: true, alias: None }).into()
+    };
+    let mut program = Program::new();
+    program.add_statement(import("std.io"));
+    program.add_statement(StatementKind::Block(vec![import("std.net")]).into());
+    let mut imports = Imports(vec![]);
+    imports.visit_program(&program);
+    assert_eq!(imports.0, ["std.io", "std.net"]);
+}
+
+#[test]
+fn visitor_mut_renames_variables() {
+    struct Rename;
+    impl VisitorMut for Rename {
+        fn visit_variable(&mut self, name: &mut String) {
+            if name == "i" {
+                *name = "index".to_string()
+            }
+        }
+    }
+    let mut expression = binary(ExpressionKind::Variable("i".to_string()).into(), "+", integer(1));
+    walk_expression_mut(&mut Rename, &mut expression);
+    let ExpressionKind::Binary(binary) = &expression.kind
+    else {
+        panic!()
+    };
+    assert!(matches!(&binary.left.kind, ExpressionKind::Variable(name) if name == "index"));
+}
+
+#[test]
+fn cfg_dump() {
+    let variable = |name: &str| -> Expression { ExpressionKind::Variable(name.to_string()).into() };
+    let return_if = |n: i64, name: &str| -> Statement {
+        StatementKind::If(IfStatement {
+            condition: binary(variable("n"), "==", integer(n)),
+            then_branch: vec![StatementKind::Return(Some(variable(name))).into()],
+            else_branch: None,
+        })
+        .into()
+    };
+    let call = ExpressionKind::Call(Box::new(CallExpression {
+        callee: variable("fibonacci_helper"),
+        arguments: vec![
+            binary(variable("n"), "-", integer(1)),
+            variable("current"),
+            binary(variable("previous"), "+", variable("current")),
+        ],
+    }));
+    let mut program = Program::new();
+    program.add_statement(
+        StatementKind::FunctionDeclaration(FunctionDefinition {
+            attributes: vec![],
+            name: "fibonacci_helper".to_string(),
+            generics: vec![],
+            parameters: vec![Parameter::new("n"), Parameter::new("previous"), Parameter::new("current")],
+            return_type: None,
+            body: vec![return_if(0, "previous"), return_if(1, "current"), StatementKind::Expression(call.into()).into()],
+            span: Default::default(),
+        })
+        .into(),
+    );
+    let module = HirModule::lower(&program).unwrap();
+    let expected = r#"function fibonacci_helper
+(
+    %n: auto,
+    %previous: auto,
+    %current: auto,
+    ^return: auto,
+)
+{
+function.begin:
+    %tmp1 = binary == (%n, 0);
+    jump 1 if %tmp1;
+    jump 2;
+if1.begin:
+    ^return(%previous);
+if1.end:
+    %tmp2 = binary == (%n, 1);
+    jump 3 if %tmp2;
+    jump 4;
+if2.begin:
+    ^return(%current);
+if2.end:
+    %tmp3 = binary - (%n, 1);
+    %tmp4 = binary + (%previous, %current);
+    %tmp5 = call fibonacci_helper (%tmp3, %current, %tmp4);
+    ^return(%tmp5);
+}
+"#;
+    assert_eq!(module.function("fibonacci_helper").unwrap().to_string(), expected);
+    assert_eq!(
+        module.entry().unwrap().blocks[0].to_string(),
+        "function.begin:\n    let %fibonacci_helper = function fibonacci_helper;\n    ^return(null);\n"
+    );
+    let optimized = module.eliminate_tail_calls();
+    assert_eq!(
+        optimized.function("fibonacci_helper").unwrap().blocks[4].to_string(),
+        "if2.end:\n    %tmp3 = binary - (%n, 1);\n    %tmp4 = binary + (%previous, %current);\n    %n = %tmp3;\n    \
+         %previous = %current;\n    %current = %tmp4;\n    jump 0;\n"
+    );
+}
+
+/// `if c { x = 2 }; x`, 其中 `x` 在入口赋值为 1
+fn diamond() -> HirFunction {
+    let x = || Variable::Local("x".to_string());
+    let block = |instructions, terminator| BasicBlock { label: String::new(), instructions, terminator };
+    let assign = |value: i64| HirInstruction::Assign { target: x(), value: Rvalue::Use(NyarValue::from(value).into()) };
+    let condition = Variable::Local("c".to_string()).into();
+    HirFunction {
+        name: "diamond".to_string(),
+        source_name: None,
+        parameters: vec![],
+        return_type: None,
+        blocks: vec![
+            block(vec![assign(1)], Terminator::Branch { condition, then_block: 1, else_block: 2 }),
+            block(vec![assign(2)], Terminator::Jump(3)),
+            block(vec![], Terminator::Jump(3)),
+            block(vec![], Terminator::Return(x().into())),
+        ],
+    }
+}
+
+#[test]
+fn cfg_analyses() {
+    let function = diamond();
+    let x = Variable::Local("x".to_string());
+    let dominators = Dominators::new(&function);
+    assert_eq!(dominators.immediate_dominator(3), Some(0));
+    assert!(dominators.dominates(0, 1) && !dominators.dominates(1, 3));
+    assert_eq!(dominators.frontiers(&function)[1].iter().collect::<Vec<_>>(), vec![&3]);
+    let liveness = Liveness::new(&function);
+    assert!(liveness.live_out(0).contains(&x) && !liveness.live_in(1).contains(&x));
+    assert!(liveness.live_in(0).contains(&Variable::Local("c".to_string())));
+    let reaching = ReachingDefinitions::new(&function);
+    assert_eq!(reaching.reach_in(3).len(), 2);
+    let use_def = UseDef::new(&function);
+    let ret = Location { block: 3, index: 0 };
+    assert_eq!(use_def.definitions(ret, &x), &[Location { block: 0, index: 0 }, Location { block: 1, index: 0 }]);
+    assert_eq!(use_def.uses(Location { block: 1, index: 0 }), vec![(ret, &x)]);
+}
+
+#[test]
+fn cfg_to_ssa() {
+    let function = diamond().to_ssa(&Default::default());
+    let version = |n| Operand::Variable(Variable::Versioned(Box::new(Variable::Local("x".to_string())), n));
+    assert_eq!(
+        function.blocks[3].instructions,
+        vec![HirInstruction::Phi {
+            target: Variable::Versioned(Box::new(Variable::Local("x".to_string())), 3),
+            sources: vec![(1, version(2)), (2, version(1))],
+        }]
+    );
+    assert_eq!(function.blocks[3].terminator, Terminator::Return(version(3)));
+    assert_eq!(function.blocks[3].to_string(), ":\n    %x.3 = phi [1: %x.2, 2: %x.1];\n    ^return(%x.3);\n");
+}
+
+#[test]
+fn parse_type_annotations() {
+    let parsed = NyarType::parse("(i32, List<String>) -> Map<String, Integer>").unwrap();
+    let expected = NyarType::function(
+        vec![NyarType::Integer, NyarType::List(Box::new(NyarType::String))],
+        NyarType::Named { name: "Map".to_string(), arguments: vec![NyarType::String, NyarType::Integer] },
+    );
+    assert_eq!(parsed, expected);
+    assert_eq!(parsed.to_string(), "(Integer, List<String>) -> Map<String, Integer>");
+    assert_eq!(NyarType::parse("bool").unwrap(), NyarType::Boolean);
+    assert!(NyarType::parse("Integer<String>").is_err());
+    assert!(NyarType::parse("(Integer").is_err());
+}
+
+#[test]
+fn type_checking() {
+    let source = r#"
+class Point {
+    x: Integer = 0
+    label: String = "origin"
+}
+function add(a: Integer, b: i32) -> Integer { a + b }
+function name(p: Point) -> String { p.label }
+function wrong() -> Integer { "zero" }
+let n: Integer = "one"
+let m = add(1, true)
+let xs: List<Integer> = [1, 2, 3]
+xs[0] = "a"
+let f: (Integer) -> Integer = x => x + 1
+let s: Shape = null
+let loose = 1
+loose = "any"
+function g(a) { a + 1 }
+g("untyped")
+name(null)
+add(1, 2).z
+function height(p: Point) { p.y }
+"#;
+    let compiled = NyarCompiler::new().compile(&parse(source)).unwrap();
+    assert_diagnostics(
+        compiled.errors(),
+        source,
+        &[
+            ("expected `Integer`, found `String`", "\"zero\""),
+            ("expected `Integer`, found `String`", "\"one\""),
+            ("expected `Integer`, found `Boolean`", "true"),
+            ("expected `Integer`, found `String`", "\"a\""),
+            ("unknown type `Shape`", "let s: Shape = null"),
+            ("expected `Integer`, found `String`", "\"untyped\""),
+            ("expected `Point`, found `null`", "null"),
+            ("`Integer` has no member `z`", "add(1, 2).z"),
+            ("`Point` has no member `y`", "p.y"),
+        ],
+    );
+}
+
+#[test]
+fn type_inference() {
+    let source = r#"
+function id(x) { x }
+let a = id(1) + 1
+let b = id("b") + "c"
+let inc = x => x + 1
+inc("s")
+function first<T>(xs: List<T>) -> T { xs[0] }
+let n: String = first([1, 2])
+function bad<T>(x: T) -> Integer { x }
+class Box<T> {
+    value: T
+}
+function unbox(boxed: Box<Integer>) -> String { boxed.value }
+let pair: Box<Integer, String>
+function apply(f, x) { f(x) }
+apply(inc, 2)
+"#;
+    let mut checker = TypeChecker::new();
+    let errors = checker.check(&parse(source));
+    let expected = [
+        ("expected `Integer`, found `String`", "\"s\""),
+        ("expected `String`, found `Integer`", "first([1, 2])"),
+        ("expected `Integer`, found `T`", "x"),
+        ("expected `String`, found `Integer`", "boxed.value"),
+        ("`Box` expects 1 type arguments, found 2", "let pair: Box<Integer, String>"),
+    ];
+    assert_diagnostics(&errors, source, &expected);
+    let hover = |text: &str| checker.type_at(source.find(text).unwrap()).unwrap().1.to_string();
+    assert_eq!(hover("function id"), "('a) -> 'a");
+    assert_eq!(hover("let a"), "Integer");
+    assert_eq!(hover("let b"), "String");
+    assert_eq!(hover("let inc"), "(Integer) -> Integer");
+    assert_eq!(hover("function first"), "(List<T>) -> T");
+    assert_eq!(hover("function apply"), "(('a) -> 'b, 'a) -> 'b");
+    assert_eq!(hover("apply(inc, 2)"), "((Integer) -> Integer, Integer) -> Integer");
+    assert_eq!(hover("(inc, 2)"), "Integer");
+
+    // 泛型函数每次调用都以实例化的返回类型参与运算, 被重新赋值的变量以初始值的类型报告
+    for (source, message, text) in [
+        ("function first(xs) { xs[0] }\nfirst([1]) + first([\"a\"])", "`Integer` and `String`", "first([1]) + first([\"a\"])"),
+        ("let r = [1]\nr = r + [2]", "`List<Integer>` and `List<Integer>`", "r + [2]"),
+    ] {
+        let expected = format!("operator `+` cannot be applied to {}", message);
+        assert_diagnostics(&TypeChecker::new().check(&parse(source)), source, &[(&expected, text)]);
+    }
+
+    // 没有注解的代码不会被拒绝: lambda 被泛化, 没有注解的列表可以存入其他类型的元素
+    for source in [
+        "let f = x => x\nf(1)\nf(\"a\")",
+        "function show(x) { if x == null { \"none\" } else { x } }\nshow(null)",
+        "let show = x => if x == null { \"none\" } else { x }\nshow(null)\nshow(1)",
+        "let a = [1, 2]\na[0] = \"z\"",
+    ] {
+        assert_diagnostics(&TypeChecker::new().check(&parse(source)), source, &[]);
+    }
+    let mut checker = TypeChecker::new();
+    let source = "let f = x => x\nlet xs: List<Integer> = [1]\nxs[0] = f(\"a\")";
+    let errors = checker.check(&parse(source));
+    assert_diagnostics(&errors, source, &[("expected `Integer`, found `String`", "f(\"a\")")]);
+    assert_eq!(checker.type_at(0).unwrap().1.to_string(), "('a) -> 'a");
+}
+
+#[test]
+fn effect_checking() {
+    let source = r#"
+function ask_twice() { raise ask + raise ask }
+function logged(x) {
+    raise log(x)
+    x
+}
+#raises(ask)
+function declared() { ask_twice() + logged(1) }
+function handled() {
+    handle log(message) { null }
+    logged(2)
+}
+let quiet = () => raise log(3)
+handle ask() { 21 }
+ask_twice()
+handled()
+quiet()
+raise exit
+"#;
+    let mut checker = EffectChecker::new();
+    let errors = checker.check(&parse(source));
+    let expected = [
+        ("function `declared` raises undeclared effect `log`", "logged(1)"),
+        ("unhandled effect `log`", "quiet()"),
+        ("unhandled effect `exit`", "raise exit"),
+    ];
+    assert_diagnostics(&errors, source, &expected);
+    let effects = |name: &str| checker.effects_of(name).unwrap().iter().cloned().collect::<Vec<_>>();
+    assert_eq!(effects("ask_twice"), ["ask"]);
+    assert_eq!(effects("logged"), ["log"]);
+    assert_eq!(effects("declared"), ["ask"]);
+    assert!(effects("handled").is_empty());
+    assert_eq!(effects("quiet"), ["log"]);
+
+    // 处理器只对同一块中其后的语句有效, 传入的 lambda 的效应计入调用处
+    let unhandled = |source: &str| -> Vec<String> {
+        EffectChecker::new().check(&parse(source)).iter().map(|error| source[error.span()].to_string()).collect()
+    };
+    assert_eq!(unhandled("function f() { raise ask }\nf()\nhandle ask() { 1 }\nf()"), ["f()"]);
+    assert_eq!(unhandled("if false { handle ask() { 1 } }\nraise ask"), ["raise ask"]);
+    assert_eq!(unhandled("function apply(f) { f() }\napply(() => raise boom)"), ["apply(() => raise boom)"]);
+    assert!(unhandled("function apply(f) { f() }\nhandle boom() { 1 }\napply(() => raise boom)").is_empty());
+    // 被调用者处理了的效应不计入调用处
+    assert!(unhandled("function with_ask(f) { handle ask() { 1 }\n f() }\nwith_ask(() => raise ask)").is_empty());
+    let source = "function ask_after(f) { f()\n handle ask() { 1 }\n f() }\nlet raising = () => raise ask\nask_after(raising)";
+    assert_eq!(unhandled(source), ["ask_after(raising)"]);
+}
+
+#[test]
+fn match_checking() {
+    let source = r#"enum Color { Red, Green, Blue }
+function name(c) { match c { Color.Red => 1, Color.Green if c == 1 => 2 } }
+match 1 { Color.Purple => 0, _ => 1 }
+match 2 { Point { x } => x, _ => 0 }"#;
+    let expected = [
+        ("non-exhaustive match, missing `Color.Green`, `Color.Blue`", "match c { Color.Red => 1, Color.Green if c == 1 => 2 }"),
+        ("`Color` has no member `Purple`", "Color.Purple => 0"),
+        ("unknown type `Point`", "Point { x } => x"),
+    ];
+    assert_diagnostics(&TypeChecker::new().check(&parse(source)), source, &expected);
+}
+
+#[test]
+fn variant_checking() {
+    let source = r#"enum Option<T> { Some(T), None }
+let x: Option<int> = Option.None
+match x { Option.Some(0) => 1, Option.None => 0 }
+match x { Option.Some(s) => s.length, Option.Some { value } => 0, _ => 1 }
+match x { Option.Some(a, b) => a, _ => 0 }
+let y = Option.Some(1, 2)"#;
+    let expected = [
+        ("non-exhaustive match, missing `Option.Some`", "match x { Option.Some(0) => 1, Option.None => 0 }"),
+        ("`Integer` has no member `length`", "s.length"),
+        ("`Option.Some` has no field `value`", "Option.Some { value } => 0"),
+        ("`Option.Some` has 1 fields, found 2", "Option.Some(a, b) => a"),
+        ("`Option.Some` has 1 fields, found 2", "Option.Some(1, 2)"),
+    ];
+    assert_diagnostics(&TypeChecker::new().check(&parse(source)), source, &expected);
+}
+
+#[test]
+fn class_checking() {
+    let source = r#"class Point { x: int = 0
+y: int = 0
+function norm() { self.x * self.x + self.y.length } }
+enum Color { Red }
+new Point { x: "far", z: 1 }
+new Color"#;
+    let expected = [
+        ("`Integer` has no member `length`", "self.y.length"),
+        ("expected `Integer`, found `String`", "\"far\""),
+        ("`Point` has no member `z`", "new Point { x: \"far\", z: 1 }"),
+        ("`Color` is not a class", "new Color"),
+    ];
+    assert_diagnostics(&TypeChecker::new().check(&parse(source)), source, &expected);
+}
+
+#[test]
+fn trait_checking() {
+    let source = r#"trait Display { function show(self)
+function banner() { self.show() } }
+class A implements Display {}
+class B implements Display { function show(self, x) { x } }
+enum Color { Red }
+let ok = new A is Display && A implements Color"#;
+    let expected = [
+        ("class `A` is missing method `show` required by trait `Display`", "class A implements Display {}"),
+        ("`B.show` expects 1 arguments, but `Display.show` expects 0", "function show(self, x) { x }"),
+        ("`Color` is not a trait", "Color"),
+    ];
+    assert_diagnostics(&TypeChecker::new().check(&parse(source)), source, &expected);
+}
+
+#[test]
+fn structure_checking() {
+    let source = "structure P { x: bool, y: f64 }\nlet p = new P { y: 1, z: 2 }\nlet s: String = p.y";
+    let expected = [
+        ("unsupported field type `bool`, expected a fixed-width integer or `f64`", "x: bool"),
+        ("`P` has no member `z`", "new P { y: 1, z: 2 }"),
+        ("expected `String`, found `Float`", "p.y"),
+    ];
+    assert_diagnostics(&TypeChecker::new().check(&parse(source)), source, &expected);
+}
+
+#[test]
+fn builtin_method_checking() {
+    let source = "'abc'.reverse()";
+    let expected = [("`String` has no member `reverse`", "'abc'.reverse")];
+    assert_diagnostics(&TypeChecker::new().check(&parse(source)), source, &expected);
+}
@@ -10,7 +10,7 @@ impl<T> Display for Gc<T> {
 impl<T> Gc<T> {
     /// 将GC指针转换为指定类型的GC指针
     pub fn transmute<U>(&self) -> Gc<U> {
-        Gc { index: self.index, phantom: PhantomData::default() }
+        Gc { index: self.index, phantom: PhantomData }
     }
     /// 将GC指针转换为任意类型的GC指针
     pub fn as_any(&self) -> Gc<NyarValue> {
@@ -22,6 +22,13 @@ impl<T> Gc<T> {
             _ => Err(NyarError::custom("Invalid type23".to_string())),
         }
     }
+    /// 只读访问指针指向的对象
+    pub fn as_object_ref<'gc>(&self, heap: &'gc Heap) -> Result<&'gc NyarObject> {
+        match heap.view_ref(*self)? {
+            NyarValue::Object(o) => Ok(o.as_ref()),
+            v => Err(NyarError::custom(format!("expect object, found {}", v.type_name()))),
+        }
+    }
     /// 解引用 GC指针，获取 Value 类型的值
     pub fn unbox<'gc>(&self, heap: &'gc Heap) -> Result<T>
     where
@@ -5,6 +5,7 @@ use nyar_error::{NyarError, Result};
 use std::{
     collections::HashSet,
     fmt::{Debug, Display, Formatter},
+    hash::{Hash, Hasher},
     marker::PhantomData,
 };
 
@@ -31,7 +32,7 @@ pub struct GcValue {
 }
 
 /// GC指针，指向堆中的值
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug)]
 pub struct Gc<T: ?Sized> {
     /// 在堆中的索引
     pub index: usize,
@@ -43,7 +44,21 @@ impl<T> Copy for Gc<T> {}
 
 impl<T> Clone for Gc<T> {
     fn clone(&self) -> Self {
-        Gc { index: 0, phantom: PhantomData }
+        *self
+    }
+}
+
+impl<T> PartialEq for Gc<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for Gc<T> {}
+
+impl<T> Hash for Gc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state)
     }
 }
 
@@ -52,6 +67,7 @@ impl Heap {
     pub fn new() -> Self {
         Self { roots: HashSet::new(), memory: Vec::new(), free_indices: Vec::new() }
     }
+    /// 在堆上分配一个值
     pub fn allocate<T>(&mut self, value: T) -> Gc<NyarValue>
     where
         T: Into<NyarValue>,
@@ -64,13 +80,18 @@ impl Heap {
                 gc
             }
             None => {
-                let gc = Gc { index: self.roots.len(), phantom: PhantomData };
+                let gc = Gc { index: self.memory.len(), phantom: PhantomData };
                 self.memory.push(value);
                 gc
             }
         }
     }
-    
+
+    /// 将值标记为根对象
+    pub fn add_root<T>(&mut self, index: Gc<T>) {
+        self.roots.insert(index.index);
+    }
+    /// 只读访问堆上的值
     pub fn view_ref<T>(&self, index: Gc<T>) -> Result<&NyarValue> {
         match self.memory.get(index.index) {
             Some(s) if s.dead => Err(NyarError::use_after_free(index.index)),
@@ -78,7 +99,8 @@ impl Heap {
             None => Err(NyarError::use_after_free(index.index)),
         }
     }
-    
+
+    /// 可变访问堆上的值
     pub fn view_mut<T>(&mut self, index: Gc<T>) -> Result<&mut NyarValue> {
         match self.memory.get_mut(index.index) {
             Some(s) if s.dead => Err(NyarError::use_after_free(index.index)),
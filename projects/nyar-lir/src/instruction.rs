@@ -1,42 +1,76 @@
-use crate::values::NyarValue;
+use crate::{
+    operators::{BinaryOperator, UnaryOperator},
+    values::NyarValue,
+};
 
 /// VM指令集
-#[derive(Debug, Clone)]
+///
+/// 跳转类指令的 `offset` 相对于跳转指令自身的位置，`offset = 1` 等价于顺序执行。
+#[derive(Debug, Clone, PartialEq)]
 pub enum Instruction {
     /// 将常量压入栈
     PushConstant { value: NyarValue },
     /// 将变量压入栈
     PushVariable { name: String },
-    /// 弹出栈顶值并存储到变量
+    /// 弹出栈顶值并存储到变量，变量不存在时在当前作用域声明
     StoreVariable { name: String },
+    /// 弹出栈顶值并在当前作用域声明变量
+    DeclareVariable { name: String },
+    /// 丢弃栈顶值
+    Pop,
+    /// 复制栈顶值
+    Duplicate,
     /// 获取数组索引
     GetIndex { index: usize },
-    /// 设置数组索引
+    /// 设置数组索引, 栈布局为 `[target, value]`
     SetIndex { index: usize },
+    /// 以栈上的键获取元素, 栈布局为 `[target, key]`
+    GetItem,
+    /// 以栈上的键设置元素, 栈布局为 `[target, key, value]`
+    SetItem,
     /// 获取对象属性
     GetProperty { name: String },
-    /// 设置对象属性
+    /// 设置对象属性, 栈布局为 `[target, value]`
     SetProperty { name: String },
-    /// 调用函数
+    /// 获取数组, 对象或字符串的长度
+    Length,
+    /// 二元运算, 栈布局为 `[lhs, rhs]`
+    Binary { operator: BinaryOperator },
+    /// 一元运算
+    Unary { operator: UnaryOperator },
+    /// 调用函数, 栈布局为 `[callee, arguments...]`
     Call { argument_count: usize },
-    /// 创建函数
-    CreateFunction { name: Option<String>, parameter_count: usize, body_size: usize },
-    /// 创建闭包
+    /// 尾调用, 栈布局与 `Call` 相同, 被调用的函数复用当前调用帧并直接返回到当前函数的调用者
+    TailCall { argument_count: usize },
+    /// 创建函数, 函数体为紧随其后的 `body_size` 条指令
+    CreateFunction { name: Option<String>, parameters: Vec<String>, body_size: usize },
+    /// 创建闭包, 按值捕获栈顶函数引用的自由变量
     CreateClosure { captured_variables: Vec<String> },
     /// 创建数组
     CreateArray { size: usize },
-    /// 创建对象
+    /// 创建对象, 栈布局为 `[key, value] * property_count`
     CreateObject { property_count: usize },
-    /// 创建类
-    CreateClass { name: String, method_count: usize, property_count: usize },
-    /// 创建特征
+    /// 创建类, 栈布局为 `[parent, trait * trait_count, [name, default] * property_count, [name, method] * method_count]`,
+    /// 类须以相同的参数个数提供特征中没有默认实现的方法
+    CreateClass { name: String, trait_count: usize, method_count: usize, property_count: usize },
+    /// 创建类或结构体的实例, 栈布局为 `[class, [name, value] * property_count]`, 未给出的属性取类及其父类的默认值, 结构体未给出的字段为零
+    CreateInstance { property_count: usize },
+    /// 调用方法, 栈布局为 `[receiver, arguments...]`, 在实例的类及其父类中找到的方法以 `self` 绑定到实例
+    CallMethod { name: String, argument_count: usize },
+    /// 调用父类的方法, 栈布局为 `[receiver, class, arguments...]`, 从 `class` 开始查找方法
+    CallSuper { name: String, argument_count: usize },
+    /// 创建特征, 栈布局为 `[name, parameters, default] * method_count`, 没有默认实现的方法的 `default` 为空值
     CreateTrait { name: String, method_count: usize },
-    /// 创建枚举
+    /// 创建枚举, 栈布局为 `[name, value] * variant_count`
     CreateEnum { name: String, variant_count: usize },
+    /// 创建枚举变体的值, 栈布局为 `[value] * fields.len()`
+    CreateVariant { enumeration: String, variant: String, tag: usize, fields: Vec<String> },
     /// 跳转
     Jump { offset: isize },
     /// 条件跳转
     JumpIfFalse { offset: isize },
+    /// 迭代, 栈布局为 `[iterable, cursor]`, 耗尽时弹出两者并跳转, 否则压入下一个元素
+    IteratorNext { offset: isize },
     /// 循环开始
     LoopStart { label: Option<String> },
     /// 循环结束
@@ -49,8 +83,18 @@ pub enum Instruction {
     MatchStart,
     /// 匹配条件
     MatchCase { fall_through: bool },
-    /// 匹配结束
+    /// 没有分支匹配, 报告运行时错误, 栈布局为 `[value]`
     MatchEnd,
+    /// 检查值的结构并压入是否匹配, 栈布局为 `[value]`, 检查实例时为 `[value, type]`
+    MatchShape { shape: Shape },
+    /// 注册异常处理器, 直到对应的 `LeaveTry` 为止抛出的值会展开到当前调用帧, 压入栈后跳转到处理器
+    EnterTry { offset: isize },
+    /// 移除最近注册的异常处理器
+    LeaveTry,
+    /// 弹出栈顶值并抛出
+    Throw,
+    /// 断言栈顶值为真, `comparison` 不为空时栈布局为 `[left, right]`, 比较失败时报告两侧的值
+    Assert { message: Option<String>, expression: Option<String>, comparison: Option<BinaryOperator> },
     /// 返回
     Return,
     /// 创建协程
@@ -67,10 +111,50 @@ pub enum Instruction {
     FireThenIgnore,
     /// 触发效应
     RaiseEffect { name: String, argument_count: usize },
-    /// 处理效应
+    /// 处理效应, 将栈顶函数注册为当前调用帧的处理器
     HandleEffect { name: String },
     /// 恢复效应
     ResumeEffect { value_count: usize },
     /// 终止程序
-    Halt
-}
\ No newline at end of file
+    Halt,
+}
+
+/// 匹配模式对值的结构要求
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shape {
+    /// 长度为 `length` 的数组, `rest` 为真时长度至少为 `length`
+    List { length: usize, rest: bool },
+    /// 含有全部 `keys` 的对象
+    Object { keys: Vec<String> },
+    /// 类或其子类的实例, 实现了特征的类的实例, 或枚举的变体
+    Instance,
+    /// 实现了特征的类或其实例, 栈布局为 `[value, trait]`
+    Implements,
+    /// 枚举的变体 `name`, 栈布局为 `[value, enum]`, 显式指定值的变体比较值是否相等
+    ///
+    /// `fields` 为位置字段的个数, 给出时字段数不同的变体不匹配
+    Variant { name: String, fields: Option<usize> },
+}
+
+impl Instruction {
+    /// 获取跳转类指令的偏移量
+    pub fn jump_offset(&self) -> Option<isize> {
+        match self {
+            Instruction::Jump { offset }
+            | Instruction::JumpIfFalse { offset }
+            | Instruction::IteratorNext { offset }
+            | Instruction::EnterTry { offset } => Some(*offset),
+            _ => None,
+        }
+    }
+    /// 修改跳转类指令的偏移量
+    pub fn set_jump_offset(&mut self, new: isize) {
+        match self {
+            Instruction::Jump { offset }
+            | Instruction::JumpIfFalse { offset }
+            | Instruction::IteratorNext { offset }
+            | Instruction::EnterTry { offset } => *offset = new,
+            _ => {}
+        }
+    }
+}
@@ -4,10 +4,16 @@
 
 mod heap;
 mod instruction;
+mod line_table;
+mod operators;
+mod optimizer;
 pub mod values;
 
 pub use crate::{
     heap::{Gc, Heap},
-    instruction::Instruction,
+    instruction::{Instruction, Shape},
+    line_table::{LineEntry, LineTable},
+    operators::{BinaryOperator, UnaryOperator},
+    optimizer::{OptimizationLevel, PeepholeOptimizer},
     values::{CoroutineState, NyarCoroutine, NyarFunction, NyarHandler, NyarValue},
 };
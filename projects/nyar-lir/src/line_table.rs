@@ -0,0 +1,63 @@
+//! 行号表模块，记录指令与源码位置的对应关系
+
+use nyar_error::ArcStr;
+use std::ops::Range;
+
+/// 行号表, 按指令位置升序记录每段指令对应的源码位置
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineTable {
+    entries: Vec<LineEntry>,
+}
+
+/// 行号表条目, 从 `instruction` 开始直到下一条目之前的指令都来自同一源码区间
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineEntry {
+    /// 起始指令位置
+    pub instruction: usize,
+    /// 源文件
+    pub file: ArcStr,
+    /// 源码区间
+    pub span: Range<usize>,
+}
+
+impl LineTable {
+    /// 创建空的行号表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录从 `instruction` 开始的指令来自的源码位置, 位置须不小于已记录的位置
+    pub fn record(&mut self, instruction: usize, file: ArcStr, span: Range<usize>) {
+        if let Some(last) = self.entries.last_mut() {
+            if last.file == file && last.span == span {
+                return;
+            }
+            if last.instruction == instruction {
+                *last = LineEntry { instruction, file, span };
+                return;
+            }
+        }
+        self.entries.push(LineEntry { instruction, file, span })
+    }
+
+    /// 查找指令对应的源码位置
+    pub fn lookup(&self, instruction: usize) -> Option<&LineEntry> {
+        let index = self.entries.partition_point(|entry| entry.instruction <= instruction);
+        self.entries.get(index.checked_sub(1)?)
+    }
+
+    /// 全部条目
+    pub fn entries(&self) -> &[LineEntry] {
+        &self.entries
+    }
+
+    /// 条目数量
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 是否没有任何条目
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,297 @@
+//! 运算符模块，定义了二元和一元运算符及其在标量值上的求值规则
+
+use crate::values::{FixedInteger, IntegerKind, NyarValue};
+use num::{BigInt, ToPrimitive, Zero};
+use nyar_error::{NyarError, Result};
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+/// 二元运算符
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BinaryOperator {
+    /// `+`
+    Add,
+    /// `-`
+    Subtract,
+    /// `*`
+    Multiply,
+    /// `/`
+    Divide,
+    /// `%`
+    Remainder,
+    /// `==`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `<`
+    Less,
+    /// `<=`
+    LessEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterEqual,
+    /// `&&`
+    And,
+    /// `||`
+    Or,
+}
+
+/// 一元运算符
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum UnaryOperator {
+    /// `+`
+    Positive,
+    /// `-`
+    Negative,
+    /// `!`
+    Not,
+}
+
+impl BinaryOperator {
+    /// 运算符的源码形式
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Remainder => "%",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterEqual => ">=",
+            BinaryOperator::And => "&&",
+            BinaryOperator::Or => "||",
+        }
+    }
+    /// 是否为比较运算符
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::Equal
+                | BinaryOperator::NotEqual
+                | BinaryOperator::Less
+                | BinaryOperator::LessEqual
+                | BinaryOperator::Greater
+                | BinaryOperator::GreaterEqual
+        )
+    }
+    /// 是否为短路运算符
+    pub fn is_short_circuit(&self) -> bool {
+        matches!(self, BinaryOperator::And | BinaryOperator::Or)
+    }
+    /// 在两个标量值上求值
+    ///
+    /// 复合值（数组、对象等）的相等性需要访问堆，由虚拟机负责处理。
+    pub fn evaluate(&self, lhs: &NyarValue, rhs: &NyarValue) -> Result<NyarValue> {
+        use NyarValue::{Boolean, Float, Integer, String};
+        // 浮点数与整数运算时整数转换为浮点数
+        if matches!(lhs, Float(_)) || matches!(rhs, Float(_)) {
+            if let (Some(a), Some(b)) = (as_float(lhs), as_float(rhs)) {
+                return self.evaluate_float(a, b, lhs, rhs);
+            }
+        }
+        if let (NyarValue::Fixed(f), _) | (_, NyarValue::Fixed(f)) = (lhs, rhs) {
+            if let (Some(a), Some(b)) = (as_bigint(lhs), as_bigint(rhs)) {
+                return self.evaluate_fixed(f.kind(), a, b, lhs, rhs);
+            }
+        }
+        let value = match (self, lhs, rhs) {
+            (BinaryOperator::Equal, _, _) => Boolean(lhs == rhs),
+            (BinaryOperator::NotEqual, _, _) => Boolean(lhs != rhs),
+            (BinaryOperator::And, Boolean(a), Boolean(b)) => Boolean(*a && *b),
+            (BinaryOperator::Or, Boolean(a), Boolean(b)) => Boolean(*a || *b),
+            (BinaryOperator::Add, String(a), String(b)) => String(Box::new(format!("{}{}", a, b))),
+            (BinaryOperator::Add, Integer(a), Integer(b)) => Integer(Box::new(a.as_ref() + b.as_ref())),
+            (BinaryOperator::Subtract, Integer(a), Integer(b)) => Integer(Box::new(a.as_ref() - b.as_ref())),
+            (BinaryOperator::Multiply, Integer(a), Integer(b)) => Integer(Box::new(a.as_ref() * b.as_ref())),
+            (BinaryOperator::Divide, Integer(a), Integer(b)) => Integer(Box::new(checked_divide(a, b)?.0)),
+            (BinaryOperator::Remainder, Integer(a), Integer(b)) => Integer(Box::new(checked_divide(a, b)?.1)),
+            (BinaryOperator::Less, Integer(a), Integer(b)) => Boolean(a < b),
+            (BinaryOperator::LessEqual, Integer(a), Integer(b)) => Boolean(a <= b),
+            (BinaryOperator::Greater, Integer(a), Integer(b)) => Boolean(a > b),
+            (BinaryOperator::GreaterEqual, Integer(a), Integer(b)) => Boolean(a >= b),
+            (BinaryOperator::Less, String(a), String(b)) => Boolean(a < b),
+            (BinaryOperator::LessEqual, String(a), String(b)) => Boolean(a <= b),
+            (BinaryOperator::Greater, String(a), String(b)) => Boolean(a > b),
+            (BinaryOperator::GreaterEqual, String(a), String(b)) => Boolean(a >= b),
+            _ => {
+                return Err(NyarError::custom(format!(
+                    "operator `{}` cannot be applied to {} and {}",
+                    self,
+                    lhs.type_name(),
+                    rhs.type_name()
+                )));
+            }
+        };
+        Ok(value)
+    }
+}
+
+impl BinaryOperator {
+    fn evaluate_float(&self, a: f64, b: f64, lhs: &NyarValue, rhs: &NyarValue) -> Result<NyarValue> {
+        use NyarValue::{Boolean, Float};
+        let value = match self {
+            BinaryOperator::Add => Float(a + b),
+            BinaryOperator::Subtract => Float(a - b),
+            BinaryOperator::Multiply => Float(a * b),
+            BinaryOperator::Divide => Float(a / b),
+            BinaryOperator::Remainder => Float(a % b),
+            BinaryOperator::Equal => Boolean(a == b),
+            BinaryOperator::NotEqual => Boolean(a != b),
+            BinaryOperator::Less => Boolean(a < b),
+            BinaryOperator::LessEqual => Boolean(a <= b),
+            BinaryOperator::Greater => Boolean(a > b),
+            BinaryOperator::GreaterEqual => Boolean(a >= b),
+            BinaryOperator::And | BinaryOperator::Or => {
+                return Err(NyarError::custom(format!(
+                    "operator `{}` cannot be applied to {} and {}",
+                    self,
+                    lhs.type_name(),
+                    rhs.type_name()
+                )));
+            }
+        };
+        Ok(value)
+    }
+}
+
+impl BinaryOperator {
+    /// 定宽整数参与的运算, 比较按数值进行, 算术运算时大整数转换为另一侧的定宽类型
+    fn evaluate_fixed(&self, kind: IntegerKind, a: BigInt, b: BigInt, lhs: &NyarValue, rhs: &NyarValue) -> Result<NyarValue> {
+        use NyarValue::Boolean;
+        let value = match self {
+            BinaryOperator::Equal => Boolean(a == b),
+            BinaryOperator::NotEqual => Boolean(a != b),
+            BinaryOperator::Less => Boolean(a < b),
+            BinaryOperator::LessEqual => Boolean(a <= b),
+            BinaryOperator::Greater => Boolean(a > b),
+            BinaryOperator::GreaterEqual => Boolean(a >= b),
+            BinaryOperator::And | BinaryOperator::Or => {
+                return Err(NyarError::custom(format!(
+                    "operator `{}` cannot be applied to {} and {}",
+                    self,
+                    lhs.type_name(),
+                    rhs.type_name()
+                )));
+            }
+            _ => {
+                let fixed = |value: &NyarValue, exact: &BigInt| match value {
+                    NyarValue::Fixed(f) => Ok(*f),
+                    _ => FixedInteger::try_from_bigint(kind, exact),
+                };
+                NyarValue::Fixed(fixed(lhs, &a)?.evaluate(*self, &fixed(rhs, &b)?)?)
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// 数值转换为浮点数
+fn as_float(value: &NyarValue) -> Option<f64> {
+    match value {
+        NyarValue::Float(f) => Some(*f),
+        NyarValue::Integer(i) => i.to_f64(),
+        NyarValue::Fixed(f) => Some(f.value() as f64),
+        _ => None,
+    }
+}
+
+/// 整数转换为大整数
+fn as_bigint(value: &NyarValue) -> Option<BigInt> {
+    match value {
+        NyarValue::Integer(i) => Some(i.as_ref().clone()),
+        NyarValue::Fixed(f) => Some(f.to_bigint()),
+        _ => None,
+    }
+}
+
+/// 整数除法，向零取整，除数为零时报错
+fn checked_divide(lhs: &BigInt, rhs: &BigInt) -> Result<(BigInt, BigInt)> {
+    if rhs.is_zero() {
+        return Err(NyarError::custom("division by zero"));
+    }
+    Ok((lhs / rhs, lhs % rhs))
+}
+
+impl UnaryOperator {
+    /// 运算符的源码形式
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            UnaryOperator::Positive => "+",
+            UnaryOperator::Negative => "-",
+            UnaryOperator::Not => "!",
+        }
+    }
+    /// 在标量值上求值
+    pub fn evaluate(&self, value: &NyarValue) -> Result<NyarValue> {
+        match (self, value) {
+            (UnaryOperator::Positive, NyarValue::Integer(v)) => Ok(NyarValue::Integer(v.clone())),
+            (UnaryOperator::Negative, NyarValue::Integer(v)) => Ok(NyarValue::Integer(Box::new(-v.as_ref()))),
+            (UnaryOperator::Positive, NyarValue::Float(v)) => Ok(NyarValue::Float(*v)),
+            (UnaryOperator::Negative, NyarValue::Float(v)) => Ok(NyarValue::Float(-v)),
+            (UnaryOperator::Positive, NyarValue::Fixed(v)) => Ok(NyarValue::Fixed(*v)),
+            (UnaryOperator::Negative, NyarValue::Fixed(v)) => {
+                let zero = FixedInteger::try_from_bigint(v.kind(), &BigInt::zero())?;
+                Ok(NyarValue::Fixed(zero.evaluate(BinaryOperator::Subtract, v)?))
+            }
+            (UnaryOperator::Not, NyarValue::Boolean(v)) => Ok(NyarValue::Boolean(!v)),
+            _ => Err(NyarError::custom(format!("operator `{}` cannot be applied to {}", self, value.type_name()))),
+        }
+    }
+}
+
+impl Display for BinaryOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+impl Display for UnaryOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+impl FromStr for BinaryOperator {
+    type Err = NyarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let operator = match s {
+            "+" => BinaryOperator::Add,
+            "-" => BinaryOperator::Subtract,
+            "*" => BinaryOperator::Multiply,
+            "/" => BinaryOperator::Divide,
+            "%" => BinaryOperator::Remainder,
+            "==" => BinaryOperator::Equal,
+            "!=" => BinaryOperator::NotEqual,
+            "<" => BinaryOperator::Less,
+            "<=" => BinaryOperator::LessEqual,
+            ">" => BinaryOperator::Greater,
+            ">=" => BinaryOperator::GreaterEqual,
+            "&&" => BinaryOperator::And,
+            "||" => BinaryOperator::Or,
+            _ => return Err(NyarError::custom(format!("unknown binary operator `{}`", s))),
+        };
+        Ok(operator)
+    }
+}
+
+impl FromStr for UnaryOperator {
+    type Err = NyarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let operator = match s {
+            "+" => UnaryOperator::Positive,
+            "-" => UnaryOperator::Negative,
+            "!" => UnaryOperator::Not,
+            _ => return Err(NyarError::custom(format!("unknown unary operator `{}`", s))),
+        };
+        Ok(operator)
+    }
+}
@@ -0,0 +1,285 @@
+//! 窥孔优化模块，在指令序列上消除冗余指令并修正跳转偏移与函数体长度
+
+use crate::{Instruction, LineTable, NyarValue};
+
+/// 优化级别
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OptimizationLevel {
+    /// 不做任何优化
+    #[default]
+    None,
+    /// 删除不可达指令、无用跳转与成对的压栈和弹出, 合并连续的跳转
+    Basic,
+    /// 在 `Basic` 的基础上, 将写入变量后立即读取同一变量合并为复制后写入, 编译器还会内联小函数
+    Full,
+}
+
+/// 窥孔优化器
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PeepholeOptimizer {
+    level: OptimizationLevel,
+}
+
+/// 指令及其在原始序列中的位置
+type Code = Vec<(Instruction, usize)>;
+
+impl PeepholeOptimizer {
+    /// 创建指定级别的优化器
+    pub fn new(level: OptimizationLevel) -> Self {
+        Self { level }
+    }
+
+    /// 优化级别
+    pub fn level(&self) -> OptimizationLevel {
+        self.level
+    }
+
+    /// 优化指令序列
+    pub fn optimize(&self, instructions: Vec<Instruction>) -> Vec<Instruction> {
+        self.optimize_with_line_table(instructions, &LineTable::default()).0
+    }
+
+    /// 优化指令序列, 并将行号表映射到优化后的指令位置
+    pub fn optimize_with_line_table(
+        &self,
+        instructions: Vec<Instruction>,
+        line_table: &LineTable,
+    ) -> (Vec<Instruction>, LineTable) {
+        let mut code: Code = instructions.into_iter().enumerate().map(|(index, instruction)| (instruction, index)).collect();
+        if self.level >= OptimizationLevel::Basic {
+            loop {
+                let mut changed = thread_jumps(&mut code);
+                changed |= remove(&mut code, unreachable);
+                changed |= remove(&mut code, redundant_jumps);
+                changed |= remove(&mut code, push_pop_pairs);
+                if self.level >= OptimizationLevel::Full {
+                    changed |= fuse_store_load(&mut code);
+                }
+                if !changed {
+                    break;
+                }
+            }
+        }
+        let mut table = LineTable::new();
+        for (position, (_, origin)) in code.iter().enumerate() {
+            if let Some(entry) = line_table.lookup(*origin) {
+                table.record(position, entry.file.clone(), entry.span.clone())
+            }
+        }
+        (code.into_iter().map(|(instruction, _)| instruction).collect(), table)
+    }
+}
+
+/// 跳转类指令的目标位置
+fn jump_target(code: &Code, index: usize) -> Option<usize> {
+    let offset = code[index].0.jump_offset()?;
+    usize::try_from(index as isize + offset).ok()
+}
+
+/// 除顺序执行外还可能从别处进入的位置: 跳转目标、函数体的开头与函数体之后、循环边界
+fn labels(code: &Code) -> Vec<bool> {
+    let mut labels = vec![false; code.len() + 1];
+    let mut mark = |index: usize| {
+        if let Some(label) = labels.get_mut(index) {
+            *label = true
+        }
+    };
+    for (index, (instruction, _)) in code.iter().enumerate() {
+        match instruction {
+            Instruction::CreateFunction { body_size, .. } => {
+                mark(index + 1);
+                mark(index + body_size + 1);
+            }
+            Instruction::LoopStart { .. } | Instruction::LoopEnd { .. } => {
+                mark(index);
+                mark(index + 1);
+            }
+            _ => {}
+        }
+        if let Some(target) = jump_target(code, index) {
+            mark(target)
+        }
+    }
+    labels
+}
+
+/// 跳转到无条件跳转时直接跳转到最终目标
+fn thread_jumps(code: &mut Code) -> bool {
+    let mut changed = false;
+    for index in 0..code.len() {
+        let mut target = match jump_target(code, index) {
+            Some(target) => target,
+            None => continue,
+        };
+        // 跳转链可能成环, 最多跟随指令条数次
+        let mut steps = 0;
+        while steps < code.len() && target < code.len() && matches!(code[target].0, Instruction::Jump { .. }) {
+            match jump_target(code, target) {
+                Some(next) if next != target => target = next,
+                _ => break,
+            }
+            steps += 1;
+        }
+        let offset = target as isize - index as isize;
+        if code[index].0.jump_offset() != Some(offset) {
+            code[index].0.set_jump_offset(offset);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// 从入口与函数体开头出发无法到达的指令
+fn unreachable(code: &mut Code, _labels: &[bool]) -> Vec<bool> {
+    let mut reachable = vec![false; code.len()];
+    let mut work = vec![0];
+    // `Break` 与 `Continue` 在运行时查找循环边界, 此时循环边界总是可达的
+    if code.iter().any(|(instruction, _)| matches!(instruction, Instruction::Break { .. } | Instruction::Continue { .. })) {
+        for (index, (instruction, _)) in code.iter().enumerate() {
+            if matches!(instruction, Instruction::LoopStart { .. } | Instruction::LoopEnd { .. }) {
+                work.push(index)
+            }
+        }
+    }
+    while let Some(index) = work.pop() {
+        if index >= code.len() || reachable[index] {
+            continue;
+        }
+        reachable[index] = true;
+        match &code[index].0 {
+            Instruction::Return
+            | Instruction::Halt
+            | Instruction::TailCall { .. }
+            | Instruction::ResumeEffect { .. }
+            | Instruction::MatchEnd
+            | Instruction::Throw
+            | Instruction::Break { .. }
+            | Instruction::Continue { .. } => {}
+            Instruction::Jump { .. } => work.extend(jump_target(code, index)),
+            Instruction::JumpIfFalse { .. } | Instruction::IteratorNext { .. } | Instruction::EnterTry { .. } => {
+                work.push(index + 1);
+                work.extend(jump_target(code, index));
+            }
+            Instruction::CreateFunction { body_size, .. } => {
+                work.push(index + 1);
+                work.push(index + body_size + 1);
+            }
+            _ => work.push(index + 1),
+        }
+    }
+    reachable.into_iter().map(|reachable| !reachable).collect()
+}
+
+/// 跳转到下一条指令的跳转, 条件跳转改为弹出条件
+fn redundant_jumps(code: &mut Code, _labels: &[bool]) -> Vec<bool> {
+    let mut removed = vec![false; code.len()];
+    for (index, (instruction, _)) in code.iter_mut().enumerate() {
+        match instruction {
+            Instruction::Jump { offset: 1 } => removed[index] = true,
+            Instruction::JumpIfFalse { offset: 1 } => *instruction = Instruction::Pop,
+            _ => {}
+        }
+    }
+    removed
+}
+
+/// 压入后立即弹出的值
+fn push_pop_pairs(code: &mut Code, labels: &[bool]) -> Vec<bool> {
+    let mut removed = vec![false; code.len()];
+    let mut index = 0;
+    while index + 1 < code.len() {
+        let push = matches!(code[index].0, Instruction::PushConstant { .. } | Instruction::Duplicate);
+        if push && code[index + 1].0 == Instruction::Pop && !labels[index + 1] {
+            removed[index] = true;
+            removed[index + 1] = true;
+            index += 2;
+        }
+        else {
+            index += 1;
+        }
+    }
+    removed
+}
+
+/// `StoreVariable x; PushVariable x` 改写为 `Duplicate; StoreVariable x`, 声明变量同理
+///
+/// 结构体存入变量时复制, 栈上留下的值不是存入的副本, 因此只改写存入值不可能是结构体的情况。
+fn fuse_store_load(code: &mut Code) -> bool {
+    let labels = labels(code);
+    let mut changed = false;
+    for index in 1..code.len() {
+        let fusable = match (&code[index - 1].0, &code[index].0) {
+            (Instruction::StoreVariable { name: store }, Instruction::PushVariable { name: load })
+            | (Instruction::DeclareVariable { name: store }, Instruction::PushVariable { name: load }) => store == load,
+            _ => false,
+        };
+        if fusable && !labels[index] && !may_be_structure(code, &labels, index - 1) {
+            let store = std::mem::replace(&mut code[index - 1].0, Instruction::Duplicate);
+            code[index].0 = store;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// 位置 `store` 的存储指令存入的值是否可能是结构体
+///
+/// 紧接函数体之后的存储存入的是函数, 其他跳转目标处的值来源未知。
+fn may_be_structure(code: &Code, labels: &[bool], store: usize) -> bool {
+    let function_end = code[..store].iter().enumerate().any(|(index, (instruction, _))| {
+        matches!(instruction, Instruction::CreateFunction { body_size, .. } if index + body_size + 1 == store)
+    });
+    if function_end {
+        return false;
+    }
+    if labels[store] || store == 0 {
+        return true;
+    }
+    match &code[store - 1].0 {
+        Instruction::PushConstant { value } => matches!(value, NyarValue::Struct(_)),
+        Instruction::Binary { .. }
+        | Instruction::Unary { .. }
+        | Instruction::Length
+        | Instruction::CreateFunction { .. }
+        | Instruction::CreateClosure { .. }
+        | Instruction::CreateArray { .. }
+        | Instruction::CreateObject { .. }
+        | Instruction::CreateClass { .. }
+        | Instruction::CreateTrait { .. }
+        | Instruction::CreateEnum { .. } => false,
+        _ => true,
+    }
+}
+
+/// 删除 `pass` 选出的指令, 并修正剩余指令的跳转偏移与函数体长度
+///
+/// 指向被删除指令的跳转改为指向其后第一条保留的指令。
+fn remove(code: &mut Code, pass: fn(&mut Code, &[bool]) -> Vec<bool>) -> bool {
+    let labels = labels(code);
+    let removed = pass(code, &labels);
+    if !removed.contains(&true) {
+        return false;
+    }
+    // 位置 `i` 之前保留的指令数, 即 `i` 之后第一条保留的指令的新位置
+    let mut kept = Vec::with_capacity(code.len() + 1);
+    kept.push(0);
+    for removed in &removed {
+        kept.push(kept.last().copied().unwrap_or(0) + usize::from(!removed));
+    }
+    let length = code.len();
+    let at = |index: usize| kept[index.min(length)];
+    for index in 0..code.len() {
+        if removed[index] {
+            continue;
+        }
+        if let Some(target) = jump_target(code, index) {
+            code[index].0.set_jump_offset(at(target) as isize - at(index) as isize);
+        }
+        if let Instruction::CreateFunction { body_size, .. } = &mut code[index].0 {
+            *body_size = at(index + *body_size + 1) - at(index) - 1;
+        }
+    }
+    let mut flags = removed.into_iter();
+    code.retain(|_| !flags.next().unwrap_or(false));
+    true
+}
@@ -0,0 +1,327 @@
+use super::NyarValue;
+use crate::operators::BinaryOperator;
+use num::{BigInt, FromPrimitive, ToPrimitive};
+use nyar_error::{NyarError, Result};
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+/// 定宽整数的类型
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IntegerKind {
+    /// 8 位有符号整数
+    I8,
+    /// 16 位有符号整数
+    I16,
+    /// 32 位有符号整数
+    I32,
+    /// 64 位有符号整数
+    I64,
+    /// 8 位无符号整数
+    U8,
+    /// 16 位无符号整数
+    U16,
+    /// 32 位无符号整数
+    U32,
+    /// 64 位无符号整数
+    U64,
+}
+
+/// 定宽整数运算溢出时的行为
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum OverflowMode {
+    /// 按二进制补码回绕
+    Wrapping,
+    /// 溢出时没有结果
+    Checked,
+    /// 取最接近的边界值
+    Saturating,
+}
+
+/// 数值转换的目标类型
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NumericType {
+    /// 大整数
+    Integer,
+    /// 64 位浮点数
+    Float,
+    /// 定宽整数
+    Fixed(IntegerKind),
+}
+
+/// 数值上的内建方法
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NumericMethod {
+    /// `to_u8`、`to_int`、`to_f64` 等, 有损的转换报错
+    Convert(NumericType),
+    /// `wrapping_to_u8` 等, 截断高位
+    Truncate(IntegerKind),
+    /// `wrapping_add`、`checked_mul`、`saturating_sub` 等
+    Arithmetic(OverflowMode, BinaryOperator),
+}
+
+/// 定宽整数, 值总是在类型的范围内
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FixedInteger {
+    kind: IntegerKind,
+    value: i128,
+}
+
+impl IntegerKind {
+    /// 类型在源码中的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntegerKind::I8 => "i8",
+            IntegerKind::I16 => "i16",
+            IntegerKind::I32 => "i32",
+            IntegerKind::I64 => "i64",
+            IntegerKind::U8 => "u8",
+            IntegerKind::U16 => "u16",
+            IntegerKind::U32 => "u32",
+            IntegerKind::U64 => "u64",
+        }
+    }
+    /// 位数
+    pub fn bits(&self) -> u32 {
+        match self {
+            IntegerKind::I8 | IntegerKind::U8 => 8,
+            IntegerKind::I16 | IntegerKind::U16 => 16,
+            IntegerKind::I32 | IntegerKind::U32 => 32,
+            IntegerKind::I64 | IntegerKind::U64 => 64,
+        }
+    }
+    /// 是否有符号
+    pub fn is_signed(&self) -> bool {
+        matches!(self, IntegerKind::I8 | IntegerKind::I16 | IntegerKind::I32 | IntegerKind::I64)
+    }
+    /// 最小值
+    pub fn min(&self) -> i128 {
+        if self.is_signed() { -(1 << (self.bits() - 1)) } else { 0 }
+    }
+    /// 最大值
+    pub fn max(&self) -> i128 {
+        if self.is_signed() { (1 << (self.bits() - 1)) - 1 } else { (1 << self.bits()) - 1 }
+    }
+    /// 截断到类型的位数, 按二进制补码解释
+    fn wrap(&self, value: i128) -> i128 {
+        let bits = self.bits();
+        let truncated = (value as u128) & ((1u128 << bits) - 1);
+        match self.is_signed() && truncated >> (bits - 1) == 1 {
+            true => truncated as i128 - (1i128 << bits),
+            false => truncated as i128,
+        }
+    }
+}
+
+impl OverflowMode {
+    /// 解析 `wrapping_add`、`checked_mul`、`saturating_sub` 等方法名
+    pub fn method(name: &str) -> Option<(OverflowMode, BinaryOperator)> {
+        let (mode, operator) = name.split_once('_')?;
+        let mode = match mode {
+            "wrapping" => OverflowMode::Wrapping,
+            "checked" => OverflowMode::Checked,
+            "saturating" => OverflowMode::Saturating,
+            _ => return None,
+        };
+        let operator = match operator {
+            "add" => BinaryOperator::Add,
+            "sub" => BinaryOperator::Subtract,
+            "mul" => BinaryOperator::Multiply,
+            "div" => BinaryOperator::Divide,
+            "rem" => BinaryOperator::Remainder,
+            _ => return None,
+        };
+        Some((mode, operator))
+    }
+}
+
+impl NumericMethod {
+    /// 解析方法名, 不是数值方法时返回 `None`
+    pub fn parse(name: &str) -> Option<Self> {
+        if let Some(kind) = name.strip_prefix("wrapping_to_") {
+            return kind.parse().ok().map(NumericMethod::Truncate);
+        }
+        if let Some(target) = name.strip_prefix("to_") {
+            return target.parse().ok().map(NumericMethod::Convert);
+        }
+        OverflowMode::method(name).map(|(mode, operator)| NumericMethod::Arithmetic(mode, operator))
+    }
+    /// 方法的参数个数
+    pub fn arity(&self) -> usize {
+        match self {
+            NumericMethod::Convert(_) | NumericMethod::Truncate(_) => 0,
+            NumericMethod::Arithmetic(..) => 1,
+        }
+    }
+}
+
+impl FixedInteger {
+    /// 无损地转换大整数, 超出范围时报错
+    pub fn try_from_bigint(kind: IntegerKind, value: &BigInt) -> Result<Self> {
+        match value.to_i128() {
+            Some(v) if (kind.min()..=kind.max()).contains(&v) => Ok(Self { kind, value: v }),
+            _ => Err(NyarError::conversion(value, kind)),
+        }
+    }
+    /// 截断大整数的高位, 按二进制补码解释
+    pub fn wrapping_from_bigint(kind: IntegerKind, value: &BigInt) -> Self {
+        let (sign, digits) = value.to_u64_digits();
+        let low = digits.first().copied().unwrap_or(0) as i128;
+        let low = if sign == num::bigint::Sign::Minus { -low } else { low };
+        Self { kind, value: kind.wrap(low) }
+    }
+    /// 类型
+    pub fn kind(&self) -> IntegerKind {
+        self.kind
+    }
+    /// 值
+    pub fn value(&self) -> i128 {
+        self.value
+    }
+    /// 转换为大整数
+    pub fn to_bigint(&self) -> BigInt {
+        BigInt::from(self.value)
+    }
+    /// 以给定的溢出行为计算, 除数为零时报错, [`OverflowMode::Checked`] 溢出时返回 `None`
+    pub fn arithmetic(&self, operator: BinaryOperator, rhs: &FixedInteger, mode: OverflowMode) -> Result<Option<Self>> {
+        if self.kind != rhs.kind {
+            return Err(NyarError::custom(format!(
+                "operator `{}` cannot be applied to {} and {}",
+                operator,
+                self.kind,
+                rhs.kind
+            )));
+        }
+        let (a, b) = (self.value, rhs.value);
+        // 两个 64 位整数的运算结果总能用 i128 表示, 只有乘法可能超出
+        let exact = match operator {
+            BinaryOperator::Add => Some(a + b),
+            BinaryOperator::Subtract => Some(a - b),
+            BinaryOperator::Multiply => a.checked_mul(b),
+            BinaryOperator::Divide | BinaryOperator::Remainder if b == 0 => {
+                return Err(NyarError::custom("division by zero"));
+            }
+            BinaryOperator::Divide => Some(a / b),
+            BinaryOperator::Remainder => Some(a % b),
+            _ => return Err(NyarError::custom(format!("operator `{}` is not an arithmetic operator", operator))),
+        };
+        let kind = self.kind;
+        let value = match (exact, mode) {
+            (Some(v), _) if (kind.min()..=kind.max()).contains(&v) => v,
+            (_, OverflowMode::Checked) => return Ok(None),
+            (Some(v), OverflowMode::Wrapping) => kind.wrap(v),
+            (None, OverflowMode::Wrapping) => kind.wrap((a as u128).wrapping_mul(b as u128) as i128),
+            (Some(v), OverflowMode::Saturating) => v.clamp(kind.min(), kind.max()),
+            (None, OverflowMode::Saturating) if (a < 0) != (b < 0) => kind.min(),
+            (None, OverflowMode::Saturating) => kind.max(),
+        };
+        Ok(Some(Self { kind, value }))
+    }
+    /// 运算符 `+`、`-`、`*`、`/`、`%` 的语义, 溢出时报错
+    pub fn evaluate(&self, operator: BinaryOperator, rhs: &FixedInteger) -> Result<Self> {
+        match self.arithmetic(operator, rhs, OverflowMode::Checked)? {
+            Some(value) => Ok(value),
+            None => {
+                let operation = format!("{} {} {}", self.value, operator, rhs.value);
+                Err(NyarError::overflow(operation, self.kind))
+            }
+        }
+    }
+}
+
+impl NumericType {
+    /// 将数值转换为该类型, 有损的转换报错
+    pub fn convert(&self, value: &NyarValue) -> Result<NyarValue> {
+        let converted = match (self, value) {
+            (NumericType::Integer, NyarValue::Integer(_)) => value.clone(),
+            (NumericType::Integer, NyarValue::Fixed(f)) => NyarValue::from(f.to_bigint()),
+            (NumericType::Integer, NyarValue::Float(f)) => NyarValue::from(float_to_bigint(*f, self)?),
+            (NumericType::Float, NyarValue::Float(_)) => value.clone(),
+            (NumericType::Float, NyarValue::Integer(i)) => NyarValue::Float(bigint_to_float(i)?),
+            (NumericType::Float, NyarValue::Fixed(f)) => NyarValue::Float(bigint_to_float(&f.to_bigint())?),
+            (NumericType::Fixed(kind), NyarValue::Integer(i)) => NyarValue::Fixed(FixedInteger::try_from_bigint(*kind, i)?),
+            (NumericType::Fixed(kind), NyarValue::Fixed(f)) => {
+                NyarValue::Fixed(FixedInteger::try_from_bigint(*kind, &f.to_bigint())?)
+            }
+            (NumericType::Fixed(kind), NyarValue::Float(f)) => {
+                NyarValue::Fixed(FixedInteger::try_from_bigint(*kind, &float_to_bigint(*f, self)?)?)
+            }
+            _ => return Err(NyarError::custom(format!("cannot convert {} to `{}`", value.type_name(), self))),
+        };
+        Ok(converted)
+    }
+}
+
+/// 只有整数值的浮点数能无损地转换为整数
+fn float_to_bigint(value: f64, target: &NumericType) -> Result<BigInt> {
+    match value.fract() == 0.0 {
+        true => BigInt::from_f64(value).ok_or_else(|| NyarError::conversion(value, target)),
+        false => Err(NyarError::conversion(value, target)),
+    }
+}
+
+/// 超过 2^53 的整数转换为浮点数时会丢失精度
+fn bigint_to_float(value: &BigInt) -> Result<f64> {
+    match value.to_f64() {
+        Some(f) if BigInt::from_f64(f).is_some_and(|back| &back == value) => Ok(f),
+        _ => Err(NyarError::conversion(value, NumericType::Float)),
+    }
+}
+
+impl Display for IntegerKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Display for NumericType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumericType::Integer => f.write_str("int"),
+            NumericType::Float => f.write_str("f64"),
+            NumericType::Fixed(kind) => write!(f, "{}", kind),
+        }
+    }
+}
+
+impl Display for FixedInteger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.value, self.kind)
+    }
+}
+
+impl FromStr for IntegerKind {
+    type Err = NyarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let kind = match s {
+            "i8" => IntegerKind::I8,
+            "i16" => IntegerKind::I16,
+            "i32" => IntegerKind::I32,
+            "i64" => IntegerKind::I64,
+            "u8" => IntegerKind::U8,
+            "u16" => IntegerKind::U16,
+            "u32" => IntegerKind::U32,
+            "u64" => IntegerKind::U64,
+            _ => return Err(NyarError::custom(format!("unknown integer type `{}`", s))),
+        };
+        Ok(kind)
+    }
+}
+
+impl FromStr for NumericType {
+    type Err = NyarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let target = match s {
+            "int" | "Integer" | "bigint" => NumericType::Integer,
+            "f64" | "Float" => NumericType::Float,
+            _ => match s.parse::<IntegerKind>() {
+                Ok(kind) => NumericType::Fixed(kind),
+                Err(_) => return Err(NyarError::custom(format!("`{}` is not a numeric type", s))),
+            },
+        };
+        Ok(target)
+    }
+}
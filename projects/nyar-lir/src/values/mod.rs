@@ -1,18 +1,30 @@
 //! 值类型模块，定义了VM支持的所有值类型
 
-use crate::heap::Gc;
-pub use self::objects::NyarObject;
-pub use self::vectors::NyarVector;
+pub use self::{
+    integers::{FixedInteger, IntegerKind, NumericMethod, NumericType, OverflowMode},
+    objects::NyarObject,
+    strings::{StringMethod, find_chars, slice_chars},
+    structures::{FieldType, FieldValue, NyarStruct, NyarStructure},
+    vectors::NyarVector,
+};
+use crate::{heap::Gc, instruction::Instruction};
 use indexmap::IndexMap;
 use num::BigInt;
-use std::{collections::HashMap, fmt::Debug};
-use std::collections::VecDeque;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    ops::Range,
+    sync::Arc,
+};
 
+mod integers;
 mod objects;
+mod strings;
+mod structures;
 mod vectors;
 
 /// VM支持的所有值类型
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum NyarValue {
     /// 空值
     Null,
@@ -20,6 +32,10 @@ pub enum NyarValue {
     Boolean(bool),
     /// 大整数
     Integer(Box<BigInt>),
+    /// 定宽整数
+    Fixed(FixedInteger),
+    /// 64 位浮点数
+    Float(f64),
     /// 字符串，存储在GC堆上
     String(Box<String>),
     /// 数组，存储在GC堆上
@@ -34,6 +50,12 @@ pub enum NyarValue {
     Trait(Box<NyarTrait>),
     /// 枚举定义
     Enum(Box<NyarEnum>),
+    /// 枚举变体的值
+    Variant(Box<NyarVariant>),
+    /// 结构体定义
+    Structure(Arc<NyarStructure>),
+    /// 结构体的值, 字段内联存储
+    Struct(Box<NyarStruct>),
     /// 协程
     Coroutine(Box<NyarCoroutine>),
     /// Effect handler
@@ -47,6 +69,8 @@ impl NyarValue {
             NyarValue::Null => "null",
             NyarValue::Boolean(_) => "boolean",
             NyarValue::Integer(_) => "bigint",
+            NyarValue::Fixed(f) => f.kind().name(),
+            NyarValue::Float(_) => "f64",
             NyarValue::String(_) => "string",
             NyarValue::Vector(_) => "array",
             NyarValue::Object(_) => "object",
@@ -54,6 +78,9 @@ impl NyarValue {
             NyarValue::Class(_) => "class",
             NyarValue::Trait(_) => "trait",
             NyarValue::Enum(_) => "enum",
+            NyarValue::Variant(_) => "variant",
+            NyarValue::Structure(_) => "structure",
+            NyarValue::Struct(_) => "struct",
             NyarValue::Coroutine(_) => "coroutine",
             NyarValue::Handler(_) => "handler",
         }
@@ -63,23 +90,77 @@ impl NyarValue {
     pub fn is_null(&self) -> bool {
         matches!(self, NyarValue::Null)
     }
+    /// 判断值在条件上下文中是否为真, 仅 `null` 与 `false` 为假
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, NyarValue::Null | NyarValue::Boolean(false))
+    }
+}
+
+impl From<bool> for NyarValue {
+    fn from(value: bool) -> Self {
+        NyarValue::Boolean(value)
+    }
+}
+
+impl From<BigInt> for NyarValue {
+    fn from(value: BigInt) -> Self {
+        NyarValue::Integer(Box::new(value))
+    }
+}
+
+impl From<i64> for NyarValue {
+    fn from(value: i64) -> Self {
+        NyarValue::Integer(Box::new(value.into()))
+    }
+}
+
+impl From<FixedInteger> for NyarValue {
+    fn from(value: FixedInteger) -> Self {
+        NyarValue::Fixed(value)
+    }
+}
+
+impl From<f64> for NyarValue {
+    fn from(value: f64) -> Self {
+        NyarValue::Float(value)
+    }
+}
+
+impl From<String> for NyarValue {
+    fn from(value: String) -> Self {
+        NyarValue::String(Box::new(value))
+    }
+}
+
+impl From<&str> for NyarValue {
+    fn from(value: &str) -> Self {
+        NyarValue::String(Box::new(value.to_string()))
+    }
+}
+
+impl From<NyarFunction> for NyarValue {
+    fn from(value: NyarFunction) -> Self {
+        NyarValue::Function(Box::new(value))
+    }
 }
 
 /// 函数定义，包含函数体和闭包环境
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NyarFunction {
     /// 函数名称, maybe None for lambda(anonymous function)
     pub name: Option<String>,
     /// 参数列表
     pub parameters: Vec<String>,
-    /// 函数体指令
-    pub body: Vec<crate::instruction::Instruction>,
-    /// 闭包环境
-    pub environment: Gc<HashMap<String, Gc<NyarValue>>>,
+    /// 所属程序的指令, 同一程序创建的函数共享
+    pub code: Arc<[Instruction]>,
+    /// 函数体在 `code` 中的区间, 起点也用于查找行号表
+    pub body: Range<usize>,
+    /// 闭包环境, 由外到内的作用域链
+    pub environment: Vec<Gc<NyarObject>>,
 }
 
 /// 类定义
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NyarClass {
     /// 类名称
     pub name: String,
@@ -89,26 +170,55 @@ pub struct NyarClass {
     pub traits: Vec<Gc<NyarTrait>>,
     /// 方法
     pub methods: HashMap<String, Gc<NyarFunction>>,
-    /// 属性
-    pub properties: HashMap<String, Gc<NyarValue>>,
+    /// 属性与默认值, 按声明顺序排列
+    pub properties: IndexMap<String, Gc<NyarValue>>,
 }
 
 /// 特征/接口定义
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NyarTrait {
     /// 特征名称
     pub name: String,
     /// 方法签名
     pub methods: HashMap<String, Vec<String>>,
+    /// 方法的默认实现
+    pub defaults: HashMap<String, Gc<NyarFunction>>,
 }
 
 /// 枚举定义
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NyarEnum {
     /// 枚举名称
     pub name: String,
-    /// 变体
-    pub variants: HashMap<String, Gc<NyarValue>>,
+    /// 按声明顺序排列的变体, 值为无字段的变体、带字段的变体的构造函数或显式指定的值
+    pub variants: Vec<(String, Gc<NyarValue>)>,
+}
+
+impl NyarEnum {
+    /// 按名称查找变体
+    pub fn variant(&self, name: &str) -> Option<Gc<NyarValue>> {
+        self.variants.iter().find(|(variant, _)| variant == name).map(|(_, value)| *value)
+    }
+}
+
+/// 枚举变体的值, 即带标签的联合
+#[derive(Debug, Clone, PartialEq)]
+pub struct NyarVariant {
+    /// 枚举名称
+    pub enumeration: String,
+    /// 变体名称
+    pub name: String,
+    /// 变体在枚举中的序号
+    pub tag: usize,
+    /// 字段, 位置字段以 `0`, `1`, ... 命名
+    pub fields: Vec<(String, Gc<NyarValue>)>,
+}
+
+impl NyarVariant {
+    /// 按名称查找字段
+    pub fn field(&self, name: &str) -> Option<Gc<NyarValue>> {
+        self.fields.iter().find(|(field, _)| field == name).map(|(_, value)| *value)
+    }
 }
 
 /// 协程状态
@@ -127,7 +237,7 @@ pub enum CoroutineState {
 }
 
 /// 协程定义
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NyarCoroutine {
     /// 协程状态
     pub state: CoroutineState,
@@ -140,13 +250,13 @@ pub struct NyarCoroutine {
     /// 当前调用栈
     pub call_stack: Vec<Gc<NyarFunction>>,
     /// 当前环境栈
-    pub environment_stack: Vec<Gc<HashMap<String, Gc<NyarValue>>>>,
+    pub environment_stack: Vec<Gc<NyarObject>>,
     /// 当前效应处理器栈
     pub effect_handlers: Vec<Gc<NyarHandler>>,
 }
 
 /// 效应处理器
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NyarHandler {
     /// 效应名称
     pub name: String,
@@ -1,14 +1,47 @@
-use crate::{Gc, NyarValue, values::NyarVector};
+use crate::{Gc, NyarValue, values::NyarClass};
 use indexmap::IndexMap;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct NyarObject {
-    dict: IndexMap<Gc<String>, Gc<NyarValue>>,
+    dict: IndexMap<String, Gc<NyarValue>>,
+    class: Option<Gc<NyarClass>>,
 }
 
 impl NyarObject {
-    pub fn insert(&mut self, name: Gc<String>, value: Gc<NyarValue>) -> Option<Gc<NyarValue>> {
-        self.dict.insert(name, value)
+    /// 创建类的实例
+    pub fn instance(class: Gc<NyarClass>) -> Self {
+        Self { dict: IndexMap::new(), class: Some(class) }
+    }
+    /// 创建该对象的类, 对象字面量没有类
+    pub fn class(&self) -> Option<Gc<NyarClass>> {
+        self.class
+    }
+    pub fn insert(&mut self, name: impl Into<String>, value: Gc<NyarValue>) -> Option<Gc<NyarValue>> {
+        self.dict.insert(name.into(), value)
+    }
+    /// 获取属性
+    pub fn get(&self, name: &str) -> Option<Gc<NyarValue>> {
+        self.dict.get(name).copied()
+    }
+    /// 获取可变属性槽位
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Gc<NyarValue>> {
+        self.dict.get_mut(name)
+    }
+    /// 是否包含属性
+    pub fn contains(&self, name: &str) -> bool {
+        self.dict.contains_key(name)
+    }
+    /// 属性个数
+    pub fn len(&self) -> usize {
+        self.dict.len()
+    }
+    /// 是否没有任何属性
+    pub fn is_empty(&self) -> bool {
+        self.dict.is_empty()
+    }
+    /// 按插入顺序遍历所有属性
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Gc<NyarValue>)> {
+        self.dict.iter().map(|(k, v)| (k.as_str(), *v))
     }
 }
 
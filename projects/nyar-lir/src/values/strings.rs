@@ -0,0 +1,107 @@
+use std::ops::RangeInclusive;
+
+/// 字符串上的内建方法, 下标与长度按字符计算
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StringMethod {
+    /// `length()`, 字符个数
+    Length,
+    /// `byte_length()`, UTF-8 编码的字节数
+    ByteLength,
+    /// `slice(start, end?)`, 截取 `[start, end)` 的字符, 负数下标从末尾计算, 越界的下标截断到两端
+    Slice,
+    /// `split(separator?)`, 按分隔符切分为列表, 没有分隔符时按空白切分, 分隔符为空时切分为字符
+    Split,
+    /// `join(items)`, 以字符串为分隔符连接列表中的值
+    Join,
+    /// `trim()`, 去除两端的空白
+    Trim,
+    /// `trim_start()`, 去除开头的空白
+    TrimStart,
+    /// `trim_end()`, 去除结尾的空白
+    TrimEnd,
+    /// `find(pattern)`, 首次出现的字符下标, 不存在时为空值
+    Find,
+    /// `replace(from, to)`, 替换所有出现
+    Replace,
+    /// `to_upper()`, 转为大写
+    Upper,
+    /// `to_lower()`, 转为小写
+    Lower,
+    /// `starts_with(prefix)`
+    StartsWith,
+    /// `ends_with(suffix)`
+    EndsWith,
+    /// `contains(pattern)`
+    Contains,
+    /// `repeat(count)`, 重复 `count` 次
+    Repeat,
+    /// `chars()`, 切分为单个字符的列表
+    Chars,
+}
+
+impl StringMethod {
+    /// 解析方法名, 不是字符串方法时返回 `None`
+    pub fn parse(name: &str) -> Option<Self> {
+        let method = match name {
+            "length" => StringMethod::Length,
+            "byte_length" => StringMethod::ByteLength,
+            "slice" => StringMethod::Slice,
+            "split" => StringMethod::Split,
+            "join" => StringMethod::Join,
+            "trim" => StringMethod::Trim,
+            "trim_start" => StringMethod::TrimStart,
+            "trim_end" => StringMethod::TrimEnd,
+            "find" => StringMethod::Find,
+            "replace" => StringMethod::Replace,
+            "to_upper" => StringMethod::Upper,
+            "to_lower" => StringMethod::Lower,
+            "starts_with" => StringMethod::StartsWith,
+            "ends_with" => StringMethod::EndsWith,
+            "contains" => StringMethod::Contains,
+            "repeat" => StringMethod::Repeat,
+            "chars" => StringMethod::Chars,
+            _ => return None,
+        };
+        Some(method)
+    }
+    /// 方法允许的参数个数
+    pub fn arity(&self) -> RangeInclusive<usize> {
+        match self {
+            StringMethod::Length
+            | StringMethod::ByteLength
+            | StringMethod::Trim
+            | StringMethod::TrimStart
+            | StringMethod::TrimEnd
+            | StringMethod::Upper
+            | StringMethod::Lower
+            | StringMethod::Chars => 0..=0,
+            StringMethod::Split => 0..=1,
+            StringMethod::Slice => 1..=2,
+            StringMethod::Join
+            | StringMethod::Find
+            | StringMethod::StartsWith
+            | StringMethod::EndsWith
+            | StringMethod::Contains
+            | StringMethod::Repeat => 1..=1,
+            StringMethod::Replace => 2..=2,
+        }
+    }
+}
+
+/// 按字符下标截取 `[start, end)`, 负数下标从末尾计算, 越界的下标截断到两端
+pub fn slice_chars(text: &str, start: i64, end: Option<i64>) -> String {
+    let length = text.chars().count() as i64;
+    let clamp = |index: i64| match index < 0 {
+        true => (length + index).max(0),
+        false => index.min(length),
+    } as usize;
+    let start = clamp(start);
+    let end = end.map_or(length as usize, clamp);
+    text.chars().skip(start).take(end.saturating_sub(start)).collect()
+}
+
+/// `pattern` 首次出现的字符下标
+pub fn find_chars(text: &str, pattern: &str) -> Option<usize> {
+    let byte = text.find(pattern)?;
+    Some(text[..byte].chars().count())
+}
@@ -0,0 +1,183 @@
+use super::{FixedInteger, IntegerKind, NumericType, NyarValue};
+use num::BigInt;
+use nyar_error::{NyarError, Result};
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+    sync::Arc,
+};
+
+/// 结构体字段的类型
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    /// 定宽整数
+    Integer(IntegerKind),
+    /// 64 位浮点数
+    F64,
+}
+
+/// 结构体字段的值, 按字段的类型内联存储
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    /// 定宽整数
+    Integer(FixedInteger),
+    /// 64 位浮点数
+    F64(f64),
+}
+
+/// 结构体定义, 字段按声明顺序排列
+#[derive(Debug, Clone, PartialEq)]
+pub struct NyarStructure {
+    /// 结构体名称
+    pub name: String,
+    /// 字段名与类型
+    pub fields: Vec<(String, FieldType)>,
+}
+
+/// 结构体的值, 赋值与传参时复制
+#[derive(Debug, Clone, PartialEq)]
+pub struct NyarStruct {
+    /// 结构体定义
+    pub structure: Arc<NyarStructure>,
+    /// 字段的值, 与定义中的字段一一对应
+    pub values: Vec<FieldValue>,
+}
+
+impl FieldType {
+    /// 类型在源码中的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            FieldType::Integer(kind) => kind.name(),
+            FieldType::F64 => "f64",
+        }
+    }
+    /// 占用的字节数, 同时也是对齐要求
+    pub fn size(&self) -> usize {
+        match self {
+            FieldType::Integer(kind) => kind.bits() as usize / 8,
+            FieldType::F64 => 8,
+        }
+    }
+    /// 类型的零值
+    pub fn zero(&self) -> FieldValue {
+        match self {
+            FieldType::Integer(kind) => FieldValue::Integer(FixedInteger::wrapping_from_bigint(*kind, &BigInt::ZERO)),
+            FieldType::F64 => FieldValue::F64(0.0),
+        }
+    }
+    /// 将值转换为该类型, 有损的转换报错
+    pub fn encode(&self, value: &NyarValue) -> Result<FieldValue> {
+        let encoded = match (self, value) {
+            (FieldType::Integer(kind), NyarValue::Integer(i)) => FieldValue::Integer(FixedInteger::try_from_bigint(*kind, i)?),
+            (FieldType::Integer(kind), NyarValue::Fixed(f)) => {
+                FieldValue::Integer(FixedInteger::try_from_bigint(*kind, &f.to_bigint())?)
+            }
+            (FieldType::F64, NyarValue::Integer(_) | NyarValue::Fixed(_) | NyarValue::Float(_)) => {
+                match NumericType::Float.convert(value)? {
+                    NyarValue::Float(f) => FieldValue::F64(f),
+                    _ => unreachable!("conversion to `f64` always yields a float"),
+                }
+            }
+            _ => return Err(NyarError::custom(format!("expect `{}`, found {}", self, value.type_name()))),
+        };
+        Ok(encoded)
+    }
+}
+
+impl FieldValue {
+    /// 转换为虚拟机的值
+    pub fn decode(&self) -> NyarValue {
+        match *self {
+            FieldValue::Integer(v) => NyarValue::Fixed(v),
+            FieldValue::F64(v) => NyarValue::Float(v),
+        }
+    }
+    /// 小端序的字节
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        match *self {
+            // 补码的低位字节即为该宽度的编码
+            FieldValue::Integer(v) => v.value().to_le_bytes()[..v.kind().bits() as usize / 8].to_vec(),
+            FieldValue::F64(v) => v.to_le_bytes().to_vec(),
+        }
+    }
+}
+
+impl NyarStructure {
+    /// 按名称查找字段的序号
+    pub fn field(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|(field, _)| field == name)
+    }
+    /// 每个字段的字节偏移, 字段按自身大小对齐
+    pub fn offsets(&self) -> Vec<usize> {
+        let mut offset = 0usize;
+        self.fields
+            .iter()
+            .map(|(_, t)| {
+                offset = offset.next_multiple_of(t.size());
+                let start = offset;
+                offset += t.size();
+                start
+            })
+            .collect()
+    }
+    /// 结构体占用的字节数, 按最大的字段对齐
+    pub fn size(&self) -> usize {
+        let align = self.fields.iter().map(|(_, t)| t.size()).max().unwrap_or(1);
+        let end = match (self.offsets().last(), self.fields.last()) {
+            (Some(offset), Some((_, t))) => offset + t.size(),
+            _ => 0,
+        };
+        end.next_multiple_of(align)
+    }
+}
+
+impl NyarStruct {
+    /// 所有字段均为零值的结构体
+    pub fn zeroed(structure: Arc<NyarStructure>) -> Self {
+        let values = structure.fields.iter().map(|(_, t)| t.zero()).collect();
+        Self { structure, values }
+    }
+    /// 读取字段
+    pub fn get(&self, name: &str) -> Result<NyarValue> {
+        match self.structure.field(name) {
+            Some(index) => Ok(self.values[index].decode()),
+            None => Err(self.missing(name)),
+        }
+    }
+    /// 写入字段, 值转换为字段声明的类型
+    pub fn set(&mut self, name: &str, value: &NyarValue) -> Result<()> {
+        let index = self.structure.field(name).ok_or_else(|| self.missing(name))?;
+        let (_, field_type) = &self.structure.fields[index];
+        self.values[index] = field_type.encode(value)?;
+        Ok(())
+    }
+    /// 按定义的布局编码为小端序的字节, 对齐的空隙填零
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; self.structure.size()];
+        for (value, offset) in self.values.iter().zip(self.structure.offsets()) {
+            let encoded = value.to_le_bytes();
+            bytes[offset..offset + encoded.len()].copy_from_slice(&encoded);
+        }
+        bytes
+    }
+    fn missing(&self, name: &str) -> NyarError {
+        NyarError::custom(format!("structure `{}` has no field `{}`", self.structure.name, name))
+    }
+}
+
+impl Display for FieldType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for FieldType {
+    type Err = NyarError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "f64" => Ok(FieldType::F64),
+            _ => s.parse().map(FieldType::Integer),
+        }
+    }
+}
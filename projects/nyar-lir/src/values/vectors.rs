@@ -1,6 +1,46 @@
 use super::*;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct NyarVector {
     list: VecDeque<Gc<NyarValue>>,
 }
+
+impl NyarVector {
+    /// 元素个数
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+    /// 获取指定位置的元素
+    pub fn get(&self, index: usize) -> Option<Gc<NyarValue>> {
+        self.list.get(index).copied()
+    }
+    /// 设置指定位置的元素, 越界时返回 `None`
+    pub fn set(&mut self, index: usize, value: Gc<NyarValue>) -> Option<Gc<NyarValue>> {
+        let slot = self.list.get_mut(index)?;
+        Some(std::mem::replace(slot, value))
+    }
+    /// 在末尾追加元素
+    pub fn push(&mut self, value: Gc<NyarValue>) {
+        self.list.push_back(value)
+    }
+    /// 遍历所有元素
+    pub fn iter(&self) -> impl Iterator<Item = Gc<NyarValue>> + '_ {
+        self.list.iter().copied()
+    }
+}
+
+impl From<Vec<Gc<NyarValue>>> for NyarVector {
+    fn from(value: Vec<Gc<NyarValue>>) -> Self {
+        Self { list: value.into() }
+    }
+}
+
+impl From<NyarVector> for NyarValue {
+    fn from(value: NyarVector) -> Self {
+        NyarValue::Vector(Box::new(value))
+    }
+}
@@ -1,4 +1,62 @@
+use nyar_error::ArcStr;
+use nyar_lir::{Instruction, LineTable, NyarValue, OptimizationLevel, PeepholeOptimizer};
+
 #[test]
 fn main() {
     println!("it works!")
 }
+
+#[test]
+fn peephole_optimizer() {
+    let push = |value: i64| Instruction::PushConstant { value: NyarValue::from(value) };
+    let x = || "x".to_string();
+    let bytecode = vec![
+        push(1),
+        Instruction::Pop,
+        Instruction::Jump { offset: 2 },
+        push(9),
+        Instruction::Jump { offset: 2 },
+        Instruction::Halt,
+        Instruction::CreateFunction { name: None, parameters: vec![], body_size: 4 },
+        push(2),
+        Instruction::Return,
+        push(3),
+        Instruction::Return,
+        Instruction::StoreVariable { name: x() },
+        Instruction::PushVariable { name: x() },
+        Instruction::JumpIfFalse { offset: 3 },
+        push(4),
+        Instruction::Return,
+        push(5),
+        Instruction::Return,
+        push(6),
+    ];
+    let mut line_table = LineTable::new();
+    for (instruction, span) in [(0, 0..1), (6, 1..2), (11, 2..3), (14, 3..4), (16, 4..5)] {
+        line_table.record(instruction, ArcStr::from("main.vk"), span);
+    }
+
+    assert_eq!(PeepholeOptimizer::new(OptimizationLevel::None).optimize(bytecode.clone()), bytecode);
+    let (basic, table) =
+        PeepholeOptimizer::new(OptimizationLevel::Basic).optimize_with_line_table(bytecode.clone(), &line_table);
+    assert_eq!(
+        basic,
+        vec![
+            Instruction::CreateFunction { name: None, parameters: vec![], body_size: 2 },
+            push(2),
+            Instruction::Return,
+            Instruction::StoreVariable { name: x() },
+            Instruction::PushVariable { name: x() },
+            Instruction::JumpIfFalse { offset: 3 },
+            push(4),
+            Instruction::Return,
+            push(5),
+            Instruction::Return,
+        ]
+    );
+    let spans: Vec<_> = table.entries().iter().map(|entry| (entry.instruction, entry.span.clone())).collect();
+    assert_eq!(spans, vec![(0, 1..2), (3, 2..3), (6, 3..4), (8, 4..5)]);
+
+    let full = PeepholeOptimizer::new(OptimizationLevel::Full).optimize(bytecode);
+    assert_eq!(full[3..5], [Instruction::Duplicate, Instruction::StoreVariable { name: x() }]);
+}
@@ -0,0 +1,165 @@
+use crate::{LockedPackage, Lockfile, PackageManifest, lockfile::LOCK_FILE};
+use nyar_error::{NyarError, Result};
+use std::path::{Component, Path, PathBuf};
+
+/// 已加载的包
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    /// 包清单
+    pub manifest: PackageManifest,
+    /// 规范化后的包根目录
+    pub root: PathBuf,
+}
+
+/// 包依赖图, 从根包出发沿路径依赖加载的所有包
+///
+/// 包按依赖顺序排列, 被依赖的包在前, 根包在最后。
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageGraph {
+    packages: Vec<Package>,
+}
+
+impl Package {
+    /// 包名
+    pub fn name(&self) -> &str {
+        &self.manifest.name
+    }
+    /// 入口文件
+    pub fn entry(&self) -> PathBuf {
+        self.root.join(&self.manifest.entry)
+    }
+    /// 模块的根目录, 即入口文件所在的目录
+    pub fn source_root(&self) -> PathBuf {
+        match self.entry().parent() {
+            Some(directory) => directory.to_path_buf(),
+            None => self.root.clone(),
+        }
+    }
+}
+
+impl PackageGraph {
+    /// 从根包目录加载依赖图, 依赖成环或同名的包位于不同目录时报错
+    pub fn load(root: impl AsRef<Path>) -> Result<Self> {
+        let mut graph = Self { packages: vec![] };
+        graph.visit(canonicalize(root.as_ref())?, &mut vec![])?;
+        Ok(graph)
+    }
+    /// 加载目录下的包及其依赖, 返回包名
+    fn visit(&mut self, root: PathBuf, loading: &mut Vec<String>) -> Result<String> {
+        let manifest = PackageManifest::load(&root)?;
+        if let Some(existing) = self.package(&manifest.name) {
+            return match existing.root == root {
+                true => Ok(manifest.name),
+                false => Err(NyarError::custom(format!(
+                    "package `{}` is found at both `{}` and `{}`",
+                    manifest.name,
+                    existing.root.display(),
+                    root.display()
+                ))),
+            };
+        }
+        if let Some(start) = loading.iter().position(|name| name == &manifest.name) {
+            let mut cycle = loading[start..].to_vec();
+            cycle.push(manifest.name);
+            return Err(NyarError::custom(format!("dependency cycle: {}", cycle.join(" -> "))));
+        }
+        loading.push(manifest.name.clone());
+        for (name, dependency) in &manifest.dependencies {
+            let path = canonicalize(&root.join(&dependency.path))?;
+            let found = self.visit(path, loading)?;
+            let Some(package) = self.package(name).filter(|_| &found == name)
+            else {
+                return Err(NyarError::custom(format!(
+                    "dependency `{}` of `{}` points to package `{}` at `{}`",
+                    name, manifest.name, found, dependency.path
+                )));
+            };
+            if let Some(requirement) = &dependency.version {
+                if !requirement.matches(&package.manifest.version) {
+                    return Err(NyarError::custom(format!(
+                        "`{}` requires `{}` {}, but found {}",
+                        manifest.name, name, requirement, package.manifest.version
+                    )));
+                }
+            }
+        }
+        loading.pop();
+        let name = manifest.name.clone();
+        self.packages.push(Package { manifest, root });
+        Ok(name)
+    }
+
+    /// 根包
+    pub fn root(&self) -> &Package {
+        self.packages.last().expect("the root package is always loaded")
+    }
+    /// 按依赖顺序排列的所有包
+    pub fn packages(&self) -> &[Package] {
+        &self.packages
+    }
+    /// 按名称查找包
+    pub fn package(&self, name: &str) -> Option<&Package> {
+        self.packages.iter().find(|package| package.name() == name)
+    }
+    /// 包含该文件的包, 包目录嵌套时取最内层的包
+    pub fn package_of(&self, file: impl AsRef<Path>) -> Option<&Package> {
+        let file = file.as_ref();
+        let packages = self.packages.iter().filter(|package| file.starts_with(&package.root));
+        packages.max_by_key(|package| package.root.components().count())
+    }
+
+    /// 描述依赖图的锁文件, 路径相对于根包目录
+    pub fn lockfile(&self) -> Lockfile {
+        let root = &self.root().root;
+        let packages = self.packages.iter().map(|package| LockedPackage {
+            name: package.name().to_string(),
+            version: package.manifest.version.clone(),
+            path: relative_path(root, &package.root),
+            dependencies: package.manifest.dependencies.keys().cloned().collect(),
+        });
+        let mut packages: Vec<_> = packages.collect();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        Lockfile { packages }
+    }
+    /// 写入根包目录下的 `nyar.lock`, 返回内容是否有变化
+    pub fn write_lockfile(&self) -> Result<bool> {
+        let file = self.root().root.join(LOCK_FILE);
+        let text = self.lockfile().to_string();
+        if std::fs::read_to_string(&file).is_ok_and(|old| old == text) {
+            return Ok(false);
+        }
+        match std::fs::write(&file, text) {
+            Ok(()) => Ok(true),
+            Err(e) => Err(NyarError::custom(format!("cannot write `{}`: {}", file.display(), e))),
+        }
+    }
+    /// 检查根包目录下的 `nyar.lock` 与依赖图一致
+    pub fn verify_lockfile(&self) -> Result<()> {
+        let locked = Lockfile::load(&self.root().root)?;
+        let expected = self.lockfile();
+        let added = expected.packages.iter().find(|package| locked.package(&package.name) != Some(package));
+        let removed = locked.packages.iter().find(|package| expected.package(&package.name).is_none());
+        match added.or(removed) {
+            Some(package) => Err(NyarError::custom(format!("`{}` is out of date for package `{}`", LOCK_FILE, package.name))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf> {
+    path.canonicalize().map_err(|e| NyarError::custom(format!("cannot find package at `{}`: {}", path.display(), e)))
+}
+
+/// `to` 相对于 `from` 的路径, 以 `/` 分隔
+fn relative_path(from: &Path, to: &Path) -> String {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let parents = std::iter::repeat_n("..".to_string(), from.len() - common);
+    let children = to[common..].iter().map(|component| component.as_os_str().to_string_lossy().into_owned());
+    let segments: Vec<String> = parents.chain(children).collect();
+    match segments.is_empty() {
+        true => ".".to_string(),
+        false => segments.join("/"),
+    }
+}
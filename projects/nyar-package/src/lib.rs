@@ -0,0 +1,17 @@
+//! Nyar 包管理
+//!
+//! 读取包根目录下的 `nyar.toml` 清单, 沿本地路径依赖构建包依赖图并生成 `nyar.lock` 锁文件。
+//! [`PackageResolver`] 按依赖图解析跨包的导入, 供编译器加载模块。
+
+mod graph;
+mod lockfile;
+mod manifest;
+mod resolver;
+
+pub use crate::{
+    graph::{Package, PackageGraph},
+    lockfile::{LOCK_FILE, LockedPackage, Lockfile},
+    manifest::{Dependency, MANIFEST_FILE, PackageManifest},
+    resolver::PackageResolver,
+};
+pub use semver::{Version, VersionReq};
@@ -0,0 +1,99 @@
+use crate::Version;
+use nyar_error::{ArcStr, NyarError, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Formatter},
+    path::Path,
+};
+
+/// 锁文件的文件名
+pub const LOCK_FILE: &str = "nyar.lock";
+
+/// 锁文件, 记录依赖图中每个包解析到的版本与位置
+///
+/// ```toml
+/// version = 1
+///
+/// [[package]]
+/// name = "math"
+/// version = "0.2.0"
+/// path = "../math"
+/// dependencies = []
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lockfile {
+    /// 按名称排列的包
+    pub packages: Vec<LockedPackage>,
+}
+
+/// 锁定的包
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockedPackage {
+    /// 包名
+    pub name: String,
+    /// 版本号
+    pub version: Version,
+    /// 包根目录, 相对于根包目录, 以 `/` 分隔
+    pub path: String,
+    /// 依赖的包名
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+/// `nyar.lock` 的结构
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LockfileFormat {
+    version: i64,
+    #[serde(default, rename = "package")]
+    packages: Vec<LockedPackage>,
+}
+
+impl Lockfile {
+    /// 锁文件格式的版本
+    pub const FORMAT: i64 = 1;
+
+    /// 解析锁文件文本, `file` 用于标注错误位置
+    pub fn parse(text: &str, file: impl Into<ArcStr>) -> Result<Self> {
+        let file = file.into();
+        let LockfileFormat { version, packages } = match toml::from_str(text) {
+            Ok(lockfile) => lockfile,
+            Err(e) => {
+                let error = NyarError::decode("lockfile", e.message()).with_file(file);
+                return Err(match e.span() {
+                    Some(span) => error.with_span(span),
+                    None => error,
+                });
+            }
+        };
+        if version != Self::FORMAT {
+            let message = format!("unsupported lockfile version {}", version);
+            return Err(NyarError::decode("lockfile", message).with_file(file));
+        }
+        Ok(Self { packages })
+    }
+
+    /// 读取目录下的 `nyar.lock`
+    pub fn load(directory: impl AsRef<Path>) -> Result<Self> {
+        let file = directory.as_ref().join(LOCK_FILE);
+        let path = file.to_string_lossy();
+        match std::fs::read_to_string(&file) {
+            Ok(text) => Self::parse(&text, path.as_ref()),
+            Err(e) => Err(NyarError::custom(format!("cannot read lockfile `{}`: {}", path, e))),
+        }
+    }
+
+    /// 按名称查找锁定的包
+    pub fn package(&self, name: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|package| package.name == name)
+    }
+}
+
+impl Display for Lockfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let format = LockfileFormat { version: Self::FORMAT, packages: self.packages.clone() };
+        writeln!(f, "# 由依赖图生成, 请勿手动修改")?;
+        f.write_str(&toml::to_string(&format).map_err(|_| std::fmt::Error)?)
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{Version, VersionReq};
+use nyar_error::{ArcStr, NyarError, Result};
+use serde::Deserialize;
+use std::{collections::BTreeMap, path::Path};
+
+/// 包清单的文件名
+pub const MANIFEST_FILE: &str = "nyar.toml";
+
+/// 包清单, 位于包根目录下的 `nyar.toml`
+///
+/// ```toml
+/// [package]
+/// name = "app"
+/// version = "0.1.0"
+/// entry = "src/main.vk"
+///
+/// [dependencies]
+/// math = { path = "../math", version = "0.2.0" }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageManifest {
+    /// 包名, 也是其他包导入时的第一段路径
+    pub name: String,
+    /// 版本号
+    pub version: Version,
+    /// 入口文件, 相对于包根目录, 默认为 `src/main.vk`, 入口文件所在的目录为模块的根目录
+    pub entry: String,
+    /// 按名称排列的依赖
+    pub dependencies: BTreeMap<String, Dependency>,
+}
+
+/// 本地路径依赖
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields, expecting = "a table like `{ path = \"...\" }`")]
+pub struct Dependency {
+    /// 依赖的包根目录, 相对于声明依赖的包根目录
+    pub path: String,
+    /// 版本要求, 与 Cargo 相同, `0.2.0` 即 `^0.2.0`
+    #[serde(default)]
+    pub version: Option<VersionReq>,
+}
+
+/// `nyar.toml` 的结构
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestFile {
+    package: PackageSection,
+    #[serde(default)]
+    dependencies: BTreeMap<String, Dependency>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PackageSection {
+    name: String,
+    version: Version,
+    entry: Option<String>,
+}
+
+impl PackageManifest {
+    /// 解析清单文本, `file` 用于标注错误位置
+    pub fn parse(text: &str, file: impl Into<ArcStr>) -> Result<Self> {
+        let file = file.into();
+        let ManifestFile { package, dependencies } = match toml::from_str(text) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                let error = NyarError::decode("manifest", e.message()).with_file(file);
+                return Err(match e.span() {
+                    Some(span) => error.with_span(span),
+                    None => error,
+                });
+            }
+        };
+        if !is_identifier(&package.name) {
+            let message = format!("package name `{}` must be an identifier", package.name);
+            return Err(NyarError::decode("manifest", message).with_file(file));
+        }
+        let entry = package.entry.unwrap_or_else(|| "src/main.vk".to_string());
+        Ok(Self { name: package.name, version: package.version, entry, dependencies })
+    }
+
+    /// 读取目录下的 `nyar.toml`
+    pub fn load(directory: impl AsRef<Path>) -> Result<Self> {
+        let file = directory.as_ref().join(MANIFEST_FILE);
+        let path = file.to_string_lossy();
+        match std::fs::read_to_string(&file) {
+            Ok(text) => Self::parse(&text, path.as_ref()),
+            Err(e) => Err(NyarError::custom(format!("cannot read manifest `{}`: {}", path, e))),
+        }
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
@@ -0,0 +1,83 @@
+use crate::{Package, PackageGraph};
+use nyar_error::{NyarError, Result};
+use nyar_hir::{ModuleResolver, ast::Program};
+use std::path::{Path, PathBuf};
+use valkyrie_parser::FileResolver;
+
+/// 按包依赖图解析模块
+///
+/// 导入路径的第一段为所在包的依赖名时从该依赖中查找: `math` 为依赖的入口文件, `math.a.b` 为依赖模块根目录下的 `a/b.vk`;
+/// 否则从所在包的模块根目录查找。字符串路径相对于导入者所在的目录。
+/// 包只能导入自身声明的依赖, 依赖的依赖不可见。
+#[derive(Debug, Clone)]
+pub struct PackageResolver {
+    graph: PackageGraph,
+    files: FileResolver,
+}
+
+impl PackageResolver {
+    /// 以依赖图创建解析器
+    pub fn new(graph: PackageGraph) -> Self {
+        let files = FileResolver::new(graph.root().source_root());
+        Self { graph, files }
+    }
+
+    /// 依赖图
+    pub fn graph(&self) -> &PackageGraph {
+        &self.graph
+    }
+
+    /// 读取并解析根包的入口文件
+    pub fn load_entry(&self) -> Result<Program> {
+        let entry = canonicalize(&self.graph.root().entry(), self.graph.root().name())?;
+        self.load(&entry)
+    }
+
+    /// 导入者所在的包, 不在任何包内时为根包
+    fn importer(&self, importer: &str) -> &Package {
+        match importer.is_empty() {
+            true => self.graph.root(),
+            false => self.graph.package_of(importer).unwrap_or_else(|| self.graph.root()),
+        }
+    }
+}
+
+impl ModuleResolver for PackageResolver {
+    fn resolve(&self, path: &str, importer: &str) -> Result<String> {
+        if path.contains(['/', '\\']) || path.ends_with(".vk") {
+            return self.files.resolve(path, importer);
+        }
+        let package = self.importer(importer);
+        let (head, rest) = match path.split_once('.') {
+            Some((head, rest)) => (head, Some(rest)),
+            None => (path, None),
+        };
+        let file = match package.manifest.dependencies.contains_key(head) {
+            true => {
+                let dependency = self.graph.package(head).expect("dependencies are loaded with the graph");
+                match rest {
+                    Some(rest) => module_file(&dependency.source_root(), rest),
+                    None => dependency.entry(),
+                }
+            }
+            false => module_file(&package.source_root(), path),
+        };
+        canonicalize(&file, path)
+    }
+
+    fn load(&self, module: &str) -> Result<Program> {
+        self.files.load(module)
+    }
+}
+
+/// 模块根目录下点分路径对应的文件
+fn module_file(root: &Path, path: &str) -> PathBuf {
+    root.join(path.replace('.', "/")).with_extension("vk")
+}
+
+fn canonicalize(file: &Path, path: &str) -> Result<String> {
+    match file.canonicalize() {
+        Ok(file) => Ok(file.to_string_lossy().into_owned()),
+        Err(e) => Err(NyarError::custom(format!("cannot find module `{}` at `{}`: {}", path, file.display(), e))),
+    }
+}
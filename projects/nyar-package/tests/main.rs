@@ -0,0 +1,152 @@
+use nyar_hir::{NyarCompiler, ast::Program};
+use nyar_lir::{Instruction, NyarValue};
+use nyar_package::{Lockfile, PackageGraph, PackageManifest, PackageResolver, Version, VersionReq};
+use nyar_vm::VirtualMachine;
+use std::path::{Path, PathBuf};
+
+/// 在临时目录下写入文件, 返回该目录
+fn workspace(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("nyar-package-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for (path, text) in files {
+        let file = root.join(path);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, text).unwrap();
+    }
+    root
+}
+
+fn manifest(name: &str, version: &str, dependencies: &[(&str, &str)]) -> String {
+    let mut text = format!("[package]\nname = \"{}\"\nversion = \"{}\"\nentry = \"src/lib.vk\"\n\n[dependencies]\n", name, version);
+    for (dependency, path) in dependencies {
+        text.push_str(&format!("{} = {{ path = \"{}\" }}\n", dependency, path));
+    }
+    text
+}
+
+/// 以 `file` 为源文件解析源码
+fn parse_at(file: &Path, source: &str) -> Program {
+    let file = file.to_string_lossy();
+    valkyrie_parser::ValkyrieParser::new().with_file(file.as_ref()).parse(source).into_result().unwrap()
+}
+
+#[test]
+fn parse_manifest() {
+    let text = r#"
+# 应用
+[package]
+name = "app"
+version = "1.2.3"
+
+[dependencies]
+math = { path = "../math", version = "0.2.0" }
+
+[dependencies.text]
+path = '../text'
+"#;
+    let manifest = PackageManifest::parse(text, "nyar.toml").unwrap();
+    assert_eq!((manifest.name.as_str(), manifest.version, manifest.entry.as_str()), ("app", Version::new(1, 2, 3), "src/main.vk"));
+    assert_eq!(manifest.dependencies["math"].version, Some("0.2.0".parse().unwrap()));
+    assert_eq!(manifest.dependencies["text"].path, "../text");
+
+    for (text, message) in [
+        ("[package]\nversion = \"1.0.0\"", "missing field `name`"),
+        ("[package]\nname = \"app\"\nversion = \"1.0\"", "unexpected end of input while parsing minor version number"),
+        ("[package]\nname = \"app\"\nversion = \"1.0.0\"\nauthor = \"me\"", "unknown field `author`"),
+        ("[package]\nname = \"app\"\nversion = \"1.0.0\"\n[dependencies]\nmath = \"0.1\"", "expected a table like `{ path"),
+        ("[package]\nname = \"app\" version = \"1.0.0\"", "expected newline"),
+    ] {
+        let error = PackageManifest::parse(text, "nyar.toml").unwrap_err();
+        assert!(error.kind().to_string().contains(message), "{}", error.kind());
+        assert_eq!(error.file().as_str(), "nyar.toml");
+    }
+    let error = PackageManifest::parse("[package]\nname = \"app\" version = \"1.0.0\"", "nyar.toml").unwrap_err();
+    assert_eq!(error.span(), 23..23);
+
+    for (requirement, version, matches) in [
+        ("0.2.0", Version::new(0, 2, 5), true),
+        ("0.2.0", Version::new(0, 3, 0), false),
+        ("1.2.0", Version::new(1, 4, 0), true),
+        ("1.2.0", Version::new(1, 1, 0), false),
+        ("0.0.3", Version::new(0, 0, 3), true),
+        ("0.0.3", Version::new(1, 0, 3), false),
+    ] {
+        assert_eq!(requirement.parse::<VersionReq>().unwrap().matches(&version), matches, "{} {}", requirement, version);
+    }
+}
+
+#[test]
+fn resolve_packages() {
+    let root = workspace("monorepo", &[
+        ("app/nyar.toml", "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nmath = { path = \"../libs/math\", version = \"0.2.0\" }\ntext = { path = \"../libs/text\" }\n"),
+        ("app/src/main.vk", "import math\nimport math.geometry.{area}\nimport text\nimport helpers.{twice}\n[math.square(3), area(2, 5), text.greet(5), twice(4)]"),
+        ("app/src/helpers.vk", "export function twice(x) { x * 2 }"),
+        ("libs/math/nyar.toml", &manifest("math", "0.2.1", &[("util", "../util")])),
+        ("libs/math/src/lib.vk", "import util\nexport function square(x) { util.mul(x, x) }"),
+        ("libs/math/src/geometry.vk", "export function area(w, h) { w * h }"),
+        ("libs/text/nyar.toml", &manifest("text", "1.0.0", &[("util", "../util")])),
+        ("libs/text/src/lib.vk", "import util.{mul}\nexport function greet(n) { mul(n, 10) }"),
+        ("libs/util/nyar.toml", &manifest("util", "0.1.0", &[])),
+        ("libs/util/src/lib.vk", "export function mul(a, b) { a * b }"),
+    ]);
+    let graph = PackageGraph::load(root.join("app")).unwrap();
+    let names: Vec<_> = graph.packages().iter().map(|package| package.name()).collect();
+    assert_eq!(names, ["util", "math", "text", "app"]);
+
+    let lockfile = graph.lockfile();
+    let text = lockfile.to_string();
+    assert!(text.contains("[[package]]\nname = \"math\"\nversion = \"0.2.1\"\npath = \"../libs/math\"\ndependencies = [\"util\"]\n"), "{}", text);
+    assert_eq!(Lockfile::parse(&text, "nyar.lock").unwrap(), lockfile);
+    assert!(graph.write_lockfile().unwrap());
+    assert!(!graph.write_lockfile().unwrap());
+    graph.verify_lockfile().unwrap();
+    std::fs::write(root.join("app/nyar.lock"), text.replace("0.2.1", "0.2.0")).unwrap();
+    let error = graph.verify_lockfile().unwrap_err();
+    assert!(error.kind().to_string().contains("`nyar.lock` is out of date for package `math`"), "{}", error.kind());
+
+    let resolver = PackageResolver::new(graph);
+    let program = resolver.load_entry().unwrap();
+    let compiled = NyarCompiler::new().with_resolver(resolver.clone()).compile(&program).unwrap();
+    assert!(compiled.is_success(), "{:?}", compiled.errors());
+    // 被两个包依赖的模块只初始化一次
+    let util = resolver.graph().package("util").unwrap().entry().canonicalize().unwrap();
+    let util = util.to_string_lossy();
+    let initializers = compiled.bytecode().iter().filter(|i| matches!(i, Instruction::CreateFunction { name: Some(name), .. } if name == &util));
+    assert_eq!(initializers.count(), 1);
+    let mut vm = VirtualMachine::new();
+    let result = vm.execute(compiled.into_bytecode()).unwrap();
+    let view = |value| vm.heap().view_ref(value).unwrap().clone();
+    let items = match view(result) {
+        NyarValue::Vector(items) => items.iter().map(view).collect::<Vec<_>>(),
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(items, [9, 10, 50, 8].map(NyarValue::from));
+
+    // 依赖的依赖不可见
+    let entry = resolver.graph().root().entry().canonicalize().unwrap();
+    let program = parse_at(&entry, "import util");
+    let compiled = NyarCompiler::new().with_resolver(resolver).compile(&program).unwrap();
+    assert!(compiled.errors()[0].kind().to_string().contains("cannot find module `util`"), "{:?}", compiled.errors());
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn invalid_package_graphs() {
+    let root = workspace("invalid", &[
+        ("a/nyar.toml", &manifest("a", "0.1.0", &[("b", "../b")])),
+        ("b/nyar.toml", &manifest("b", "0.1.0", &[("a", "../a")])),
+        ("c/nyar.toml", "[package]\nname = \"c\"\nversion = \"0.1.0\"\n[dependencies]\nd = { path = \"../d\", version = \"2.0.0\" }\n"),
+        ("d/nyar.toml", &manifest("d", "1.5.0", &[])),
+        ("e/nyar.toml", &manifest("e", "0.1.0", &[("renamed", "../d")])),
+    ]);
+    for (package, message) in [
+        ("a", "dependency cycle: a -> b -> a"),
+        ("c", "`c` requires `d` ^2.0.0, but found 1.5.0"),
+        ("e", "dependency `renamed` of `e` points to package `d` at `../d`"),
+        ("missing", "cannot find package at"),
+    ] {
+        let error = PackageGraph::load(root.join(package)).unwrap_err();
+        assert!(error.kind().to_string().contains(message), "{}", error.kind());
+    }
+    std::fs::remove_dir_all(root).unwrap();
+}
@@ -1,3 +1,7 @@
+pub mod testing;
 pub mod vm;
 
-pub use crate::vm::VirtualMachine;
\ No newline at end of file
+pub use crate::{
+    testing::{TestReport, TestResult, TestRunner},
+    vm::{VirtualMachine, VmState},
+};
@@ -0,0 +1,130 @@
+//! 测试运行器, 执行程序中以 `#test` 标注的函数
+//!
+//! 每个测试在新的虚拟机中先执行整个程序, 再调用测试函数, 测试之间不共享全局变量。
+//! 测试函数没有抛出错误即为通过。
+
+use crate::VirtualMachine;
+use nyar_error::NyarError;
+use nyar_hir::{
+    NyarCompiler,
+    ast::{FunctionDefinition, Program, Statement, StatementKind},
+};
+use nyar_lir::{Instruction, OptimizationLevel};
+use std::fmt::{Display, Formatter};
+
+/// 测试运行器
+#[derive(Debug, Default)]
+pub struct TestRunner {
+    optimization: OptimizationLevel,
+}
+
+/// 单个测试的结果
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    /// 测试函数名
+    pub name: String,
+    /// 测试失败的原因, 通过时为空
+    pub error: Option<NyarError>,
+}
+
+/// 测试报告, 按测试函数的声明顺序排列
+#[derive(Debug, Clone, Default)]
+pub struct TestReport {
+    /// 每个测试的结果
+    pub results: Vec<TestResult>,
+}
+
+impl TestRunner {
+    /// 创建一个新的测试运行器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置编译测试程序时的优化级别
+    pub fn with_optimization_level(mut self, level: OptimizationLevel) -> Self {
+        self.optimization = level;
+        self
+    }
+
+    /// 程序顶层以 `#test` 标注的函数, 按声明顺序排列
+    pub fn discover(program: &Program) -> Vec<&FunctionDefinition> {
+        fn declaration(statement: &Statement) -> Option<&FunctionDefinition> {
+            match &statement.kind {
+                StatementKind::FunctionDeclaration(function) => Some(function),
+                StatementKind::Export(export) => declaration(&export.declaration),
+                _ => None,
+            }
+        }
+        let functions = program.statements.iter().filter_map(declaration);
+        functions.filter(|function| function.attributes.iter().any(|attribute| attribute.name == "test")).collect()
+    }
+
+    /// 运行程序中的所有测试, 程序无法编译时报错
+    pub fn run(&self, program: &Program) -> Result<TestReport, NyarError> {
+        let compiled = NyarCompiler::new().with_optimization_level(self.optimization).compile(program)?;
+        if let Some(error) = compiled.errors().first() {
+            return Err(error.clone());
+        }
+        let (bytecode, line_table) = compiled.into_parts();
+        let mut report = TestReport::default();
+        for function in Self::discover(program) {
+            let error = match function.parameters.len() {
+                0 => {
+                    let mut vm = VirtualMachine::new();
+                    vm.set_line_table(line_table.clone());
+                    let call = vec![
+                        Instruction::PushVariable { name: function.name.clone() },
+                        Instruction::Call { argument_count: 0 },
+                        Instruction::Return,
+                    ];
+                    vm.execute(bytecode.clone()).and_then(|_| vm.execute(call)).err()
+                }
+                _ => {
+                    let message = format!("test `{}` must not take parameters", function.name);
+                    Some(function.span.locate(NyarError::custom(message)))
+                }
+            };
+            report.results.push(TestResult { name: function.name.clone(), error });
+        }
+        Ok(report)
+    }
+}
+
+impl TestResult {
+    /// 测试是否通过
+    pub fn is_passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl TestReport {
+    /// 通过的测试个数
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|result| result.is_passed()).count()
+    }
+    /// 失败的测试个数
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+    /// 是否所有测试都通过
+    pub fn is_success(&self) -> bool {
+        self.failed() == 0
+    }
+}
+
+impl Display for TestReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for result in &self.results {
+            writeln!(f, "test {} ... {}", result.name, if result.is_passed() { "ok" } else { "FAILED" })?;
+        }
+        let failures: Vec<_> = self.results.iter().filter_map(|result| Some((&result.name, result.error.as_ref()?))).collect();
+        if !failures.is_empty() {
+            writeln!(f, "\nfailures:")?;
+            for (name, error) in failures {
+                writeln!(f, "---- {} ----\n{}", name, error.kind())?;
+            }
+        }
+        let status = if self.is_success() { "ok" } else { "FAILED" };
+        write!(f, "\ntest result: {}. {} passed; {} failed", status, self.passed(), self.failed())
+    }
+}
@@ -1,12 +1,12 @@
 //! 协程管理模块，负责管理协程的创建、恢复和暂停
 
 use nyar_error::NyarError;
-use nyar_lir::{Gc, NyarValue, CoroutineState, NyarCoroutine, NyarFunction};
+use nyar_lir::{Gc, NyarCoroutine};
 
 use super::VirtualMachine;
 
 /// 协程管理器，负责管理协程的创建、恢复和暂停
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct CoroutineManager {
     /// 当前活跃的协程
     active_coroutines: Vec<Gc<NyarCoroutine>>,
@@ -15,51 +15,26 @@ pub struct CoroutineManager {
 impl CoroutineManager {
     /// 创建一个新的协程管理器
     pub fn new() -> Self {
-        Self {
-            active_coroutines: Vec::new(),
-        }
+        Self { active_coroutines: Vec::new() }
+    }
+
+    /// 当前活跃的协程数量
+    pub fn active_count(&self) -> usize {
+        self.active_coroutines.len()
     }
 
     /// 创建一个新的协程
-    pub fn create_coroutine(&mut self, vm: &mut VirtualMachine) -> Result<(), NyarError> {
-        todo!()
+    pub fn create_coroutine(&mut self, _vm: &mut VirtualMachine) -> Result<(), NyarError> {
+        Err(NyarError::custom("coroutines are not supported yet"))
     }
 
     /// 恢复协程执行
-    pub fn resume_coroutine(&mut self, vm: &mut VirtualMachine) -> Result<(), NyarError> {
-        todo!()
+    pub fn resume_coroutine(&mut self, _vm: &mut VirtualMachine) -> Result<(), NyarError> {
+        Err(NyarError::custom("coroutines are not supported yet"))
     }
 
     /// 暂停协程执行
-    pub fn yield_coroutine(&mut self, vm: &mut VirtualMachine, value_count: usize) -> Result<(), NyarError> {
-        todo!()
-    }
-
-    /// 保存VM状态
-    fn save_vm_state(&self, vm: &VirtualMachine) -> CoroutineSavedState {
-        todo!()
-    }
-
-    /// 恢复VM状态
-    fn restore_vm_state(&self, vm: &mut VirtualMachine, state: CoroutineSavedState) {
-        todo!()
-    }
-
-    /// 设置协程的执行环境
-    fn setup_coroutine_environment(&self, vm: &mut VirtualMachine, coroutine: &mut NyarCoroutine) {
-        todo!()
-    }
-
-    /// 恢复协程的执行环境
-    fn restore_coroutine_environment(&self, vm: &mut VirtualMachine, coroutine: &mut NyarCoroutine) {
-        todo!()
+    pub fn yield_coroutine(&mut self, _vm: &mut VirtualMachine, _value_count: usize) -> Result<(), NyarError> {
+        Err(NyarError::custom("coroutines are not supported yet"))
     }
 }
-
-/// 协程保存的VM状态
-#[derive(Debug, Clone)]
-struct CoroutineSavedState {
-    instruction_pointer: usize,
-    value_stack: Vec<Gc<NyarValue>>,
-    // 其他需要保存的状态...
-}
\ No newline at end of file
@@ -1,16 +1,37 @@
 //! 效应处理器模块，负责处理代数效应
 
-use super::VirtualMachine;
-use nyar_error::{NyarError, Result};
-use nyar_lir::{Gc, Heap, NyarFunction, NyarHandler, NyarValue, values::NyarObject};
+use nyar_lir::{Gc, NyarHandler};
 
 /// 效应处理器，负责处理代数效应
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct EffectHandler {
-    /// Registered Handlers
-    handlers: Gc<NyarObject>,
+    /// Registered Handlers, 以及注册它们的调用帧深度
+    handlers: Vec<(usize, NyarHandler)>,
 }
 
 impl EffectHandler {
-   
+    /// 在指定深度的调用帧中注册处理器
+    pub fn register(&mut self, depth: usize, handler: NyarHandler) {
+        self.handlers.push((depth, handler))
+    }
+
+    /// 查找最近注册的同名处理器
+    pub fn find(&self, name: &str) -> Option<Gc<nyar_lir::NyarFunction>> {
+        self.handlers.iter().rev().find(|(_, h)| h.name == name).map(|(_, h)| h.handler)
+    }
+
+    /// 调用帧退出时移除其注册的处理器
+    pub fn leave_frame(&mut self, depth: usize) {
+        while let Some((d, _)) = self.handlers.last() {
+            if *d < depth {
+                break;
+            }
+            self.handlers.pop();
+        }
+    }
+
+    /// 移除所有处理器
+    pub fn clear(&mut self) {
+        self.handlers.clear()
+    }
 }
@@ -1,9 +1,10 @@
 //! 环境管理模块，负责管理变量环境和作用域
 
-use nyar_lir::{Gc, values::NyarObject};
+use nyar_error::{NyarError, Result};
+use nyar_lir::{Gc, Heap, NyarValue, values::NyarObject};
 
 /// 环境管理器，负责管理变量环境和作用域
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Environment {
     /// 环境栈，每个作用域一个环境
     environments: Vec<Gc<NyarObject>>,
@@ -14,4 +15,49 @@ impl Environment {
     pub fn new(builtin: Gc<NyarObject>) -> Self {
         Self { environments: vec![builtin] }
     }
+
+    /// 从捕获的作用域链创建环境, 并追加一个新的局部作用域
+    pub fn from_scopes(heap: &mut Heap, scopes: &[Gc<NyarObject>]) -> Self {
+        let local = heap.allocate(NyarObject::default()).transmute();
+        let mut environments = scopes.to_vec();
+        environments.push(local);
+        Self { environments }
+    }
+
+    /// 由外到内的作用域链
+    pub fn scopes(&self) -> &[Gc<NyarObject>] {
+        &self.environments
+    }
+
+    /// 由内向外查找变量
+    pub fn lookup(&self, heap: &Heap, name: &str) -> Result<Option<Gc<NyarValue>>> {
+        for scope in self.environments.iter().rev() {
+            if let Some(value) = scope.as_object_ref(heap)?.get(name) {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 在最内层作用域声明变量
+    pub fn declare(&self, heap: &mut Heap, name: &str, value: Gc<NyarValue>) -> Result<()> {
+        let scope = self.innermost()?;
+        scope.as_object(heap)?.insert(name, value);
+        Ok(())
+    }
+
+    /// 为最近的同名变量赋值, 变量不存在时在最内层作用域声明
+    pub fn assign(&self, heap: &mut Heap, name: &str, value: Gc<NyarValue>) -> Result<()> {
+        for scope in self.environments.iter().rev() {
+            if let Some(slot) = scope.as_object(heap)?.get_mut(name) {
+                *slot = value;
+                return Ok(());
+            }
+        }
+        self.declare(heap, name, value)
+    }
+
+    fn innermost(&self) -> Result<Gc<NyarObject>> {
+        self.environments.last().copied().ok_or_else(|| NyarError::custom("empty environment"))
+    }
 }
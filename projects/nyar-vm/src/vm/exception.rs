@@ -0,0 +1,58 @@
+//! 异常处理模块，负责结构化的异常处理
+
+/// 已注册的异常处理器
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TryBlock {
+    /// 注册处理器的调用帧深度
+    pub depth: usize,
+    /// 处理器在调用帧指令中的位置
+    pub target: usize,
+    /// 注册时值栈的长度, 展开时截断到此长度
+    pub stack_depth: usize,
+}
+
+/// 异常处理器栈, 栈顶为最内层的处理器
+#[derive(Debug, Default)]
+pub struct ExceptionHandler {
+    blocks: Vec<TryBlock>,
+}
+
+impl ExceptionHandler {
+    /// 注册处理器
+    pub fn enter(&mut self, block: TryBlock) {
+        self.blocks.push(block)
+    }
+
+    /// 移除指定深度的调用帧中最近注册的处理器
+    pub fn leave(&mut self, depth: usize) -> Option<TryBlock> {
+        match self.blocks.last() {
+            Some(block) if block.depth == depth => self.blocks.pop(),
+            _ => None,
+        }
+    }
+
+    /// 取出最内层的处理器
+    pub fn unwind(&mut self) -> Option<TryBlock> {
+        self.blocks.pop()
+    }
+
+    /// 调用帧退出时移除其注册的处理器
+    pub fn leave_frame(&mut self, depth: usize) {
+        while let Some(block) = self.blocks.last() {
+            if block.depth < depth {
+                break;
+            }
+            self.blocks.pop();
+        }
+    }
+
+    /// 是否没有任何处理器
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// 移除所有处理器
+    pub fn clear(&mut self) {
+        self.blocks.clear()
+    }
+}
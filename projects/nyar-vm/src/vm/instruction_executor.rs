@@ -1,137 +1,486 @@
 //! 指令执行器模块，负责执行各种VM指令
 
 use nyar_error::NyarError;
-use nyar_lir::{Gc, Instruction, NyarValue};
+use nyar_lir::{
+    Gc, Instruction, NyarFunction, NyarHandler, NyarValue, Shape,
+    values::{NyarClass, NyarEnum, NyarObject, NyarStruct, NyarTrait, NyarVariant, NyarVector},
+};
 use std::collections::HashMap;
 
-use super::VirtualMachine;
+use super::{Environment, TryBlock, ValueHandler, VirtualMachine};
 
 /// 指令执行器，负责执行各种VM指令
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct InstructionExecutor {
-    // 指令执行器的配置和状态
+    values: ValueHandler,
 }
 
 impl InstructionExecutor {
     /// 创建一个新的指令执行器
     pub fn new() -> Self {
-        Self {}
+        Self { values: ValueHandler::new() }
     }
 
     /// 执行单条指令
     pub fn execute_instruction(&self, vm: &mut VirtualMachine, instruction: &Instruction) -> Result<(), NyarError> {
         match instruction {
-            Instruction::PushConstant { .. } => {
-                todo!()
-            }
-            Instruction::PushVariable { .. } => {
-                todo!()
-            }
-            Instruction::StoreVariable { .. } => {
-                todo!()
-            }
-            Instruction::GetIndex { .. } => {
-                todo!()
-            }
-            Instruction::SetIndex { .. } => {
-                todo!()
-            }
-            Instruction::GetProperty { .. } => {
-                todo!()
-            }
-            Instruction::SetProperty { .. } => {
-                todo!()
-            }
-            Instruction::Call { .. } => {
-                todo!()
-            }
-            Instruction::CreateFunction { .. } => {
-                todo!()
-            }
-            Instruction::CreateClosure { .. } => {
-                todo!()
-            }
-            Instruction::CreateArray { .. } => {
-                todo!()
-            }
-            Instruction::CreateObject { .. } => {
-                todo!()
-            }
-            Instruction::CreateClass { .. } => {
-                todo!()
-            }
-            Instruction::CreateTrait { .. } => {
-                todo!()
-            }
-            Instruction::CreateEnum { .. } => {
-                todo!()
-            }
-            Instruction::Jump { .. } => {
-                todo!()
-            }
-            Instruction::JumpIfFalse { .. } => {
-                todo!()
-            }
-            Instruction::LoopStart { .. } => {
-                todo!()
-            }
-            Instruction::LoopEnd { .. } => {
-                todo!()
-            }
-            Instruction::Break { .. } => {
-                todo!()
-            }
-            Instruction::Continue { .. } => {
-                todo!()
-            }
-            Instruction::MatchStart => {
-                todo!()
-            }
-            Instruction::MatchCase { .. } => {
-                todo!()
-            }
-            Instruction::MatchEnd => {
-                todo!()
-            }
-            Instruction::Return => {
-                todo!()
-            }
-            Instruction::CreateCoroutine => {
-                todo!()
-            }
-            Instruction::ResumeCoroutine => {
-                todo!()
-            }
-            Instruction::YieldCoroutine { .. } => {
-                todo!()
-            }
-            Instruction::Await => {
-                todo!()
-            }
-            Instruction::BlockOn => {
-                todo!()
-            }
-            Instruction::FireThenIgnore => {
-                todo!()
-            }
-            Instruction::RaiseEffect { .. } => {
-                todo!()
-            }
-            Instruction::HandleEffect { .. } => {
-                todo!()
-            }
-            Instruction::ResumeEffect { .. } => {
-                todo!()
+            Instruction::PushConstant { value } => vm.push_value(value.clone()),
+            Instruction::PushVariable { name } => {
+                let value = vm.frame()?.environment.lookup(&vm.memory, name)?;
+                match value {
+                    Some(value) => vm.push(value),
+                    None => Err(NyarError::custom(format!("undefined variable `{}`", name))),
+                }
+            }
+            Instruction::StoreVariable { name } => {
+                let value = vm.pop()?;
+                let value = self.copy(vm, value)?;
+                let environment = vm.frame()?.environment.clone();
+                environment.assign(&mut vm.memory, name, value)
+            }
+            Instruction::DeclareVariable { name } => {
+                let value = vm.pop()?;
+                let value = self.copy(vm, value)?;
+                let environment = vm.frame()?.environment.clone();
+                environment.declare(&mut vm.memory, name, value)
+            }
+            Instruction::Pop => vm.pop().map(|_| ()),
+            Instruction::Duplicate => {
+                let value = vm.peek(0)?;
+                vm.push(value)
+            }
+            Instruction::GetIndex { index } => {
+                let target = vm.pop()?;
+                let value = self.values.element(&mut vm.memory, target, *index)?;
+                vm.push(value)
+            }
+            Instruction::SetIndex { index } => {
+                let value = vm.pop()?;
+                let target = vm.pop()?;
+                self.set_index(vm, target, *index, value)
+            }
+            Instruction::GetItem => {
+                let key = vm.pop()?;
+                let target = vm.pop()?;
+                let value = match vm.view(key)? {
+                    NyarValue::String(name) => {
+                        let name = name.to_string();
+                        self.get_property(vm, target, &name)?
+                    }
+                    _ => {
+                        let index = self.index_of(vm, key)?;
+                        self.values.element(&mut vm.memory, target, index)?
+                    }
+                };
+                vm.push(value)
+            }
+            Instruction::SetItem => {
+                let value = vm.pop()?;
+                let key = vm.pop()?;
+                let target = vm.pop()?;
+                match vm.view(key)? {
+                    NyarValue::String(name) => {
+                        let name = name.to_string();
+                        self.set_property(vm, target, &name, value)
+                    }
+                    _ => {
+                        let index = self.index_of(vm, key)?;
+                        self.set_index(vm, target, index, value)
+                    }
+                }
+            }
+            Instruction::GetProperty { name } => {
+                let target = vm.pop()?;
+                let value = self.get_property(vm, target, name)?;
+                vm.push(value)
+            }
+            Instruction::SetProperty { name } => {
+                let value = vm.pop()?;
+                let target = vm.pop()?;
+                self.set_property(vm, target, name, value)
+            }
+            Instruction::Length => {
+                let target = vm.pop()?;
+                let length = self.values.length(&vm.memory, target)?;
+                vm.push_value(NyarValue::from(length as i64))
+            }
+            Instruction::Binary { operator } => {
+                let rhs = vm.pop()?;
+                let lhs = vm.pop()?;
+                let value = self.values.binary(&vm.memory, *operator, lhs, rhs)?;
+                vm.push_value(value)
+            }
+            Instruction::Unary { operator } => {
+                let value = vm.pop()?;
+                let value = operator.evaluate(vm.view(value)?)?;
+                vm.push_value(value)
+            }
+            Instruction::Call { argument_count } => self.handle_function_call(vm, *argument_count),
+            Instruction::TailCall { argument_count } => {
+                let arguments = vm.pop_many(*argument_count)?;
+                let callee = vm.pop()?;
+                let (function, environment) = self.prepare_call(vm, callee, arguments)?;
+                vm.replace_frame(function.code, function.body, environment)
+            }
+            Instruction::CreateFunction { name, parameters, body_size } => {
+                self.create_function(vm, name.clone(), parameters.clone(), *body_size)
+            }
+            Instruction::CreateClosure { captured_variables } => {
+                let function = vm.pop()?;
+                let mut function = self.expect_function(vm, function)?;
+                let mut captured = NyarObject::default();
+                for name in captured_variables {
+                    if let Some(value) = vm.frame()?.environment.lookup(&vm.memory, name)? {
+                        captured.insert(name.as_str(), value);
+                    }
+                }
+                function.environment.push(vm.allocate(captured).transmute());
+                vm.push_value(function)
+            }
+            Instruction::CreateArray { size } => {
+                let items = vm.pop_many(*size)?;
+                let items = items.into_iter().map(|item| self.copy(vm, item)).collect::<Result<Vec<_>, _>>()?;
+                vm.push_value(NyarVector::from(items))
+            }
+            Instruction::CreateObject { property_count } => {
+                let pairs = vm.pop_many(property_count * 2)?;
+                let mut object = NyarObject::default();
+                for pair in pairs.chunks(2) {
+                    let value = self.copy(vm, pair[1])?;
+                    object.insert(self.expect_string(vm, pair[0])?, value);
+                }
+                vm.push_value(object)
+            }
+            Instruction::CreateClass { name, trait_count, method_count, property_count } => {
+                let methods = vm.pop_many(method_count * 2)?;
+                let properties = vm.pop_many(property_count * 2)?;
+                let traits = vm.pop_many(*trait_count)?;
+                let parent = match vm.pop()? {
+                    p if vm.view(p)?.is_null() => None,
+                    p => match vm.view(p)? {
+                        NyarValue::Class(_) => Some(p.transmute()),
+                        v => return Err(NyarError::custom(format!("cannot inherit from {}", v.type_name()))),
+                    },
+                };
+                let mut class = NyarClass {
+                    name: name.clone(),
+                    parent,
+                    traits: Vec::with_capacity(traits.len()),
+                    methods: HashMap::new(),
+                    properties: Default::default(),
+                };
+                for value in traits {
+                    self.expect_trait(vm, value)?;
+                    class.traits.push(value.transmute());
+                }
+                for pair in properties.chunks(2) {
+                    class.properties.insert(self.expect_string(vm, pair[0])?, pair[1]);
+                }
+                for pair in methods.chunks(2) {
+                    self.expect_function(vm, pair[1])?;
+                    class.methods.insert(self.expect_string(vm, pair[0])?, pair[1].transmute());
+                }
+                let class = vm.allocate(NyarValue::Class(Box::new(class)));
+                self.check_traits(vm, class.transmute())?;
+                vm.push(class)
+            }
+            Instruction::CreateInstance { property_count } => {
+                let pairs = vm.pop_many(property_count * 2)?;
+                let class = vm.pop()?;
+                if let NyarValue::Structure(structure) = vm.view(class)? {
+                    let mut value = NyarStruct::zeroed(structure.clone());
+                    for pair in pairs.chunks(2) {
+                        value.set(&self.expect_string(vm, pair[0])?, vm.view(pair[1])?)?;
+                    }
+                    return vm.push_value(NyarValue::Struct(Box::new(value)));
+                }
+                let class = self.expect_class(vm, class)?;
+                let mut instance = NyarObject::instance(class);
+                // 父类的默认值先写入, 被子类的默认值覆盖
+                for class in self.class_chain(vm, class)?.into_iter().rev() {
+                    let defaults: Vec<_> = match vm.view(class.as_any())? {
+                        NyarValue::Class(c) => c.properties.iter().map(|(name, value)| (name.clone(), *value)).collect(),
+                        _ => vec![],
+                    };
+                    for (name, default) in defaults {
+                        // 每个实例持有默认值的副本, 实例之间不共享数组与对象
+                        let value = vm.view(default)?.clone();
+                        instance.insert(name, vm.allocate(value));
+                    }
+                }
+                for pair in pairs.chunks(2) {
+                    let name = self.expect_string(vm, pair[0])?;
+                    if !instance.contains(&name) {
+                        let class = self.expect_class_value(vm, class)?;
+                        return Err(NyarError::custom(format!("class `{}` has no property `{}`", class.name, name)));
+                    }
+                    let value = self.copy(vm, pair[1])?;
+                    instance.insert(name, value);
+                }
+                vm.push_value(instance)
+            }
+            Instruction::CallMethod { name, argument_count } => {
+                let arguments = vm.pop_many(*argument_count)?;
+                let receiver = vm.pop()?;
+                // 对象的 `to_string` 可以由类自定义, 其他值按默认格式展示
+                if name == "to_string" && arguments.is_empty() && !matches!(vm.view(receiver)?, NyarValue::Object(_)) {
+                    let text = self.values.format(&vm.memory, receiver)?;
+                    return vm.push_value(text);
+                }
+                if let Some(result) = self.values.numeric_method(&vm.memory, name, receiver, &arguments)? {
+                    return vm.push_value(result);
+                }
+                if let Some(result) = self.values.string_method(&mut vm.memory, name, receiver, &arguments)? {
+                    return vm.push_value(result);
+                }
+                // `Trait.method(value, ...)` 分派到 `value` 的类的实现
+                if matches!(vm.view(receiver)?, NyarValue::Trait(t) if t.methods.contains_key(name)) {
+                    return self.call_trait_method(vm, receiver, name, arguments);
+                }
+                // 实例自身的属性优先于类的方法
+                let class = match vm.view(receiver)? {
+                    NyarValue::Object(o) if !o.contains(name) => o.class(),
+                    _ => None,
+                };
+                let method = match class {
+                    Some(class) => self.find_method(vm, class, name)?,
+                    None => None,
+                };
+                match method {
+                    Some(method) => self.invoke_method(vm, receiver, method, arguments),
+                    None if name == "to_string"
+                        && arguments.is_empty()
+                        && matches!(vm.view(receiver)?, NyarValue::Object(o) if !o.contains(name)) =>
+                    {
+                        let text = self.values.format(&vm.memory, receiver)?;
+                        vm.push_value(text)
+                    }
+                    None => {
+                        let callee = self.get_property(vm, receiver, name)?;
+                        self.invoke(vm, callee, arguments)
+                    }
+                }
+            }
+            Instruction::CallSuper { name, argument_count } => {
+                let arguments = vm.pop_many(*argument_count)?;
+                let class = vm.pop()?;
+                let class = self.expect_class(vm, class)?;
+                let receiver = vm.pop()?;
+                match self.find_method(vm, class, name)? {
+                    Some(method) => self.invoke_method(vm, receiver, method, arguments),
+                    None => {
+                        let class = self.expect_class_value(vm, class)?;
+                        Err(NyarError::custom(format!("class `{}` has no method `{}`", class.name, name)))
+                    }
+                }
+            }
+            Instruction::CreateTrait { name, method_count } => {
+                let triples = vm.pop_many(method_count * 3)?;
+                let mut methods = HashMap::new();
+                let mut defaults = HashMap::new();
+                for triple in triples.chunks(3) {
+                    let method = self.expect_string(vm, triple[0])?;
+                    let parameters = match vm.view(triple[1])? {
+                        NyarValue::Vector(v) => v.iter().collect::<Vec<_>>(),
+                        v => return Err(NyarError::custom(format!("expect parameter list, found {}", v.type_name()))),
+                    };
+                    let parameters = parameters.into_iter().map(|p| self.expect_string(vm, p)).collect::<Result<_, _>>()?;
+                    if !vm.view(triple[2])?.is_null() {
+                        self.expect_function(vm, triple[2])?;
+                        defaults.insert(method.clone(), triple[2].transmute());
+                    }
+                    methods.insert(method, parameters);
+                }
+                vm.push_value(NyarValue::Trait(Box::new(NyarTrait { name: name.clone(), methods, defaults })))
+            }
+            Instruction::CreateEnum { name, variant_count } => {
+                let pairs = vm.pop_many(variant_count * 2)?;
+                let mut variants = Vec::with_capacity(*variant_count);
+                for pair in pairs.chunks(2) {
+                    variants.push((self.expect_string(vm, pair[0])?, pair[1]));
+                }
+                vm.push_value(NyarValue::Enum(Box::new(NyarEnum { name: name.clone(), variants })))
+            }
+            Instruction::CreateVariant { enumeration, variant, tag, fields } => {
+                let values = vm.pop_many(fields.len())?;
+                let variant = NyarVariant {
+                    enumeration: enumeration.clone(),
+                    name: variant.clone(),
+                    tag: *tag,
+                    fields: fields.iter().cloned().zip(values).collect(),
+                };
+                vm.push_value(NyarValue::Variant(Box::new(variant)))
+            }
+            Instruction::Jump { offset } => vm.jump(*offset),
+            Instruction::JumpIfFalse { offset } => {
+                let condition = vm.pop()?;
+                match vm.view(condition)?.is_truthy() {
+                    true => Ok(()),
+                    false => vm.jump(*offset),
+                }
+            }
+            Instruction::IteratorNext { offset } => {
+                let cursor = vm.peek(0)?;
+                let iterable = vm.peek(1)?;
+                let index = self.index_of(vm, cursor)?;
+                if index < self.values.length(&vm.memory, iterable)? {
+                    let item = self.values.element(&mut vm.memory, iterable, index)?;
+                    vm.pop()?;
+                    vm.push_value(NyarValue::from(index as i64 + 1))?;
+                    vm.push(item)
+                }
+                else {
+                    vm.pop_many(2)?;
+                    vm.jump(*offset)
+                }
+            }
+            Instruction::LoopStart { .. } | Instruction::LoopEnd { .. } => Ok(()),
+            Instruction::Break { label } => self.seek_loop(vm, label, true),
+            Instruction::Continue { label } => self.seek_loop(vm, label, false),
+            Instruction::Assert { message, expression, comparison } => {
+                let expression = expression.clone().unwrap_or_default();
+                let Some(operator) = comparison
+                else {
+                    let condition = vm.pop()?;
+                    return match vm.view(condition)?.is_truthy() {
+                        true => Ok(()),
+                        false => Err(NyarError::assertion(expression, message.clone(), None)),
+                    };
+                };
+                let right = vm.pop()?;
+                let left = vm.pop()?;
+                let result = self.values.binary(&vm.memory, *operator, left, right)?;
+                match result.is_truthy() {
+                    true => Ok(()),
+                    false => {
+                        let operands = (self.values.render(&vm.memory, left)?, self.values.render(&vm.memory, right)?);
+                        Err(NyarError::assertion(expression, message.clone(), Some(operands)))
+                    }
+                }
+            }
+            Instruction::EnterTry { offset } => {
+                // 指令指针已经指向下一条指令
+                let target = usize::try_from(vm.instruction_pointer as isize - 1 + offset)
+                    .map_err(|_| NyarError::custom("exception handler out of range"))?;
+                let block = TryBlock { depth: vm.call_depth() - 1, target, stack_depth: vm.frame()?.value_stack.len() };
+                vm.exceptions.enter(block);
+                Ok(())
+            }
+            Instruction::LeaveTry => match vm.exceptions.leave(vm.call_depth() - 1) {
+                Some(_) => Ok(()),
+                None => Err(NyarError::custom("`LeaveTry` without a matching `EnterTry`")),
+            },
+            Instruction::Throw => {
+                let value = vm.pop()?;
+                vm.throw(value)
+            }
+            Instruction::Return => self.handle_return(vm),
+            Instruction::RaiseEffect { name, argument_count } => {
+                let function = match vm.effects.find(name) {
+                    Some(function) => function,
+                    None => return Err(NyarError::custom(format!("unhandled effect `{}`", name))),
+                };
+                let arguments = vm.pop_many(*argument_count)?;
+                self.invoke(vm, function.as_any(), arguments)
+            }
+            Instruction::HandleEffect { name } => {
+                let function = vm.pop()?;
+                self.expect_function(vm, function)?;
+                let handler = NyarHandler { name: name.clone(), handler: function.transmute(), resume_point: None };
+                let depth = vm.call_depth() - 1;
+                vm.effects.register(depth, handler);
+                Ok(())
+            }
+            Instruction::ResumeEffect { value_count } => {
+                let mut values = vm.pop_many(*value_count)?;
+                let value = match values.len() {
+                    0 => vm.allocate(NyarValue::Null),
+                    1 => values.remove(0),
+                    _ => vm.allocate(NyarVector::from(values)),
+                };
+                vm.return_from_frame(value)
             }
             Instruction::Halt => {
-                todo!()
+                let value = match vm.frame_mut()?.value_stack.pop() {
+                    Some(value) => value,
+                    None => vm.allocate(NyarValue::Null),
+                };
+                vm.halt(value);
+                Ok(())
             }
+            Instruction::MatchEnd => {
+                let value = vm.pop()?;
+                Err(NyarError::custom(format!("no match arm for {}", self.values.render(&vm.memory, value)?)))
+            }
+            Instruction::MatchShape { shape } => {
+                let matched = self.match_shape(vm, shape)?;
+                vm.push_value(NyarValue::Boolean(matched))
+            }
+            Instruction::MatchStart
+            | Instruction::MatchCase { .. }
+            | Instruction::CreateCoroutine
+            | Instruction::ResumeCoroutine
+            | Instruction::YieldCoroutine { .. }
+            | Instruction::Await
+            | Instruction::BlockOn
+            | Instruction::FireThenIgnore => Err(NyarError::custom(format!("unsupported instruction {:?}", instruction))),
         }
     }
 
     /// 处理函数调用
     fn handle_function_call(&self, vm: &mut VirtualMachine, argument_count: usize) -> Result<(), NyarError> {
-        todo!()
+        let arguments = vm.pop_many(argument_count)?;
+        let callee = vm.pop()?;
+        self.invoke(vm, callee, arguments)
+    }
+
+    /// 以给定参数调用函数值
+    fn invoke(&self, vm: &mut VirtualMachine, callee: Gc<NyarValue>, arguments: Vec<Gc<NyarValue>>) -> Result<(), NyarError> {
+        let (function, environment) = self.prepare_call(vm, callee, arguments)?;
+        vm.enter_frame(function.code, function.body, environment)
+    }
+
+    /// 调用方法, 第一个参数名为 `self` 时实例作为第一个参数传入, 否则在方法的作用域中声明 `self`
+    fn invoke_method(
+        &self,
+        vm: &mut VirtualMachine,
+        receiver: Gc<NyarValue>,
+        method: Gc<NyarValue>,
+        mut arguments: Vec<Gc<NyarValue>>,
+    ) -> Result<(), NyarError> {
+        let explicit = matches!(vm.view(method)?, NyarValue::Function(f) if f.parameters.first().is_some_and(|p| p == "self"));
+        if explicit {
+            arguments.insert(0, receiver);
+        }
+        let (function, environment) = self.prepare_call(vm, method, arguments)?;
+        if !explicit {
+            environment.declare(&mut vm.memory, "self", receiver)?;
+        }
+        vm.enter_frame(function.code, function.body, environment)
+    }
+
+    /// 检查参数并创建被调用函数的环境
+    fn prepare_call(
+        &self,
+        vm: &mut VirtualMachine,
+        callee: Gc<NyarValue>,
+        arguments: Vec<Gc<NyarValue>>,
+    ) -> Result<(NyarFunction, Environment), NyarError> {
+        let function = self.expect_function(vm, callee)?;
+        if function.parameters.len() != arguments.len() {
+            return Err(NyarError::custom(format!(
+                "function `{}` expects {} arguments, found {}",
+                function.name.as_deref().unwrap_or("<lambda>"),
+                function.parameters.len(),
+                arguments.len()
+            )));
+        }
+        let environment = Environment::from_scopes(&mut vm.memory, &function.environment);
+        for (name, value) in function.parameters.iter().zip(arguments) {
+            let value = self.copy(vm, value)?;
+            environment.declare(&mut vm.memory, name, value)?;
+        }
+        Ok((function, environment))
     }
 
     /// 创建函数
@@ -139,14 +488,381 @@ impl InstructionExecutor {
         &self,
         vm: &mut VirtualMachine,
         name: Option<String>,
-        parameter_count: usize,
+        parameters: Vec<String>,
         body_size: usize,
     ) -> Result<(), NyarError> {
-        todo!()
+        let frame = vm.frame()?;
+        // 函数体与所在帧共享指令, 只记录区间
+        let start = frame.body.start + vm.instruction_pointer;
+        let body = start..start + body_size;
+        if body.end > frame.body.end {
+            return Err(NyarError::custom("function body exceeds instruction sequence"));
+        }
+        let code = frame.instructions.clone();
+        let environment = frame.environment.scopes().to_vec();
+        vm.instruction_pointer += body_size;
+        vm.push_value(NyarFunction { name, parameters, code, body, environment })
     }
 
     /// 处理返回指令
     fn handle_return(&self, vm: &mut VirtualMachine) -> Result<(), NyarError> {
-        todo!()
+        let value = match vm.frame_mut()?.value_stack.pop() {
+            Some(value) => value,
+            None => vm.allocate(NyarValue::Null),
+        };
+        vm.return_from_frame(value)
+    }
+
+    /// 跳转到匹配的循环边界, `forward` 为真时跳到 `LoopEnd`, 否则跳到 `LoopStart` 之后
+    fn seek_loop(&self, vm: &mut VirtualMachine, label: &Option<String>, forward: bool) -> Result<(), NyarError> {
+        let frame = vm.frame()?;
+        let code = &frame.instructions[frame.body.clone()];
+        let mut depth = 0usize;
+        let mut index = vm.instruction_pointer as isize - 1;
+        loop {
+            index += if forward { 1 } else { -1 };
+            let instruction = match usize::try_from(index).ok().and_then(|i| code.get(i)) {
+                Some(s) => s,
+                None => return Err(NyarError::custom("`break` or `continue` outside of a loop")),
+            };
+            match (instruction, forward) {
+                (Instruction::LoopStart { .. }, true) | (Instruction::LoopEnd { .. }, false) => depth += 1,
+                (Instruction::LoopEnd { label: l }, true) | (Instruction::LoopStart { label: l }, false) => {
+                    if depth == 0 && (label.is_none() || label == l) {
+                        break;
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+                _ => {}
+            }
+        }
+        vm.instruction_pointer = index as usize + if forward { 0 } else { 1 };
+        Ok(())
+    }
+
+    /// 读取属性, 实例的方法绑定到实例
+    fn get_property(&self, vm: &mut VirtualMachine, target: Gc<NyarValue>, name: &str) -> Result<Gc<NyarValue>, NyarError> {
+        let found = match vm.view(target)? {
+            NyarValue::Object(o) => match (o.get(name), o.class()) {
+                (None, Some(class)) => match self.find_method(vm, class, name)? {
+                    Some(method) => Some(self.bind_method(vm, target, method)?),
+                    None => None,
+                },
+                (found, _) => found,
+            },
+            NyarValue::Class(c) => c.properties.get(name).copied().or_else(|| c.methods.get(name).map(|m| m.as_any())),
+            NyarValue::Enum(e) => e.variant(name),
+            NyarValue::Struct(s) => {
+                let field = s.get(name)?;
+                Some(vm.allocate(field))
+            }
+            // 字段优先, 其次为变体的序号与名称
+            NyarValue::Variant(v) => match name {
+                _ if v.field(name).is_some() => v.field(name),
+                "tag" => Some(vm.allocate(NyarValue::from(v.tag as i64))),
+                "variant" => Some(vm.allocate(NyarValue::from(v.name.as_str()))),
+                _ => None,
+            },
+            v => return Err(NyarError::custom(format!("{} has no property `{}`", v.type_name(), name))),
+        };
+        found.ok_or_else(|| NyarError::custom(format!("property `{}` not found", name)))
+    }
+
+    /// 把方法绑定到接收者, 与 [`invoke_method`](Self::invoke_method) 相同地传入 `self`
+    fn bind_method(
+        &self,
+        vm: &mut VirtualMachine,
+        receiver: Gc<NyarValue>,
+        method: Gc<NyarValue>,
+    ) -> Result<Gc<NyarValue>, NyarError> {
+        let mut function = self.expect_function(vm, method)?;
+        if function.parameters.first().is_some_and(|p| p == "self") {
+            function.parameters.remove(0);
+        }
+        let environment = Environment::from_scopes(&mut vm.memory, &function.environment);
+        environment.declare(&mut vm.memory, "self", receiver)?;
+        function.environment = environment.scopes().to_vec();
+        Ok(vm.allocate(function))
+    }
+
+    /// 类及其父类, 由子类到父类排列
+    fn class_chain(&self, vm: &VirtualMachine, class: Gc<NyarClass>) -> Result<Vec<Gc<NyarClass>>, NyarError> {
+        let mut chain = vec![];
+        let mut current = Some(class);
+        while let Some(class) = current {
+            if chain.contains(&class) {
+                break;
+            }
+            chain.push(class);
+            current = self.expect_class_value(vm, class)?.parent;
+        }
+        Ok(chain)
+    }
+
+    /// 沿父类链查找方法, 找不到时使用类及其父类所实现特征的默认实现
+    fn find_method(&self, vm: &VirtualMachine, class: Gc<NyarClass>, name: &str) -> Result<Option<Gc<NyarValue>>, NyarError> {
+        let chain = self.class_chain(vm, class)?;
+        for class in &chain {
+            if let Some(method) = self.expect_class_value(vm, *class)?.methods.get(name) {
+                return Ok(Some(method.as_any()));
+            }
+        }
+        for class in &chain {
+            for t in &self.expect_class_value(vm, *class)?.traits {
+                if let Some(default) = self.expect_trait(vm, t.as_any())?.defaults.get(name) {
+                    return Ok(Some(default.as_any()));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// 类及其父类是否实现了特征
+    fn implements(&self, vm: &VirtualMachine, class: Gc<NyarClass>, target: Gc<NyarValue>) -> Result<bool, NyarError> {
+        for class in self.class_chain(vm, class)? {
+            if self.expect_class_value(vm, class)?.traits.iter().any(|t| t.as_any() == target) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// 检查类以相同的参数个数提供了所实现特征的全部方法
+    fn check_traits(&self, vm: &VirtualMachine, class: Gc<NyarClass>) -> Result<(), NyarError> {
+        // 不计入接收者 `self` 的参数个数
+        fn arity(parameters: &[String]) -> usize {
+            parameters.len() - usize::from(parameters.first().is_some_and(|p| p == "self"))
+        }
+        let value = self.expect_class_value(vm, class)?;
+        for t in &value.traits {
+            let t = self.expect_trait(vm, t.as_any())?;
+            let mut methods: Vec<_> = t.methods.iter().collect();
+            methods.sort();
+            for (method, parameters) in methods {
+                let found = match self.find_method(vm, class, method)? {
+                    Some(found) => self.expect_function(vm, found)?,
+                    None => {
+                        return Err(NyarError::custom(format!(
+                            "class `{}` does not implement `{}.{}`",
+                            value.name, t.name, method
+                        )));
+                    }
+                };
+                if arity(&found.parameters) != arity(parameters) {
+                    return Err(NyarError::custom(format!(
+                        "`{}.{}` expects {} arguments, but `{}.{}` expects {}",
+                        value.name,
+                        method,
+                        arity(&found.parameters),
+                        t.name,
+                        method,
+                        arity(parameters)
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 以第一个参数为接收者调用特征方法 `Trait.method(value, ...)`
+    fn call_trait_method(
+        &self,
+        vm: &mut VirtualMachine,
+        target: Gc<NyarValue>,
+        name: &str,
+        mut arguments: Vec<Gc<NyarValue>>,
+    ) -> Result<(), NyarError> {
+        let trait_name = self.expect_trait(vm, target)?.name.clone();
+        if arguments.is_empty() {
+            return Err(NyarError::custom(format!("`{}.{}` expects a receiver", trait_name, name)));
+        }
+        let receiver = arguments.remove(0);
+        let class = match vm.view(receiver)? {
+            NyarValue::Object(o) => o.class(),
+            _ => None,
+        };
+        let method = match class {
+            Some(class) if self.implements(vm, class, target)? => self.find_method(vm, class, name)?,
+            _ => None,
+        };
+        match (method, class) {
+            (Some(method), _) => self.invoke_method(vm, receiver, method, arguments),
+            (None, Some(class)) => {
+                let class = self.expect_class_value(vm, class)?;
+                Err(NyarError::custom(format!("class `{}` does not implement `{}`", class.name, trait_name)))
+            }
+            (None, None) => {
+                Err(NyarError::custom(format!("{} does not implement `{}`", vm.view(receiver)?.type_name(), trait_name)))
+            }
+        }
+    }
+
+    /// 弹出值并检查其结构, 检查实例时先弹出类型
+    fn match_shape(&self, vm: &mut VirtualMachine, shape: &Shape) -> Result<bool, NyarError> {
+        match shape {
+            Shape::Variant { name, fields } => return self.match_variant(vm, name, *fields),
+            Shape::Instance => return self.match_instance(vm),
+            Shape::Implements => return self.match_implements(vm),
+            _ => {}
+        }
+        let value = vm.pop()?;
+        let matched = match (shape, vm.view(value)?) {
+            (Shape::List { length, rest }, NyarValue::Vector(items)) => {
+                items.len() == *length || *rest && items.len() > *length
+            }
+            (Shape::Object { keys }, NyarValue::Object(object)) => keys.iter().all(|key| object.contains(key)),
+            _ => false,
+        };
+        Ok(matched)
+    }
+    /// 弹出类型与值, 检查值是否为类的实例, 实现了特征的类的实例, 或枚举的变体
+    fn match_instance(&self, vm: &mut VirtualMachine) -> Result<bool, NyarError> {
+        let target = vm.pop()?;
+        let value = vm.pop()?;
+        let class = match vm.view(value)? {
+            NyarValue::Object(object) => object.class(),
+            _ => None,
+        };
+        match vm.view(target)? {
+            NyarValue::Class(_) => match class {
+                Some(class) => Ok(self.class_chain(vm, class)?.iter().any(|c| c.as_any() == target)),
+                None => Ok(false),
+            },
+            NyarValue::Trait(_) => match class {
+                Some(class) => self.implements(vm, class, target),
+                None => Ok(false),
+            },
+            NyarValue::Enum(e) => Ok(matches!(vm.view(value)?, NyarValue::Variant(v) if v.enumeration == e.name)),
+            NyarValue::Structure(s) => Ok(matches!(vm.view(value)?, NyarValue::Struct(v) if v.structure == *s)),
+            v => Err(NyarError::custom(format!("{} is not a class, trait, enum or structure", v.type_name()))),
+        }
+    }
+    /// 弹出特征与值, 检查值是否为实现了特征的类或其实例
+    fn match_implements(&self, vm: &mut VirtualMachine) -> Result<bool, NyarError> {
+        let target = vm.pop()?;
+        let value = vm.pop()?;
+        self.expect_trait(vm, target)?;
+        let class = match vm.view(value)? {
+            NyarValue::Class(_) => Some(value.transmute()),
+            NyarValue::Object(object) => object.class(),
+            _ => None,
+        };
+        match class {
+            Some(class) => self.implements(vm, class, target),
+            None => Ok(false),
+        }
+    }
+    /// 弹出枚举与值, 检查值是否为枚举的变体 `name`, 给出 `fields` 时还要求字段数相同
+    fn match_variant(&self, vm: &mut VirtualMachine, name: &str, fields: Option<usize>) -> Result<bool, NyarError> {
+        let enumeration = vm.pop()?;
+        let value = vm.pop()?;
+        let (enumeration, entry) = match vm.view(enumeration)? {
+            NyarValue::Enum(e) => match e.variant(name) {
+                Some(entry) => (e.name.clone(), entry),
+                None => return Err(NyarError::custom(format!("property `{}` not found", name))),
+            },
+            v => return Err(NyarError::custom(format!("{} is not an enum", v.type_name()))),
+        };
+        match (vm.view(entry)?, vm.view(value)?) {
+            (NyarValue::Variant(_) | NyarValue::Function(_), NyarValue::Variant(v)) => {
+                Ok(v.enumeration == enumeration && v.name == name && fields.is_none_or(|count| v.fields.len() == count))
+            }
+            (NyarValue::Variant(_) | NyarValue::Function(_), _) => Ok(false),
+            _ => self.values.equals(&vm.memory, value, entry),
+        }
+    }
+
+    fn set_property(
+        &self,
+        vm: &mut VirtualMachine,
+        target: Gc<NyarValue>,
+        name: &str,
+        value: Gc<NyarValue>,
+    ) -> Result<(), NyarError> {
+        let value = self.copy(vm, value)?;
+        // 结构体的字段按声明的类型存储值本身
+        if matches!(vm.view(target)?, NyarValue::Struct(_)) {
+            let field = vm.view(value)?.clone();
+            if let NyarValue::Struct(s) = vm.memory.view_mut(target)? {
+                return s.set(name, &field);
+            }
+        }
+        match vm.memory.view_mut(target)? {
+            NyarValue::Object(o) => {
+                o.insert(name, value);
+                Ok(())
+            }
+            v => Err(NyarError::custom(format!("cannot set property `{}` on {}", name, v.type_name()))),
+        }
+    }
+
+    fn set_index(
+        &self,
+        vm: &mut VirtualMachine,
+        target: Gc<NyarValue>,
+        index: usize,
+        value: Gc<NyarValue>,
+    ) -> Result<(), NyarError> {
+        let value = self.copy(vm, value)?;
+        match vm.memory.view_mut(target)? {
+            NyarValue::Vector(v) => match v.set(index, value) {
+                Some(_) => Ok(()),
+                None => Err(NyarError::custom(format!("index {} out of bounds for length {}", index, v.len()))),
+            },
+            v => Err(NyarError::custom(format!("cannot set index on {}", v.type_name()))),
+        }
+    }
+
+    /// 结构体按值传递, 存储与传参时复制一份
+    fn copy(&self, vm: &mut VirtualMachine, value: Gc<NyarValue>) -> Result<Gc<NyarValue>, NyarError> {
+        match vm.view(value)? {
+            NyarValue::Struct(s) => {
+                let copied = NyarValue::Struct(s.clone());
+                Ok(vm.allocate(copied))
+            }
+            _ => Ok(value),
+        }
+    }
+
+    fn index_of(&self, vm: &VirtualMachine, value: Gc<NyarValue>) -> Result<usize, NyarError> {
+        match vm.view(value)? {
+            NyarValue::Integer(i) => usize::try_from(i.as_ref()).map_err(|_| NyarError::custom(format!("invalid index {}", i))),
+            v => Err(NyarError::custom(format!("expect integer index, found {}", v.type_name()))),
+        }
+    }
+
+    fn expect_string(&self, vm: &VirtualMachine, value: Gc<NyarValue>) -> Result<String, NyarError> {
+        match vm.view(value)? {
+            NyarValue::String(s) => Ok(s.to_string()),
+            v => Err(NyarError::custom(format!("expect string, found {}", v.type_name()))),
+        }
+    }
+
+    fn expect_class(&self, vm: &VirtualMachine, value: Gc<NyarValue>) -> Result<Gc<NyarClass>, NyarError> {
+        match vm.view(value)? {
+            NyarValue::Class(_) => Ok(value.transmute()),
+            v => Err(NyarError::custom(format!("{} is not a class", v.type_name()))),
+        }
+    }
+
+    fn expect_class_value<'a>(&self, vm: &'a VirtualMachine, class: Gc<NyarClass>) -> Result<&'a NyarClass, NyarError> {
+        match vm.view(class.as_any())? {
+            NyarValue::Class(c) => Ok(c),
+            v => Err(NyarError::custom(format!("{} is not a class", v.type_name()))),
+        }
+    }
+
+    fn expect_trait<'a>(&self, vm: &'a VirtualMachine, value: Gc<NyarValue>) -> Result<&'a NyarTrait, NyarError> {
+        match vm.view(value)? {
+            NyarValue::Trait(t) => Ok(t),
+            v => Err(NyarError::custom(format!("{} is not a trait", v.type_name()))),
+        }
+    }
+
+    fn expect_function(&self, vm: &VirtualMachine, value: Gc<NyarValue>) -> Result<NyarFunction, NyarError> {
+        match vm.view(value)? {
+            NyarValue::Function(f) => Ok(f.as_ref().clone()),
+            v => Err(NyarError::custom(format!("{} is not callable", v.type_name()))),
+        }
     }
 }
@@ -3,15 +3,20 @@
 mod coroutine;
 mod effect_handler;
 mod environment;
+mod exception;
 mod instruction_executor;
 mod value_handler;
 
 use nyar_error::NyarError;
-use nyar_lir::{Gc, Heap, Instruction, NyarValue};
+use nyar_lir::{
+    Gc, Heap, Instruction, LineEntry, LineTable, NyarValue,
+    values::{NyarClass, NyarObject},
+};
+use std::{collections::HashMap, ops::Range, sync::Arc};
 
 pub use self::{
     coroutine::CoroutineManager, effect_handler::EffectHandler, environment::Environment,
-    instruction_executor::InstructionExecutor, value_handler::ValueHandler,
+    exception::{ExceptionHandler, TryBlock}, instruction_executor::InstructionExecutor, value_handler::ValueHandler,
 };
 
 /// 虚拟机状态
@@ -40,24 +45,356 @@ pub struct VirtualMachine {
     max_stack_depth: usize,
     /// 最大调用深度
     max_call_depth: usize,
+    /// 调用栈, 栈顶为当前执行的帧
+    call_stack: Vec<ExecutionState>,
+    /// 全局作用域
+    globals: Gc<NyarObject>,
+    /// 已注册的效应处理器
+    effects: EffectHandler,
+    /// 已注册的异常处理器
+    exceptions: ExceptionHandler,
+    /// 虚拟机自身产生的错误被捕获时包装为该类的实例
+    runtime_error: Gc<NyarClass>,
+    /// 运行状态
+    state: VmState,
+    /// 程序的返回值
+    result: Option<Gc<NyarValue>>,
+    /// 正在执行的程序的行号表
+    line_table: LineTable,
+}
+
+impl Default for VirtualMachine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl VirtualMachine {
     /// 创建一个新的虚拟机实例
     pub fn new() -> Self {
-        Self { memory: Heap::default(), instruction_pointer: 0, max_stack_depth: 1024, max_call_depth: 128 }
+        let mut memory = Heap::default();
+        let globals = memory.allocate(NyarObject::default()).transmute();
+        memory.add_root(globals);
+        let mut class = NyarClass {
+            name: "RuntimeError".to_string(),
+            parent: None,
+            traits: vec![],
+            methods: HashMap::new(),
+            properties: Default::default(),
+        };
+        class.properties.insert("message".to_string(), memory.allocate(NyarValue::Null));
+        let runtime_error: Gc<NyarClass> = memory.allocate(NyarValue::Class(Box::new(class))).transmute();
+        if let Ok(scope) = globals.as_object(&mut memory) {
+            scope.insert("RuntimeError", runtime_error.as_any());
+        }
+        Self {
+            memory,
+            instruction_pointer: 0,
+            max_stack_depth: 1024,
+            max_call_depth: 128,
+            call_stack: vec![],
+            globals,
+            effects: EffectHandler::default(),
+            exceptions: ExceptionHandler::default(),
+            runtime_error,
+            state: VmState::Initial,
+            result: None,
+            line_table: LineTable::default(),
+        }
+    }
+
+    /// 设置最大栈深度
+    pub fn with_max_stack_depth(mut self, depth: usize) -> Self {
+        self.max_stack_depth = depth;
+        self
+    }
+
+    /// 设置最大调用深度
+    pub fn with_max_call_depth(mut self, depth: usize) -> Self {
+        self.max_call_depth = depth;
+        self
+    }
+
+    /// 堆内存
+    pub fn heap(&self) -> &Heap {
+        &self.memory
+    }
+
+    /// 可变访问堆内存
+    pub fn heap_mut(&mut self) -> &mut Heap {
+        &mut self.memory
+    }
+
+    /// 运行状态
+    pub fn state(&self) -> &VmState {
+        &self.state
+    }
+
+    /// 读取全局变量
+    pub fn get_global(&self, name: &str) -> Option<Gc<NyarValue>> {
+        self.globals.as_object_ref(&self.memory).ok()?.get(name)
+    }
+
+    /// 设置全局变量
+    pub fn set_global(&mut self, name: &str, value: NyarValue) -> Result<(), NyarError> {
+        let value = self.memory.allocate(value);
+        self.globals.as_object(&mut self.memory)?.insert(name, value);
+        Ok(())
+    }
+
+    /// 设置之后执行的程序的行号表, 运行时错误会据此标注源码位置
+    pub fn set_line_table(&mut self, line_table: LineTable) {
+        self.line_table = line_table;
+    }
+
+    /// 当前指令的源码位置, 供调试器与覆盖率工具使用
+    pub fn current_location(&self) -> Option<&LineEntry> {
+        let frame = self.call_stack.last()?;
+        // 指令指针已经指向下一条指令
+        let current = self.instruction_pointer.checked_sub(1)?;
+        self.line_table.lookup(frame.body.start + current)
     }
 
     /// 执行指令序列
     pub fn execute(&mut self, instructions: Vec<Instruction>) -> Result<Gc<NyarValue>, NyarError> {
-        todo!()
+        self.call_stack.clear();
+        self.effects.clear();
+        self.exceptions.clear();
+        self.result = None;
+        self.instruction_pointer = 0;
+        let body = 0..instructions.len();
+        self.call_stack.push(ExecutionState::new(instructions.into(), body, Environment::new(self.globals)));
+        self.state = VmState::Running;
+        let executor = InstructionExecutor::new();
+        while self.state == VmState::Running {
+            if let Err(error) = self.step(&executor).or_else(|error| self.catch(self.locate(error))) {
+                self.call_stack.clear();
+                self.effects.clear();
+                self.exceptions.clear();
+                self.state = VmState::Failed(error.clone());
+                return Err(error);
+            }
+        }
+        match self.result.take() {
+            Some(value) => Ok(value),
+            None => Ok(self.memory.allocate(NyarValue::Null)),
+        }
+    }
+
+    /// 为没有位置信息的错误标注当前指令的源码位置
+    fn locate(&self, error: NyarError) -> NyarError {
+        match self.current_location() {
+            Some(entry) if error.span().is_empty() => error.with_span(entry.span.clone()).with_file(entry.file.clone()),
+            _ => error,
+        }
+    }
+
+    /// 有异常处理器时将虚拟机自身产生的错误包装为 `RuntimeError` 的实例抛出
+    fn catch(&mut self, error: NyarError) -> Result<(), NyarError> {
+        if self.exceptions.is_empty() {
+            return Err(error);
+        }
+        let message = self.allocate(error.kind().message());
+        let mut instance = NyarObject::instance(self.runtime_error);
+        instance.insert("message", message);
+        let value = self.allocate(instance);
+        self.throw(value)
+    }
+
+    /// 抛出值, 展开到最内层异常处理器所在的调用帧并跳转到处理器, 没有处理器时报错
+    pub(crate) fn throw(&mut self, value: Gc<NyarValue>) -> Result<(), NyarError> {
+        let block = match self.exceptions.unwind() {
+            Some(block) => block,
+            None => return Err(NyarError::custom(format!("uncaught exception: {}", self.describe(value)?))),
+        };
+        self.call_stack.truncate(block.depth + 1);
+        self.effects.leave_frame(block.depth + 1);
+        self.instruction_pointer = block.target;
+        self.frame_mut()?.value_stack.truncate(block.stack_depth);
+        self.push(value)
+    }
+
+    /// 未捕获的值的描述, 带有 `message` 属性的对象为该属性的文本形式, 其他值为值本身的文本形式
+    fn describe(&self, value: Gc<NyarValue>) -> Result<String, NyarError> {
+        let message = match self.view(value)? {
+            NyarValue::Object(object) => object.get("message"),
+            _ => None,
+        };
+        ValueHandler::new().format(&self.memory, message.unwrap_or(value))
+    }
+
+    /// 执行一条指令
+    fn step(&mut self, executor: &InstructionExecutor) -> Result<(), NyarError> {
+        let frame = self.frame()?;
+        let code = frame.instructions.clone();
+        match code[frame.body.clone()].get(self.instruction_pointer) {
+            Some(instruction) => {
+                self.instruction_pointer += 1;
+                executor.execute_instruction(self, instruction)
+            }
+            // 指令耗尽时隐式返回
+            None => {
+                let value = match self.frame_mut()?.value_stack.pop() {
+                    Some(value) => value,
+                    None => self.allocate(NyarValue::Null),
+                };
+                self.return_from_frame(value)
+            }
+        }
+    }
+
+    /// 当前调用帧
+    pub(crate) fn frame(&self) -> Result<&ExecutionState, NyarError> {
+        self.call_stack.last().ok_or_else(|| NyarError::custom("no active call frame"))
+    }
+
+    /// 可变访问当前调用帧
+    pub(crate) fn frame_mut(&mut self) -> Result<&mut ExecutionState, NyarError> {
+        self.call_stack.last_mut().ok_or_else(|| NyarError::custom("no active call frame"))
+    }
+
+    /// 在堆上分配值
+    pub(crate) fn allocate(&mut self, value: impl Into<NyarValue>) -> Gc<NyarValue> {
+        self.memory.allocate(value)
+    }
+
+    /// 读取堆上的值
+    pub(crate) fn view(&self, value: Gc<NyarValue>) -> Result<&NyarValue, NyarError> {
+        self.memory.view_ref(value)
+    }
+
+    /// 将值压入当前帧的栈
+    pub(crate) fn push(&mut self, value: Gc<NyarValue>) -> Result<(), NyarError> {
+        let limit = self.max_stack_depth;
+        let stack = &mut self.frame_mut()?.value_stack;
+        if stack.len() >= limit {
+            return Err(NyarError::custom(format!("stack overflow, exceeded maximum stack depth {}", limit)));
+        }
+        stack.push(value);
+        Ok(())
+    }
+
+    /// 分配值并压入当前帧的栈
+    pub(crate) fn push_value(&mut self, value: impl Into<NyarValue>) -> Result<(), NyarError> {
+        let value = self.allocate(value);
+        self.push(value)
+    }
+
+    /// 弹出当前帧的栈顶值
+    pub(crate) fn pop(&mut self) -> Result<Gc<NyarValue>, NyarError> {
+        self.frame_mut()?.value_stack.pop().ok_or_else(|| NyarError::custom("value stack underflow"))
+    }
+
+    /// 弹出当前帧栈顶的 `count` 个值, 按压栈顺序返回
+    pub(crate) fn pop_many(&mut self, count: usize) -> Result<Vec<Gc<NyarValue>>, NyarError> {
+        let stack = &mut self.frame_mut()?.value_stack;
+        if stack.len() < count {
+            return Err(NyarError::custom("value stack underflow"));
+        }
+        Ok(stack.split_off(stack.len() - count))
+    }
+
+    /// 查看当前帧栈顶往下第 `depth` 个值
+    pub(crate) fn peek(&self, depth: usize) -> Result<Gc<NyarValue>, NyarError> {
+        let stack = &self.frame()?.value_stack;
+        match stack.len().checked_sub(depth + 1) {
+            Some(index) => Ok(stack[index]),
+            None => Err(NyarError::custom("value stack underflow")),
+        }
+    }
+
+    /// 相对当前指令跳转
+    pub(crate) fn jump(&mut self, offset: isize) -> Result<(), NyarError> {
+        // 指令指针已经指向下一条指令
+        let current = self.instruction_pointer as isize - 1;
+        match usize::try_from(current + offset) {
+            Ok(target) if target <= self.frame()?.body.len() => {
+                self.instruction_pointer = target;
+                Ok(())
+            }
+            _ => Err(NyarError::custom(format!("jump target {} out of range", current + offset))),
+        }
+    }
+
+    /// 进入新的调用帧
+    pub(crate) fn enter_frame(
+        &mut self,
+        instructions: Arc<[Instruction]>,
+        body: Range<usize>,
+        environment: Environment,
+    ) -> Result<(), NyarError> {
+        if self.call_stack.len() >= self.max_call_depth {
+            return Err(NyarError::custom(format!("call stack overflow, exceeded maximum call depth {}", self.max_call_depth)));
+        }
+        let ip = self.instruction_pointer;
+        self.frame_mut()?.instruction_pointer = ip;
+        self.call_stack.push(ExecutionState::new(instructions, body, environment));
+        self.instruction_pointer = 0;
+        Ok(())
+    }
+
+    /// 以新的调用帧替换当前调用帧, 调用深度不变, 当前帧注册的效应处理器随之移除
+    pub(crate) fn replace_frame(
+        &mut self,
+        instructions: Arc<[Instruction]>,
+        body: Range<usize>,
+        environment: Environment,
+    ) -> Result<(), NyarError> {
+        self.call_stack.pop();
+        self.effects.leave_frame(self.call_stack.len());
+        self.exceptions.leave_frame(self.call_stack.len());
+        self.call_stack.push(ExecutionState::new(instructions, body, environment));
+        self.instruction_pointer = 0;
+        Ok(())
+    }
+
+    /// 从当前调用帧返回, 将返回值交给调用者
+    pub(crate) fn return_from_frame(&mut self, value: Gc<NyarValue>) -> Result<(), NyarError> {
+        self.call_stack.pop();
+        self.effects.leave_frame(self.call_stack.len());
+        self.exceptions.leave_frame(self.call_stack.len());
+        match self.call_stack.last() {
+            Some(caller) => {
+                self.instruction_pointer = caller.instruction_pointer;
+                self.push(value)
+            }
+            None => {
+                self.result = Some(value);
+                self.state = VmState::Completed;
+                Ok(())
+            }
+        }
+    }
+
+    /// 停止执行
+    pub(crate) fn halt(&mut self, value: Gc<NyarValue>) {
+        self.result = Some(value);
+        self.state = VmState::Completed;
+    }
+
+    /// 调用帧深度
+    pub(crate) fn call_depth(&self) -> usize {
+        self.call_stack.len()
     }
 }
 
 /// 执行状态，用于保存和恢复执行上下文
 #[derive(Debug, Clone)]
 pub struct ExecutionState {
+    /// 调用其他函数时保存的指令指针
     instruction_pointer: usize,
-    instructions: Vec<Instruction>,
+    /// 所属程序的指令
+    instructions: Arc<[Instruction]>,
+    /// 正在执行的指令在所属程序中的区间, 指令指针相对于区间起点
+    body: Range<usize>,
+    /// 值栈
     value_stack: Vec<Gc<NyarValue>>,
+    /// 变量环境
+    environment: Environment,
+}
+
+impl ExecutionState {
+    fn new(instructions: Arc<[Instruction]>, body: Range<usize>, environment: Environment) -> Self {
+        Self { instruction_pointer: 0, instructions, body, value_stack: vec![], environment }
+    }
 }
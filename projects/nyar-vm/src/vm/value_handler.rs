@@ -1,10 +1,13 @@
 //! 值处理器模块，负责处理不同类型的值操作
 
-use nyar_lir::{Gc, NyarValue};
 use nyar_error::NyarError;
+use nyar_lir::{
+    BinaryOperator, Gc, Heap, NyarValue,
+    values::{FixedInteger, NumericMethod, NyarVector, StringMethod, find_chars, slice_chars},
+};
 
 /// 值处理器，负责处理不同类型的值操作
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ValueHandler {
     // 值处理器的配置和状态
 }
@@ -14,4 +17,322 @@ impl ValueHandler {
     pub fn new() -> Self {
         Self {}
     }
-}
\ No newline at end of file
+
+    /// 计算二元运算
+    pub fn binary(
+        &self,
+        heap: &Heap,
+        operator: BinaryOperator,
+        lhs: Gc<NyarValue>,
+        rhs: Gc<NyarValue>,
+    ) -> Result<NyarValue, NyarError> {
+        match operator {
+            BinaryOperator::Equal => Ok(NyarValue::Boolean(self.equals(heap, lhs, rhs)?)),
+            BinaryOperator::NotEqual => Ok(NyarValue::Boolean(!self.equals(heap, lhs, rhs)?)),
+            _ => operator.evaluate(heap.view_ref(lhs)?, heap.view_ref(rhs)?),
+        }
+    }
+
+    /// 结构相等, 数组、对象、枚举变体与结构体逐元素比较, 函数等其他引用类型比较地址
+    pub fn equals(&self, heap: &Heap, lhs: Gc<NyarValue>, rhs: Gc<NyarValue>) -> Result<bool, NyarError> {
+        if lhs == rhs {
+            return Ok(true);
+        }
+        match (heap.view_ref(lhs)?, heap.view_ref(rhs)?) {
+            (NyarValue::Vector(a), NyarValue::Vector(b)) => {
+                if a.len() != b.len() {
+                    return Ok(false);
+                }
+                for (x, y) in a.iter().zip(b.iter()) {
+                    if !self.equals(heap, x, y)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            (NyarValue::Object(a), NyarValue::Object(b)) => {
+                if a.len() != b.len() {
+                    return Ok(false);
+                }
+                for (key, x) in a.iter() {
+                    match b.get(key) {
+                        Some(y) if self.equals(heap, x, y)? => {}
+                        _ => return Ok(false),
+                    }
+                }
+                Ok(true)
+            }
+            (NyarValue::Variant(a), NyarValue::Variant(b)) => {
+                if a.enumeration != b.enumeration || a.tag != b.tag || a.fields.len() != b.fields.len() {
+                    return Ok(false);
+                }
+                for ((_, x), (_, y)) in a.fields.iter().zip(b.fields.iter()) {
+                    if !self.equals(heap, *x, *y)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            (NyarValue::Struct(a), NyarValue::Struct(b)) => Ok(a == b),
+            // 整数与浮点数按数值比较
+            (
+                a @ (NyarValue::Integer(_) | NyarValue::Fixed(_) | NyarValue::Float(_)),
+                b @ (NyarValue::Integer(_) | NyarValue::Fixed(_) | NyarValue::Float(_)),
+            ) => {
+                match BinaryOperator::Equal.evaluate(a, b)? {
+                    NyarValue::Boolean(equal) => Ok(equal),
+                    _ => Ok(false),
+                }
+            }
+            (a @ (NyarValue::Null | NyarValue::Boolean(_) | NyarValue::String(_)), b) => Ok(a == b),
+            _ => Ok(false),
+        }
+    }
+
+    /// 调用数值上的内建方法, 接收者不是数值时返回 `None`, `checked_*` 溢出时返回空值
+    pub fn numeric_method(
+        &self,
+        heap: &Heap,
+        name: &str,
+        receiver: Gc<NyarValue>,
+        arguments: &[Gc<NyarValue>],
+    ) -> Result<Option<NyarValue>, NyarError> {
+        let value = heap.view_ref(receiver)?;
+        if !matches!(value, NyarValue::Integer(_) | NyarValue::Fixed(_) | NyarValue::Float(_)) {
+            return Ok(None);
+        }
+        let unknown = || NyarError::custom(format!("{} has no method `{}`", value.type_name(), name));
+        let method = NumericMethod::parse(name).ok_or_else(unknown)?;
+        if arguments.len() != method.arity() {
+            return Err(NyarError::custom(format!(
+                "method `{}` expects {} arguments, found {}",
+                name,
+                method.arity(),
+                arguments.len()
+            )));
+        }
+        let result = match (method, value) {
+            (NumericMethod::Convert(target), _) => target.convert(value)?,
+            (NumericMethod::Truncate(kind), NyarValue::Integer(i)) => FixedInteger::wrapping_from_bigint(kind, i).into(),
+            (NumericMethod::Truncate(kind), NyarValue::Fixed(f)) => FixedInteger::wrapping_from_bigint(kind, &f.to_bigint()).into(),
+            (NumericMethod::Arithmetic(mode, operator), NyarValue::Fixed(lhs)) => {
+                let rhs = match heap.view_ref(arguments[0])? {
+                    NyarValue::Fixed(rhs) => *rhs,
+                    NyarValue::Integer(rhs) => FixedInteger::try_from_bigint(lhs.kind(), rhs)?,
+                    rhs => return Err(NyarError::custom(format!("expect `{}`, found {}", lhs.kind(), rhs.type_name()))),
+                };
+                lhs.arithmetic(operator, &rhs, mode)?.map(NyarValue::Fixed).unwrap_or(NyarValue::Null)
+            }
+            _ => return Err(unknown()),
+        };
+        Ok(Some(result))
+    }
+
+    /// 调用字符串上的内建方法, 接收者不是字符串时返回 `None`
+    pub fn string_method(
+        &self,
+        heap: &mut Heap,
+        name: &str,
+        receiver: Gc<NyarValue>,
+        arguments: &[Gc<NyarValue>],
+    ) -> Result<Option<NyarValue>, NyarError> {
+        let text = match heap.view_ref(receiver)? {
+            NyarValue::String(text) => text.to_string(),
+            _ => return Ok(None),
+        };
+        let method = StringMethod::parse(name).ok_or_else(|| NyarError::custom(format!("string has no method `{}`", name)))?;
+        let arity = method.arity();
+        if !arity.contains(&arguments.len()) {
+            let expected = match arity.start() == arity.end() {
+                true => arity.start().to_string(),
+                false => format!("{} to {}", arity.start(), arity.end()),
+            };
+            return Err(NyarError::custom(format!(
+                "method `{}` expects {} arguments, found {}",
+                name,
+                expected,
+                arguments.len()
+            )));
+        }
+        let string = |index: usize| expect_str(heap, arguments[index]);
+        let result = match method {
+            StringMethod::Length => NyarValue::from(text.chars().count() as i64),
+            StringMethod::ByteLength => NyarValue::from(text.len() as i64),
+            StringMethod::Slice => {
+                let start = expect_index(heap, arguments[0])?;
+                let end = arguments.get(1).map(|end| expect_index(heap, *end)).transpose()?;
+                NyarValue::from(slice_chars(&text, start, end))
+            }
+            StringMethod::Split => {
+                let parts: Vec<String> = match arguments.first() {
+                    None => text.split_whitespace().map(str::to_string).collect(),
+                    Some(_) => match string(0)? {
+                        "" => text.chars().map(String::from).collect(),
+                        separator => text.split(separator).map(str::to_string).collect(),
+                    },
+                };
+                return Ok(Some(string_list(heap, parts)));
+            }
+            StringMethod::Chars => return Ok(Some(string_list(heap, text.chars().map(String::from).collect()))),
+            StringMethod::Join => {
+                let items: Vec<_> = match heap.view_ref(arguments[0])? {
+                    NyarValue::Vector(items) => items.iter().collect(),
+                    v => return Err(NyarError::custom(format!("expect list, found {}", v.type_name()))),
+                };
+                let parts = items.into_iter().map(|item| self.format(heap, item)).collect::<Result<Vec<_>, _>>()?;
+                NyarValue::from(parts.join(&text))
+            }
+            StringMethod::Trim => NyarValue::from(text.trim()),
+            StringMethod::TrimStart => NyarValue::from(text.trim_start()),
+            StringMethod::TrimEnd => NyarValue::from(text.trim_end()),
+            StringMethod::Find => match find_chars(&text, string(0)?) {
+                Some(index) => NyarValue::from(index as i64),
+                None => NyarValue::Null,
+            },
+            StringMethod::Replace => NyarValue::from(text.replace(string(0)?, string(1)?)),
+            StringMethod::Upper => NyarValue::from(text.to_uppercase()),
+            StringMethod::Lower => NyarValue::from(text.to_lowercase()),
+            StringMethod::StartsWith => NyarValue::Boolean(text.starts_with(string(0)?)),
+            StringMethod::EndsWith => NyarValue::Boolean(text.ends_with(string(0)?)),
+            StringMethod::Contains => NyarValue::Boolean(text.contains(string(0)?)),
+            StringMethod::Repeat => match usize::try_from(expect_index(heap, arguments[0])?) {
+                Ok(count) => NyarValue::from(text.repeat(count)),
+                Err(_) => return Err(NyarError::custom("repeat count must not be negative")),
+            },
+        };
+        Ok(Some(result))
+    }
+
+    /// 值的文本形式, 用于字符串插值与 `to_string`: 字符串为其内容, 定宽整数不带类型后缀, 其他值同 [`render`](Self::render)
+    pub fn format(&self, heap: &Heap, value: Gc<NyarValue>) -> Result<String, NyarError> {
+        match heap.view_ref(value)? {
+            NyarValue::String(text) => Ok(text.to_string()),
+            NyarValue::Fixed(f) => Ok(f.value().to_string()),
+            _ => self.render(heap, value),
+        }
+    }
+
+    /// 以源码的形式展示值, 用于断言失败等诊断信息, 嵌套过深的部分以 `...` 代替
+    pub fn render(&self, heap: &Heap, value: Gc<NyarValue>) -> Result<String, NyarError> {
+        self.render_nested(heap, value, 0)
+    }
+    fn render_nested(&self, heap: &Heap, value: Gc<NyarValue>, depth: usize) -> Result<String, NyarError> {
+        if depth > 8 {
+            return Ok("...".to_string());
+        }
+        let join = |items: Vec<String>| items.join(", ");
+        let value = heap.view_ref(value)?;
+        if let Some(text) = render_scalar(value) {
+            return Ok(text);
+        }
+        let text = match value {
+            NyarValue::Vector(v) => {
+                let items = v.iter().map(|item| self.render_nested(heap, item, depth + 1)).collect::<Result<_, _>>()?;
+                format!("[{}]", join(items))
+            }
+            NyarValue::Object(o) => {
+                let fields = o
+                    .iter()
+                    .map(|(key, item)| Ok(format!("{}: {}", key, self.render_nested(heap, item, depth + 1)?)))
+                    .collect::<Result<_, NyarError>>()?;
+                match o.class().map(|class| heap.view_ref(class.as_any())).transpose()? {
+                    Some(NyarValue::Class(class)) => format!("{} {{ {} }}", class.name, join(fields)),
+                    _ => format!("{{ {} }}", join(fields)),
+                }
+            }
+            NyarValue::Variant(v) => {
+                let fields =
+                    v.fields.iter().map(|(_, item)| self.render_nested(heap, *item, depth + 1)).collect::<Result<Vec<_>, _>>()?;
+                match fields.is_empty() {
+                    true => format!("{}.{}", v.enumeration, v.name),
+                    false => format!("{}.{}({})", v.enumeration, v.name, join(fields)),
+                }
+            }
+            NyarValue::Struct(v) => {
+                let fields = v.structure.fields.iter().zip(&v.values);
+                let fields = fields.map(|((name, _), value)| format!("{}: {}", name, render_scalar(&value.decode()).unwrap_or_default()));
+                let fields = fields.collect();
+                format!("{} {{ {} }}", v.structure.name, join(fields))
+            }
+            NyarValue::Function(f) => format!("<function {}>", f.name.as_deref().unwrap_or("<lambda>")),
+            NyarValue::Class(c) => format!("<class {}>", c.name),
+            NyarValue::Trait(t) => format!("<trait {}>", t.name),
+            NyarValue::Enum(e) => format!("<enum {}>", e.name),
+            NyarValue::Structure(s) => format!("<structure {}>", s.name),
+            v => format!("<{}>", v.type_name()),
+        };
+        Ok(text)
+    }
+
+    /// 可迭代值的元素个数
+    pub fn length(&self, heap: &Heap, value: Gc<NyarValue>) -> Result<usize, NyarError> {
+        match heap.view_ref(value)? {
+            NyarValue::Vector(v) => Ok(v.len()),
+            NyarValue::Object(o) => Ok(o.len()),
+            NyarValue::String(s) => Ok(s.chars().count()),
+            NyarValue::Variant(v) => Ok(v.fields.len()),
+            v => Err(NyarError::custom(format!("{} is not iterable", v.type_name()))),
+        }
+    }
+
+    /// 可迭代值的第 `index` 个元素, 对象迭代其键, 字符串迭代其字符, 枚举变体迭代其字段
+    pub fn element(&self, heap: &mut Heap, value: Gc<NyarValue>, index: usize) -> Result<Gc<NyarValue>, NyarError> {
+        let element = match heap.view_ref(value)? {
+            NyarValue::Vector(v) => return v.get(index).ok_or_else(|| out_of_bounds(index, v.len())),
+            NyarValue::Variant(v) => {
+                return v.fields.get(index).map(|(_, value)| *value).ok_or_else(|| out_of_bounds(index, v.fields.len()));
+            }
+            NyarValue::Object(o) => match o.iter().nth(index) {
+                Some((key, _)) => NyarValue::from(key),
+                None => return Err(out_of_bounds(index, o.len())),
+            },
+            NyarValue::String(s) => match s.chars().nth(index) {
+                Some(c) => NyarValue::from(c.to_string()),
+                None => return Err(out_of_bounds(index, s.chars().count())),
+            },
+            v => return Err(NyarError::custom(format!("{} is not indexable", v.type_name()))),
+        };
+        Ok(heap.allocate(element))
+    }
+}
+
+/// 标量值的源码形式, 不是标量时返回 `None`
+fn render_scalar(value: &NyarValue) -> Option<String> {
+    let text = match value {
+        NyarValue::Null => "null".to_string(),
+        NyarValue::Boolean(b) => b.to_string(),
+        NyarValue::Integer(i) => i.to_string(),
+        NyarValue::Fixed(f) => f.to_string(),
+        NyarValue::Float(f) => format!("{:?}", f),
+        NyarValue::String(s) => format!("{:?}", s),
+        _ => return None,
+    };
+    Some(text)
+}
+
+fn expect_str(heap: &Heap, value: Gc<NyarValue>) -> Result<&str, NyarError> {
+    match heap.view_ref(value)? {
+        NyarValue::String(s) => Ok(s.as_str()),
+        v => Err(NyarError::custom(format!("expect string, found {}", v.type_name()))),
+    }
+}
+
+/// 整数下标, 超出 `i64` 范围时报错
+fn expect_index(heap: &Heap, value: Gc<NyarValue>) -> Result<i64, NyarError> {
+    let index = match heap.view_ref(value)? {
+        NyarValue::Integer(i) => i64::try_from(i.as_ref()).ok(),
+        NyarValue::Fixed(f) => i64::try_from(f.value()).ok(),
+        v => return Err(NyarError::custom(format!("expect integer, found {}", v.type_name()))),
+    };
+    index.ok_or_else(|| NyarError::custom("index out of range"))
+}
+
+/// 在堆上分配字符串列表
+fn string_list(heap: &mut Heap, parts: Vec<String>) -> NyarValue {
+    let items = parts.into_iter().map(|part| heap.allocate(NyarValue::from(part))).collect::<Vec<_>>();
+    NyarValue::from(NyarVector::from(items))
+}
+
+fn out_of_bounds(index: usize, length: usize) -> NyarError {
+    NyarError::custom(format!("index {} out of bounds for length {}", index, length))
+}
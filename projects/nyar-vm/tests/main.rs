@@ -1,4 +1,1002 @@
+use nyar_error::{NyarError, NyarErrorKind};
+use nyar_hir::{
+    NyarCompiler,
+    ast::{
+        Assignment, BinaryExpression, CallExpression, EffectExpression, EffectHandlerDefinition, Expression, ExpressionKind,
+        FunctionDefinition, IfStatement, LambdaExpression, Literal, LoopStatement, Parameter, Program, Statement,
+        StatementKind, VariableDeclaration,
+    },
+    cfg::HirModule,
+};
+use nyar_lir::{
+    Instruction, NyarValue, OptimizationLevel,
+    values::{FieldType, FixedInteger, IntegerKind, NyarStruct, NyarStructure},
+};
+use nyar_vm::{TestRunner, VirtualMachine};
+use std::sync::Arc;
+
+fn integer(value: i64) -> Expression {
+    ExpressionKind::Literal(Literal::Integer(value)).into()
+}
+
+fn variable(name: &str) -> Expression {
+    ExpressionKind::Variable(name.to_string()).into()
+}
+
+fn binary(left: Expression, operator: &str, right: Expression) -> Expression {
+    ExpressionKind::Binary(Box::new(BinaryExpression { left, operator: operator.to_string(), right })).into()
+}
+
+fn call(callee: &str, arguments: Vec<Expression>) -> Expression {
+    ExpressionKind::Call(Box::new(CallExpression { callee: variable(callee), arguments })).into()
+}
+
+fn let_(name: &str, value: Expression) -> Statement {
+    StatementKind::VariableDeclaration(VariableDeclaration {
+        name: name.to_string(),
+        type_annotation: None,
+        initializer: Some(value),
+        is_constant: false,
+    })
+    .into()
+}
+
+fn function(name: &str, parameters: &[&str], body: Vec<Statement>) -> Statement {
+    StatementKind::FunctionDeclaration(FunctionDefinition {
+        attributes: vec![],
+        name: name.to_string(),
+        generics: vec![],
+        parameters: parameters.iter().map(|p| Parameter::new(*p)).collect(),
+        return_type: None,
+        body,
+        span: Default::default(),
+    })
+    .into()
+}
+
+fn run(statements: Vec<Statement>) -> NyarValue {
+    let mut program = Program::new();
+    statements.into_iter().for_each(|s| program.add_statement(s));
+    let compiled = NyarCompiler::new().compile(&program).unwrap();
+    assert!(compiled.is_success(), "{:?}", compiled.errors());
+    let mut vm = VirtualMachine::new();
+    let result = vm.execute(compiled.into_bytecode()).unwrap();
+    vm.heap().view_ref(result).unwrap().clone()
+}
+
+/// 在各优化级别下编译运行返回数组的源码, 各级别结果应一致
+fn parse(source: &str) -> Program {
+    valkyrie_parser::ValkyrieParser::new().parse(source).into_result().unwrap()
+}
+
+/// 编译并执行, 返回运行时错误
+fn run_error(source: &str) -> NyarError {
+    let bytecode = NyarCompiler::new().compile(&parse(source)).unwrap().into_bytecode();
+    VirtualMachine::new().execute(bytecode).unwrap_err()
+}
+
+/// 编译并执行, 编译不能报告错误
+fn run_value(source: &str) -> NyarValue {
+    let compiled = NyarCompiler::new().compile(&parse(source)).unwrap();
+    assert!(compiled.is_success(), "{:?}", compiled.errors());
+    let mut vm = VirtualMachine::new();
+    let result = vm.execute(compiled.into_bytecode()).unwrap();
+    vm.heap().view_ref(result).unwrap().clone()
+}
+
+fn run_source(source: &str) -> Vec<NyarValue> {
+    let program = parse(source);
+    let mut results = vec![];
+    for level in [OptimizationLevel::None, OptimizationLevel::Full] {
+        let compiled = NyarCompiler::new().with_optimization_level(level).compile(&program).unwrap();
+        assert!(compiled.is_success(), "{:?}", compiled.errors());
+        let mut vm = VirtualMachine::new();
+        let result = vm.execute(compiled.into_bytecode()).unwrap();
+        let view = |value| vm.heap().view_ref(value).unwrap().clone();
+        match view(result) {
+            NyarValue::Vector(items) => results.push(items.iter().map(view).collect::<Vec<_>>()),
+            other => panic!("{:?}", other),
+        }
+    }
+    assert_eq!(results[0], results[1], "{}", source);
+    results.swap_remove(0)
+}
+
+#[test]
+fn arithmetic() {
+    let value = run(vec![StatementKind::Expression(binary(integer(6), "*", binary(integer(3), "-", integer(1)))).into()]);
+    assert_eq!(value, NyarValue::from(12));
+}
+
+#[test]
+fn recursive_function() {
+    let value = run(vec![
+        function(
+            "fibonacci",
+            &["n"],
+            vec![
+                StatementKind::If(IfStatement {
+                    condition: binary(variable("n"), "<", integer(2)),
+                    then_branch: vec![StatementKind::Return(Some(variable("n"))).into()],
+                    else_branch: None,
+                })
+                .into(),
+                StatementKind::Expression(binary(
+                    call("fibonacci", vec![binary(variable("n"), "-", integer(1))]),
+                    "+",
+                    call("fibonacci", vec![binary(variable("n"), "-", integer(2))]),
+                ))
+                .into(),
+            ],
+        ),
+        StatementKind::Expression(call("fibonacci", vec![integer(10)])).into(),
+    ]);
+    assert_eq!(value, NyarValue::from(55));
+}
+
+#[test]
+fn loops_and_assignment() {
+    let value = run(vec![
+        let_("sum", integer(0)),
+        StatementKind::Loop(LoopStatement::ForEach {
+            variable: "x".to_string(),
+            iterable: ExpressionKind::Literal(Literal::List(vec![integer(1), integer(2), integer(3), integer(4)])).into(),
+            body: vec![
+                StatementKind::If(IfStatement {
+                    condition: binary(variable("x"), "==", integer(4)),
+                    then_branch: vec![StatementKind::Break.into()],
+                    else_branch: None,
+                })
+                .into(),
+                StatementKind::Assignment(Assignment {
+                    target: variable("sum"),
+                    value: binary(variable("sum"), "+", variable("x")),
+                })
+                .into(),
+            ],
+        })
+        .into(),
+        StatementKind::Expression(variable("sum")).into(),
+    ]);
+    assert_eq!(value, NyarValue::from(6));
+}
+
+#[test]
+fn closure_captures_by_value() {
+    let value = run(vec![
+        let_("base", integer(10)),
+        let_(
+            "add",
+            ExpressionKind::Lambda(Box::new(LambdaExpression {
+                parameters: vec!["x".to_string()],
+                body: binary(variable("x"), "+", variable("base")),
+            }))
+            .into(),
+        ),
+        StatementKind::Assignment(Assignment { target: variable("base"), value: integer(100) }).into(),
+        StatementKind::Expression(call("add", vec![integer(1)])).into(),
+    ]);
+    assert_eq!(value, NyarValue::from(11));
+}
+
+#[test]
+fn functions_share_program_code() {
+    let source = "function adder(i) { x => x + i }\n[adder(1), adder(2), adder(3)]";
+    let program = parse(source);
+    let bytecode = NyarCompiler::new().compile(&program).unwrap().into_bytecode();
+    let mut vm = VirtualMachine::new();
+    let result = vm.execute(bytecode).unwrap();
+    let view = |value| vm.heap().view_ref(value).unwrap().clone();
+    let functions = match view(result) {
+        NyarValue::Vector(items) => items.iter().map(view).collect::<Vec<_>>(),
+        other => panic!("{:?}", other),
+    };
+    let [NyarValue::Function(a), NyarValue::Function(b), NyarValue::Function(c)] = functions.as_slice()
+    else {
+        panic!("{:?}", functions)
+    };
+    // 每次调用创建的函数都不复制函数体
+    assert!(Arc::ptr_eq(&a.code, &b.code) && Arc::ptr_eq(&b.code, &c.code));
+    assert_eq!((&a.body, &b.body), (&c.body, &c.body));
+}
+
 #[test]
-fn main() {
-    println!("it works!")
+fn effect_handler() {
+    let value = run(vec![
+        StatementKind::EffectHandler(EffectHandlerDefinition {
+            name: "ask".to_string(),
+            handler: FunctionDefinition {
+                attributes: vec![],
+                name: "ask".to_string(),
+                generics: vec![],
+                parameters: vec![],
+                return_type: None,
+                body: vec![StatementKind::Expression(integer(42)).into()],
+                span: Default::default(),
+            },
+        })
+        .into(),
+        StatementKind::Expression(
+            ExpressionKind::Effect(Box::new(EffectExpression { name: "ask".to_string(), arguments: vec![] })).into(),
+        )
+        .into(),
+    ]);
+    assert_eq!(value, NyarValue::from(42));
+
+    let source = "handle ask() { 21 }\nfunction double() { raise ask * 2 }\ndouble()";
+    assert_eq!(run_value(source), NyarValue::from(42));
+    let source = "function with_ask(f) { handle ask() { 1 }\n f() }\nwith_ask(() => raise ask)";
+    assert_eq!(run_value(source), NyarValue::from(1));
+}
+
+#[test]
+fn call_depth_limit() {
+    let mut program = Program::new();
+    // 尾调用复用调用帧, 非尾部位置的递归才会超出调用深度
+    let body = binary(integer(1), "+", call("forever", vec![]));
+    program.add_statement(function("forever", &[], vec![StatementKind::Expression(body).into()]));
+    program.add_statement(StatementKind::Expression(call("forever", vec![])).into());
+    let compiled = NyarCompiler::new().compile(&program).unwrap();
+    assert!(VirtualMachine::new().execute(compiled.into_bytecode()).is_err());
+}
+
+#[test]
+fn tail_calls_reuse_frames() {
+    let source = r#"
+    function sum(n, total) {
+        if n == 0 { return total }
+        sum(n - 1, total + n)
+    }
+    function is_even(n) {
+        if n == 0 { return true }
+        return is_odd(n - 1)
+    }
+    function is_odd(n) {
+        if n == 0 { return false }
+        is_even(n - 1)
+    }
+    function count(n) {
+        return if n == 0 { 'done' } else { count(n - 1) }
+    }
+    let apply = (f, x) => f(x)
+    [sum(1000, 0), is_even(1001), apply(is_odd, 999), count(100)]
+    "#;
+    let program = parse(source);
+    let bytecode = NyarCompiler::new().compile(&program).unwrap().into_bytecode();
+    assert!(bytecode.iter().any(|instruction| matches!(instruction, Instruction::TailCall { .. })));
+    let mut vm = VirtualMachine::new().with_max_call_depth(16);
+    let result = vm.execute(bytecode).unwrap();
+    let items = match vm.heap().view_ref(result).unwrap() {
+        NyarValue::Vector(items) => items.iter().map(|item| vm.heap().view_ref(item).unwrap().clone()).collect::<Vec<_>>(),
+        other => panic!("expected a vector, found {:?}", other),
+    };
+    let expected = [NyarValue::from(500500), NyarValue::Boolean(false), NyarValue::Boolean(true), NyarValue::from("done")];
+    assert_eq!(items, expected);
+}
+
+#[test]
+fn parse_and_run() {
+    let source = r#"
+    function fibonacci(n) {
+        if n < 2 { return n }
+        fibonacci(n - 1) + fibonacci(n - 2)
+    }
+    let total = 0
+    for (let i = 0; i < 5; i += 1) { total += fibonacci(i) }
+    total
+    "#;
+    assert_eq!(run_value(source), NyarValue::from(7));
+}
+
+#[test]
+fn runtime_error_location() {
+    let source = "function divide(a, b) {\n    a / b\n}\ndivide(1, 0)";
+    let program = valkyrie_parser::ValkyrieParser::new().with_file("main.vk").parse(source).into_result().unwrap();
+    for level in [OptimizationLevel::None, OptimizationLevel::Full] {
+        let mut compiler = NyarCompiler::new().with_optimization_level(level);
+        let (bytecode, line_table) = compiler.compile(&program).unwrap().into_parts();
+        let mut vm = VirtualMachine::new();
+        vm.set_line_table(line_table);
+        let error = vm.execute(bytecode).unwrap_err();
+        assert_eq!(&source[error.span()], "a / b");
+        assert_eq!(error.file().as_str(), "main.vk");
+    }
+}
+
+const PROGRAMS: [&str; 7] = [
+    "function f(n) { if n < 2 { return n } f(n - 1) + f(n - 2) }\nf(10)",
+    "let total = 0\nfor x in [1, 2, 3, 4] { if x == 2 { continue } if x == 4 { break } total = total + x }\ntotal",
+    "let i = 0\nwhile i < 5 { i = i + 1 }\nlet base = 10\nlet add = a => a + base\nadd(i) || 0",
+    "function sum(n) {\n let total = 0\n let i = 0\n while i < n { i = i + 1\n if i == 2 { continue }\n total = total + i }\n total\n}\nsum(4)",
+    "let count = 0\nfunction bump() { count = count + 1 }\nbump()\nbump()\ncount",
+    "let o = { a: 1 }\no.a = 2\nlet v = [1, 2]\nv[1] = o.a + v[0]\nif v[1] > 2 && o.a == 2 { 'yes' } else { 'no' }",
+    "const n = 3 * 4\nconst big = n > 10\nfunction f(m) { if big && m > 0 { return m + n }\n 0 }\nf(1) + n",
+];
+
+fn run_cfg(source: &str, pass: impl Fn(HirModule) -> HirModule) -> NyarValue {
+    let program = parse(source);
+    let module = pass(HirModule::lower(&program).unwrap());
+    let bytecode = module.to_lir().unwrap();
+    let mut vm = VirtualMachine::new();
+    let result = vm.execute(bytecode).unwrap();
+    vm.heap().view_ref(result).unwrap().clone()
+}
+
+#[test]
+fn cfg_matches_compiler() {
+    for source in PROGRAMS {
+        let program = parse(source);
+        let compiled = NyarCompiler::new().compile(&program).unwrap();
+        let mut vm = VirtualMachine::new();
+        let result = vm.execute(compiled.into_bytecode()).unwrap();
+        let expected = vm.heap().view_ref(result).unwrap().clone();
+        assert_eq!(run_cfg(source, |module| module), expected, "{}", source);
+        assert_eq!(run_cfg(source, |module| module.to_ssa()), expected, "{}", source);
+        let optimized = |module: HirModule| module.inline_functions(16).eliminate_tail_calls().to_ssa();
+        assert_eq!(run_cfg(source, optimized), expected, "{}", source);
+    }
+}
+
+#[test]
+fn cfg_tail_calls() {
+    let source = "function sum(n, total) { if n == 0 { return total } sum(n - 1, total + n) }\n\
+                  function swap(a, b, n) { if n == 0 { return a - b } swap(b, a, n - 1) }\n\
+                  function is_even(n) { if n == 0 { return true } return is_odd(n - 1) }\n\
+                  function is_odd(n) { if n == 0 { return false } return is_even(n - 1) }\n\
+                  [sum(1000, 0), swap(1, 2, 101), is_even(1001)]";
+    let program = parse(source);
+    let module = HirModule::lower(&program).unwrap().eliminate_tail_calls();
+    for module in [module.clone(), module.to_ssa()] {
+        let bytecode = module.to_lir().unwrap();
+        assert!(bytecode.iter().any(|instruction| matches!(instruction, Instruction::TailCall { .. })));
+        let mut vm = VirtualMachine::new().with_max_call_depth(16);
+        let result = vm.execute(bytecode).unwrap();
+        let items = match vm.heap().view_ref(result).unwrap() {
+            NyarValue::Vector(items) => items.iter().map(|item| vm.heap().view_ref(item).unwrap().clone()).collect::<Vec<_>>(),
+            other => panic!("expected a vector, found {:?}", other),
+        };
+        assert_eq!(items, vec![NyarValue::from(500500), NyarValue::from(1), NyarValue::Boolean(false)]);
+    }
+}
+
+#[test]
+fn optimizer_preserves_results() {
+    let tail_calls =
+        "function count(n) { if n == 0 { return 'done' }\n count(n - 1) }\nfunction f() { return count(100)\n 1 }\nf()";
+    for source in PROGRAMS.iter().copied().chain([tail_calls]) {
+        let program = parse(source);
+        let mut sizes = vec![];
+        let mut results = vec![];
+        for level in [OptimizationLevel::None, OptimizationLevel::Basic, OptimizationLevel::Full] {
+            let compiled = NyarCompiler::new().with_optimization_level(level).compile(&program).unwrap();
+            sizes.push(compiled.bytecode().len());
+            let mut vm = VirtualMachine::new();
+            let result = vm.execute(compiled.into_bytecode()).unwrap();
+            results.push(vm.heap().view_ref(result).unwrap().clone());
+        }
+        // `Full` 内联函数, 指令数可能增加
+        assert!(sizes[1] <= sizes[0], "{}: {:?}", source, sizes);
+        assert_eq!(results[1], results[0], "{}", source);
+        assert_eq!(results[2], results[0], "{}", source);
+    }
+}
+
+#[test]
+fn cfg_inlining() {
+    use nyar_hir::cfg::{HirFunction, HirInstruction, Operand, Rvalue, Variable};
+    let source = "function double(x) { x * 2 }\nlet offset = 1\nfunction shift(x) { double(x) + offset }\n\
+                  let square = x => x * x\nfunction f(n) { if n < 2 { return n }\n f(n - 1) + f(n - 2) }\n\
+                  let base = 10\nlet add = a => a + base\nfunction g(offset) { shift(offset) }\n\
+                  shift(3) + square(4) + f(6) + add(1) + g(5)";
+    let calls = |function: &HirFunction| -> Vec<String> {
+        let instructions = function.blocks.iter().flat_map(|block| &block.instructions);
+        instructions
+            .filter_map(|instruction| match instruction {
+                HirInstruction::Declare { value: Rvalue::Call { callee, .. }, .. }
+                | HirInstruction::Assign { value: Rvalue::Call { callee, .. }, .. } => match callee {
+                    Operand::Variable(Variable::Local(name) | Variable::Global(name)) => Some(name.clone()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    };
+    let program = parse(source);
+    let module = HirModule::lower(&program).unwrap();
+    let inlined = module.inline_functions(8);
+    assert_eq!(calls(inlined.entry().unwrap()), vec!["f", "add"]);
+    // `g` 的参数遮蔽了 `shift` 读取的外层变量
+    assert_eq!(calls(inlined.function("g").unwrap()), vec!["shift"]);
+    for module in [module.clone(), inlined.clone(), inlined.to_ssa()] {
+        let mut vm = VirtualMachine::new();
+        let result = vm.execute(module.to_lir().unwrap()).unwrap();
+        assert_eq!(vm.heap().view_ref(result).unwrap(), &NyarValue::from(53));
+    }
+    // 编译器在 `Full` 级别下内联, 调用指令减少且结果不变
+    let mut counts = vec![];
+    for level in [OptimizationLevel::None, OptimizationLevel::Full] {
+        let compiled = NyarCompiler::new().with_optimization_level(level).compile(&program).unwrap();
+        counts.push(compiled.bytecode().iter().filter(|i| matches!(i, Instruction::Call { .. })).count());
+        let mut vm = VirtualMachine::new();
+        let result = vm.execute(compiled.into_bytecode()).unwrap();
+        assert_eq!(vm.heap().view_ref(result).unwrap(), &NyarValue::from(53));
+    }
+    assert!(counts[1] < counts[0], "{:?}", counts);
+}
+
+#[test]
+fn annotated_code_runs() {
+    let source =
+        "function add(a: Integer, b: Integer) -> Integer { a + b }\nlet xs: List<Integer> = [add(1, 2), 3]\nxs[0] + xs[1]";
+    assert_eq!(run_value(source), NyarValue::from(6));
+}
+
+#[test]
+fn pattern_matching() {
+    let source = r#"
+enum Color { Red, Green, Blue }
+function describe(value) {
+    match value {
+        0 => "zero",
+        "hi" => "greeting",
+        [x] => "one",
+        [x, y, ..] if x == y => "pair",
+        [first, ..] => first,
+        { kind: "circle", radius } => radius * 2,
+        _ => "other",
+    }
+}
+function name(c) {
+    match c {
+        Color.Red => "red"
+        Color.Green => "green"
+        Color.Blue => "blue"
+    }
+}
+let f = match 7 { n => () => n }
+[describe(0), describe("hi"), describe([5]), describe([3, 3, 9]), describe([4, 1]), describe({ kind: "circle", radius: 5 }), describe(true), name(Color.Blue), f()]
+"#;
+    let expected: Vec<NyarValue> = vec![
+        "zero".into(),
+        "greeting".into(),
+        "one".into(),
+        "pair".into(),
+        4.into(),
+        10.into(),
+        "other".into(),
+        "blue".into(),
+        7.into(),
+    ];
+    assert_eq!(run_source(source), expected);
+
+    let error = run_error("match 3 { 1 => 0 }");
+    assert!(error.to_string().contains("no match arm for 3"), "{}", error);
+}
+
+#[test]
+fn algebraic_data_types() {
+    let source = r#"
+enum Option<T> { Some(T), None }
+enum Shape { Circle(int), Rect { width: int, height: int }, Empty }
+enum Color { Red, Green = 5 }
+function unwrap_or(option, default) {
+    match option {
+        Option.Some(x) => x
+        Option.None => default
+    }
+}
+function area(shape) {
+    match shape {
+        Shape.Circle(r) => 3 * r * r
+        Shape.Rect { width, height } => width * height
+        Shape.Empty => 0
+    }
+}
+let rect = Shape.Rect(3, 4)
+[
+    unwrap_or(Option.Some(7), 0), unwrap_or(Option.None, 1),
+    area(Shape.Circle(2)), area(rect), area(Shape.Empty),
+    rect.width, rect.tag, rect.variant, Option.Some(9)[0],
+    Option.Some([1, 2]) == Option.Some([1, 2]), Option.Some(1) == Option.Some(2), Option.None == Option.None,
+    Option.Some(1) == Shape.Circle(1), Color.Green, match Color.Green { Color.Green => true, _ => false },
+]
+"#;
+    let expected: Vec<NyarValue> = vec![
+        7.into(),
+        1.into(),
+        12.into(),
+        12.into(),
+        0.into(),
+        3.into(),
+        1.into(),
+        "Rect".into(),
+        9.into(),
+        true.into(),
+        false.into(),
+        true.into(),
+        false.into(),
+        5.into(),
+        true.into(),
+    ];
+    assert_eq!(run_source(source), expected);
+    // 字段数不同的位置模式不匹配, 编译时也会报告
+    let source = "enum E { A(int, int) }\nmatch E.A(1, 2) { E.A(x) => x, _ => 0 }";
+    let compiled = NyarCompiler::new().compile(&parse(source)).unwrap();
+    assert_eq!(compiled.errors().len(), 1, "{:?}", compiled.errors());
+    let mut vm = VirtualMachine::new();
+    let result = vm.execute(compiled.into_bytecode()).unwrap();
+    assert_eq!(vm.heap().view_ref(result).unwrap(), &NyarValue::from(0));
+    let error = run_error("enum E { A(int, int) }\nE.A(1)");
+    assert!(error.to_string().contains("expects 2 arguments, found 1"), "{}", error);
+}
+
+#[test]
+fn classes_and_methods() {
+    let source = r#"
+class Shape {
+    name = "shape"
+    function area() { 0 }
+    function describe() { [self.name, self.area()] }
+}
+class Rect extends Shape {
+    name = "rect"
+    width: int = 1
+    height: int = 1
+    function area() { self.width * self.height }
+    function width_of(self) { self.width }
+}
+class Square extends Rect {
+    function area() { super.area() + 1 }
+    function scaled(k) { new Square { width: self.width * k, height: self.height * k } }
+}
+let r = new Rect { width: 3, height: 4 }
+let s = new Square { width: 2, height: 2 }
+let area = s.area
+let width_of = r.width_of
+[r.area(), s.area(), s.scaled(3).area(), r.describe()[0], r.describe()[1], s.describe()[0], s.describe()[1],
+ (new Shape).describe()[0], (new Shape).describe()[1], r.width_of(), (new Rect).height,
+ match s { Rect { width } => width, _ => 0 }, match r { Square { width } => width, _ => 0 }, area(), width_of()]
+"#;
+    let items = run_source(source);
+    assert_eq!(items[..3], [NyarValue::from(12), NyarValue::from(5), NyarValue::from(37)]);
+    assert_eq!(items[3..5], [NyarValue::from("rect"), NyarValue::from(12)]);
+    assert_eq!(items[5..7], [NyarValue::from("rect"), NyarValue::from(5)]);
+    assert_eq!(items[7..9], [NyarValue::from("shape"), NyarValue::from(0)]);
+    assert_eq!(items[9..13], [NyarValue::from(3), NyarValue::from(1), NyarValue::from(2), NyarValue::from(0)]);
+    // 从实例取出的方法绑定到实例
+    assert_eq!(items[13..], [NyarValue::from(5), NyarValue::from(3)]);
+
+    let error = run_error("class A { x = 1 }\nnew A { y: 2 }");
+    assert!(error.to_string().contains("class `A` has no property `y`"), "{}", error);
+
+    let source = "class A { function f() { super.f() } }\n1";
+    let compiled = NyarCompiler::new().compile(&parse(source)).unwrap();
+    let [error] = compiled.errors()
+    else {
+        panic!("{:?}", compiled.errors())
+    };
+    assert!(error.to_string().contains("`super` can only be used in methods of a class with a parent class"), "{}", error);
+    assert_eq!(&source[error.span()], "super");
+}
+
+#[test]
+fn traits_and_dispatch() {
+    let source = r#"
+trait Display {
+    function show(self)
+    function banner() { "[" + self.show() + "]" }
+}
+trait Area { function area() }
+class Point implements Display {
+    x = 0
+    function show(self) { "point" }
+}
+class Labeled extends Point {
+    function banner() { "labeled" }
+}
+enum Color { Red, Green }
+let p = new Point
+let l = new Labeled
+let shows = [Display.show(p), Display.show(l)]
+[p.banner(), l.banner(), shows[0], shows[1], Display.banner(l), p is Point, l is Point, p is Labeled, l is Display, p is Area,
+ Color.Red is Color, p is Color, Point implements Display, l implements Display, Point implements Area]
+"#;
+    let items = run_source(source);
+    assert_eq!(items[..2], [NyarValue::from("[point]"), NyarValue::from("labeled")]);
+    assert_eq!(items[2..4], [NyarValue::from("point"), NyarValue::from("point")]);
+    let checks: Vec<_> = [true, true, false, true, false, true, false, true, true, false].map(NyarValue::Boolean).into();
+    assert_eq!(items[4], NyarValue::from("labeled"));
+    assert_eq!(items[5..], checks[..]);
+
+    for (source, message) in [
+        ("trait T { function f() }\nclass A implements T {}", "class `A` does not implement `T.f`"),
+        (
+            "trait T { function f(x) }\nclass A implements T { function f() { 1 } }",
+            "`A.f` expects 0 arguments, but `T.f` expects 1",
+        ),
+        ("trait T { function f() }\nclass A {}\nT.f(new A)", "class `A` does not implement `T`"),
+    ] {
+        let error = run_error(source);
+        assert!(error.to_string().contains(message), "{}", error);
+    }
+}
+
+#[test]
+fn structures() {
+    let source = r#"
+structure UTF8View {
+    offset: u32,
+    length: u32
+    scale: f64
+}
+function moved(view: UTF8View) {
+    view.offset = view.offset + 10
+    view.offset
+}
+let a = new UTF8View { offset: 1, length: 5 }
+let b = a
+b.length = 7
+let c = new UTF8View { offset: 1, length: 5 }
+let inner = moved(a)
+a.scale = 3
+[a.offset, a.length, b.length, inner, a == c, a == b, a is UTF8View, a.scale * 2 == 6, a.scale / 2]
+"#;
+    let fixed = |kind: IntegerKind, value: i64| NyarValue::Fixed(FixedInteger::try_from_bigint(kind, &value.into()).unwrap());
+    let expected = [
+        fixed(IntegerKind::U32, 1),
+        fixed(IntegerKind::U32, 5),
+        fixed(IntegerKind::U32, 7),
+        fixed(IntegerKind::U32, 11),
+        NyarValue::Boolean(false),
+        NyarValue::Boolean(false),
+        NyarValue::Boolean(true),
+        NyarValue::Boolean(true),
+        NyarValue::Float(1.5),
+    ];
+    assert_eq!(run_source(source), expected);
+
+    // 存入变量的是结构体的副本, 优化不能让之后的写入落到未存入的值上
+    let source = "structure S { a: u8 }\nlet a = new S { a: 1 }\nlet b = a\nb.a = 7\n[a.a * 100 + b.a]";
+    assert_eq!(run_source(source), [fixed(IntegerKind::U8, 107)]);
+    // 传入构造器的结构体同样复制
+    let source = "structure S { a: u8 }\nclass C { s = null }\n\
+                  let s = new S { a: 1 }\nlet c = new C { s: s }\nc.s.a = 7\n[s.a, c.s.a]";
+    assert_eq!(run_source(source), [fixed(IntegerKind::U8, 1), fixed(IntegerKind::U8, 7)]);
+
+    let layout = NyarStructure {
+        name: "Header".to_string(),
+        fields: vec![
+            ("tag".to_string(), FieldType::Integer(IntegerKind::U8)),
+            ("size".to_string(), FieldType::Integer(IntegerKind::U32)),
+            ("id".to_string(), FieldType::Integer(IntegerKind::I32)),
+        ],
+    };
+    assert_eq!((layout.offsets(), layout.size()), (vec![0, 4, 8], 12));
+    let mut header = NyarStruct::zeroed(Arc::new(layout));
+    header.set("tag", &NyarValue::from(7)).unwrap();
+    header.set("id", &NyarValue::from(-2)).unwrap();
+    assert_eq!(header.to_bytes(), [7, 0, 0, 0, 0, 0, 0, 0, 0xFE, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(header.get("id").unwrap(), fixed(IntegerKind::I32, -2));
+    let error = header.set("tag", &NyarValue::from(256)).unwrap_err();
+    assert_eq!(error.kind().to_string(), "Conversion error: 256 cannot be represented as `u8`");
+
+    for (source, message) in [
+        ("structure P { x: u8 }\nnew P { x: 300 }", "Conversion error: 300 cannot be represented as `u8`"),
+        ("structure P { x: u8 }\nnew P { y: 1 }", "structure `P` has no field `y`"),
+        ("structure P { x: f64 }\nlet p = new P\np.x = \"a\"", "expect `f64`, found string"),
+    ] {
+        let error = run_error(source);
+        assert!(error.kind().to_string().contains(message), "{}", error);
+    }
+}
+
+#[test]
+fn fixed_width_integers() {
+    let source = r#"
+let big = 300
+let byte = big.wrapping_to_u8()
+let max = 127.to_i8()
+let small = 200.to_u8()
+[
+    byte,
+    max.wrapping_add(1),
+    max.checked_add(1),
+    max.saturating_add(100),
+    small.saturating_sub(250),
+    small.checked_mul(2),
+    small + 55,
+    small / 3,
+    -max,
+    small == 200,
+    small.to_int() * 10,
+    small.to_f64() / 16,
+]
+"#;
+    let fixed = |kind: IntegerKind, value: i64| NyarValue::Fixed(FixedInteger::try_from_bigint(kind, &value.into()).unwrap());
+    let expected = [
+        fixed(IntegerKind::U8, 44),
+        fixed(IntegerKind::I8, -128),
+        NyarValue::Null,
+        fixed(IntegerKind::I8, 127),
+        fixed(IntegerKind::U8, 0),
+        NyarValue::Null,
+        fixed(IntegerKind::U8, 255),
+        fixed(IntegerKind::U8, 66),
+        fixed(IntegerKind::I8, -127),
+        NyarValue::Boolean(true),
+        NyarValue::from(2000),
+        NyarValue::Float(12.5),
+    ];
+    assert_eq!(run_source(source), expected);
+
+    for (source, message) in [
+        ("200.to_u8() + 56", "Overflow error: `200 + 56` overflows `u8`"),
+        ("127.to_i8() * 2", "Overflow error: `127 * 2` overflows `i8`"),
+        ("(-1).to_u32()", "Conversion error: -1 cannot be represented as `u32`"),
+        ("1.to_u8() + 1.to_i8()", "operator `+` cannot be applied to u8 and i8"),
+        ("1.to_u8() + 300", "Conversion error: 300 cannot be represented as `u8`"),
+        ("1.to_u8().wrapping_shl(1)", "u8 has no method `wrapping_shl`"),
+    ] {
+        let error = run_error(source);
+        assert!(error.kind().to_string().contains(message), "{}", error);
+    }
+}
+
+#[test]
+fn exceptions() {
+    let source = r#"
+class NotFound { path = "" }
+class Denied { }
+let trace = ""
+function divide(a, b) { a / b }
+function read(path) {
+    if path == "missing" { throw new NotFound { path: path } }
+    if path == "secret" { throw new Denied }
+    if path == "zero" { return divide(1, 0) }
+    path
+}
+function attempt(path) {
+    try {
+        return read(path)
+    }
+    catch (e: NotFound) {
+        return "not found: " + e.path
+    }
+    catch (e: RuntimeError) {
+        return e.message
+    }
+    finally {
+        trace = trace + path + ";"
+    }
+}
+function guarded(path) {
+    try {
+        return attempt(path)
+    }
+    catch e {
+        return "uncaught " + path
+    }
+}
+let count = 0
+for i in [1, 2, 3, 4] {
+    try {
+        if i == 2 { continue }
+        if i == 4 { break }
+        count = count + 10
+    }
+    finally {
+        count = count + 1
+    }
+}
+let results = [attempt("a"), attempt("missing"), attempt("zero"), guarded("secret")]
+[results[0], results[1], results[2], results[3], trace, count]
+"#;
+    let items = run_source(source);
+    let expected = ["a", "not found: missing", "division by zero", "uncaught secret"].map(NyarValue::from);
+    assert_eq!(items[..4], expected);
+    assert_eq!(items[4..], [NyarValue::from("a;missing;zero;secret;"), NyarValue::from(24)]);
+
+    for (source, message) in [
+        ("throw \"boom\"", "uncaught exception: boom"),
+        ("class E { }\ntry { throw 1 } catch (e: E) { 2 }", "uncaught exception: 1"),
+        ("try { throw [1, \"a\"] } catch (e: RuntimeError) { 2 }", "uncaught exception: [1, \"a\"]"),
+        ("let zero = 0\ntry { 1 / zero } catch e { throw e }", "uncaught exception: division by zero"),
+    ] {
+        let error = run_error(source);
+        assert_eq!(error.kind().to_string(), format!("Custom error: {}", message));
+    }
+
+    // 编译期求值出错的运算照常生成, 在运行时抛出
+    let source = "function f() { try { return 1 / 0 } catch e { return 'caught' } }\nf()";
+    let program = parse(source);
+    let compiled = NyarCompiler::new().compile(&program).unwrap();
+    assert_eq!(compiled.errors().len(), 1);
+    let mut vm = VirtualMachine::new();
+    let result = vm.execute(compiled.into_bytecode()).unwrap();
+    assert_eq!(vm.heap().view_ref(result).unwrap(), &NyarValue::from("caught"));
+    let error = run_error("\"a\" + 1");
+    assert!(error.to_string().contains("cannot be applied to string and bigint"), "{}", error);
+}
+
+#[test]
+fn assertions() {
+    for (source, expected) in [
+        ("let a = 1\nassert a + 1 == 3", "Assertion failed: `a + 1 == 3`\n  left: 2\n right: 3"),
+        ("let xs = [1, \"two\"]\nassert xs != [1, \"two\"]", "Assertion failed: `xs != [1, \"two\"]`\n  left: [1, \"two\"]\n right: [1, \"two\"]"),
+        ("let ok = false\nassert ok", "Assertion failed: `ok`"),
+    ] {
+        let program = parse(source);
+        for level in [OptimizationLevel::None, OptimizationLevel::Full] {
+            let bytecode = NyarCompiler::new().with_optimization_level(level).compile(&program).unwrap().into_bytecode();
+            let error = VirtualMachine::new().execute(bytecode).unwrap_err();
+            assert!(matches!(error.kind(), NyarErrorKind::Assertion { .. }), "{}", error);
+            assert_eq!(error.kind().to_string(), expected);
+        }
+    }
+
+    let source = r#"
+function double(x) { x * 2 }
+#test
+function doubles() {
+    assert double(2) == 4
+}
+#test
+function breaks() {
+    assert double(3) == 7
+}
+#test
+function throws() {
+    throw "oops"
+}
+function helper() { assert false }
+"#;
+    let program = parse(source);
+    let report = TestRunner::new().run(&program).unwrap();
+    let names: Vec<_> = report.results.iter().map(|result| (result.name.as_str(), result.is_passed())).collect();
+    assert_eq!(names, [("doubles", true), ("breaks", false), ("throws", false)]);
+    assert_eq!((report.passed(), report.failed(), report.is_success()), (1, 2, false));
+    let text = report.to_string();
+    assert!(text.contains("test doubles ... ok\ntest breaks ... FAILED"), "{}", text);
+    assert!(text.contains("`double(3) == 7`\n  left: 6\n right: 7"), "{}", text);
+    assert!(text.ends_with("test result: FAILED. 1 passed; 2 failed"), "{}", text);
+}
+
+#[test]
+fn modules() {
+    let resolver = valkyrie_parser::MemoryResolver::new()
+        .with_module("math.core", "let hidden = 10\nexport function square(x) { x * x }\nexport let offset = hidden + 1")
+        .with_module("math.extra", "import math.core.{square}\nexport function quad(x) { square(square(x)) }")
+        .with_module("a", "import b\nexport let x = 1")
+        .with_module("b", "function f() { import c }\nexport let y = 2")
+        .with_module("c", "import a\nexport let z = 3");
+    let source = r#"
+import math.core
+import math.extra.*
+import math.core.{offset} as shift
+function load() {
+    import math.core as m
+    m.square(5)
+}
+[core.square(3), quad(2), shift, load()]
+"#;
+    let program = valkyrie_parser::ValkyrieParser::new().with_file("main.vk").parse(source).into_result().unwrap();
+    for level in [OptimizationLevel::None, OptimizationLevel::Full] {
+        let compiled = NyarCompiler::new().with_optimization_level(level).with_resolver(resolver.clone()).compile(&program).unwrap();
+        assert!(compiled.is_success(), "{:?}", compiled.errors());
+        // 导入多次的模块只编译一次
+        let initializers = compiled.bytecode().iter().filter(|i| matches!(i, Instruction::CreateFunction { name: Some(name), .. } if name == "math.core"));
+        assert_eq!(initializers.count(), 1);
+        let mut vm = VirtualMachine::new();
+        let result = vm.execute(compiled.into_bytecode()).unwrap();
+        let view = |value| vm.heap().view_ref(value).unwrap().clone();
+        let items = match view(result) {
+            NyarValue::Vector(items) => items.iter().map(view).collect::<Vec<_>>(),
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(items, [9, 16, 11, 25].map(NyarValue::from));
+    }
+
+    for (source, message) in [
+        ("import math.core.{hidden}", "module `math.core` does not export `hidden`"),
+        ("import missing", "cannot find module `missing`"),
+        ("import a", "import cycle: a -> b -> c -> a"),
+    ] {
+        let program = valkyrie_parser::ValkyrieParser::new().with_file("main.vk").parse(source).into_result().unwrap();
+        let compiled = NyarCompiler::new().with_resolver(resolver.clone()).compile(&program).unwrap();
+        let error = &compiled.errors()[0];
+        assert_eq!(error.kind().to_string(), format!("Custom error: {}", message));
+    }
+    let program = parse("import math.core\ncore.hidden");
+    let bytecode = NyarCompiler::new().with_resolver(resolver).compile(&program).unwrap().into_bytecode();
+    assert!(VirtualMachine::new().execute(bytecode).is_err());
+
+    // 模块中的错误报告模块自己的文件与区间
+    let divide = "export function divide(a, b) {\n    a / b\n}\nexport let wrong: int = true";
+    let resolver = valkyrie_parser::MemoryResolver::new().with_module("divide", divide);
+    let program = valkyrie_parser::ValkyrieParser::new().with_file("main.vk").parse("import divide.{divide}\ndivide(1, 0)");
+    let compiled = NyarCompiler::new().with_resolver(resolver).compile(&program.into_result().unwrap()).unwrap();
+    let error = &compiled.errors()[0];
+    assert_eq!((error.file().as_str(), &divide[error.span()]), ("divide", "true"));
+    let (bytecode, line_table) = compiled.into_parts();
+    let mut vm = VirtualMachine::new();
+    vm.set_line_table(line_table);
+    let error = vm.execute(bytecode).unwrap_err();
+    assert_eq!((error.file().as_str(), &divide[error.span()]), ("divide", "a / b"));
+}
+
+#[test]
+fn strings() {
+    let source = r#"
+class Point {
+    x = 0
+    y = 0
+    function to_string() { "(${self.x}, ${self.y})" }
+}
+let name = "wörld"
+let words = "  a  b c ".split()
+[
+    "hello ${name}!",
+    "${1 + 2} = ${if true { "three" } else { "?" }}, ${[1, "a", null]} ${10.to_f64() / 4} ${new Point { x: 1, y: 2 }}",
+    "nested ${"inner ${name.to_upper()}"} \${name} \u{4e2d}\t$",
+    "${name}",
+    name.length(),
+    name.byte_length(),
+    name.slice(1, 3),
+    name.slice(-2),
+    name.slice(3, 100),
+    words.to_string(),
+    "-".join(words),
+    "a,b,,c".split(",").to_string(),
+    "ab".split("").to_string(),
+    "  padded\n".trim(),
+    "  padded ".trim_start(),
+    name.find("r"),
+    name.find("z"),
+    "a-b-c".replace("-", "+"),
+    "MiXeD".to_lower(),
+    name.starts_with("wö"),
+    name.ends_with("x"),
+    name.contains("rl"),
+    "ab".repeat(3),
+    name.chars().to_string(),
+    3.to_string() + true.to_string() + null.to_string(),
+]
+"#;
+    let expected = [
+        NyarValue::from("hello wörld!"),
+        NyarValue::from("3 = three, [1, \"a\", null] 2.5 (1, 2)"),
+        NyarValue::from("nested inner WÖRLD ${name} 中\t$"),
+        NyarValue::from("wörld"),
+        NyarValue::from(5),
+        NyarValue::from(6),
+        NyarValue::from("ör"),
+        NyarValue::from("ld"),
+        NyarValue::from("ld"),
+        NyarValue::from(r#"["a", "b", "c"]"#),
+        NyarValue::from("a-b-c"),
+        NyarValue::from(r#"["a", "b", "", "c"]"#),
+        NyarValue::from(r#"["a", "b"]"#),
+        NyarValue::from("padded"),
+        NyarValue::from("padded "),
+        NyarValue::from(2),
+        NyarValue::Null,
+        NyarValue::from("a+b+c"),
+        NyarValue::from("mixed"),
+        NyarValue::Boolean(true),
+        NyarValue::Boolean(false),
+        NyarValue::Boolean(true),
+        NyarValue::from("ababab"),
+        NyarValue::from(r#"["w", "ö", "r", "l", "d"]"#),
+        NyarValue::from("3truenull"),
+    ];
+    assert_eq!(run_source(source), expected);
+
+    for (source, message) in [
+        ("'abc'.slice()", "method `slice` expects 1 to 2 arguments, found 0"),
+        ("'abc'.repeat(-1)", "repeat count must not be negative"),
+        ("'abc'.reverse()", "string has no method `reverse`"),
+    ] {
+        let error = run_error(source);
+        assert!(error.kind().to_string().contains(message), "{}", error);
+    }
 }
@@ -0,0 +1,268 @@
+//! 词法分析模块，将源码切分为带位置信息的记号
+
+use nyar_error::NyarError;
+use std::ops::Range;
+
+/// 符号, 按长度降序排列以便最长匹配
+const PUNCTUATIONS: &[&str] = &[
+    "=>", "->", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=", "*=", "/=", "%=", "+", "-", "*", "/", "%", "=", "<", ">", "!",
+    "(", ")", "{", "}", "[", "]", ",", ";", ":", "..", ".", "#", "@",
+];
+
+/// 记号类型
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    /// 标识符或关键字
+    Identifier(String),
+    /// 整数字面量, 保留原始数字
+    Integer(String),
+    /// 字符串字面量, 已处理转义
+    String(String),
+    /// 含有 `${...}` 插值的字符串字面量
+    Template(Vec<TemplatePart>),
+    /// 符号
+    Punctuation(&'static str),
+    /// 输入结束
+    End,
+}
+
+/// 插值字符串的组成部分
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplatePart {
+    /// 原样输出的文本, 已处理转义
+    Text(String),
+    /// `${` 与 `}` 之间的记号, 以位于 `}` 的 [`TokenKind::End`] 结尾
+    Code(Vec<Token>),
+}
+
+/// 记号
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    /// 记号类型
+    pub kind: TokenKind,
+    /// 源码区间
+    pub span: Range<usize>,
+    /// 记号之前是否有换行
+    pub newline_before: bool,
+}
+
+/// 词法分析器
+pub struct Lexer<'s> {
+    source: &'s str,
+    offset: usize,
+    newline: bool,
+    errors: Vec<NyarError>,
+}
+
+impl<'s> Lexer<'s> {
+    /// 创建词法分析器
+    pub fn new(source: &'s str) -> Self {
+        Self { source, offset: 0, newline: true, errors: vec![] }
+    }
+
+    /// 切分全部记号, 总以 [`TokenKind::End`] 结尾
+    pub fn tokenize(mut self) -> (Vec<Token>, Vec<NyarError>) {
+        let mut tokens = vec![];
+        loop {
+            let token = self.token();
+            let end = token.kind == TokenKind::End;
+            tokens.push(token);
+            if end {
+                break;
+            }
+        }
+        (tokens, self.errors)
+    }
+
+    /// 切分下一个记号, 输入结束时为 [`TokenKind::End`]
+    fn token(&mut self) -> Token {
+        loop {
+            self.skip_trivia();
+            let start = self.offset;
+            let newline_before = std::mem::take(&mut self.newline);
+            let kind = match self.peek() {
+                None => TokenKind::End,
+                Some(c) if c.is_ascii_digit() => self.number(),
+                Some(c) if c == '_' || c.is_alphabetic() => self.identifier(),
+                Some(q @ ('"' | '\'')) => self.string(q),
+                Some(c) => match PUNCTUATIONS.iter().find(|p| self.rest().starts_with(**p)) {
+                    Some(p) => {
+                        self.offset += p.len();
+                        TokenKind::Punctuation(p)
+                    }
+                    None => {
+                        self.offset += c.len_utf8();
+                        self.errors.push(NyarError::syntax_error(format!("unexpected character `{}`", c), start..self.offset));
+                        continue;
+                    }
+                },
+            };
+            return Token { kind, span: start..self.offset, newline_before };
+        }
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.source[self.offset..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+
+    /// 跳过空白与注释
+    fn skip_trivia(&mut self) {
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("//") {
+                let end = rest.find('\n').unwrap_or(rest.len());
+                self.offset += end;
+            }
+            else if let Some(body) = rest.strip_prefix("/*") {
+                let start = self.offset;
+                match body.find("*/") {
+                    Some(end) => {
+                        self.newline |= rest[..end + 2].contains('\n');
+                        self.offset += end + 4;
+                    }
+                    None => {
+                        self.offset = self.source.len();
+                        self.errors.push(NyarError::syntax_error("unterminated block comment", start..self.offset));
+                    }
+                }
+            }
+            else {
+                match self.peek() {
+                    Some('\n') => {
+                        self.newline = true;
+                        self.offset += 1;
+                    }
+                    Some(c) if c.is_whitespace() => self.offset += c.len_utf8(),
+                    _ => break,
+                }
+            }
+        }
+    }
+
+    fn number(&mut self) -> TokenKind {
+        let mut digits = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '0'..='9' => digits.push(c),
+                '_' => {}
+                _ => break,
+            }
+            self.offset += 1;
+        }
+        TokenKind::Integer(digits)
+    }
+
+    fn identifier(&mut self) -> TokenKind {
+        let start = self.offset;
+        while let Some(c) = self.peek() {
+            if c == '_' || c.is_alphanumeric() {
+                self.offset += c.len_utf8();
+            }
+            else {
+                break;
+            }
+        }
+        TokenKind::Identifier(self.source[start..self.offset].to_string())
+    }
+
+    fn string(&mut self, quote: char) -> TokenKind {
+        let start = self.offset;
+        self.bump();
+        let mut text = String::new();
+        let mut parts = vec![];
+        loop {
+            match self.bump() {
+                None => {
+                    self.errors.push(NyarError::syntax_error("unterminated string literal", start..self.offset));
+                    break;
+                }
+                Some(c) if c == quote => break,
+                Some('$') if self.peek() == Some('{') => {
+                    self.offset += 1;
+                    if !text.is_empty() {
+                        parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(TemplatePart::Code(self.interpolation(start)));
+                }
+                Some('\\') => {
+                    let escape_start = self.offset - 1;
+                    match self.escape() {
+                        Some(c) => text.push(c),
+                        None => self.errors.push(NyarError::syntax_error("invalid escape sequence", escape_start..self.offset)),
+                    }
+                }
+                Some(c) => text.push(c),
+            }
+        }
+        if parts.is_empty() {
+            return TokenKind::String(text);
+        }
+        if !text.is_empty() {
+            parts.push(TemplatePart::Text(text));
+        }
+        TokenKind::Template(parts)
+    }
+
+    /// 切分 `${` 之后直到配对的 `}` 的记号, `}` 处记为 [`TokenKind::End`]
+    fn interpolation(&mut self, start: usize) -> Vec<Token> {
+        let newline = std::mem::take(&mut self.newline);
+        let mut tokens = vec![];
+        let mut depth = 0usize;
+        loop {
+            let mut token = self.token();
+            match token.kind {
+                TokenKind::Punctuation("{") => depth += 1,
+                TokenKind::Punctuation("}") if depth > 0 => depth -= 1,
+                TokenKind::Punctuation("}") => {
+                    token.kind = TokenKind::End;
+                    tokens.push(token);
+                    break;
+                }
+                TokenKind::End => {
+                    self.errors.push(NyarError::syntax_error("unterminated string interpolation", start..self.offset));
+                    tokens.push(token);
+                    break;
+                }
+                _ => {}
+            }
+            tokens.push(token);
+        }
+        self.newline = newline;
+        tokens
+    }
+
+    /// 处理反斜杠之后的转义序列
+    fn escape(&mut self) -> Option<char> {
+        let c = match self.bump()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '\'' => '\'',
+            '$' => '$',
+            'u' => {
+                if self.bump()? != '{' {
+                    return None;
+                }
+                let end = self.rest().find('}')?;
+                let code = u32::from_str_radix(&self.rest()[..end], 16).ok()?;
+                self.offset += end + 1;
+                char::from_u32(code)?
+            }
+            _ => return None,
+        };
+        Some(c)
+    }
+}
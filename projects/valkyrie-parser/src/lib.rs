@@ -0,0 +1,77 @@
+//! Valkyrie 语言的语法分析器
+//!
+//! 将 Valkyrie 源码解析为 [`nyar_hir::ast::Program`], 每个节点都带有源码区间。
+//! 语法错误以 [`NyarError`] 报告, 分析器会跳过出错的语句继续解析, 一次报告尽可能多的错误。
+
+mod lexer;
+mod modules;
+mod parser;
+mod printer;
+
+pub use crate::{
+    modules::{FileResolver, MemoryResolver},
+    printer::ValkyriePrinter,
+};
+use crate::{lexer::Lexer, parser::Parser};
+use nyar_error::{ArcStr, NyarError};
+use nyar_hir::ast::Program;
+
+/// Valkyrie 语法分析器
+#[derive(Debug, Default)]
+pub struct ValkyrieParser {
+    file: ArcStr,
+}
+
+/// 解析结果
+#[derive(Debug)]
+pub struct ValkyrieParsed {
+    program: Program,
+    errors: Vec<NyarError>,
+}
+
+impl ValkyrieParser {
+    /// 创建一个新的语法分析器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置源文件, 会记录在程序与错误中
+    pub fn with_file(mut self, file: impl Into<ArcStr>) -> Self {
+        self.file = file.into();
+        self
+    }
+
+    /// 解析源码
+    pub fn parse(&self, source: &str) -> ValkyrieParsed {
+        let (tokens, errors) = Lexer::new(source).tokenize();
+        let mut errors: Vec<NyarError> = errors.into_iter().map(|e| e.with_file(self.file.clone())).collect();
+        let mut parser = Parser::new(tokens, self.file.clone());
+        let mut program = parser.parse_program();
+        program.source = source.into();
+        errors.extend(parser.into_errors());
+        errors.sort_by_key(|e| e.span().start);
+        ValkyrieParsed { program, errors }
+    }
+}
+
+impl ValkyrieParsed {
+    /// 解析得到的程序, 出错的语句已被跳过
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+    /// 语法错误
+    pub fn errors(&self) -> &[NyarError] {
+        &self.errors
+    }
+    /// 是否没有任何错误
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+    }
+    /// 没有错误时返回程序, 否则返回第一个错误
+    pub fn into_result(self) -> nyar_error::Result<Program> {
+        match self.errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(self.program),
+        }
+    }
+}
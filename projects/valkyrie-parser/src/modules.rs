@@ -0,0 +1,92 @@
+//! 模块解析器, 供编译器加载 `import` 的模块
+
+use crate::ValkyrieParser;
+use nyar_error::{NyarError, Result};
+use nyar_hir::{ModuleResolver, ast::Program};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// 从文件系统加载模块
+///
+/// `import a.b` 映射为 `root/a/b.vk`, 含路径分隔符或以扩展名结尾的字符串路径相对于导入者所在的目录。
+/// 模块以规范化后的绝对路径为标识。
+#[derive(Debug, Clone)]
+pub struct FileResolver {
+    root: PathBuf,
+    extension: String,
+}
+
+/// 从内存中的源码加载模块, 以导入路径为标识
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+    modules: BTreeMap<String, String>,
+}
+
+impl FileResolver {
+    /// 以 `root` 为根目录解析模块
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into(), extension: "vk".to_string() }
+    }
+
+    /// 设置模块文件的扩展名, 默认为 `vk`
+    pub fn with_extension(mut self, extension: impl Into<String>) -> Self {
+        self.extension = extension.into();
+        self
+    }
+
+    fn is_file_path(&self, path: &str) -> bool {
+        path.contains(['/', '\\']) || Path::new(path).extension().is_some_and(|extension| extension == self.extension.as_str())
+    }
+}
+
+impl ModuleResolver for FileResolver {
+    fn resolve(&self, path: &str, importer: &str) -> Result<String> {
+        let file = match self.is_file_path(path) {
+            true => match Path::new(importer).parent() {
+                Some(directory) if !importer.is_empty() => directory.join(path),
+                _ => self.root.join(path),
+            },
+            false => self.root.join(path.replace('.', "/")).with_extension(&self.extension),
+        };
+        match file.canonicalize() {
+            Ok(file) => Ok(file.to_string_lossy().into_owned()),
+            Err(e) => Err(NyarError::custom(format!("cannot find module `{}` at `{}`: {}", path, file.display(), e))),
+        }
+    }
+
+    fn load(&self, module: &str) -> Result<Program> {
+        match std::fs::read_to_string(module) {
+            Ok(source) => ValkyrieParser::new().with_file(module).parse(&source).into_result(),
+            Err(e) => Err(NyarError::custom(format!("cannot read module `{}`: {}", module, e))),
+        }
+    }
+}
+
+impl MemoryResolver {
+    /// 创建空的解析器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加模块源码
+    pub fn with_module(mut self, path: impl Into<String>, source: impl Into<String>) -> Self {
+        self.modules.insert(path.into(), source.into());
+        self
+    }
+}
+
+impl ModuleResolver for MemoryResolver {
+    fn resolve(&self, path: &str, _: &str) -> Result<String> {
+        match self.modules.contains_key(path) {
+            true => Ok(path.to_string()),
+            false => Err(NyarError::custom(format!("cannot find module `{}`", path))),
+        }
+    }
+
+    fn load(&self, module: &str) -> Result<Program> {
+        let source = self.modules.get(module).ok_or_else(|| NyarError::custom(format!("cannot find module `{}`", module)))?;
+        ValkyrieParser::new().with_file(module).parse(source).into_result()
+    }
+}
@@ -0,0 +1,281 @@
+use super::*;
+use nyar_hir::ast::{
+    Attribute, ClassDefinition, EffectHandlerDefinition, EnumDefinition, EnumVariant, FunctionDefinition, Parameter,
+    PropertyDefinition, Statement, StatementKind, StructureDefinition, StructureField, TraitDefinition, TraitMethod,
+    VariantFields,
+};
+
+impl Parser {
+    /// 解析 `#name(args)` 修饰的函数或类声明
+    pub(crate) fn parse_annotated(&mut self) -> Result<Statement> {
+        let start = self.start();
+        let attributes = self.parse_attributes()?;
+        let kind = if self.check_keyword("class") {
+            StatementKind::ClassDeclaration(self.parse_class(attributes)?)
+        }
+        else if self.check_keyword("function") || self.check_keyword("def") {
+            StatementKind::FunctionDeclaration(self.parse_function(attributes)?)
+        }
+        else {
+            return Err(self.unexpected("`function` or `class` after attribute"));
+        };
+        Ok(Statement::new(kind, self.span_from(start)))
+    }
+
+    fn parse_attributes(&mut self) -> Result<Vec<Attribute>> {
+        let mut attributes = vec![];
+        while self.check("#") {
+            let start = self.start();
+            self.advance();
+            let name = self.expect_identifier()?;
+            let arguments = if self.check("(") && !self.peek().newline_before { self.parse_arguments()? } else { vec![] };
+            attributes.push(Attribute { name, arguments, span: self.span_from(start) });
+        }
+        Ok(attributes)
+    }
+
+    /// `function name(a: T, b) -> R { body }`
+    pub(crate) fn parse_function(&mut self, attributes: Vec<Attribute>) -> Result<FunctionDefinition> {
+        let start = self.start();
+        if !self.eat_keyword("def") {
+            self.expect_keyword("function")?;
+        }
+        let name = self.expect_identifier()?;
+        let generics = self.parse_generics()?;
+        let parameters = self.parse_parameters()?;
+        let return_type = self.parse_return_type()?;
+        let body = self.parse_block()?;
+        Ok(FunctionDefinition { attributes, name, generics, parameters, return_type, body, span: self.span_from(start) })
+    }
+
+    /// `<T, U>`, 没有泛型参数时为空
+    fn parse_generics(&mut self) -> Result<Vec<String>> {
+        let mut generics = vec![];
+        if !self.eat("<") {
+            return Ok(generics);
+        }
+        while !self.check(">") {
+            generics.push(self.expect_identifier()?);
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(">")?;
+        Ok(generics)
+    }
+
+    fn parse_parameters(&mut self) -> Result<Vec<Parameter>> {
+        self.expect("(")?;
+        let mut parameters = vec![];
+        while !self.check(")") {
+            let start = self.start();
+            let name = self.expect_identifier()?;
+            let type_annotation = if self.eat(":") { Some(self.parse_type()?) } else { None };
+            parameters.push(Parameter { name, type_annotation, span: self.span_from(start) });
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(")")?;
+        Ok(parameters)
+    }
+
+    fn parse_return_type(&mut self) -> Result<Option<String>> {
+        match self.eat("->") || self.eat(":") {
+            true => Ok(Some(self.parse_type()?)),
+            false => Ok(None),
+        }
+    }
+
+    /// `class A extends B implements T1, T2 { fields; methods }`
+    pub(crate) fn parse_class(&mut self, attributes: Vec<Attribute>) -> Result<ClassDefinition> {
+        let start = self.start();
+        self.expect_keyword("class")?;
+        let name = self.expect_identifier()?;
+        let generics = self.parse_generics()?;
+        let parent = if self.eat_keyword("extends") { Some(self.parse_type()?) } else { None };
+        let mut traits = vec![];
+        if self.eat_keyword("implements") {
+            loop {
+                traits.push(self.parse_type()?);
+                if !self.eat(",") {
+                    break;
+                }
+            }
+        }
+        self.expect("{")?;
+        let mut class = ClassDefinition {
+            attributes,
+            name,
+            generics,
+            parent,
+            traits,
+            properties: vec![],
+            methods: vec![],
+            span: Span::default(),
+        };
+        while !self.check("}") && !self.at_end() {
+            let start = self.position;
+            if let Err(error) = self.parse_class_member(&mut class) {
+                self.report(error);
+                self.synchronize(start);
+            }
+        }
+        self.expect("}")?;
+        class.span = self.span_from(start);
+        Ok(class)
+    }
+
+    fn parse_class_member(&mut self, class: &mut ClassDefinition) -> Result<()> {
+        let attributes = self.parse_attributes()?;
+        // 可见性修饰暂不影响语义
+        if !self.eat_keyword("public") {
+            self.eat_keyword("private");
+        }
+        if self.check_keyword("function") || self.check_keyword("def") {
+            class.methods.push(self.parse_function(attributes)?);
+            return Ok(());
+        }
+        let start = self.start();
+        let name = self.expect_identifier()?;
+        let type_annotation = if self.eat(":") { Some(self.parse_type()?) } else { None };
+        let default = if self.eat("=") { Some(self.parse_expression()?) } else { None };
+        if !self.eat(";") && !self.eat(",") && !self.at_statement_end() {
+            return Err(self.unexpected("`;`"));
+        }
+        class.properties.push(PropertyDefinition { name, type_annotation, default, span: self.span_from(start) });
+        Ok(())
+    }
+
+    /// `trait T { function m(a); function n() { ... } }`
+    pub(crate) fn parse_trait(&mut self) -> Result<TraitDefinition> {
+        let start = self.start();
+        self.expect_keyword("trait")?;
+        let name = self.expect_identifier()?;
+        self.expect("{")?;
+        let mut methods = vec![];
+        while !self.check("}") && !self.at_end() {
+            let start = self.position;
+            match self.parse_trait_method() {
+                Ok(method) => methods.push(method),
+                Err(error) => {
+                    self.report(error);
+                    self.synchronize(start);
+                }
+            }
+        }
+        self.expect("}")?;
+        Ok(TraitDefinition { name, methods, span: self.span_from(start) })
+    }
+
+    fn parse_trait_method(&mut self) -> Result<TraitMethod> {
+        let start = self.start();
+        if !self.eat_keyword("def") {
+            self.expect_keyword("function")?;
+        }
+        let name = self.expect_identifier()?;
+        let parameters = self.parse_parameters()?;
+        let return_type = self.parse_return_type()?;
+        let body = match self.check("{") {
+            true => Some(self.parse_block()?),
+            false => {
+                self.eat(";");
+                None
+            }
+        };
+        Ok(TraitMethod { name, parameters, return_type, body, span: self.span_from(start) })
+    }
+
+    /// `enum E<T> { A, B = 5, C(T), D { x: int } }`
+    pub(crate) fn parse_enum(&mut self) -> Result<EnumDefinition> {
+        let start = self.start();
+        self.expect_keyword("enum")?;
+        let name = self.expect_identifier()?;
+        let generics = self.parse_generics()?;
+        self.expect("{")?;
+        let mut variants = vec![];
+        while !self.check("}") {
+            let start = self.start();
+            let name = self.expect_identifier()?;
+            let fields = self.parse_variant_fields()?;
+            let value = if self.eat("=") { Some(self.parse_expression()?) } else { None };
+            variants.push(EnumVariant { name, fields, value, span: self.span_from(start) });
+            if !self.eat(",") && !self.eat(";") {
+                break;
+            }
+        }
+        self.expect("}")?;
+        Ok(EnumDefinition { name, generics, variants, span: self.span_from(start) })
+    }
+
+    /// `structure S { offset: u32, length: u32 }`, 字段以逗号、分号或换行分隔
+    pub(crate) fn parse_structure(&mut self) -> Result<StructureDefinition> {
+        let start = self.start();
+        self.expect_keyword("structure")?;
+        let name = self.expect_identifier()?;
+        self.expect("{")?;
+        let mut fields = vec![];
+        while !self.check("}") {
+            let start = self.start();
+            let name = self.expect_identifier()?;
+            self.expect(":")?;
+            let field_type = self.parse_type()?;
+            fields.push(StructureField { name, field_type, span: self.span_from(start) });
+            if !self.eat(",") && !self.eat(";") && !self.at_statement_end() {
+                break;
+            }
+        }
+        self.expect("}")?;
+        Ok(StructureDefinition { name, fields, span: self.span_from(start) })
+    }
+
+    /// `(T, U)` 或 `{ x: T, y }`, 没有字段时为 [`VariantFields::Unit`]
+    fn parse_variant_fields(&mut self) -> Result<VariantFields> {
+        if self.eat("(") {
+            let mut types = vec![];
+            while !self.check(")") {
+                types.push(self.parse_type()?);
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            self.expect(")")?;
+            return Ok(VariantFields::Positional(types));
+        }
+        if !self.eat("{") {
+            return Ok(VariantFields::Unit);
+        }
+        let mut fields = vec![];
+        while !self.check("}") {
+            let start = self.start();
+            let name = self.expect_identifier()?;
+            let type_annotation = if self.eat(":") { Some(self.parse_type()?) } else { None };
+            fields.push(Parameter { name, type_annotation, span: self.span_from(start) });
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect("}")?;
+        Ok(VariantFields::Named(fields))
+    }
+
+    /// `handle name(params) { body }`
+    pub(crate) fn parse_handler(&mut self) -> Result<EffectHandlerDefinition> {
+        let start = self.start();
+        self.expect_keyword("handle")?;
+        let name = self.expect_identifier()?;
+        let parameters = self.parse_parameters()?;
+        let return_type = self.parse_return_type()?;
+        let body = self.parse_block()?;
+        let handler = FunctionDefinition {
+            attributes: vec![],
+            name: name.clone(),
+            generics: vec![],
+            parameters,
+            return_type,
+            body,
+            span: self.span_from(start),
+        };
+        Ok(EffectHandlerDefinition { name, handler })
+    }
+}
@@ -0,0 +1,300 @@
+use super::*;
+use crate::lexer::TemplatePart;
+use indexmap::IndexMap;
+use nyar_hir::ast::{
+    BinaryExpression, CallExpression, ConditionalExpression, EffectExpression, Expression, ExpressionKind,
+    IndexAccessExpression, InterpolationExpression, InterpolationPart, LambdaExpression, Literal, MemberAccessExpression,
+    NewExpression, UnaryExpression,
+};
+
+/// 二元运算符的优先级, 数值越大结合越紧
+pub(crate) fn binary_precedence(operator: &str) -> Option<u8> {
+    let precedence = match operator {
+        "||" => 1,
+        "&&" => 2,
+        "==" | "!=" => 3,
+        "<" | "<=" | ">" | ">=" | "is" | "implements" => 4,
+        "+" | "-" => 5,
+        "*" | "/" | "%" => 6,
+        _ => return None,
+    };
+    Some(precedence)
+}
+
+impl Parser {
+    /// 解析表达式
+    pub(crate) fn parse_expression(&mut self) -> Result<Expression> {
+        self.parse_binary(0)
+    }
+
+    fn parse_binary(&mut self, minimum: u8) -> Result<Expression> {
+        let start = self.start();
+        let mut left = self.parse_unary()?;
+        while let Some((operator, precedence)) = self.peek_binary(minimum) {
+            self.advance();
+            let right = self.parse_binary(precedence)?;
+            let kind = ExpressionKind::Binary(Box::new(BinaryExpression { left, operator: operator.to_string(), right }));
+            left = Expression::new(kind, self.span_from(start));
+        }
+        Ok(left)
+    }
+
+    /// 当前记号是否为优先级高于 `minimum` 的二元运算符
+    fn peek_binary(&self, minimum: u8) -> Option<(&'static str, u8)> {
+        match &self.peek().kind {
+            TokenKind::Punctuation(p) => binary_precedence(p).filter(|precedence| *precedence > minimum).map(|x| (*p, x)),
+            // 换行之后的 `is` 不延续上一行的表达式
+            TokenKind::Identifier(name) if !self.peek().newline_before => {
+                let operator = ["is", "implements"].into_iter().find(|operator| operator == name)?;
+                binary_precedence(operator).filter(|precedence| *precedence > minimum).map(|x| (operator, x))
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression> {
+        let start = self.start();
+        for operator in ["!", "-", "+"] {
+            if self.eat(operator) {
+                let operand = self.parse_unary()?;
+                let kind = ExpressionKind::Unary(Box::new(UnaryExpression { operator: operator.to_string(), operand }));
+                return Ok(Expression::new(kind, self.span_from(start)));
+            }
+        }
+        let primary = self.parse_primary()?;
+        self.parse_postfix(start, primary)
+    }
+
+    fn parse_postfix(&mut self, start: usize, mut expression: Expression) -> Result<Expression> {
+        loop {
+            // 换行之后的括号开始新的语句, 而不是调用
+            let kind = if self.check("(") && !self.peek().newline_before {
+                let arguments = self.parse_arguments()?;
+                ExpressionKind::Call(Box::new(CallExpression { callee: expression, arguments }))
+            }
+            else if self.check("[") && !self.peek().newline_before {
+                self.advance();
+                let index = self.parse_expression()?;
+                self.expect("]")?;
+                ExpressionKind::IndexAccess(Box::new(IndexAccessExpression { object: expression, index }))
+            }
+            else if self.eat(".") {
+                let member = self.expect_identifier()?;
+                ExpressionKind::MemberAccess(Box::new(MemberAccessExpression { object: expression, member }))
+            }
+            else {
+                return Ok(expression);
+            };
+            expression = Expression::new(kind, self.span_from(start));
+        }
+    }
+
+    /// 解析括号包围的参数列表
+    pub(crate) fn parse_arguments(&mut self) -> Result<Vec<Expression>> {
+        // 括号内的 `=>` 总是 lambda
+        let in_guard = std::mem::replace(&mut self.in_guard, false);
+        let arguments = self.parse_argument_list();
+        self.in_guard = in_guard;
+        arguments
+    }
+    fn parse_argument_list(&mut self) -> Result<Vec<Expression>> {
+        self.expect("(")?;
+        let mut arguments = vec![];
+        while !self.check(")") {
+            arguments.push(self.parse_expression()?);
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(")")?;
+        Ok(arguments)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression> {
+        let start = self.start();
+        let token = self.peek().clone();
+        let kind = match token.kind {
+            TokenKind::Integer(digits) => {
+                self.advance();
+                match digits.parse::<i64>() {
+                    Ok(value) => ExpressionKind::Literal(Literal::Integer(value)),
+                    Err(_) => return Err(self.error(format!("integer literal `{}` is too large", digits), token.span)),
+                }
+            }
+            TokenKind::String(text) => {
+                self.advance();
+                ExpressionKind::Literal(Literal::String(text))
+            }
+            TokenKind::Template(parts) => {
+                self.advance();
+                let parts = parts.into_iter().map(|part| self.parse_template_part(part));
+                ExpressionKind::Interpolation(Box::new(InterpolationExpression { parts: parts.collect::<Result<_>>()? }))
+            }
+            TokenKind::Identifier(name) => match name.as_str() {
+                "true" | "false" => {
+                    self.advance();
+                    ExpressionKind::Literal(Literal::Boolean(name == "true"))
+                }
+                "null" => {
+                    self.advance();
+                    ExpressionKind::Literal(Literal::Null)
+                }
+                "if" => return self.parse_conditional(),
+                "match" => return self.parse_match(),
+                "new" => return self.parse_new(),
+                "raise" => {
+                    self.advance();
+                    let name = self.expect_identifier()?;
+                    let arguments = if self.check("(") { self.parse_arguments()? } else { vec![] };
+                    ExpressionKind::Effect(Box::new(EffectExpression { name, arguments }))
+                }
+                _ if !self.in_guard && self.check_at(1, "=>") => {
+                    let parameter = self.expect_identifier()?;
+                    return self.parse_lambda_body(start, vec![parameter]);
+                }
+                _ => ExpressionKind::Variable(self.expect_identifier()?),
+            },
+            TokenKind::Punctuation("(") if !self.in_guard && self.is_lambda() => {
+                self.advance();
+                let mut parameters = vec![];
+                while !self.check(")") {
+                    parameters.push(self.expect_identifier()?);
+                    if !self.eat(",") {
+                        break;
+                    }
+                }
+                self.expect(")")?;
+                return self.parse_lambda_body(start, parameters);
+            }
+            TokenKind::Punctuation("(") => {
+                self.advance();
+                let inner = self.parse_expression()?;
+                self.expect(")")?;
+                // 括号只影响结合, 区间包含括号本身
+                return Ok(Expression::new(inner.kind, self.span_from(start)));
+            }
+            TokenKind::Punctuation("[") => {
+                self.advance();
+                let mut items = vec![];
+                while !self.check("]") {
+                    items.push(self.parse_expression()?);
+                    if !self.eat(",") {
+                        break;
+                    }
+                }
+                self.expect("]")?;
+                ExpressionKind::Literal(Literal::List(items))
+            }
+            TokenKind::Punctuation("{") => {
+                self.advance();
+                let mut properties = IndexMap::new();
+                while !self.check("}") {
+                    let key = match &self.peek().kind {
+                        TokenKind::String(text) => {
+                            let text = text.clone();
+                            self.advance();
+                            text
+                        }
+                        _ => self.expect_identifier()?,
+                    };
+                    self.expect(":")?;
+                    properties.insert(key, self.parse_expression()?);
+                    if !self.eat(",") {
+                        break;
+                    }
+                }
+                self.expect("}")?;
+                ExpressionKind::Literal(Literal::Object(properties))
+            }
+            _ => return Err(self.unexpected("expression")),
+        };
+        Ok(Expression::new(kind, self.span_from(start)))
+    }
+
+    /// 当前的左括号是否开始一个 `(a, b) => body` 形式的 lambda
+    fn is_lambda(&self) -> bool {
+        let mut depth = 0usize;
+        let mut n = 0;
+        loop {
+            match &self.peek_at(n).kind {
+                TokenKind::Punctuation("(") => depth += 1,
+                TokenKind::Punctuation(")") => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return self.check_at(n + 1, "=>");
+                    }
+                }
+                TokenKind::End => return false,
+                _ => {}
+            }
+            n += 1;
+        }
+    }
+
+    fn parse_lambda_body(&mut self, start: usize, parameters: Vec<String>) -> Result<Expression> {
+        self.expect("=>")?;
+        let body = self.parse_expression()?;
+        let kind = ExpressionKind::Lambda(Box::new(LambdaExpression { parameters, body }));
+        Ok(Expression::new(kind, self.span_from(start)))
+    }
+
+    /// 解析 `new Point { y: 1 }` 形式的实例化, 属性以逗号或换行分隔, 省略花括号时全部属性取默认值
+    fn parse_new(&mut self) -> Result<Expression> {
+        let start = self.start();
+        self.expect_keyword("new")?;
+        let class = self.parse_type()?;
+        let mut properties = vec![];
+        if self.check("{") && !self.peek().newline_before {
+            self.advance();
+            while !self.check("}") {
+                let key = self.expect_identifier()?;
+                self.expect(":")?;
+                properties.push((key, self.parse_expression()?));
+                if !self.eat(",") && !self.peek().newline_before {
+                    break;
+                }
+            }
+            self.expect("}")?;
+        }
+        let kind = ExpressionKind::New(Box::new(NewExpression { class, properties }));
+        Ok(Expression::new(kind, self.span_from(start)))
+    }
+
+    /// 解析 `if c { a } else { b }` 形式的条件表达式
+    fn parse_conditional(&mut self) -> Result<Expression> {
+        let start = self.start();
+        self.expect_keyword("if")?;
+        let condition = self.parse_expression()?;
+        let then_branch = self.parse_expression_block()?;
+        let else_branch = match self.eat_keyword("else") {
+            true if self.check_keyword("if") => Some(self.parse_conditional()?),
+            true => Some(self.parse_expression_block()?),
+            false => None,
+        };
+        let kind = ExpressionKind::Conditional(Box::new(ConditionalExpression { condition, then_branch, else_branch }));
+        Ok(Expression::new(kind, self.span_from(start)))
+    }
+
+    /// 插值部分的记号由独立的分析器解析, 必须恰好是一个表达式
+    fn parse_template_part(&mut self, part: TemplatePart) -> Result<InterpolationPart> {
+        let tokens = match part {
+            TemplatePart::Text(text) => return Ok(InterpolationPart::Text(text)),
+            TemplatePart::Code(tokens) => tokens,
+        };
+        let mut parser = Parser::new(tokens, self.file.clone());
+        let expression = match parser.parse_expression() {
+            Ok(_) if !parser.at_end() => Err(parser.unexpected("`}`")),
+            result => result,
+        };
+        self.errors.append(&mut parser.errors);
+        expression.map(InterpolationPart::Expression)
+    }
+
+    fn parse_expression_block(&mut self) -> Result<Expression> {
+        self.expect("{")?;
+        let expression = self.parse_expression()?;
+        self.eat(";");
+        self.expect("}")?;
+        Ok(expression)
+    }
+}
@@ -0,0 +1,191 @@
+//! 语法分析模块，递归下降地将记号序列转换为 AST
+
+use crate::lexer::{Token, TokenKind};
+use nyar_error::{ArcStr, NyarError, Result};
+use nyar_hir::ast::Span;
+use std::ops::Range;
+
+mod declaration;
+mod expression;
+mod pattern;
+mod statement;
+
+pub(crate) use self::expression::binary_precedence;
+
+/// 语句关键字, 错误恢复时在这些关键字处重新开始
+#[rustfmt::skip]
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "let", "const", "function", "def", "class", "trait", "enum", "structure", "if", "while", "for", "loop", "break", "continue",
+    "return", "try", "throw", "assert", "import", "export", "handle",
+];
+
+/// 不能作为标识符使用的关键字
+#[rustfmt::skip]
+pub(crate) const RESERVED: &[&str] = &[
+    "let", "const", "function", "def", "class", "trait", "enum", "structure", "if", "else", "while", "for", "in", "loop",
+    "break", "continue", "return", "try", "catch", "finally", "throw", "assert", "import", "export", "handle", "raise", "true",
+    "false", "null", "match", "new", "is", "implements",
+];
+
+/// 语法分析器状态
+pub(crate) struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    errors: Vec<NyarError>,
+    file: ArcStr,
+    /// 正在解析匹配分支的守卫, 此时 `=>` 结束守卫而不是开始 lambda
+    in_guard: bool,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>, file: ArcStr) -> Self {
+        Self { tokens, position: 0, errors: vec![], file, in_guard: false }
+    }
+
+    pub fn into_errors(self) -> Vec<NyarError> {
+        self.errors
+    }
+
+    fn peek(&self) -> &Token {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, n: usize) -> &Token {
+        let index = (self.position + n).min(self.tokens.len() - 1);
+        &self.tokens[index]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token.kind != TokenKind::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn at_end(&self) -> bool {
+        self.peek().kind == TokenKind::End
+    }
+
+    /// 当前记号的起始位置
+    fn start(&self) -> usize {
+        self.peek().span.start
+    }
+
+    /// 正在分析的文件中从 `start` 到上一个记号结尾的位置
+    fn span_from(&self, start: usize) -> Span {
+        let end = match self.position.checked_sub(1) {
+            Some(i) => self.tokens[i].span.end,
+            None => start,
+        };
+        self.span(start..end.max(start))
+    }
+
+    /// 正在分析的文件中 `range` 处的位置
+    fn span(&self, range: Range<usize>) -> Span {
+        Span::new(self.file.clone(), range)
+    }
+
+    fn check(&self, punctuation: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Punctuation(p) if *p == punctuation)
+    }
+
+    fn check_at(&self, n: usize, punctuation: &str) -> bool {
+        matches!(&self.peek_at(n).kind, TokenKind::Punctuation(p) if *p == punctuation)
+    }
+
+    fn eat(&mut self, punctuation: &str) -> bool {
+        let matched = self.check(punctuation);
+        if matched {
+            self.advance();
+        }
+        matched
+    }
+
+    fn expect(&mut self, punctuation: &str) -> Result<Range<usize>> {
+        match self.check(punctuation) {
+            true => Ok(self.advance().span),
+            false => Err(self.unexpected(&format!("`{}`", punctuation))),
+        }
+    }
+
+    fn check_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Identifier(name) if name == keyword)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let matched = self.check_keyword(keyword);
+        if matched {
+            self.advance();
+        }
+        matched
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<Range<usize>> {
+        match self.check_keyword(keyword) {
+            true => Ok(self.advance().span),
+            false => Err(self.unexpected(&format!("`{}`", keyword))),
+        }
+    }
+
+    fn expect_identifier(&mut self) -> Result<String> {
+        match &self.peek().kind {
+            TokenKind::Identifier(name) if !RESERVED.contains(&name.as_str()) => {
+                let name = name.clone();
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(self.unexpected("identifier")),
+        }
+    }
+
+    /// 构造 "expected X, found Y" 错误
+    fn unexpected(&self, expected: &str) -> NyarError {
+        let token = self.peek();
+        let found = match &token.kind {
+            TokenKind::Identifier(name) => format!("`{}`", name),
+            TokenKind::Integer(digits) => format!("integer `{}`", digits),
+            TokenKind::String(_) => "string literal".to_string(),
+            TokenKind::Template(_) => "interpolated string".to_string(),
+            TokenKind::Punctuation(p) => format!("`{}`", p),
+            TokenKind::End => "end of input".to_string(),
+        };
+        self.error(format!("expected {}, found {}", expected, found), token.span.clone())
+    }
+
+    fn error(&self, message: String, span: Range<usize>) -> NyarError {
+        NyarError::syntax_error(message, span).with_file(self.file.clone())
+    }
+
+    fn report(&mut self, error: NyarError) {
+        self.errors.push(error)
+    }
+
+    /// 语句是否在此处结束: 分号、右花括号、换行或输入结束
+    fn at_statement_end(&self) -> bool {
+        self.check(";") || self.check("}") || self.at_end() || self.peek().newline_before
+    }
+
+    /// 跳过记号直到可能的语句起点, 用于错误恢复
+    ///
+    /// `start` 是出错语句的第一个记号, 保证至少越过它以免原地重复报错。
+    fn synchronize(&mut self, start: usize) {
+        loop {
+            if self.at_end() || self.check("}") {
+                break;
+            }
+            if self.eat(";") {
+                break;
+            }
+            let token = self.peek();
+            if self.position > start && token.newline_before {
+                if let TokenKind::Identifier(name) = &token.kind {
+                    if STATEMENT_KEYWORDS.contains(&name.as_str()) {
+                        break;
+                    }
+                }
+            }
+            self.advance();
+        }
+    }
+}
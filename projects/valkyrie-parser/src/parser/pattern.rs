@@ -0,0 +1,153 @@
+use super::*;
+use nyar_hir::ast::{Expression, ExpressionKind, Literal, MatchArm, MatchExpression, Pattern};
+
+impl Parser {
+    /// 解析 `match value { pattern if guard => body, ... }` 形式的匹配表达式, 分支以逗号或换行分隔
+    pub(crate) fn parse_match(&mut self) -> Result<Expression> {
+        let start = self.start();
+        self.expect_keyword("match")?;
+        let in_guard = std::mem::replace(&mut self.in_guard, false);
+        let subject = self.parse_expression()?;
+        self.expect("{")?;
+        let mut arms = vec![];
+        while !self.check("}") && !self.at_end() {
+            let arm_start = self.start();
+            let pattern = self.parse_pattern()?;
+            let guard = match self.eat_keyword("if") {
+                true => {
+                    self.in_guard = true;
+                    let guard = self.parse_expression();
+                    self.in_guard = false;
+                    Some(guard?)
+                }
+                false => None,
+            };
+            self.expect("=>")?;
+            let body = self.parse_expression()?;
+            arms.push(MatchArm { pattern, guard, body, span: self.span_from(arm_start) });
+            if !self.eat(",") && !self.peek().newline_before {
+                break;
+            }
+        }
+        self.expect("}")?;
+        self.in_guard = in_guard;
+        let kind = ExpressionKind::Match(Box::new(MatchExpression { subject, arms }));
+        Ok(Expression::new(kind, self.span_from(start)))
+    }
+
+    /// 解析模式
+    fn parse_pattern(&mut self) -> Result<Pattern> {
+        let token = self.peek().clone();
+        let pattern = match token.kind {
+            TokenKind::Integer(digits) => {
+                self.advance();
+                Pattern::Literal(Literal::Integer(self.pattern_integer(&digits, token.span)?))
+            }
+            TokenKind::Punctuation("-") => {
+                self.advance();
+                let token = self.peek().clone();
+                match token.kind {
+                    TokenKind::Integer(digits) => {
+                        self.advance();
+                        Pattern::Literal(Literal::Integer(self.pattern_integer(&format!("-{}", digits), token.span)?))
+                    }
+                    _ => return Err(self.unexpected("integer")),
+                }
+            }
+            TokenKind::String(text) => {
+                self.advance();
+                Pattern::Literal(Literal::String(text))
+            }
+            TokenKind::Punctuation("[") => {
+                self.advance();
+                let mut items = vec![];
+                let mut rest = false;
+                while !self.check("]") {
+                    if self.eat("..") {
+                        rest = true;
+                        break;
+                    }
+                    items.push(self.parse_pattern()?);
+                    if !self.eat(",") {
+                        break;
+                    }
+                }
+                self.expect("]")?;
+                Pattern::List { items, rest }
+            }
+            TokenKind::Punctuation("{") => Pattern::Object(self.parse_pattern_fields()?),
+            TokenKind::Identifier(name) => match name.as_str() {
+                "true" | "false" => {
+                    self.advance();
+                    Pattern::Literal(Literal::Boolean(name == "true"))
+                }
+                "null" => {
+                    self.advance();
+                    Pattern::Literal(Literal::Null)
+                }
+                "_" => {
+                    self.advance();
+                    Pattern::Wildcard
+                }
+                _ => {
+                    let name = self.expect_identifier()?;
+                    if self.eat(".") {
+                        let variant = self.expect_identifier()?;
+                        let fields = match self.check("{") {
+                            true => self.parse_pattern_fields()?,
+                            false => self.parse_positional_patterns()?,
+                        };
+                        Pattern::Variant { enumeration: name, variant, fields }
+                    }
+                    else if self.check("{") {
+                        Pattern::Class { name, fields: self.parse_pattern_fields()? }
+                    }
+                    else {
+                        Pattern::Binding(name)
+                    }
+                }
+            },
+            _ => return Err(self.unexpected("pattern")),
+        };
+        Ok(pattern)
+    }
+
+    /// `{ x, y: pattern }`, 省略模式时绑定到同名变量
+    fn parse_pattern_fields(&mut self) -> Result<Vec<(String, Pattern)>> {
+        self.expect("{")?;
+        let mut fields = vec![];
+        while !self.check("}") {
+            let key = self.expect_identifier()?;
+            let pattern = match self.eat(":") {
+                true => self.parse_pattern()?,
+                false => Pattern::Binding(key.clone()),
+            };
+            fields.push((key, pattern));
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect("}")?;
+        Ok(fields)
+    }
+
+    /// `(a, b)` 中的模式以 `0`, `1`, ... 命名, 没有括号时为空
+    fn parse_positional_patterns(&mut self) -> Result<Vec<(String, Pattern)>> {
+        let mut fields = vec![];
+        if !self.eat("(") {
+            return Ok(fields);
+        }
+        while !self.check(")") {
+            fields.push((fields.len().to_string(), self.parse_pattern()?));
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(")")?;
+        Ok(fields)
+    }
+
+    fn pattern_integer(&self, digits: &str, span: Range<usize>) -> Result<i64> {
+        digits.parse().map_err(|_| self.error(format!("integer literal `{}` is too large", digits), span))
+    }
+}
@@ -0,0 +1,305 @@
+use super::*;
+use nyar_hir::ast::{
+    Assignment, BinaryExpression, CatchBlock, ExportStatement, Expression, ExpressionKind, IfStatement, ImportStatement,
+    Literal, LoopStatement, Program, Statement, StatementKind, TryCatchStatement, VariableDeclaration,
+};
+
+impl Parser {
+    /// 解析整个程序, 出错时跳过出错的语句继续解析
+    pub(crate) fn parse_program(&mut self) -> Program {
+        let mut program = Program::new();
+        program.file = self.file.clone();
+        while !self.at_end() {
+            if self.check("}") {
+                let error = self.unexpected("statement");
+                self.report(error);
+                self.advance();
+                continue;
+            }
+            if let Some(statement) = self.parse_statement_recover() {
+                program.add_statement(statement)
+            }
+        }
+        program.span = self.span(0..self.peek().span.end);
+        program
+    }
+
+    /// 解析语句, 出错时记录错误并同步到下一条语句
+    fn parse_statement_recover(&mut self) -> Option<Statement> {
+        let start = self.position;
+        match self.parse_statement() {
+            Ok(statement) => Some(statement),
+            Err(error) => {
+                self.report(error);
+                self.synchronize(start);
+                None
+            }
+        }
+    }
+
+    /// 解析花括号包围的语句块
+    pub(crate) fn parse_block(&mut self) -> Result<Vec<Statement>> {
+        self.expect("{")?;
+        let mut statements = vec![];
+        while !self.check("}") && !self.at_end() {
+            if let Some(statement) = self.parse_statement_recover() {
+                statements.push(statement)
+            }
+        }
+        self.expect("}")?;
+        Ok(statements)
+    }
+
+    /// 解析一条语句
+    pub(crate) fn parse_statement(&mut self) -> Result<Statement> {
+        let start = self.start();
+        let keyword = match &self.peek().kind {
+            TokenKind::Identifier(name) => name.clone(),
+            TokenKind::Punctuation("#") => return self.parse_annotated(),
+            TokenKind::Punctuation("{") => {
+                let block = self.parse_block()?;
+                return Ok(Statement::new(StatementKind::Block(block), self.span_from(start)));
+            }
+            _ => String::new(),
+        };
+        let kind = match keyword.as_str() {
+            "let" | "const" => StatementKind::VariableDeclaration(self.parse_variable()?),
+            "function" | "def" => StatementKind::FunctionDeclaration(self.parse_function(vec![])?),
+            "class" => StatementKind::ClassDeclaration(self.parse_class(vec![])?),
+            "trait" => StatementKind::TraitDeclaration(self.parse_trait()?),
+            "enum" => StatementKind::EnumDeclaration(self.parse_enum()?),
+            "structure" => StatementKind::StructureDeclaration(self.parse_structure()?),
+            "handle" => StatementKind::EffectHandler(self.parse_handler()?),
+            "if" => StatementKind::If(self.parse_if()?),
+            "while" => {
+                self.advance();
+                let condition = self.parse_expression()?;
+                let body = self.parse_block()?;
+                StatementKind::Loop(LoopStatement::While { condition, body })
+            }
+            "loop" => {
+                self.advance();
+                StatementKind::Loop(LoopStatement::Infinite { body: self.parse_block()? })
+            }
+            "for" => StatementKind::Loop(self.parse_for()?),
+            "break" => {
+                self.advance();
+                StatementKind::Break
+            }
+            "continue" => {
+                self.advance();
+                StatementKind::Continue
+            }
+            "return" => {
+                self.advance();
+                match self.at_statement_end() {
+                    true => StatementKind::Return(None),
+                    false => StatementKind::Return(Some(self.parse_expression()?)),
+                }
+            }
+            "throw" => {
+                self.advance();
+                StatementKind::Throw(self.parse_expression()?)
+            }
+            "assert" => {
+                self.advance();
+                let condition = self.parse_expression()?;
+                let message = match self.eat(",") {
+                    true => match self.advance().kind {
+                        TokenKind::String(text) => Some(text),
+                        _ => return Err(self.error("expected assertion message".to_string(), self.span_from(start).range)),
+                    },
+                    false => None,
+                };
+                StatementKind::Assert(condition, message)
+            }
+            "try" => StatementKind::TryCatch(self.parse_try()?),
+            "import" => StatementKind::Import(self.parse_import()?),
+            "export" => {
+                self.advance();
+                let declaration = self.parse_statement()?;
+                return Ok(Statement::new(
+                    StatementKind::Export(ExportStatement { declaration: Box::new(declaration) }),
+                    self.span_from(start),
+                ));
+            }
+            _ => self.parse_expression_statement()?,
+        };
+        self.eat(";");
+        Ok(Statement::new(kind, self.span_from(start)))
+    }
+
+    /// 表达式语句或赋值语句
+    fn parse_expression_statement(&mut self) -> Result<StatementKind> {
+        let target = self.parse_expression()?;
+        if self.eat("=") {
+            let value = self.parse_expression()?;
+            return Ok(StatementKind::Assignment(Assignment { target, value }));
+        }
+        for (compound, operator) in [("+=", "+"), ("-=", "-"), ("*=", "*"), ("/=", "/"), ("%=", "%")] {
+            if self.eat(compound) {
+                let right = self.parse_expression()?;
+                let span = target.span.to(&right.span);
+                let binary = BinaryExpression { left: target.clone(), operator: operator.to_string(), right };
+                let value = Expression::new(ExpressionKind::Binary(Box::new(binary)), span);
+                return Ok(StatementKind::Assignment(Assignment { target, value }));
+            }
+        }
+        if !self.at_statement_end() {
+            return Err(self.unexpected("end of statement"));
+        }
+        Ok(StatementKind::Expression(target))
+    }
+
+    fn parse_variable(&mut self) -> Result<VariableDeclaration> {
+        let is_constant = match self.eat_keyword("const") {
+            true => true,
+            false => {
+                self.expect_keyword("let")?;
+                false
+            }
+        };
+        let name = self.expect_identifier()?;
+        let type_annotation = if self.eat(":") { Some(self.parse_type()?) } else { None };
+        let initializer = if self.eat("=") { Some(self.parse_expression()?) } else { None };
+        Ok(VariableDeclaration { name, type_annotation, initializer, is_constant })
+    }
+
+    fn parse_if(&mut self) -> Result<IfStatement> {
+        self.expect_keyword("if")?;
+        let condition = self.parse_expression()?;
+        let then_branch = self.parse_block()?;
+        let else_branch = match self.eat_keyword("else") {
+            true if self.check_keyword("if") => {
+                let start = self.start();
+                let nested = self.parse_if()?;
+                Some(vec![Statement::new(StatementKind::If(nested), self.span_from(start))])
+            }
+            true => Some(self.parse_block()?),
+            false => None,
+        };
+        Ok(IfStatement { condition, then_branch, else_branch })
+    }
+
+    /// `for x in xs { }` 或 `for (init; condition; update) { }`
+    fn parse_for(&mut self) -> Result<LoopStatement> {
+        self.expect_keyword("for")?;
+        if self.eat("(") {
+            let initializer = Box::new(self.parse_statement()?);
+            let condition = match self.check(";") {
+                true => Expression::new(ExpressionKind::Literal(Literal::Boolean(true)), self.span(self.peek().span.clone())),
+                false => self.parse_expression()?,
+            };
+            self.expect(";")?;
+            let update = Box::new(self.parse_statement()?);
+            self.expect(")")?;
+            let body = self.parse_block()?;
+            return Ok(LoopStatement::For { initializer, condition, update, body });
+        }
+        let variable = self.expect_identifier()?;
+        self.expect_keyword("in")?;
+        let iterable = self.parse_expression()?;
+        let body = self.parse_block()?;
+        Ok(LoopStatement::ForEach { variable, iterable, body })
+    }
+
+    fn parse_try(&mut self) -> Result<TryCatchStatement> {
+        self.expect_keyword("try")?;
+        let try_block = self.parse_block()?;
+        let mut catch_blocks = vec![];
+        while self.check_keyword("catch") {
+            let start = self.start();
+            self.advance();
+            let parenthesized = self.eat("(");
+            let error_variable = self.expect_identifier()?;
+            let error_type = if self.eat(":") { Some(self.parse_type()?) } else { None };
+            if parenthesized {
+                self.expect(")")?;
+            }
+            let handler = self.parse_block()?;
+            catch_blocks.push(CatchBlock { error_type, error_variable, handler, span: self.span_from(start) });
+        }
+        let finally_block = if self.eat_keyword("finally") { Some(self.parse_block()?) } else { None };
+        if catch_blocks.is_empty() && finally_block.is_none() {
+            return Err(self.unexpected("`catch` or `finally`"));
+        }
+        Ok(TryCatchStatement { try_block, catch_blocks, finally_block })
+    }
+
+    /// `import a.b`, `import a.b as c`, `import a.b.{x, y}`, `import a.b.*` 或 `import "path" as c`
+    fn parse_import(&mut self) -> Result<ImportStatement> {
+        self.expect_keyword("import")?;
+        let mut import = ImportStatement { path: String::new(), symbols: vec![], is_all: false, alias: None };
+        if let TokenKind::String(path) = &self.peek().kind {
+            import.path = path.clone();
+            self.advance();
+        }
+        else {
+            let mut segments = vec![self.expect_identifier()?];
+            while self.eat(".") {
+                if self.eat("*") {
+                    import.is_all = true;
+                    break;
+                }
+                if self.eat("{") {
+                    while !self.check("}") {
+                        import.symbols.push(self.expect_identifier()?);
+                        if !self.eat(",") {
+                            break;
+                        }
+                    }
+                    self.expect("}")?;
+                    break;
+                }
+                segments.push(self.expect_identifier()?);
+            }
+            import.path = segments.join(".");
+        }
+        if self.eat_keyword("as") {
+            import.alias = Some(self.expect_identifier()?);
+        }
+        Ok(import)
+    }
+
+    /// 解析类型, 返回其规范的文本形式
+    pub(crate) fn parse_type(&mut self) -> Result<String> {
+        if self.eat("(") {
+            let mut parameters = vec![];
+            while !self.check(")") {
+                parameters.push(self.parse_type()?);
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            self.expect(")")?;
+            self.expect("->")?;
+            let output = self.parse_type()?;
+            return Ok(format!("({}) -> {}", parameters.join(", "), output));
+        }
+        let mut name = self.expect_type_name()?;
+        while self.eat(".") {
+            name.push('.');
+            name.push_str(&self.expect_type_name()?);
+        }
+        if self.eat("<") {
+            let mut arguments = vec![];
+            while !self.check(">") {
+                arguments.push(self.parse_type()?);
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            self.expect(">")?;
+            name = format!("{}<{}>", name, arguments.join(", "));
+        }
+        Ok(name)
+    }
+
+    fn expect_type_name(&mut self) -> Result<String> {
+        // `null` 可以作为类型名
+        if self.eat_keyword("null") {
+            return Ok("null".to_string());
+        }
+        self.expect_identifier()
+    }
+}
@@ -0,0 +1,683 @@
+//! 格式化模块，将 AST 输出为规范的 Valkyrie 源码
+
+use crate::parser::{RESERVED, binary_precedence};
+use nyar_hir::ast::{
+    Attribute, ClassDefinition, EnumDefinition, Expression, ExpressionKind, FunctionDefinition, IfStatement, ImportStatement,
+    InterpolationPart, Literal, LoopStatement, Parameter, Pattern, Program, Statement, StatementKind, StructureDefinition,
+    TraitDefinition, TryCatchStatement, VariantFields,
+};
+
+/// Valkyrie 源码格式化器
+#[derive(Debug, Clone)]
+pub struct ValkyriePrinter {
+    indent: String,
+    line_width: usize,
+}
+
+impl Default for ValkyriePrinter {
+    fn default() -> Self {
+        Self { indent: "    ".to_string(), line_width: 100 }
+    }
+}
+
+impl ValkyriePrinter {
+    /// 创建一个新的格式化器, 默认缩进四个空格, 行宽 100
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置一级缩进的文本
+    pub fn with_indent(mut self, indent: impl Into<String>) -> Self {
+        self.indent = indent.into();
+        self
+    }
+
+    /// 设置行宽, 超出行宽的列表、对象与参数会拆成多行
+    pub fn with_line_width(mut self, width: usize) -> Self {
+        self.line_width = width;
+        self
+    }
+
+    /// 格式化程序
+    pub fn print(&self, program: &Program) -> String {
+        let mut writer = Writer::new(self);
+        writer.statements(&program.statements);
+        writer.output
+    }
+
+    /// 格式化单个表达式
+    pub fn print_expression(&self, expression: &Expression) -> String {
+        Writer::new(self).expression(expression)
+    }
+}
+
+struct Writer<'c> {
+    config: &'c ValkyriePrinter,
+    output: String,
+    /// 缩进层级
+    level: usize,
+    /// 写在下一行开头的前缀, 如 `export `
+    prefix: &'static str,
+    /// 下一行是否接在上一行末尾
+    join: bool,
+}
+
+/// 表达式在父节点中的位置, 决定是否需要括号
+#[derive(Copy, Clone)]
+enum Position {
+    Free,
+    Left(u8),
+    Right(u8),
+    Operand,
+    Postfix,
+}
+
+impl<'c> Writer<'c> {
+    fn new(config: &'c ValkyriePrinter) -> Self {
+        Self { config, output: String::new(), level: 0, prefix: "", join: false }
+    }
+
+    fn line(&mut self, text: &str) {
+        if !std::mem::take(&mut self.join) {
+            for _ in 0..self.level {
+                self.output.push_str(&self.config.indent);
+            }
+        }
+        self.output.push_str(std::mem::take(&mut self.prefix));
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    /// 将下一行接在上一行末尾, 如 `} else {`
+    fn join_next(&mut self) {
+        self.output.pop();
+        self.join = true;
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for (index, statement) in statements.iter().enumerate() {
+            // 声明前后空一行
+            if index > 0 && (is_declaration(statement) || is_declaration(&statements[index - 1])) {
+                self.output.push('\n');
+            }
+            self.statement(statement);
+        }
+    }
+
+    fn block(&mut self, head: &str, statements: &[Statement], tail: &str) {
+        if statements.is_empty() {
+            self.line(&format!("{}{{}}{}", head, tail));
+            return;
+        }
+        self.line(&format!("{}{{", head));
+        self.level += 1;
+        self.statements(statements);
+        self.level -= 1;
+        self.line(&format!("}}{}", tail));
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::If(statement) => self.if_statement("if ", statement),
+            StatementKind::Loop(LoopStatement::While { condition, body }) => {
+                let head = format!("while {} ", self.expression(condition));
+                self.block(&head, body, "")
+            }
+            StatementKind::Loop(LoopStatement::For { initializer, condition, update, body }) => {
+                let head = format!(
+                    "for ({}; {}; {}) ",
+                    self.inline_statement(initializer),
+                    self.expression(condition),
+                    self.inline_statement(update)
+                );
+                self.block(&head, body, "")
+            }
+            StatementKind::Loop(LoopStatement::ForEach { variable, iterable, body }) => {
+                let head = format!("for {} in {} ", variable, self.expression(iterable));
+                self.block(&head, body, "")
+            }
+            StatementKind::Loop(LoopStatement::Infinite { body }) => self.block("loop ", body, ""),
+            StatementKind::Block(statements) => self.block("", statements, ""),
+            StatementKind::FunctionDeclaration(function) => self.function(function),
+            StatementKind::ClassDeclaration(class) => self.class(class),
+            StatementKind::TraitDeclaration(definition) => self.trait_definition(definition),
+            StatementKind::EnumDeclaration(definition) => self.enum_definition(definition),
+            StatementKind::StructureDeclaration(definition) => self.structure(definition),
+            StatementKind::EffectHandler(handler) => {
+                let function = &handler.handler;
+                let head = format!("handle {}{} ", handler.name, self.signature(&function.parameters, &function.return_type));
+                self.block(&head, &function.body, "")
+            }
+            StatementKind::Export(export) => {
+                self.prefix = "export ";
+                self.statement(&export.declaration)
+            }
+            StatementKind::TryCatch(statement) => self.try_catch(statement),
+            _ => {
+                let text = self.inline_statement(statement);
+                self.line(&text)
+            }
+        }
+    }
+
+    /// 可以写在一行内的语句
+    fn inline_statement(&mut self, statement: &Statement) -> String {
+        match &statement.kind {
+            StatementKind::Expression(expression) => {
+                let text = self.expression(expression);
+                // 以 `if`、`{` 或正负号开头的表达式语句会被解析成其他语句
+                match needs_statement_parentheses(expression) {
+                    true => format!("({})", text),
+                    false => text,
+                }
+            }
+            StatementKind::VariableDeclaration(declaration) => {
+                let mut text = format!("{} {}", if declaration.is_constant { "const" } else { "let" }, declaration.name);
+                if let Some(annotation) = &declaration.type_annotation {
+                    text.push_str(&format!(": {}", annotation));
+                }
+                if let Some(initializer) = &declaration.initializer {
+                    text.push_str(&format!(" = {}", self.expression(initializer)));
+                }
+                text
+            }
+            StatementKind::Assignment(assignment) => {
+                format!("{} = {}", self.expression(&assignment.target), self.expression(&assignment.value))
+            }
+            StatementKind::Break => "break".to_string(),
+            StatementKind::Continue => "continue".to_string(),
+            StatementKind::Return(None) => "return".to_string(),
+            StatementKind::Return(Some(value)) => format!("return {}", self.expression(value)),
+            StatementKind::Import(import) => import_text(import),
+            StatementKind::Throw(value) => format!("throw {}", self.expression(value)),
+            StatementKind::Assert(condition, None) => format!("assert {}", self.expression(condition)),
+            StatementKind::Assert(condition, Some(message)) => {
+                format!("assert {}, {}", self.expression(condition), quote(message))
+            }
+            _ => {
+                // 块状语句在单行位置上按缩进输出后压缩
+                let mut writer = Writer::new(self.config);
+                writer.statement(statement);
+                writer.output.lines().map(str::trim).collect::<Vec<_>>().join(" ")
+            }
+        }
+    }
+
+    fn if_statement(&mut self, head: &str, statement: &IfStatement) {
+        let head = format!("{}{} ", head, self.expression(&statement.condition));
+        match &statement.else_branch {
+            None => self.block(&head, &statement.then_branch, ""),
+            Some(branch) => {
+                self.block(&head, &statement.then_branch, " else ");
+                self.join_next();
+                match branch.as_slice() {
+                    [Statement { kind: StatementKind::If(nested), .. }] => self.if_statement("if ", nested),
+                    _ => self.block("", branch, ""),
+                }
+            }
+        }
+    }
+
+    fn try_catch(&mut self, statement: &TryCatchStatement) {
+        let mut clauses = vec![];
+        for block in &statement.catch_blocks {
+            let head = match &block.error_type {
+                Some(error_type) => format!("catch ({}: {}) ", block.error_variable, error_type),
+                None => format!("catch ({}) ", block.error_variable),
+            };
+            clauses.push((head, &block.handler));
+        }
+        if let Some(block) = &statement.finally_block {
+            clauses.push(("finally ".to_string(), block));
+        }
+        self.block("try ", &statement.try_block, if clauses.is_empty() { "" } else { " " });
+        for (index, (head, body)) in clauses.iter().enumerate() {
+            self.join_next();
+            self.block(head, body, if index + 1 < clauses.len() { " " } else { "" });
+        }
+    }
+
+    fn attributes(&mut self, attributes: &[Attribute]) {
+        for attribute in attributes {
+            let text = match attribute.arguments.is_empty() {
+                true => format!("#{}", attribute.name),
+                false => format!("#{}({})", attribute.name, self.list(&attribute.arguments)),
+            };
+            self.line(&text)
+        }
+    }
+
+    fn signature(&mut self, parameters: &[Parameter], return_type: &Option<String>) -> String {
+        let parameters: Vec<_> = parameters
+            .iter()
+            .map(|parameter| match &parameter.type_annotation {
+                Some(annotation) => format!("{}: {}", parameter.name, annotation),
+                None => parameter.name.clone(),
+            })
+            .collect();
+        match return_type {
+            Some(output) => format!("({}) -> {}", parameters.join(", "), output),
+            None => format!("({})", parameters.join(", ")),
+        }
+    }
+
+    fn function(&mut self, function: &FunctionDefinition) {
+        self.attributes(&function.attributes);
+        let head = format!(
+            "function {}{}{} ",
+            function.name,
+            generics(&function.generics),
+            self.signature(&function.parameters, &function.return_type)
+        );
+        self.block(&head, &function.body, "")
+    }
+
+    fn class(&mut self, class: &ClassDefinition) {
+        self.attributes(&class.attributes);
+        let mut head = format!("class {}{}", class.name, generics(&class.generics));
+        if let Some(parent) = &class.parent {
+            head.push_str(&format!(" extends {}", parent));
+        }
+        if !class.traits.is_empty() {
+            head.push_str(&format!(" implements {}", class.traits.join(", ")));
+        }
+        if class.properties.is_empty() && class.methods.is_empty() {
+            return self.line(&format!("{} {{}}", head));
+        }
+        self.line(&format!("{} {{", head));
+        self.level += 1;
+        for property in &class.properties {
+            let mut text = property.name.clone();
+            if let Some(annotation) = &property.type_annotation {
+                text.push_str(&format!(": {}", annotation));
+            }
+            if let Some(default) = &property.default {
+                text.push_str(&format!(" = {}", self.expression(default)));
+            }
+            self.line(&text)
+        }
+        for (index, method) in class.methods.iter().enumerate() {
+            if index > 0 || !class.properties.is_empty() {
+                self.output.push('\n');
+            }
+            self.function(method)
+        }
+        self.level -= 1;
+        self.line("}")
+    }
+
+    fn trait_definition(&mut self, definition: &TraitDefinition) {
+        if definition.methods.is_empty() {
+            return self.line(&format!("trait {} {{}}", definition.name));
+        }
+        self.line(&format!("trait {} {{", definition.name));
+        self.level += 1;
+        for method in &definition.methods {
+            let head = format!("function {}{}", method.name, self.signature(&method.parameters, &method.return_type));
+            match &method.body {
+                Some(body) => self.block(&format!("{} ", head), body, ""),
+                None => self.line(&format!("{};", head)),
+            }
+        }
+        self.level -= 1;
+        self.line("}")
+    }
+
+    fn structure(&mut self, definition: &StructureDefinition) {
+        if definition.fields.is_empty() {
+            return self.line(&format!("structure {} {{}}", definition.name));
+        }
+        self.line(&format!("structure {} {{", definition.name));
+        self.level += 1;
+        for field in &definition.fields {
+            self.line(&format!("{}: {},", field.name, field.field_type));
+        }
+        self.level -= 1;
+        self.line("}")
+    }
+
+    fn enum_definition(&mut self, definition: &EnumDefinition) {
+        let head = format!("enum {}{}", definition.name, generics(&definition.generics));
+        if definition.variants.is_empty() {
+            return self.line(&format!("{} {{}}", head));
+        }
+        self.line(&format!("{} {{", head));
+        self.level += 1;
+        for variant in &definition.variants {
+            let fields = match &variant.fields {
+                VariantFields::Unit => String::new(),
+                VariantFields::Positional(types) => format!("({})", types.join(", ")),
+                VariantFields::Named(fields) => {
+                    let fields: Vec<_> = fields
+                        .iter()
+                        .map(|field| match &field.type_annotation {
+                            Some(annotation) => format!("{}: {}", field.name, annotation),
+                            None => field.name.clone(),
+                        })
+                        .collect();
+                    format!(" {{ {} }}", fields.join(", "))
+                }
+            };
+            let text = match &variant.value {
+                Some(value) => format!("{}{} = {},", variant.name, fields, self.expression(value)),
+                None => format!("{}{},", variant.name, fields),
+            };
+            self.line(&text)
+        }
+        self.level -= 1;
+        self.line("}")
+    }
+
+    fn expression(&mut self, expression: &Expression) -> String {
+        self.expression_at(expression, Position::Free)
+    }
+
+    fn expression_at(&mut self, expression: &Expression, position: Position) -> String {
+        let text = match &expression.kind {
+            ExpressionKind::Literal(literal) => self.literal(literal),
+            ExpressionKind::Variable(name) => name.clone(),
+            ExpressionKind::Binary(binary) => {
+                let precedence = binary_precedence(&binary.operator).unwrap_or(0);
+                format!(
+                    "{} {} {}",
+                    self.expression_at(&binary.left, Position::Left(precedence)),
+                    binary.operator,
+                    self.expression_at(&binary.right, Position::Right(precedence))
+                )
+            }
+            ExpressionKind::Unary(unary) => {
+                format!("{}{}", unary.operator, self.expression_at(&unary.operand, Position::Operand))
+            }
+            ExpressionKind::Call(call) => {
+                let arguments = self.list(&call.arguments);
+                format!("{}({})", self.expression_at(&call.callee, Position::Postfix), arguments)
+            }
+            ExpressionKind::Lambda(lambda) => {
+                let body = self.expression(&lambda.body);
+                match lambda.parameters.as_slice() {
+                    [parameter] => format!("{} => {}", parameter, body),
+                    parameters => format!("({}) => {}", parameters.join(", "), body),
+                }
+            }
+            ExpressionKind::Conditional(conditional) => {
+                let mut text = format!(
+                    "if {} {{ {} }}",
+                    self.expression(&conditional.condition),
+                    self.expression(&conditional.then_branch)
+                );
+                match &conditional.else_branch {
+                    Some(branch @ Expression { kind: ExpressionKind::Conditional(_), .. }) => {
+                        text.push_str(&format!(" else {}", self.expression(branch)))
+                    }
+                    Some(branch) => text.push_str(&format!(" else {{ {} }}", self.expression(branch))),
+                    None => {}
+                }
+                text
+            }
+            ExpressionKind::MemberAccess(access) => {
+                format!("{}.{}", self.expression_at(&access.object, Position::Postfix), access.member)
+            }
+            ExpressionKind::IndexAccess(access) => {
+                let index = self.expression(&access.index);
+                format!("{}[{}]", self.expression_at(&access.object, Position::Postfix), index)
+            }
+            ExpressionKind::Effect(effect) => match effect.arguments.is_empty() {
+                true => format!("raise {}", effect.name),
+                false => format!("raise {}({})", effect.name, self.list(&effect.arguments)),
+            },
+            ExpressionKind::Match(expression) => {
+                let mut text = format!("match {} {{\n", self.expression(&expression.subject));
+                self.level += 1;
+                let indent = self.config.indent.repeat(self.level);
+                for arm in &expression.arms {
+                    let guard = match &arm.guard {
+                        Some(guard) => format!(" if {}", self.expression(guard)),
+                        None => String::new(),
+                    };
+                    let body = self.expression(&arm.body);
+                    text.push_str(&format!("{}{}{} => {},\n", indent, pattern(&arm.pattern), guard, body));
+                }
+                self.level -= 1;
+                text.push_str(&self.config.indent.repeat(self.level));
+                text.push('}');
+                text
+            }
+            ExpressionKind::New(expression) => {
+                let properties: Vec<_> =
+                    expression.properties.iter().map(|(name, value)| format!("{}: {}", name, self.expression(value))).collect();
+                match properties.is_empty() {
+                    true => format!("new {}", expression.class),
+                    false => format!("new {} {{ {} }}", expression.class, properties.join(", ")),
+                }
+            }
+            ExpressionKind::Interpolation(expression) => {
+                let mut text = String::from('"');
+                for part in &expression.parts {
+                    match part {
+                        InterpolationPart::Text(part) => escape(&mut text, part),
+                        InterpolationPart::Expression(part) => text.push_str(&format!("${{{}}}", self.expression(part))),
+                    }
+                }
+                text.push('"');
+                text
+            }
+        };
+        match needs_parentheses(expression, position) {
+            true => format!("({})", text),
+            false => text,
+        }
+    }
+
+    fn literal(&mut self, literal: &Literal) -> String {
+        match literal {
+            Literal::Null => "null".to_string(),
+            Literal::Boolean(value) => value.to_string(),
+            // 字面量只能表示非负数, 最小值需要借助减法
+            Literal::Integer(i64::MIN) => format!("-{} - 1", i64::MAX),
+            Literal::Integer(value) => value.to_string(),
+            Literal::String(text) => quote(text),
+            Literal::List(items) => format!("[{}]", self.list(items)),
+            Literal::Object(properties) => {
+                let items: Vec<_> = properties
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = if is_identifier(key) { key.clone() } else { quote(key) };
+                        format!("{}: {}", key, self.expression(value))
+                    })
+                    .collect();
+                match items.is_empty() {
+                    true => "{}".to_string(),
+                    false => self.wrap("{ ", items, " }", "{", "}"),
+                }
+            }
+        }
+    }
+
+    /// 逗号分隔的表达式列表, 超出行宽时拆成多行
+    fn list(&mut self, items: &[Expression]) -> String {
+        let items: Vec<_> = items.iter().map(|item| self.expression(item)).collect();
+        self.wrap("", items, "", "", "")
+    }
+
+    fn wrap(&self, open: &str, items: Vec<String>, close: &str, multiline_open: &str, multiline_close: &str) -> String {
+        let flat = format!("{}{}{}", open, items.join(", "), close);
+        let width = self.config.indent.len() * self.level + flat.len();
+        if width <= self.config.line_width || items.len() < 2 || flat.contains('\n') {
+            return flat;
+        }
+        let indent = self.config.indent.repeat(self.level + 1);
+        let mut text = format!("{}\n", multiline_open);
+        for item in items {
+            text.push_str(&format!("{}{},\n", indent, item));
+        }
+        text.push_str(&self.config.indent.repeat(self.level));
+        text.push_str(multiline_close);
+        text
+    }
+}
+
+fn pattern(pattern: &Pattern) -> String {
+    let fields = |fields: &[(String, Pattern)]| {
+        let fields: Vec<_> = fields
+            .iter()
+            .map(|(key, field)| match field {
+                Pattern::Binding(name) if name == key => key.clone(),
+                field => format!("{}: {}", key, self::pattern(field)),
+            })
+            .collect();
+        match fields.is_empty() {
+            true => "{}".to_string(),
+            false => format!("{{ {} }}", fields.join(", ")),
+        }
+    };
+    match pattern {
+        Pattern::Wildcard => "_".to_string(),
+        Pattern::Literal(Literal::String(text)) => quote(text),
+        Pattern::Literal(Literal::Integer(value)) => value.to_string(),
+        Pattern::Literal(Literal::Boolean(value)) => value.to_string(),
+        Pattern::Literal(_) => "null".to_string(),
+        Pattern::Binding(name) => name.clone(),
+        Pattern::List { items, rest } => {
+            let mut items: Vec<_> = items.iter().map(self::pattern).collect();
+            if *rest {
+                items.push("..".to_string())
+            }
+            format!("[{}]", items.join(", "))
+        }
+        Pattern::Object(items) => fields(items),
+        Pattern::Variant { enumeration, variant, fields: items } if items.is_empty() => {
+            format!("{}.{}", enumeration, variant)
+        }
+        // 位置字段按 `0`, `1`, ... 的顺序命名
+        Pattern::Variant { enumeration, variant, fields: items }
+            if items.iter().enumerate().all(|(index, (key, _))| *key == index.to_string()) =>
+        {
+            let items: Vec<_> = items.iter().map(|(_, item)| self::pattern(item)).collect();
+            format!("{}.{}({})", enumeration, variant, items.join(", "))
+        }
+        Pattern::Variant { enumeration, variant, fields: items } => format!("{}.{} {}", enumeration, variant, fields(items)),
+        Pattern::Class { name, fields: items } => format!("{} {}", name, fields(items)),
+    }
+}
+
+fn is_declaration(statement: &Statement) -> bool {
+    match &statement.kind {
+        StatementKind::FunctionDeclaration(_)
+        | StatementKind::ClassDeclaration(_)
+        | StatementKind::TraitDeclaration(_)
+        | StatementKind::EnumDeclaration(_)
+        | StatementKind::StructureDeclaration(_)
+        | StatementKind::EffectHandler(_) => true,
+        StatementKind::Export(export) => is_declaration(&export.declaration),
+        _ => false,
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_alphabetic() => {}
+        _ => return false,
+    }
+    chars.all(|c| c == '_' || c.is_alphanumeric()) && !RESERVED.contains(&text)
+}
+
+/// 表达式在给定位置上是否需要括号
+fn needs_parentheses(expression: &Expression, position: Position) -> bool {
+    match (&expression.kind, position) {
+        (_, Position::Free) => false,
+        // lambda 体会吞掉其后的全部运算
+        (ExpressionKind::Lambda(_), _) => true,
+        (ExpressionKind::Binary(binary), position) => {
+            let precedence = binary_precedence(&binary.operator).unwrap_or(0);
+            match position {
+                Position::Left(parent) => precedence < parent,
+                Position::Right(parent) => precedence <= parent,
+                _ => true,
+            }
+        }
+        (ExpressionKind::Literal(Literal::Integer(value)), Position::Postfix | Position::Operand) => *value < 0,
+        (ExpressionKind::Literal(Literal::Integer(i64::MIN)), _) => true,
+        // 无参数的效应操作之后的括号会被当作效应参数, 实例化之后的后缀运算加括号以免与类型混淆
+        (
+            ExpressionKind::Unary(_) | ExpressionKind::Conditional(_) | ExpressionKind::Effect(_) | ExpressionKind::New(_),
+            Position::Postfix,
+        ) => true,
+        _ => false,
+    }
+}
+
+/// 表达式语句是否需要括号, 以免开头的记号被当作其他语句
+fn needs_statement_parentheses(expression: &Expression) -> bool {
+    let mut current = expression;
+    loop {
+        let (next, position) = match &current.kind {
+            ExpressionKind::Binary(binary) => (&binary.left, Position::Left(binary_precedence(&binary.operator).unwrap_or(0))),
+            ExpressionKind::Call(call) => (&call.callee, Position::Postfix),
+            ExpressionKind::MemberAccess(access) => (&access.object, Position::Postfix),
+            ExpressionKind::IndexAccess(access) => (&access.object, Position::Postfix),
+            ExpressionKind::Conditional(_) | ExpressionKind::Literal(Literal::Object(_)) => return true,
+            ExpressionKind::Unary(unary) => return unary.operator != "!",
+            ExpressionKind::Literal(Literal::Integer(value)) => return *value < 0,
+            _ => return false,
+        };
+        // 已经带括号的子表达式不会产生歧义
+        if needs_parentheses(next, position) {
+            return false;
+        }
+        current = next;
+    }
+}
+
+fn import_text(import: &ImportStatement) -> String {
+    let dotted = import.path.split('.').all(is_identifier);
+    let mut text = match dotted {
+        true => format!("import {}", import.path),
+        false => format!("import {}", quote(&import.path)),
+    };
+    if import.is_all {
+        text.push_str(".*")
+    }
+    else if !import.symbols.is_empty() {
+        text.push_str(&format!(".{{{}}}", import.symbols.join(", ")))
+    }
+    if let Some(alias) = &import.alias {
+        text.push_str(&format!(" as {}", alias))
+    }
+    text
+}
+
+/// 带转义的双引号字符串
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    escape(&mut quoted, text);
+    quoted.push('"');
+    quoted
+}
+
+/// 转义字符串内容, `${` 中的 `$` 也需要转义以免被当作插值
+fn escape(quoted: &mut String, text: &str) {
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            '\0' => quoted.push_str("\\0"),
+            '$' if chars.peek() == Some(&'{') => quoted.push_str("\\$"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+}
+
+/// 泛型参数列表, 没有泛型参数时为空
+fn generics(generics: &[String]) -> String {
+    match generics.is_empty() {
+        true => String::new(),
+        false => format!("<{}>", generics.join(", ")),
+    }
+}
@@ -0,0 +1,263 @@
+use nyar_hir::{
+    ModuleResolver,
+    ast::{ExpressionKind, InterpolationPart, Literal, LoopStatement, Statement, StatementKind},
+};
+use valkyrie_parser::{FileResolver, ValkyrieParser, ValkyriePrinter};
+
+fn parse(source: &str) -> Vec<Statement> {
+    let parsed = ValkyrieParser::new().parse(source);
+    assert!(parsed.is_success(), "{:?}", parsed.errors());
+    parsed.into_result().unwrap().statements
+}
+
+#[test]
+fn parse_precedence() {
+    let statements = parse("1 + 2 * 3 == 7 && !false");
+    let StatementKind::Expression(expression) = &statements[0].kind
+    else {
+        panic!("{:?}", statements[0])
+    };
+    let ExpressionKind::Binary(and) = &expression.kind
+    else {
+        panic!("{:?}", expression)
+    };
+    assert_eq!(and.operator, "&&");
+    let ExpressionKind::Binary(equal) = &and.left.kind
+    else {
+        panic!("{:?}", and.left)
+    };
+    let ExpressionKind::Binary(add) = &equal.left.kind
+    else {
+        panic!("{:?}", equal.left)
+    };
+    assert_eq!(add.operator, "+");
+    assert_eq!(add.right.span.range, 4..9);
+}
+
+#[test]
+fn parse_declarations() {
+    let statements = parse(
+        r#"
+        #inline
+        function add(a: int, b: int) -> int {
+            a + b
+        }
+        class Point extends Base implements Display {
+            x: int = 0
+            y: int = 0
+            function norm() { x * x + y * y }
+        }
+        trait Display { function show(self); }
+        enum Color { Red, Green = 5 }
+        let xs: List<int> = [1, 2, 3]
+        for x in xs { total += x }
+        "#,
+    );
+    assert_eq!(statements.len(), 6);
+    let StatementKind::FunctionDeclaration(function) = &statements[0].kind
+    else {
+        panic!()
+    };
+    assert_eq!(function.attributes[0].name, "inline");
+    assert_eq!(function.return_type.as_deref(), Some("int"));
+    let StatementKind::ClassDeclaration(class) = &statements[1].kind
+    else {
+        panic!()
+    };
+    assert_eq!((class.properties.len(), class.methods.len()), (2, 1));
+    let StatementKind::VariableDeclaration(xs) = &statements[4].kind
+    else {
+        panic!()
+    };
+    assert_eq!(xs.type_annotation.as_deref(), Some("List<int>"));
+    let StatementKind::Loop(LoopStatement::ForEach { body, .. }) = &statements[5].kind
+    else {
+        panic!()
+    };
+    assert!(matches!(body[0].kind, StatementKind::Assignment(_)));
+}
+
+#[test]
+fn statement_spans() {
+    let source = "let a = 1\nif a > 0 { a = 2 } else { a = 3 }";
+    let statements = ValkyrieParser::new().with_file("main.vk").parse(source).into_result().unwrap().statements;
+    assert_eq!(&source[statements[0].span.range.clone()], "let a = 1");
+    assert_eq!(&source[statements[1].span.range.clone()], "if a > 0 { a = 2 } else { a = 3 }");
+    // 节点记录所在的文件, 导入的模块据此报告位置
+    assert_eq!(statements[1].span.file.as_str(), "main.vk");
+}
+
+#[test]
+fn recover_from_errors() {
+    let parsed = ValkyrieParser::new().with_file("main.vk").parse("let = 1\nlet b = 2\nlet c = (3\nlet d = 4");
+    assert_eq!(parsed.errors().len(), 2);
+    assert_eq!(parsed.errors()[0].span(), 4..5);
+    assert_eq!(parsed.errors()[0].file().as_str(), "main.vk");
+    assert_eq!(parsed.program().statements.len(), 2);
+}
+
+#[test]
+fn parse_interpolation() {
+    let source = r#""a\t${x + 1}${ {k: "}"}.k }\${y}$""#;
+    let statements = parse(source);
+    let StatementKind::Expression(expression) = &statements[0].kind
+    else {
+        panic!("{:?}", statements[0])
+    };
+    let ExpressionKind::Interpolation(interpolation) = &expression.kind
+    else {
+        panic!("{:?}", expression)
+    };
+    let [
+        InterpolationPart::Text(head),
+        InterpolationPart::Expression(sum),
+        InterpolationPart::Expression(member),
+        InterpolationPart::Text(tail),
+    ] = interpolation.parts.as_slice()
+    else {
+        panic!("{:?}", interpolation.parts)
+    };
+    assert_eq!((head.as_str(), tail.as_str()), ("a\t", "${y}$"));
+    assert_eq!(&source[sum.span.range.clone()], "x + 1");
+    assert_eq!(&source[member.span.range.clone()], r#"{k: "}"}.k"#);
+    // 没有插值的字符串仍是普通字面量
+    let statements = parse(r#""plain \$ {x}""#);
+    let StatementKind::Expression(expression) = &statements[0].kind
+    else {
+        panic!("{:?}", statements[0])
+    };
+    assert!(matches!(&expression.kind, ExpressionKind::Literal(Literal::String(text)) if text == "plain $ {x}"));
+
+    for (source, message) in [
+        (r#""${1 2}""#, "expected `}`, found integer `2`"),
+        (r#""${}""#, "expected expression, found end of input"),
+        (r#""${x"#, "unterminated string interpolation"),
+    ] {
+        let parsed = ValkyrieParser::new().parse(source);
+        assert!(parsed.errors().iter().any(|e| e.kind().to_string().contains(message)), "{}: {:?}", source, parsed.errors());
+    }
+}
+
+const FORMATTED: &str = r#"import std.io.{read, write} as io
+
+#inline
+function add(a: int, b: int) -> int {
+    a + b
+}
+
+class Point extends Base implements Display {
+    x: int = 0
+    y = 1
+
+    function norm() {
+        x * x + y * y
+    }
+}
+
+trait Display {
+    function show(self);
+    function banner() {
+        show()
+    }
+}
+
+enum Color {
+    Red,
+    Green = 5,
+}
+
+enum Shape<T> {
+    Circle(T),
+    Rect { width: T, height },
+    Empty,
+}
+
+class Box<T> {
+    value: T
+}
+
+structure UTF8View {
+    offset: u32,
+    length: u32,
+}
+
+function first<T>(xs: List<T>) -> T {
+    xs[0]
+}
+
+export const limit = (1 + 2) * 3 - (4 - 5)
+let f = (a, b) => a * (b + 1)
+let origin = new Point { x: 0, y: limit }
+(new Box<int>).value
+let greeting = "hello ${name.trim()}, ${"\${raw}"} ${f(1, 2) + 1}!\n"
+let checked = origin is Point && Point implements Display != origin is Color
+(if f(1, 2) > 3 { "big" } else { "small" }).length
+if limit > 0 {
+    print("positive\n")
+} else if limit < 0 {
+    print(-limit)
+} else {
+    loop {
+        break
+    }
+}
+for (let i = 0; i < 10; i = i + 1) {
+    total = total + i
+}
+try {
+    throw "oops"
+} catch (e: Error) {
+    assert e != null, "caught"
+} finally {}
+let label = match shape {
+    Color.Red => "red",
+    Shape.Circle(Shape.Empty) => 0,
+    Shape.Rect { width, height: 0 } => width,
+    [x, _, ..] if x > 0 && ok => x,
+    Point { x, y: 0 } => x,
+    { name: "origin", z } => -1,
+    _ => null,
+}
+"#;
+
+#[test]
+fn print_round_trip() {
+    let program = ValkyrieParser::new().parse(FORMATTED).into_result().unwrap();
+    let printed = ValkyriePrinter::new().print(&program);
+    assert_eq!(printed, FORMATTED);
+}
+
+#[test]
+fn print_canonical_form() {
+    let source = "let x=1+(2*3)\nlet y = -(a+b).c\nlet s = {key: [1,2], 'not ident': x => x}";
+    let program = ValkyrieParser::new().parse(source).into_result().unwrap();
+    let printed = ValkyriePrinter::new().with_indent("\t").print(&program);
+    assert_eq!(printed, "let x = 1 + 2 * 3\nlet y = -(a + b).c\nlet s = { key: [1, 2], \"not ident\": x => x }\n");
+    let reparsed = ValkyrieParser::new().parse(&printed).into_result().unwrap();
+    assert_eq!(ValkyriePrinter::new().with_indent("\t").print(&reparsed), printed);
+}
+
+#[test]
+fn print_wraps_long_lines() {
+    let program = ValkyrieParser::new().parse("call(first_argument, second_argument, [1, 2, 3])").into_result().unwrap();
+    let printed = ValkyriePrinter::new().with_line_width(20).print(&program);
+    assert_eq!(printed, "call(\n    first_argument,\n    second_argument,\n    [1, 2, 3],\n)\n");
+    let reparsed = ValkyrieParser::new().parse(&printed).into_result().unwrap();
+    assert_eq!(ValkyriePrinter::new().with_line_width(20).print(&reparsed), printed);
+}
+
+#[test]
+fn resolve_module_files() {
+    let root = std::env::temp_dir().join(format!("valkyrie-modules-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("lib")).unwrap();
+    std::fs::write(root.join("lib/util.vk"), "export let x = 1").unwrap();
+    let resolver = FileResolver::new(&root);
+    let util = resolver.resolve("lib.util", "").unwrap();
+    assert_eq!(resolver.resolve("./util.vk", &util).unwrap(), util);
+    assert_eq!(resolver.resolve("lib/util.vk", "").unwrap(), util);
+    let program = resolver.load(&util).unwrap();
+    assert_eq!((program.file.as_str(), program.statements.len()), (util.as_str(), 1));
+    let error = resolver.resolve("lib.missing", "").unwrap_err();
+    assert!(error.kind().to_string().contains("cannot find module `lib.missing`"), "{}", error);
+    std::fs::remove_dir_all(root).unwrap();
+}